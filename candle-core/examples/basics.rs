@@ -11,7 +11,7 @@ fn main() -> Result<()> {
     let inp = Tensor::randn(0f32, 1., (2, 320, 96, 96), &Device::Cpu)?;
     let w = Tensor::randn(0f32, 1., (320, 320, 3, 3), &Device::Cpu)?;
     let start = std::time::Instant::now();
-    let res = inp.conv2d(&w, 0, 1);
+    let res = inp.conv2d(&w, 0, 1, 1, 1);
     println!("{:?}", start.elapsed());
     println!("{res:?}");
     Ok(())
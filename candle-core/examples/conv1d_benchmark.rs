@@ -5,20 +5,59 @@ extern crate intel_mkl_src;
 extern crate accelerate_src;
 
 use anyhow::Result;
-use candle_core::{Device, Tensor};
+use candle_core::{DType, Device, Tensor};
 
 pub const N_ITERS: usize = 5;
 
-fn main() -> Result<()> {
-    let inp = Tensor::randn(0f32, 1., (1, 384, 3000), &Device::Cpu)?;
-    let w = Tensor::randn(0f32, 1., (384, 384, 3), &Device::Cpu)?;
-    let res = inp.conv1d(&w, 0, 1);
-    println!("{res:?}");
+fn bench(inp: &Tensor, w: &Tensor) -> Result<std::time::Duration> {
+    // Warmup run.
+    let _res = inp.conv1d(w, 0, 1, 1, 1)?;
     let start = std::time::Instant::now();
-    for i in 0..N_ITERS {
-        let res = inp.conv1d(&w, 0, 1);
-        println!("{i} {res:?}");
+    for _ in 0..N_ITERS {
+        let res = inp.conv1d(w, 0, 1, 1, 1)?;
+        std::hint::black_box(res);
     }
-    println!("{:?}", start.elapsed() / N_ITERS as u32);
+    Ok(start.elapsed() / N_ITERS as u32)
+}
+
+// Small integer values so that the float results are exact and can be compared with the
+// integer ones.
+fn small_ints(dims: (usize, usize, usize), m: f64) -> Result<Tensor> {
+    let n = dims.0 * dims.1 * dims.2;
+    let t = Tensor::arange(0i64, n as i64, &Device::Cpu)?.rem(m)?;
+    Ok(t.affine(1., -(m / 2.).floor())?.reshape(dims)?)
+}
+
+// The CANDLE_DISABLE_IM2COL environment variable is only read once so the float path cannot be
+// switched at runtime, the integer convolutions always use the direct kernels and are used as
+// the comparison point. Run with CANDLE_DISABLE_IM2COL=1 to time the direct float kernels.
+fn main() -> Result<()> {
+    let inp = small_ints((1, 384, 3000), 7.)?;
+    let w = small_ints((384, 384, 3), 5.)?;
+    let (inp_f32, w_f32) = (inp.to_dtype(DType::F32)?, w.to_dtype(DType::F32)?);
+    let res = inp_f32.conv1d(&w_f32, 0, 1, 1, 1)?;
+    println!("{res:?}");
+
+    let float = bench(&inp_f32, &w_f32)?;
+    let float_path = if candle_core::utils::use_im2col_conv() {
+        "im2col + gemm"
+    } else {
+        "direct"
+    };
+    println!("f32 ({float_path}): {float:?}");
+
+    let direct = bench(&inp, &w)?;
+    println!("i64 (direct): {direct:?}");
+    let res_direct = inp.conv1d(&w, 0, 1, 1, 1)?;
+
+    let diff = (res.to_dtype(DType::I64)? - res_direct)?
+        .abs()?
+        .flatten_all()?
+        .max(0)?;
+    println!("max diff: {}", diff.to_scalar::<i64>()?);
+    println!(
+        "speedup: {:.1}x",
+        direct.as_secs_f64() / float.as_secs_f64()
+    );
     Ok(())
 }
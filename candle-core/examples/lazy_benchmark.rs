@@ -0,0 +1,165 @@
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+#[cfg(feature = "accelerate")]
+extern crate accelerate_src;
+
+use anyhow::Result;
+use candle_core::{DType, Device, Tensor, D};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const N_ITERS: usize = 5;
+const N_LAYER: usize = 4;
+const HIDDEN: usize = 512;
+const INTERMEDIATE: usize = 1376;
+const N_HEAD: usize = 8;
+const SEQ_LEN: usize = 64;
+
+// Counts the allocations made by the process and the number of bytes that they request.
+struct Counting;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+struct Layer {
+    wq: Tensor,
+    wk: Tensor,
+    wv: Tensor,
+    wo: Tensor,
+    w_gate: Tensor,
+    w_up: Tensor,
+    w_down: Tensor,
+    norm: Tensor,
+}
+
+impl Layer {
+    fn new() -> Result<Self> {
+        let w = |o: usize, i: usize| Tensor::randn(0f32, 0.05, (o, i), &Device::Cpu);
+        Ok(Self {
+            wq: w(HIDDEN, HIDDEN)?,
+            wk: w(HIDDEN, HIDDEN)?,
+            wv: w(HIDDEN, HIDDEN)?,
+            wo: w(HIDDEN, HIDDEN)?,
+            w_gate: w(INTERMEDIATE, HIDDEN)?,
+            w_up: w(INTERMEDIATE, HIDDEN)?,
+            w_down: w(HIDDEN, INTERMEDIATE)?,
+            norm: Tensor::ones(HIDDEN, DType::F32, &Device::Cpu)?,
+        })
+    }
+
+    fn to_device(&self, device: &Device) -> Result<Self> {
+        Ok(Self {
+            wq: self.wq.to_device(device)?,
+            wk: self.wk.to_device(device)?,
+            wv: self.wv.to_device(device)?,
+            wo: self.wo.to_device(device)?,
+            w_gate: self.w_gate.to_device(device)?,
+            w_up: self.w_up.to_device(device)?,
+            w_down: self.w_down.to_device(device)?,
+            norm: self.norm.to_device(device)?,
+        })
+    }
+
+    // A llama decoder layer without the rotary embeddings.
+    fn forward(&self, xs: &Tensor, mask: &Tensor) -> Result<Tensor> {
+        let (seq_len, hidden) = xs.dims2()?;
+        let head_dim = hidden / N_HEAD;
+        let heads = |t: Tensor| t.reshape((seq_len, N_HEAD, head_dim))?.transpose(0, 1);
+        let h = rms_norm(xs, &self.norm)?;
+        let q = heads(h.matmul(&self.wq.t()?)?)?;
+        let k = heads(h.matmul(&self.wk.t()?)?)?;
+        let v = heads(h.matmul(&self.wv.t()?)?)?;
+        let att = (q.matmul(&k.t()?)? / (head_dim as f64).sqrt())?.broadcast_add(mask)?;
+        let att = att.broadcast_sub(&att.max_keepdim(D::Minus1)?)?.exp()?;
+        let att = att.broadcast_div(&att.sum_keepdim(D::Minus1)?)?;
+        let ys = att
+            .matmul(&v)?
+            .transpose(0, 1)?
+            .reshape((seq_len, hidden))?;
+        let xs = (xs + ys.matmul(&self.wo.t()?)?)?;
+        let h = rms_norm(&xs, &self.norm)?;
+        let gate = h.matmul(&self.w_gate.t()?)?;
+        let silu = (&gate / (gate.neg()?.exp()? + 1.)?)?;
+        let ys = (silu * h.matmul(&self.w_up.t()?)?)?.matmul(&self.w_down.t()?)?;
+        Ok((xs + ys)?)
+    }
+}
+
+fn rms_norm(xs: &Tensor, weight: &Tensor) -> Result<Tensor> {
+    let norm = (xs.sqr()?.mean_keepdim(D::Minus1)? + 1e-5)?.sqrt()?;
+    Ok(xs.broadcast_div(&norm)?.broadcast_mul(weight)?)
+}
+
+fn forward(layers: &[Layer], xs: &Tensor, mask: &Tensor) -> Result<Vec<f32>> {
+    let mut xs = xs.clone();
+    for layer in layers.iter() {
+        xs = layer.forward(&xs, mask)?;
+    }
+    Ok(xs.flatten_all()?.to_vec1::<f32>()?)
+}
+
+// Returns the average time, allocations and allocated bytes of a forward pass.
+fn bench(layers: &[Layer], device: &Device) -> Result<(std::time::Duration, usize, usize)> {
+    let layers = layers
+        .iter()
+        .map(|l| l.to_device(device))
+        .collect::<Result<Vec<_>>>()?;
+    let xs = Tensor::randn(0f32, 1., (SEQ_LEN, HIDDEN), &Device::Cpu)?.to_device(device)?;
+    let mask: Vec<f32> = (0..SEQ_LEN * SEQ_LEN)
+        .map(|i| {
+            if i % SEQ_LEN > i / SEQ_LEN {
+                f32::NEG_INFINITY
+            } else {
+                0.
+            }
+        })
+        .collect();
+    let mask = Tensor::from_vec(mask, (SEQ_LEN, SEQ_LEN), device)?;
+    // Warmup run.
+    let _res = forward(&layers, &xs, &mask)?;
+    let (allocs, bytes) = (
+        ALLOCS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    let start = std::time::Instant::now();
+    for _ in 0..N_ITERS {
+        let res = forward(&layers, &xs, &mask)?;
+        std::hint::black_box(res);
+    }
+    let elapsed = start.elapsed() / N_ITERS as u32;
+    let allocs = (ALLOCS.load(Ordering::Relaxed) - allocs) / N_ITERS;
+    let bytes = (BYTES.load(Ordering::Relaxed) - bytes) / N_ITERS;
+    Ok((elapsed, allocs, bytes))
+}
+
+fn main() -> Result<()> {
+    let layers = (0..N_LAYER)
+        .map(|_| Layer::new())
+        .collect::<Result<Vec<_>>>()?;
+    let (eager, eager_allocs, eager_bytes) = bench(&layers, &Device::Cpu)?;
+    println!("eager: {eager:?}, {eager_allocs} allocations, {eager_bytes} bytes");
+    let (lazy, lazy_allocs, lazy_bytes) = bench(&layers, &Device::Lazy)?;
+    println!("lazy:  {lazy:?}, {lazy_allocs} allocations, {lazy_bytes} bytes");
+    println!(
+        "speedup: {:.2}x, bytes: {:.2}x fewer",
+        eager.as_secs_f64() / lazy.as_secs_f64(),
+        eager_bytes as f64 / lazy_bytes as f64
+    );
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::op::{BinaryOpT, CmpOp, ReduceOp, UnaryOpT};
+use crate::op::{BinaryOpT, CmpOp, ReduceOp, ScanOp, UnaryOpT};
 use crate::{CpuStorage, DType, Layout, Result, Shape};
 
 pub trait BackendStorage: Sized {
@@ -19,6 +19,12 @@ pub trait BackendStorage: Sized {
 
     fn reduce_op(&self, _: ReduceOp, _: &Layout, _: &[usize]) -> Result<Self>;
 
+    fn scan(&self, _: ScanOp, _: &Layout, _: usize) -> Result<Self>;
+
+    // Returns the u32 indexes that sort the values along the given dimension, in ascending order
+    // if the last argument is true and in descending order otherwise.
+    fn arg_sort(&self, _: &Layout, _: usize, _: bool) -> Result<Self>;
+
     fn cmp(&self, _: CmpOp, _: &Self, _: &Layout, _: &Layout) -> Result<Self>;
 
     fn to_dtype(&self, _: &Layout, _: DType) -> Result<Self>;
@@ -27,6 +33,24 @@ pub trait BackendStorage: Sized {
 
     fn binary_impl<B: BinaryOpT>(&self, _: &Self, _: &Layout, _: &Layout) -> Result<Self>;
 
+    // In place variants of the unary and binary ops, the result is written to self using the
+    // (lhs) layout.
+    fn unary_inplace<B: UnaryOpT>(&mut self, _: &Layout) -> Result<()>;
+
+    fn binary_inplace<B: BinaryOpT>(&mut self, _: &Self, _: &Layout, _: &Layout) -> Result<()>;
+
+    // Writes the slices of the source (last two arguments) along the given dimension at the
+    // positions given by the ids, this is the in place counterpart of index_add.
+    fn index_put(
+        &mut self,
+        _: &Layout,
+        _: &Self,
+        _: &Layout,
+        _: &Self,
+        _: &Layout,
+        _: usize,
+    ) -> Result<()>;
+
     fn where_cond(&self, _: &Layout, _: &Self, _: &Layout, _: &Self, _: &Layout) -> Result<Self>;
 
     fn conv1d(
@@ -45,9 +69,41 @@ pub trait BackendStorage: Sized {
         _params: &crate::conv::ParamsConv2D,
     ) -> Result<Self>;
 
-    fn avg_pool2d(&self, _: &Layout, _: (usize, usize), _: (usize, usize)) -> Result<Self>;
-    fn max_pool2d(&self, _: &Layout, _: (usize, usize), _: (usize, usize)) -> Result<Self>;
+    fn conv3d(
+        &self,
+        _l: &Layout,
+        _kernel: &Self,
+        _kernel_l: &Layout,
+        _params: &crate::conv::ParamsConv3D,
+    ) -> Result<Self>;
+
+    fn conv_transpose1d(
+        &self,
+        _l: &Layout,
+        _kernel: &Self,
+        _kernel_l: &Layout,
+        _params: &crate::conv::ParamsConvTranspose1D,
+    ) -> Result<Self>;
+
+    fn conv_transpose2d(
+        &self,
+        _l: &Layout,
+        _kernel: &Self,
+        _kernel_l: &Layout,
+        _params: &crate::conv::ParamsConvTranspose2D,
+    ) -> Result<Self>;
+
+    fn avg_pool(&self, _: &Layout, _: &crate::pool::ParamsPool) -> Result<Self>;
+    fn max_pool(&self, _: &Layout, _: &crate::pool::ParamsPool) -> Result<Self>;
     fn upsample_nearest2d(&self, _: &Layout, _: usize, _: usize) -> Result<Self>;
+    fn interpolate2d(
+        &self,
+        _: &Layout,
+        _: usize,
+        _: usize,
+        _: crate::InterpolateMode,
+    ) -> Result<Self>;
+    fn fft(&self, _: &Layout, _: crate::fft::FftKind) -> Result<Self>;
 
     fn gather(&self, _: &Layout, _: &Self, _: &Layout, _: usize) -> Result<Self>;
     fn scatter_add(
@@ -79,6 +135,10 @@ pub trait BackendStorage: Sized {
     ) -> Result<Self>;
 
     fn copy_strided_src(&self, _: &mut Self, _: usize, _: &Layout) -> Result<()>;
+
+    // Similar to copy_strided_src but the destination is also strided, the dst and src layouts
+    // must have the same shape.
+    fn copy_strided(&self, _: &mut Self, _: &Layout, _: &Layout) -> Result<()>;
 }
 
 pub trait BackendDevice: Sized + std::fmt::Debug + Clone {
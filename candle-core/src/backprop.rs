@@ -1,4 +1,5 @@
-use crate::op::{BinaryOp, Op, ReduceOp, UnaryOp};
+use crate::fft::FftKind;
+use crate::op::{BinaryOp, Op, ReduceOp, ScanOp, UnaryOp};
 use crate::{Error, Result, Tensor, TensorId};
 use std::collections::HashMap;
 
@@ -60,6 +61,21 @@ impl Tensor {
                         kernel: rhs,
                         ..
                     }
+                    | Op::Conv3D {
+                        arg: lhs,
+                        kernel: rhs,
+                        ..
+                    }
+                    | Op::ConvTranspose1D {
+                        arg: lhs,
+                        kernel: rhs,
+                        ..
+                    }
+                    | Op::ConvTranspose2D {
+                        arg: lhs,
+                        kernel: rhs,
+                        ..
+                    }
                     | Op::CustomOp2(lhs, rhs, _)
                     | Op::Binary(lhs, rhs, _)
                     | Op::Gather(lhs, rhs, _)
@@ -85,17 +101,23 @@ impl Tensor {
                             nodes
                         }
                     }
+                    // Comparisons have no gradient, e.g. the predicate of a where_cond.
+                    Op::Cmp(_, _) => nodes,
                     Op::Reshape(node)
                     | Op::UpsampleNearest2D(node)
-                    | Op::AvgPool2D { arg: node, .. }
-                    | Op::MaxPool2D { arg: node, .. }
+                    | Op::Interpolate2D { arg: node, .. }
+                    | Op::Fft { arg: node, .. }
+                    | Op::AvgPool { arg: node, .. }
+                    | Op::MaxPool { arg: node, .. }
                     | Op::Copy(node)
                     | Op::Broadcast(node)
-                    | Op::Cmp(node, _)
                     | Op::Reduce(node, _, _)
+                    | Op::Scan(node, _, _)
                     | Op::ToDType(node)
                     | Op::ToDevice(node)
                     | Op::Transpose(node, _, _)
+                    | Op::Permute(node, _)
+                    | Op::AsStrided(node)
                     | Op::Narrow(node, _, _, _)
                     | Op::Unary(node, _)
                     | Op::Elu(node, _)
@@ -128,6 +150,7 @@ impl Tensor {
                 continue;
             }
             let grad = grads.remove(node).unwrap();
+            node.check_saved_versions()?;
             // TODO: We should perform all these operations in place (or at least not track the
             // whole graph). The only drawback would be if we wanted to support grad of grad but
             // this is out of scope.
@@ -161,6 +184,51 @@ impl Tensor {
                         let rhs_sum_grad = grads.or_insert(rhs)?;
                         *rhs_sum_grad = rhs_sum_grad.sub(&rhs_grad)?;
                     }
+                    Op::Binary(lhs, rhs, op @ (BinaryOp::Maximum | BinaryOp::Minimum)) => {
+                        // The gradient goes to the selected value and is split evenly on ties.
+                        let selected = match op {
+                            BinaryOp::Maximum => lhs.gt(rhs)?,
+                            _ => lhs.lt(rhs)?,
+                        };
+                        let selected = selected.to_dtype(grad.dtype())?;
+                        let ties = (lhs.eq(rhs)?.to_dtype(grad.dtype())? * 0.5)?;
+                        let lhs_mask = selected.add(&ties)?;
+                        let lhs_grad = grad.mul(&lhs_mask)?;
+                        let lhs_sum_grad = grads.or_insert(lhs)?;
+                        *lhs_sum_grad = lhs_sum_grad.add(&lhs_grad)?;
+                        let rhs_grad = grad.mul(&lhs_mask.affine(-1., 1.)?)?;
+                        let rhs_sum_grad = grads.or_insert(rhs)?;
+                        *rhs_sum_grad = rhs_sum_grad.add(&rhs_grad)?;
+                    }
+                    Op::Binary(lhs, rhs, BinaryOp::Pow) => {
+                        let lhs_grad = grad.mul(&rhs.mul(&lhs.pow(&rhs.affine(1., -1.)?)?)?)?;
+                        let lhs_sum_grad = grads.or_insert(lhs)?;
+                        *lhs_sum_grad = lhs_sum_grad.add(&lhs_grad)?;
+                        // d(x^y)/dy = x^y.ln(x), which is taken to be 0 for x = 0.
+                        let zeros = lhs.zeros_like()?;
+                        let log_lhs = lhs.eq(&zeros)?.where_cond(&zeros, &lhs.log()?)?;
+                        let rhs_grad = grad.mul(node)?.mul(&log_lhs)?;
+                        let rhs_sum_grad = grads.or_insert(rhs)?;
+                        *rhs_sum_grad = rhs_sum_grad.add(&rhs_grad)?;
+                    }
+                    Op::Binary(lhs, rhs, BinaryOp::Atan2) => {
+                        // d(atan2(y, x))/dy = x / (x^2 + y^2), d(atan2(y, x))/dx = -y / (x^2 + y^2)
+                        let grad = grad.div(&lhs.sqr()?.add(&rhs.sqr()?)?)?;
+                        let lhs_grad = grad.mul(rhs)?;
+                        let lhs_sum_grad = grads.or_insert(lhs)?;
+                        *lhs_sum_grad = lhs_sum_grad.add(&lhs_grad)?;
+                        let rhs_grad = grad.mul(lhs)?;
+                        let rhs_sum_grad = grads.or_insert(rhs)?;
+                        *rhs_sum_grad = rhs_sum_grad.sub(&rhs_grad)?;
+                    }
+                    Op::Binary(lhs, rhs, BinaryOp::Rem) => {
+                        // rem(x, y) = x - y.floor(x / y) and floor(x / y) = (x - rem(x, y)) / y.
+                        let lhs_sum_grad = grads.or_insert(lhs)?;
+                        *lhs_sum_grad = lhs_sum_grad.add(&grad)?;
+                        let rhs_grad = grad.mul(&lhs.sub(node)?.div(rhs)?)?;
+                        let rhs_sum_grad = grads.or_insert(rhs)?;
+                        *rhs_sum_grad = rhs_sum_grad.sub(&rhs_grad)?;
+                    }
                     Op::WhereCond(pred, t, f) => {
                         let zeros = grad.zeros_like()?;
                         let t_sum_grad = grads.or_insert(t)?;
@@ -170,13 +238,343 @@ impl Tensor {
                         let f_grad = pred.where_cond(&zeros, &grad)?;
                         *f_sum_grad = f_sum_grad.add(&f_grad)?;
                     }
-                    Op::Conv1D { .. } => Err(Error::BackwardNotSupported { op: "conv1d" })?,
-                    Op::Conv2D { .. } => Err(Error::BackwardNotSupported { op: "conv2d" })?,
-                    Op::AvgPool2D { .. } => Err(Error::BackwardNotSupported { op: "avg-pool2d" })?,
-                    Op::MaxPool2D { .. } => Err(Error::BackwardNotSupported { op: "max-pool2d" })?,
-                    Op::UpsampleNearest2D { .. } => Err(Error::BackwardNotSupported {
-                        op: "upsample-nearest2d",
-                    })?,
+                    Op::Conv1D {
+                        arg,
+                        kernel,
+                        padding,
+                        stride,
+                        dilation,
+                        groups,
+                    } => {
+                        // The gradient with respect to the input is a transposed convolution,
+                        // the output padding recovers the input elements that were not used
+                        // because of the stride. Groups are handled one at a time.
+                        let (_, c_in, l_in) = arg.dims3()?;
+                        let (c_out, _, k_size) = kernel.dims3()?;
+                        let l_out = grad.dim(2)?;
+                        let output_padding =
+                            l_in + 2 * padding - (l_out - 1) * stride - dilation * (k_size - 1) - 1;
+                        let (c_in_g, c_out_g) = (c_in / groups, c_out / groups);
+                        let mut arg_grads = Vec::with_capacity(*groups);
+                        let mut kernel_grads = Vec::with_capacity(*groups);
+                        for g in 0..*groups {
+                            let grad = grad.narrow(1, g * c_out_g, c_out_g)?;
+                            let kernel = kernel.narrow(0, g * c_out_g, c_out_g)?;
+                            let arg = arg.narrow(1, g * c_in_g, c_in_g)?;
+                            let arg_grad = grad.conv_transpose1d(
+                                &kernel,
+                                *padding,
+                                output_padding,
+                                *stride,
+                                *dilation,
+                            )?;
+                            arg_grads.push(arg_grad);
+                            // For the kernel, the batch dimension is contracted and the roles of
+                            // the stride and dilation are swapped.
+                            let kernel_grad = arg.transpose(0, 1)?.conv1d(
+                                &grad.transpose(0, 1)?,
+                                *padding,
+                                *dilation,
+                                *stride,
+                                1,
+                            )?;
+                            let kernel_grad = kernel_grad.narrow(2, 0, k_size)?.transpose(0, 1)?;
+                            kernel_grads.push(kernel_grad);
+                        }
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&arg_grads, 1)?)?;
+                        let sum_grad = grads.or_insert(kernel)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&kernel_grads, 0)?)?;
+                    }
+                    Op::Conv2D {
+                        arg,
+                        kernel,
+                        padding,
+                        stride,
+                        dilation,
+                        groups,
+                    } => {
+                        let (_, c_in, i_h, i_w) = arg.dims4()?;
+                        let (c_out, _, k_h, k_w) = kernel.dims4()?;
+                        let (_, _, o_h, o_w) = grad.dims4()?;
+                        // The output padding can only be set for both dimensions at once so
+                        // the largest one is used and the result is narrowed afterwards.
+                        let output_padding = usize::max(
+                            i_h + 2 * padding - (o_h - 1) * stride - dilation * (k_h - 1) - 1,
+                            i_w + 2 * padding - (o_w - 1) * stride - dilation * (k_w - 1) - 1,
+                        );
+                        let (c_in_g, c_out_g) = (c_in / groups, c_out / groups);
+                        let mut arg_grads = Vec::with_capacity(*groups);
+                        let mut kernel_grads = Vec::with_capacity(*groups);
+                        for g in 0..*groups {
+                            let grad = grad.narrow(1, g * c_out_g, c_out_g)?;
+                            let kernel = kernel.narrow(0, g * c_out_g, c_out_g)?;
+                            let arg = arg.narrow(1, g * c_in_g, c_in_g)?;
+                            let arg_grad = grad
+                                .conv_transpose2d(
+                                    &kernel,
+                                    *padding,
+                                    output_padding,
+                                    *stride,
+                                    *dilation,
+                                )?
+                                .narrow(2, 0, i_h)?
+                                .narrow(3, 0, i_w)?;
+                            arg_grads.push(arg_grad);
+                            let kernel_grad = arg.transpose(0, 1)?.conv2d(
+                                &grad.transpose(0, 1)?,
+                                *padding,
+                                *dilation,
+                                *stride,
+                                1,
+                            )?;
+                            let kernel_grad = kernel_grad
+                                .narrow(2, 0, k_h)?
+                                .narrow(3, 0, k_w)?
+                                .transpose(0, 1)?;
+                            kernel_grads.push(kernel_grad);
+                        }
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&arg_grads, 1)?)?;
+                        let sum_grad = grads.or_insert(kernel)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&kernel_grads, 0)?)?;
+                    }
+                    Op::ConvTranspose1D {
+                        arg,
+                        kernel,
+                        padding,
+                        output_padding: _,
+                        stride,
+                        dilation,
+                    } => {
+                        // The transposed convolution is the gradient of the convolution so its
+                        // own gradient with respect to the input is a plain convolution.
+                        let l_in = arg.dim(2)?;
+                        let arg_grad = grad.conv1d(kernel, *padding, *stride, *dilation, 1)?;
+                        let arg_grad = arg_grad.narrow(2, 0, l_in)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+
+                        // For the kernel, the roles of stride and dilation are swapped and the
+                        // batch dimension is the one being contracted.
+                        let k_size = kernel.dim(2)?;
+                        let kernel_grad = grad.transpose(0, 1)?.conv1d(
+                            &arg.transpose(0, 1)?,
+                            *padding,
+                            *dilation,
+                            *stride,
+                            1,
+                        )?;
+                        let kernel_grad = kernel_grad.narrow(2, 0, k_size)?.transpose(0, 1)?;
+                        let sum_grad = grads.or_insert(kernel)?;
+                        *sum_grad = sum_grad.add(&kernel_grad)?;
+                    }
+                    Op::ConvTranspose2D {
+                        arg,
+                        kernel,
+                        padding,
+                        output_padding: _,
+                        stride,
+                        dilation,
+                    } => {
+                        let (_, _, i_h, i_w) = arg.dims4()?;
+                        let arg_grad = grad.conv2d(kernel, *padding, *stride, *dilation, 1)?;
+                        let arg_grad = arg_grad.narrow(2, 0, i_h)?.narrow(3, 0, i_w)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+
+                        let (_, _, k_h, k_w) = kernel.dims4()?;
+                        let kernel_grad = grad.transpose(0, 1)?.conv2d(
+                            &arg.transpose(0, 1)?,
+                            *padding,
+                            *dilation,
+                            *stride,
+                            1,
+                        )?;
+                        let kernel_grad = kernel_grad
+                            .narrow(2, 0, k_h)?
+                            .narrow(3, 0, k_w)?
+                            .transpose(0, 1)?;
+                        let sum_grad = grads.or_insert(kernel)?;
+                        *sum_grad = sum_grad.add(&kernel_grad)?;
+                    }
+                    Op::Conv3D {
+                        arg,
+                        kernel,
+                        padding,
+                        stride,
+                        dilation,
+                        groups,
+                    } => {
+                        // There is no transposed 3d convolution, instead the gradient with
+                        // respect to the input is a stride 1 convolution of the spread out
+                        // gradient with the flipped kernel.
+                        let (_, c_in, i_d, i_h, i_w) = arg.dims5()?;
+                        let (c_out, _, k_d, k_h, k_w) = kernel.dims5()?;
+                        let (c_in_g, c_out_g) = (c_in / groups, c_out / groups);
+                        let mut arg_grads = Vec::with_capacity(*groups);
+                        let mut kernel_grads = Vec::with_capacity(*groups);
+                        for g in 0..*groups {
+                            let grad = grad.narrow(1, g * c_out_g, c_out_g)?;
+                            let kernel = kernel.narrow(0, g * c_out_g, c_out_g)?;
+                            let arg = arg.narrow(1, g * c_in_g, c_in_g)?;
+                            let mut spread = grad.clone();
+                            for (dim, i, k) in [(2, i_d, k_d), (3, i_h, k_h), (4, i_w, k_w)] {
+                                spread = conv_grad_spread(
+                                    &spread, dim, i, k, *padding, *stride, *dilation,
+                                )?;
+                            }
+                            let flipped = kernel.flip((2, 3, 4))?.transpose(0, 1)?;
+                            let arg_grad = spread.conv3d(&flipped, 0, 1, *dilation, 1)?;
+                            arg_grads.push(arg_grad);
+                            let kernel_grad = arg.transpose(0, 1)?.conv3d(
+                                &grad.transpose(0, 1)?,
+                                *padding,
+                                *dilation,
+                                *stride,
+                                1,
+                            )?;
+                            let kernel_grad = kernel_grad
+                                .narrow(2, 0, k_d)?
+                                .narrow(3, 0, k_h)?
+                                .narrow(4, 0, k_w)?
+                                .transpose(0, 1)?;
+                            kernel_grads.push(kernel_grad);
+                        }
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&arg_grads, 1)?)?;
+                        let sum_grad = grads.or_insert(kernel)?;
+                        *sum_grad = sum_grad.add(&Tensor::cat(&kernel_grads, 0)?)?;
+                    }
+                    Op::AvgPool { arg, params } => {
+                        // Each output element spreads its gradient evenly over its window, the
+                        // divisor can vary between windows with ceil mode.
+                        let mut grad = grad.clone();
+                        for (i, divisor) in pool_divisors(params, arg.dims())?.iter().enumerate() {
+                            let mut shape = vec![1; grad.rank()];
+                            shape[i + 2] = divisor.len();
+                            let divisor = Tensor::new(divisor.as_slice(), grad.device())?
+                                .to_dtype(grad.dtype())?
+                                .reshape(shape)?;
+                            grad = grad.broadcast_div(&divisor)?;
+                        }
+                        let padded_dims = pool_padded_dims(params, arg.dims(), node.dims());
+                        let mut arg_grad =
+                            Tensor::zeros(padded_dims.as_slice(), grad.dtype(), grad.device())?;
+                        for offset in pool_offsets(&params.kernel_size) {
+                            let g = pool_window_grad(&grad, &padded_dims, &offset, &params.stride)?;
+                            arg_grad = arg_grad.add(&g)?;
+                        }
+                        let arg_grad = pool_unpad(&arg_grad, params, arg.dims())?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+                    }
+                    Op::MaxPool { arg, params } => {
+                        // The gradient goes to the first maximum of each window in the order in
+                        // which the forward pass scans it, the padding never holds the maximum.
+                        let padded_dims = pool_padded_dims(params, arg.dims(), node.dims());
+                        let mut padded = arg.clone();
+                        for (i, &padding) in params.padding.iter().enumerate() {
+                            let dim = i + 2;
+                            let end = padded_dims[dim] - arg.dims()[dim] - padding;
+                            padded = pad_with_neg_infinity(&padded, dim, padding, end)?;
+                        }
+                        let mut taken = node.zeros_like()?;
+                        let mut arg_grad = padded.zeros_like()?;
+                        for offset in pool_offsets(&params.kernel_size) {
+                            let window =
+                                pool_window(&padded, &offset, &params.stride, node.dims())?;
+                            let is_max = window.eq(node)?.to_dtype(grad.dtype())?;
+                            let mask = is_max.mul(&taken.affine(-1., 1.)?)?;
+                            taken = taken.add(&mask)?;
+                            let g = grad.mul(&mask)?;
+                            let g = pool_window_grad(&g, &padded_dims, &offset, &params.stride)?;
+                            arg_grad = arg_grad.add(&g)?;
+                        }
+                        let arg_grad = pool_unpad(&arg_grad, params, arg.dims())?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+                    }
+                    Op::UpsampleNearest2D(arg) => {
+                        // Sum the gradients of all the output elements that were copied from
+                        // the same input element, the indexes match the forward pass.
+                        let (b_sz, c, src_h, src_w) = arg.dims4()?;
+                        let (_, _, dst_h, dst_w) = node.dims4()?;
+                        let idxs = |src: usize, dst: usize| {
+                            let scale = src as f64 / dst as f64;
+                            let idxs = (0..dst)
+                                .map(|i| usize::min(src - 1, (i as f64 * scale) as usize) as u32)
+                                .collect::<Vec<_>>();
+                            Tensor::new(idxs.as_slice(), arg.device())
+                        };
+                        let zeros =
+                            Tensor::zeros((b_sz, c, src_h, dst_w), grad.dtype(), arg.device())?;
+                        let arg_grad = zeros.index_add(&idxs(src_h, dst_h)?, &grad, 2)?;
+                        let zeros = arg.zeros_like()?;
+                        let arg_grad = zeros.index_add(&idxs(src_w, dst_w)?, &arg_grad, 3)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+                    }
+                    Op::Interpolate2D { arg, mode } => {
+                        // The interpolation is linear and separable, each plane of the output is
+                        // a_h . x . a_w^t where a_h and a_w hold the interpolation weights along
+                        // each dimension so the gradient is a_h^t . grad . a_w.
+                        let (b_sz, c, src_h, src_w) = arg.dims4()?;
+                        let (_, _, dst_h, dst_w) = node.dims4()?;
+                        let weights = |src: usize, dst: usize| {
+                            crate::interpolate::taps_matrix(src, dst, *mode, arg.device())?
+                                .to_dtype(grad.dtype())
+                        };
+                        let arg_grad = grad
+                            .reshape((b_sz * c * dst_h, dst_w))?
+                            .matmul(&weights(src_w, dst_w)?)?
+                            .reshape((b_sz * c, dst_h, src_w))?
+                            .transpose(1, 2)?
+                            .reshape((b_sz * c * src_w, dst_h))?
+                            .matmul(&weights(src_h, dst_h)?)?
+                            .reshape((b_sz, c, src_w, src_h))?
+                            .transpose(2, 3)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+                    }
+                    Op::Fft { arg, kind } => {
+                        // The dft matrix F is symmetric so the gradient of y = F.x is conj(F).g,
+                        // for the real transforms only the real inputs, resp. the non-redundant
+                        // frequencies, contribute.
+                        let rank = arg.rank();
+                        let arg_grad = match *kind {
+                            FftKind::Fft => {
+                                let n = arg.dims()[rank - 2];
+                                grad.fft_op(FftKind::Ifft)?.affine(n as f64, 0.)?
+                            }
+                            FftKind::Ifft => {
+                                let n = arg.dims()[rank - 2];
+                                grad.fft_op(FftKind::Fft)?.affine(1. / n as f64, 0.)?
+                            }
+                            FftKind::Rfft => {
+                                let n = arg.dims()[rank - 1];
+                                let m = grad.dims()[rank - 1];
+                                grad.pad_with_zeros(rank - 1, 0, n - m)?
+                                    .fft_op(FftKind::Ifft)?
+                                    .narrow(rank, 0, 1)?
+                                    .squeeze(rank)?
+                                    .affine(n as f64, 0.)?
+                            }
+                            FftKind::Irfft { n } => {
+                                // The frequencies other than dc and nyquist appear twice in the
+                                // hermitian spectrum.
+                                let m = n / 2 + 1;
+                                let w: Vec<f64> = (0..m)
+                                    .map(|k| if k == 0 || 2 * k == n { 1. } else { 2. } / n as f64)
+                                    .collect();
+                                let w = Tensor::from_vec(w, (m, 1), grad.device())?
+                                    .to_dtype(grad.dtype())?;
+                                grad.fft_op(FftKind::Rfft)?.broadcast_mul(&w)?
+                            }
+                        };
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?;
+                    }
                     Op::Gather(arg, indexes, dim) => {
                         let sum_grad = grads.or_insert(arg)?;
                         *sum_grad = sum_grad.scatter_add(indexes, &grad, *dim)?;
@@ -251,6 +649,18 @@ impl Tensor {
                         let sum_grad = grads.or_insert(arg)?;
                         *sum_grad = sum_grad.add(&grad)?;
                     }
+                    Op::Reduce(arg, ReduceOp::Prod, reduced_dims) => {
+                        // d(prod)/dx_i is the product of the other elements. `reduced_dims` is the
+                        // shape of the result with a size of 1 for the reduced dimensions, also
+                        // including the other dimensions of size 1 does not change the product.
+                        let dims: Vec<usize> = (0..reduced_dims.len())
+                            .filter(|&d| reduced_dims[d] == 1)
+                            .collect();
+                        let grad = broadcast_back(arg, &grad, reduced_dims)?;
+                        let grad = grad.mul(&prod_of_others(arg, &dims)?)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&grad)?;
+                    }
                     Op::Cmp(_args, _) => {}
                     Op::Reduce(arg, ReduceOp::Max, reduced_dims) => {
                         let node = broadcast_back(arg, node, reduced_dims)?;
@@ -336,6 +746,36 @@ impl Tensor {
                         let sum_grad = grads.or_insert(arg)?;
                         *sum_grad = sum_grad.add(&arg_grad)?
                     }
+                    &Op::Scan(ref arg, ScanOp::Sum, dim) => {
+                        let arg_grad = reverse_cumsum(&grad, dim)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?
+                    }
+                    &Op::Scan(ref arg, ScanOp::Prod, dim) => {
+                        let arg_grad = cumprod_backward(arg, &grad, dim)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?
+                    }
+                    &Op::Scan(ref arg, ScanOp::LogSumExp, dim) => {
+                        // d node[j] / d arg[i] = exp(arg[i] - node[j]) for i <= j. The sum over j
+                        // is computed in log space, separately for the positive and negative
+                        // parts of the gradient, so that only exp(arg[i] - node[j]) <= 1 terms
+                        // get exponentiated.
+                        let reverse_sum = |grad: Tensor| -> Result<Tensor> {
+                            grad.log()?
+                                .sub(node)?
+                                .flip(dim)?
+                                .logcumsumexp(dim)?
+                                .flip(dim)?
+                                .add(arg)?
+                                .exp()
+                        };
+                        let arg_grad =
+                            reverse_sum(grad.relu()?)?.sub(&reverse_sum(grad.neg()?.relu()?)?)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?
+                    }
+                    Op::Scan(_, ScanOp::ArgMax, _) => {}
                     Op::Reduce(_, ReduceOp::ArgMin, _) => {}
                     Op::Reduce(_, ReduceOp::ArgMax, _) => {}
                     Op::Reshape(arg) => {
@@ -343,7 +783,62 @@ impl Tensor {
                         let sum_grad = grads.or_insert(arg)?;
                         *sum_grad = sum_grad.add(&arg_grad)?
                     }
-                    Op::Unary(_, UnaryOp::Gelu) => Err(Error::BackwardNotSupported { op: "gelu" })?,
+                    Op::Unary(arg, UnaryOp::Gelu) => {
+                        // gelu(x) = 0.5 x (1 + tanh(c (x + 0.044715 x^3))) with c = sqrt(2 / pi)
+                        let c = (2. / std::f64::consts::PI).sqrt();
+                        let cube = arg.sqr()?.mul(arg)?;
+                        let tanh = (arg + (cube * 0.044715)?)?.affine(c, 0.)?.tanh()?;
+                        let dtanh = tanh.sqr()?.affine(-1., 1.)?;
+                        let dinner = arg.sqr()?.affine(3. * 0.044715 * c, c)?;
+                        let gelu_grad = (tanh.affine(0.5, 0.5)?
+                            + (arg * dtanh)?.mul(&dinner)?.affine(0.5, 0.)?)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * gelu_grad)?)?
+                    }
+                    Op::Unary(arg, UnaryOp::GeluErf) => {
+                        // d/dx x.cdf(x) = cdf(x) + x.pdf(x)
+                        let cdf = arg
+                            .affine(std::f64::consts::FRAC_1_SQRT_2, 0.)?
+                            .erf()?
+                            .affine(0.5, 0.5)?;
+                        let pdf = arg
+                            .sqr()?
+                            .affine(-0.5, 0.)?
+                            .exp()?
+                            .affine(1. / (2. * std::f64::consts::PI).sqrt(), 0.)?;
+                        let gelu_grad = (cdf + arg.mul(&pdf)?)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * gelu_grad)?)?
+                    }
+                    Op::Unary(arg, UnaryOp::Tanh) => {
+                        let tanh_grad = node.sqr()?.affine(-1., 1.)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * tanh_grad)?)?
+                    }
+                    Op::Unary(arg, UnaryOp::Erf) => {
+                        // d/dx erf(x) = 2/sqrt(pi) e^(-x^2)
+                        let erf_grad = arg
+                            .sqr()?
+                            .neg()?
+                            .exp()?
+                            .affine(2. / std::f64::consts::PI.sqrt(), 0.)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * erf_grad)?)?
+                    }
+                    Op::Unary(_, UnaryOp::Floor | UnaryOp::Ceil | UnaryOp::Round) => {}
+                    Op::Unary(arg, UnaryOp::Sigmoid) => {
+                        let sigmoid_grad = node.mul(&node.affine(-1., 1.)?)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * sigmoid_grad)?)?
+                    }
+                    Op::Unary(arg, UnaryOp::Softplus) => {
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad * arg.sigmoid()?)?)?
+                    }
+                    Op::Unary(arg, UnaryOp::Log1p) => {
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&(&grad / arg.affine(1., 1.)?)?)?
+                    }
                     Op::Unary(arg, UnaryOp::Relu) => {
                         let sum_grad = grads.or_insert(arg)?;
                         let relu_grad = arg.ge(&arg.zeros_like()?)?.to_dtype(arg.dtype())?;
@@ -403,6 +898,33 @@ impl Tensor {
                         let sum_grad = grads.or_insert(arg)?;
                         *sum_grad = sum_grad.add(&arg_grad)?
                     }
+                    Op::Permute(arg, dims) => {
+                        let mut inv_dims = vec![0; dims.len()];
+                        for (i, &dim) in dims.iter().enumerate() {
+                            inv_dims[dim] = i
+                        }
+                        let arg_grad = grad.permute(inv_dims)?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?
+                    }
+                    Op::AsStrided(arg) => {
+                        // The argument is contiguous, each element of the result is read from
+                        // some position in it and the gradients are accumulated on these
+                        // positions as they may overlap.
+                        let arg_offset = arg.layout().start_offset();
+                        let positions: Vec<u32> = node
+                            .layout()
+                            .strided_index()
+                            .map(|i| (i - arg_offset) as u32)
+                            .collect();
+                        let positions = Tensor::new(positions.as_slice(), grad.device())?;
+                        let arg_grad =
+                            Tensor::zeros(arg.elem_count(), grad.dtype(), grad.device())?
+                                .index_add(&positions, &grad.flatten_all()?, 0)?
+                                .reshape(arg.dims())?;
+                        let sum_grad = grads.or_insert(arg)?;
+                        *sum_grad = sum_grad.add(&arg_grad)?
+                    }
                 };
             }
         }
@@ -410,6 +932,240 @@ impl Tensor {
     }
 }
 
+// Cumulative sum starting from the end of dimension `dim`.
+fn reverse_cumsum(t: &Tensor, dim: usize) -> Result<Tensor> {
+    t.flip(dim)?.cumsum(dim)?.flip(dim)
+}
+
+// The product of the elements before each position along `dim`, the first element is 1.
+fn exclusive_cumprod(t: &Tensor, dim: usize) -> Result<Tensor> {
+    let n = t.dim(dim)?;
+    let ones = t.narrow(dim, 0, 1)?.ones_like()?;
+    Tensor::cat(&[&ones, &t.narrow(dim, 0, n - 1)?], dim)?.cumprod(dim)
+}
+
+// For each element, the product of the other elements over the `dims` dimensions. This is the
+// product of the exclusive cumulative products in both directions over the flattened `dims`,
+// which unlike `prod / x` is also defined when some elements are zero.
+fn prod_of_others(t: &Tensor, dims: &[usize]) -> Result<Tensor> {
+    if dims.is_empty() || t.elem_count() == 0 {
+        return t.ones_like();
+    }
+    let mut perm: Vec<usize> = (0..t.rank()).filter(|d| !dims.contains(d)).collect();
+    let kept = perm.len();
+    perm.extend_from_slice(dims);
+    let t = t.permute(perm.as_slice())?;
+    let permuted_dims = t.dims().to_vec();
+    let t = t.flatten_from(kept)?;
+    let before = exclusive_cumprod(&t, kept)?;
+    let after = exclusive_cumprod(&t.flip(kept)?, kept)?.flip(kept)?;
+    let mut inv_perm = vec![0; perm.len()];
+    for (i, &p) in perm.iter().enumerate() {
+        inv_perm[p] = i
+    }
+    before
+        .mul(&after)?
+        .reshape(permuted_dims)?
+        .permute(inv_perm)
+}
+
+// The gradient of `cumprod` along `dim`. The gradient for `x_i` is `prod_{k < i} x_k` times
+// `sum_{j >= i} grad_j prod_{i < k <= j} x_k`. The inner products are computed as cumulative
+// products over a `(n, n)` matrix rather than by dividing the result by `x_i` so that zeros in
+// the input are supported.
+fn cumprod_backward(arg: &Tensor, grad: &Tensor, dim: usize) -> Result<Tensor> {
+    let n = arg.dim(dim)?;
+    if n == 0 {
+        return arg.zeros_like();
+    }
+    let last = arg.rank() - 1;
+    let (arg, grad) = (arg.transpose(dim, last)?, grad.transpose(dim, last)?);
+    let mut dims = arg.dims().to_vec();
+    dims.push(n);
+    let idx = Tensor::arange(0u32, n as u32, arg.device())?;
+    let rows = idx.reshape((n, 1))?.broadcast_as((n, n))?;
+    let cols = idx.reshape((1, n))?.broadcast_as((n, n))?;
+    // xs[.., i, j] is x_j for j > i and 1 otherwise.
+    let xs = arg.unsqueeze(last)?.broadcast_as(dims.as_slice())?;
+    let xs = cols
+        .gt(&rows)?
+        .broadcast_as(dims.as_slice())?
+        .where_cond(&xs, &xs.ones_like()?)?;
+    // prods[.., i, j] is prod_{i < k <= j} x_k for j >= i and 0 otherwise.
+    let prods = cols
+        .ge(&rows)?
+        .broadcast_as(dims.as_slice())?
+        .where_cond(&xs.cumprod(last + 1)?, &xs.zeros_like()?)?;
+    let sums = prods.broadcast_mul(&grad.unsqueeze(last)?)?.sum(last + 1)?;
+    exclusive_cumprod(&arg, last)?
+        .mul(&sums)?
+        .transpose(dim, last)
+}
+
+// The elements `start + i * stride` for `i` in `0..len` along dimension `dim`.
+fn strided_narrow(
+    t: &Tensor,
+    dim: usize,
+    start: usize,
+    stride: usize,
+    len: usize,
+) -> Result<Tensor> {
+    let t = t.narrow(dim, start, (len - 1) * stride + 1)?;
+    if stride == 1 {
+        return Ok(t);
+    }
+    let t = t.pad_with_zeros(dim, 0, stride - 1)?;
+    let mut dims = t.dims().to_vec();
+    dims[dim] = len;
+    dims.insert(dim + 1, stride);
+    t.reshape(dims)?.narrow(dim + 1, 0, 1)?.squeeze(dim + 1)
+}
+
+// The reverse of `strided_narrow`, the elements of `t` are spread over a dimension of size `size`
+// and the remaining positions are filled with zeros.
+fn strided_pad(t: &Tensor, dim: usize, start: usize, stride: usize, size: usize) -> Result<Tensor> {
+    let len = t.dim(dim)?;
+    let used = (len - 1) * stride + 1;
+    let t = if stride == 1 {
+        t.clone()
+    } else {
+        let t = t
+            .unsqueeze(dim + 1)?
+            .pad_with_zeros(dim + 1, 0, stride - 1)?;
+        let mut dims = t.dims().to_vec();
+        dims.remove(dim + 1);
+        dims[dim] = len * stride;
+        t.reshape(dims)?.narrow(dim, 0, used)?
+    };
+    t.pad_with_zeros(dim, start, size - start - used)
+}
+
+// Spreads the gradient of a strided convolution over dimension `dim` and pads it so that a stride
+// 1 convolution with the flipped kernel returns the gradient for an input of size `i`. The start
+// padding is negative, i.e. elements are removed, when the convolution padding is larger than the
+// dilated kernel.
+fn conv_grad_spread(
+    grad: &Tensor,
+    dim: usize,
+    i: usize,
+    k: usize,
+    padding: usize,
+    stride: usize,
+    dilation: usize,
+) -> Result<Tensor> {
+    let o = grad.dim(dim)?;
+    let used = (o - 1) * stride + 1;
+    let start = (dilation * (k - 1)) as isize - padding as isize;
+    let end = (i + padding) as isize - used as isize;
+    let size = start.max(0) as usize + used + end.max(0) as usize;
+    let grad = strided_pad(grad, dim, start.max(0) as usize, stride, size)?;
+    grad.narrow(dim, (-start).max(0) as usize, i + dilation * (k - 1))
+}
+
+// All the positions within a pooling window of size `kernel_size`, in row-major order.
+fn pool_offsets(kernel_size: &[usize]) -> Vec<Vec<usize>> {
+    let mut offsets = vec![vec![]];
+    for &k in kernel_size.iter() {
+        offsets = offsets
+            .into_iter()
+            .flat_map(|o| {
+                (0..k).map(move |i| {
+                    let mut o = o.clone();
+                    o.push(i);
+                    o
+                })
+            })
+            .collect()
+    }
+    offsets
+}
+
+// The dims of the input once padded so that every window, including the ones past the end of
+// the input in ceil mode, is fully contained in it.
+fn pool_padded_dims(
+    params: &crate::pool::ParamsPool,
+    arg_dims: &[usize],
+    out_dims: &[usize],
+) -> Vec<usize> {
+    let mut dims = arg_dims.to_vec();
+    for (i, d) in dims.iter_mut().skip(2).enumerate() {
+        let (k, s, p) = (params.kernel_size[i], params.stride[i], params.padding[i]);
+        let o = out_dims[i + 2];
+        *d = usize::max(*d + 2 * p, (o - 1) * s + k)
+    }
+    dims
+}
+
+// Removes the padding added by `pool_padded_dims`.
+fn pool_unpad(t: &Tensor, params: &crate::pool::ParamsPool, arg_dims: &[usize]) -> Result<Tensor> {
+    let mut t = t.clone();
+    for (i, &p) in params.padding.iter().enumerate() {
+        t = t.narrow(i + 2, p, arg_dims[i + 2])?
+    }
+    Ok(t)
+}
+
+// For each spatial dimension, the number of elements that avg pooling divides by.
+fn pool_divisors(params: &crate::pool::ParamsPool, arg_dims: &[usize]) -> Result<Vec<Vec<f64>>> {
+    let out_dims = params.out_dims(arg_dims, "avg-pool")?;
+    let divisors = arg_dims[2..]
+        .iter()
+        .zip(out_dims[2..].iter())
+        .enumerate()
+        .map(|(i, (&in_size, &out_size))| {
+            (0..out_size)
+                .map(|o| params.window(i, in_size, o).2 as f64)
+                .collect()
+        })
+        .collect();
+    Ok(divisors)
+}
+
+fn pad_with_neg_infinity(t: &Tensor, dim: usize, start: usize, end: usize) -> Result<Tensor> {
+    let pad = |size: usize| {
+        let mut dims = t.dims().to_vec();
+        dims[dim] = size;
+        Tensor::ones(dims, t.dtype(), t.device())?.affine(0., f64::NEG_INFINITY)
+    };
+    let mut ts = vec![];
+    if start > 0 {
+        ts.push(pad(start)?)
+    }
+    ts.push(t.clone());
+    if end > 0 {
+        ts.push(pad(end)?)
+    }
+    Tensor::cat(&ts, dim)
+}
+
+// The input elements at position `offset` in each pooling window.
+fn pool_window(
+    arg: &Tensor,
+    offset: &[usize],
+    stride: &[usize],
+    out_dims: &[usize],
+) -> Result<Tensor> {
+    let mut t = arg.clone();
+    for (i, (&o, &s)) in offset.iter().zip(stride.iter()).enumerate() {
+        t = strided_narrow(&t, i + 2, o, s, out_dims[i + 2])?
+    }
+    Ok(t)
+}
+
+// Scatters `grad` back to the input elements at position `offset` in each pooling window.
+fn pool_window_grad(
+    grad: &Tensor,
+    arg_dims: &[usize],
+    offset: &[usize],
+    stride: &[usize],
+) -> Result<Tensor> {
+    let mut t = grad.clone();
+    for (i, (&o, &s)) in offset.iter().zip(stride.iter()).enumerate() {
+        t = strided_pad(&t, i + 2, o, s, arg_dims[i + 2])?
+    }
+    Ok(t)
+}
+
 pub struct GradStore(HashMap<TensorId, Tensor>);
 
 impl GradStore {
@@ -1,3 +1,44 @@
+/// How the spatial dimensions of the input are padded with zeros before a convolution, see
+/// [`crate::Tensor::conv1d_padded`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvPadding {
+    /// The same number of zeros on both sides.
+    Symmetric(usize),
+    /// `start` zeros before the input and `end` zeros after it.
+    Asymmetric { start: usize, end: usize },
+    /// Pads so that the output has the same size as the input, this requires a stride of 1. When
+    /// the total padding is odd, the extra zero is added at the end as in PyTorch.
+    Same,
+}
+
+impl Default for ConvPadding {
+    fn default() -> Self {
+        Self::Symmetric(0)
+    }
+}
+
+impl ConvPadding {
+    // Returns the padding at the start and at the end of a dimension for the given kernel size.
+    pub(crate) fn start_end(
+        &self,
+        k_size: usize,
+        stride: usize,
+        dilation: usize,
+    ) -> crate::Result<(usize, usize)> {
+        match *self {
+            Self::Symmetric(p) => Ok((p, p)),
+            Self::Asymmetric { start, end } => Ok((start, end)),
+            Self::Same => {
+                if stride != 1 {
+                    crate::bail!("same padding is only supported with a stride of 1, got {stride}")
+                }
+                let total = dilation * (k_size.max(1) - 1);
+                Ok((total / 2, total - total / 2))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParamsConv1D {
     pub(crate) b_size: usize,
@@ -5,16 +46,18 @@ pub struct ParamsConv1D {
     // the weights.
     pub(crate) l_in: usize,
     pub(crate) c_out: usize,
+    // The number of input channels, the kernel has c_in / groups channels.
     pub(crate) c_in: usize,
     pub(crate) k_size: usize,
     pub(crate) padding: usize,
     pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+    pub(crate) groups: usize,
 }
 
 impl ParamsConv1D {
     pub(crate) fn l_out(&self) -> usize {
-        let dilation = 1;
-        (self.l_in + 2 * self.padding - dilation * (self.k_size - 1) - 1) / self.stride + 1
+        (self.l_in + 2 * self.padding - self.dilation * (self.k_size - 1) - 1) / self.stride + 1
     }
 
     pub(crate) fn out_dims(&self) -> Vec<usize> {
@@ -31,20 +74,123 @@ pub struct ParamsConv2D {
     pub(crate) k_h: usize,
     pub(crate) k_w: usize,
     pub(crate) c_out: usize,
+    // The number of input channels, the kernel has c_in / groups channels.
     pub(crate) c_in: usize,
     pub(crate) padding: usize,
     pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+    pub(crate) groups: usize,
 }
 
 impl ParamsConv2D {
     pub(crate) fn out_h(&self) -> usize {
-        let dilation = 1;
-        (self.i_h + 2 * self.padding - dilation * (self.k_h - 1) - 1) / self.stride + 1
+        (self.i_h + 2 * self.padding - self.dilation * (self.k_h - 1) - 1) / self.stride + 1
+    }
+
+    pub(crate) fn out_w(&self) -> usize {
+        (self.i_w + 2 * self.padding - self.dilation * (self.k_w - 1) - 1) / self.stride + 1
+    }
+
+    pub(crate) fn out_dims(&self) -> Vec<usize> {
+        vec![self.b_size, self.c_out, self.out_h(), self.out_w()]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsConv3D {
+    pub(crate) b_size: usize,
+    pub(crate) i_d: usize,
+    pub(crate) i_h: usize,
+    pub(crate) i_w: usize,
+    pub(crate) k_d: usize,
+    pub(crate) k_h: usize,
+    pub(crate) k_w: usize,
+    pub(crate) c_out: usize,
+    // The number of input channels, the kernel has c_in / groups channels.
+    pub(crate) c_in: usize,
+    pub(crate) padding: usize,
+    pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+    pub(crate) groups: usize,
+}
+
+impl ParamsConv3D {
+    fn out_size(&self, i: usize, k: usize) -> usize {
+        (i + 2 * self.padding - self.dilation * (k - 1) - 1) / self.stride + 1
+    }
+
+    pub(crate) fn out_d(&self) -> usize {
+        self.out_size(self.i_d, self.k_d)
+    }
+
+    pub(crate) fn out_h(&self) -> usize {
+        self.out_size(self.i_h, self.k_h)
+    }
+
+    pub(crate) fn out_w(&self) -> usize {
+        self.out_size(self.i_w, self.k_w)
+    }
+
+    pub(crate) fn out_dims(&self) -> Vec<usize> {
+        vec![
+            self.b_size,
+            self.c_out,
+            self.out_d(),
+            self.out_h(),
+            self.out_w(),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsConvTranspose1D {
+    pub(crate) b_size: usize,
+    pub(crate) l_in: usize,
+    pub(crate) c_out: usize,
+    pub(crate) c_in: usize,
+    pub(crate) k_size: usize,
+    pub(crate) padding: usize,
+    pub(crate) output_padding: usize,
+    pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+}
+
+impl ParamsConvTranspose1D {
+    pub(crate) fn l_out(&self) -> usize {
+        (self.l_in - 1) * self.stride + self.dilation * (self.k_size - 1) + self.output_padding + 1
+            - 2 * self.padding
+    }
+
+    pub(crate) fn out_dims(&self) -> Vec<usize> {
+        let l_out = self.l_out();
+        vec![self.b_size, self.c_out, l_out]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsConvTranspose2D {
+    pub(crate) b_size: usize,
+    pub(crate) i_h: usize,
+    pub(crate) i_w: usize,
+    pub(crate) k_h: usize,
+    pub(crate) k_w: usize,
+    pub(crate) c_out: usize,
+    pub(crate) c_in: usize,
+    pub(crate) padding: usize,
+    pub(crate) output_padding: usize,
+    pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+}
+
+impl ParamsConvTranspose2D {
+    pub(crate) fn out_h(&self) -> usize {
+        (self.i_h - 1) * self.stride + self.dilation * (self.k_h - 1) + self.output_padding + 1
+            - 2 * self.padding
     }
 
     pub(crate) fn out_w(&self) -> usize {
-        let dilation = 1;
-        (self.i_w + 2 * self.padding - dilation * (self.k_w - 1) - 1) / self.stride + 1
+        (self.i_w - 1) * self.stride + self.dilation * (self.k_w - 1) + self.output_padding + 1
+            - 2 * self.padding
     }
 
     pub(crate) fn out_dims(&self) -> Vec<usize> {
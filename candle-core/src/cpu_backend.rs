@@ -1,7 +1,8 @@
 use crate::backend::{BackendDevice, BackendStorage};
-use crate::op::{BinaryOpT, CmpOp, ReduceOp, UnaryOpT};
+use crate::op::{BinaryOpT, CmpOp, ReduceOp, ScanOp, UnaryOpT};
 use crate::{DType, Error, IntDType, Layout, Result, Shape, WithDType};
 use half::{bf16, f16};
+use num_traits::AsPrimitive;
 
 // TODO: Maybe we should not implement [Clone] here and instead have an explicit allocator +
 // intercept the oom errors to avoid panicking and provide a proper error.
@@ -9,10 +10,16 @@ use half::{bf16, f16};
 pub enum CpuStorage {
     U8(Vec<u8>),
     U32(Vec<u32>),
+    I8(Vec<i8>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
     BF16(Vec<bf16>),
     F16(Vec<f16>),
     F32(Vec<f32>),
     F64(Vec<f64>),
+    /// Booleans are stored as `u8` values that are either 0 or 1.
+    Bool(Vec<u8>),
 }
 
 #[derive(Debug, Clone)]
@@ -25,10 +32,15 @@ pub trait Map1 {
         match vs {
             CpuStorage::U8(vs) => Ok(CpuStorage::U8(self.f(vs, layout)?)),
             CpuStorage::U32(vs) => Ok(CpuStorage::U32(self.f(vs, layout)?)),
+            CpuStorage::I8(vs) => Ok(CpuStorage::I8(self.f(vs, layout)?)),
+            CpuStorage::I16(vs) => Ok(CpuStorage::I16(self.f(vs, layout)?)),
+            CpuStorage::I32(vs) => Ok(CpuStorage::I32(self.f(vs, layout)?)),
+            CpuStorage::I64(vs) => Ok(CpuStorage::I64(self.f(vs, layout)?)),
             CpuStorage::BF16(vs) => Ok(CpuStorage::BF16(self.f(vs, layout)?)),
             CpuStorage::F16(vs) => Ok(CpuStorage::F16(self.f(vs, layout)?)),
             CpuStorage::F32(vs) => Ok(CpuStorage::F32(self.f(vs, layout)?)),
             CpuStorage::F64(vs) => Ok(CpuStorage::F64(self.f(vs, layout)?)),
+            CpuStorage::Bool(vs) => Ok(CpuStorage::Bool(self.f(vs, layout)?)),
         }
     }
 }
@@ -45,10 +57,15 @@ pub trait Map1Any {
         match vs {
             CpuStorage::U8(vs) => Ok(self.f(vs, layout, CpuStorage::U8)?),
             CpuStorage::U32(vs) => Ok(self.f(vs, layout, CpuStorage::U32)?),
+            CpuStorage::I8(vs) => Ok(self.f(vs, layout, CpuStorage::I8)?),
+            CpuStorage::I16(vs) => Ok(self.f(vs, layout, CpuStorage::I16)?),
+            CpuStorage::I32(vs) => Ok(self.f(vs, layout, CpuStorage::I32)?),
+            CpuStorage::I64(vs) => Ok(self.f(vs, layout, CpuStorage::I64)?),
             CpuStorage::BF16(vs) => Ok(self.f(vs, layout, CpuStorage::BF16)?),
             CpuStorage::F16(vs) => Ok(self.f(vs, layout, CpuStorage::F16)?),
             CpuStorage::F32(vs) => Ok(self.f(vs, layout, CpuStorage::F32)?),
             CpuStorage::F64(vs) => Ok(self.f(vs, layout, CpuStorage::F64)?),
+            CpuStorage::Bool(vs) => Ok(self.f(vs, layout, CpuStorage::Bool)?),
         }
     }
 }
@@ -68,10 +85,15 @@ pub trait Map2 {
         match (v1, v2) {
             (C::U8(v1), C::U8(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
             (C::U32(v1), C::U32(v2)) => Ok(C::U32(self.f(v1, l1, v2, l2)?)),
+            (C::I8(v1), C::I8(v2)) => Ok(C::I8(self.f(v1, l1, v2, l2)?)),
+            (C::I16(v1), C::I16(v2)) => Ok(C::I16(self.f(v1, l1, v2, l2)?)),
+            (C::I32(v1), C::I32(v2)) => Ok(C::I32(self.f(v1, l1, v2, l2)?)),
+            (C::I64(v1), C::I64(v2)) => Ok(C::I64(self.f(v1, l1, v2, l2)?)),
             (C::BF16(v1), C::BF16(v2)) => Ok(C::BF16(self.f(v1, l1, v2, l2)?)),
             (C::F16(v1), C::F16(v2)) => Ok(C::F16(self.f(v1, l1, v2, l2)?)),
             (C::F32(v1), C::F32(v2)) => Ok(C::F32(self.f(v1, l1, v2, l2)?)),
             (C::F64(v1), C::F64(v2)) => Ok(C::F64(self.f(v1, l1, v2, l2)?)),
+            (C::Bool(v1), C::Bool(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
             _ => Err(Error::DTypeMismatchBinaryOp {
                 lhs: v1.dtype(),
                 rhs: v2.dtype(),
@@ -82,7 +104,8 @@ pub trait Map2 {
     }
 }
 
-pub trait Map2U8 {
+/// Binary ops returning a boolean tensor, the result of `f` should only contain 0 or 1 values.
+pub trait Map2Bool {
     const OP: &'static str;
     fn f<T: WithDType>(&self, v1: &[T], l1: &Layout, v2: &[T], l2: &Layout) -> Result<Vec<u8>>;
 
@@ -94,12 +117,17 @@ pub trait Map2U8 {
         l2: &Layout,
     ) -> Result<CpuStorage> {
         match (v1, v2) {
-            (C::U8(v1), C::U8(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
-            (C::U32(v1), C::U32(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
-            (C::BF16(v1), C::BF16(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
-            (C::F16(v1), C::F16(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
-            (C::F32(v1), C::F32(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
-            (C::F64(v1), C::F64(v2)) => Ok(C::U8(self.f(v1, l1, v2, l2)?)),
+            (C::U8(v1), C::U8(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::U32(v1), C::U32(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::I8(v1), C::I8(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::I16(v1), C::I16(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::I32(v1), C::I32(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::I64(v1), C::I64(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::BF16(v1), C::BF16(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::F16(v1), C::F16(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::F32(v1), C::F32(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::F64(v1), C::F64(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
+            (C::Bool(v1), C::Bool(v2)) => Ok(C::Bool(self.f(v1, l1, v2, l2)?)),
             _ => Err(Error::DTypeMismatchBinaryOp {
                 lhs: v1.dtype(),
                 rhs: v2.dtype(),
@@ -111,7 +139,7 @@ pub trait Map2U8 {
 }
 
 struct Cmp(CmpOp);
-impl Map2U8 for Cmp {
+impl Map2Bool for Cmp {
     const OP: &'static str = "cmp";
     #[inline(always)]
     fn f<T: WithDType>(
@@ -278,7 +306,147 @@ impl Map1Any for ReduceIndex {
     }
 }
 
+// Calls `f` on each 1d lane of `layout` along dimension `dim`. The arguments are the offset of
+// the lane start in the storage and the offset of the lane start in a contiguous tensor with the
+// same shape. The lane elements are separated by `layout.stride()[dim]` in the storage and by the
+// product of the dimensions after `dim` in the contiguous tensor.
+fn for_each_lane<F: FnMut(usize, usize)>(layout: &Layout, dim: usize, mut f: F) -> Result<()> {
+    let dims = layout.dims();
+    let right_len: usize = dims[dim + 1..].iter().product();
+    let lanes = layout.narrow(dim, 0, 1)?;
+    for (lane_index, src_index) in lanes.strided_index().enumerate() {
+        let dst_index = (lane_index / right_len) * dims[dim] * right_len + lane_index % right_len;
+        f(src_index, dst_index)
+    }
+    Ok(())
+}
+
+struct ArgSort {
+    dim: usize,
+    asc: bool,
+}
+
+impl ArgSort {
+    // NaN values are considered to be larger than all the other values.
+    fn cmp<T: PartialOrd>(a: &T, b: &T) -> std::cmp::Ordering {
+        a.partial_cmp(b).unwrap_or_else(|| {
+            let a_is_nan = a.partial_cmp(a).is_none();
+            let b_is_nan = b.partial_cmp(b).is_none();
+            a_is_nan.cmp(&b_is_nan)
+        })
+    }
+}
+
+impl Map1Any for ArgSort {
+    fn f<T: WithDType, W: Fn(Vec<T>) -> CpuStorage>(
+        &self,
+        src: &[T],
+        src_l: &Layout,
+        _wrap: W,
+    ) -> Result<CpuStorage> {
+        let dims = src_l.dims();
+        let dim_size = dims[self.dim];
+        let dim_stride = src_l.stride()[self.dim];
+        let right_len: usize = dims[self.dim + 1..].iter().product();
+        let mut dst = vec![0u32; src_l.shape().elem_count()];
+        if dst.is_empty() {
+            return Ok(CpuStorage::U32(dst));
+        }
+        let mut ids: Vec<u32> = Vec::with_capacity(dim_size);
+        for_each_lane(src_l, self.dim, |src_index, dst_index| {
+            let src = &src[src_index..];
+            ids.clear();
+            ids.extend(0..dim_size as u32);
+            if self.asc {
+                ids.sort_by(|&i, &j| {
+                    Self::cmp(&src[i as usize * dim_stride], &src[j as usize * dim_stride])
+                })
+            } else {
+                ids.sort_by(|&i, &j| {
+                    Self::cmp(&src[j as usize * dim_stride], &src[i as usize * dim_stride])
+                })
+            }
+            for (i, &id) in ids.iter().enumerate() {
+                dst[dst_index + i * right_len] = id
+            }
+        })?;
+        Ok(CpuStorage::U32(dst))
+    }
+}
+
+struct Scan {
+    op: ScanOp,
+    dim: usize,
+}
+
+impl Scan {
+    fn scan<T: Copy, U: Copy, F: FnMut(U, T, usize) -> U>(
+        &self,
+        src: &[T],
+        src_l: &Layout,
+        init: U,
+        mut f: F,
+    ) -> Result<Vec<U>> {
+        let dims = src_l.dims();
+        let dim_size = dims[self.dim];
+        let dim_stride = src_l.stride()[self.dim];
+        let right_len: usize = dims[self.dim + 1..].iter().product();
+        let mut dst = vec![init; src_l.shape().elem_count()];
+        if dst.is_empty() {
+            return Ok(dst);
+        }
+        for_each_lane(src_l, self.dim, |src_index, dst_index| {
+            let mut acc = init;
+            for i in 0..dim_size {
+                acc = f(acc, src[src_index + i * dim_stride], i);
+                dst[dst_index + i * right_len] = acc
+            }
+        })?;
+        Ok(dst)
+    }
+}
+
+impl Map1Any for Scan {
+    fn f<T: WithDType, W: Fn(Vec<T>) -> CpuStorage>(
+        &self,
+        src: &[T],
+        src_l: &Layout,
+        wrap: W,
+    ) -> Result<CpuStorage> {
+        let dst = match self.op {
+            ScanOp::Sum => wrap(self.scan(src, src_l, T::zero(), |acc, v, _| acc + v)?),
+            ScanOp::Prod => wrap(self.scan(src, src_l, T::one(), |acc, v, _| acc * v)?),
+            ScanOp::LogSumExp => {
+                let dst = self.scan(src, src_l, f64::NEG_INFINITY, |acc, v, _| {
+                    let v = v.to_f64();
+                    let max = acc.max(v);
+                    if max == f64::NEG_INFINITY {
+                        max
+                    } else {
+                        max + ((acc - max).exp() + (v - max).exp()).ln()
+                    }
+                })?;
+                wrap(dst.into_iter().map(T::from_f64).collect())
+            }
+            ScanOp::ArgMax => {
+                // The accumulator holds the index of the running maximum and its value, the
+                // value is only read once an index has been set.
+                let dst = self.scan(src, src_l, (0u32, T::zero()), |(index, max), v, i| {
+                    if i == 0 || v > max {
+                        (i as u32, v)
+                    } else {
+                        (index, max)
+                    }
+                })?;
+                CpuStorage::U32(dst.into_iter().map(|(index, _)| index).collect())
+            }
+        };
+        Ok(dst)
+    }
+}
+
 struct Reduce<'a> {
+    op: ReduceOp,
     dst_shape: &'a Shape,
     reduce_dims: &'a [usize],
     reduce_dims_and_stride: Vec<(usize, usize)>,
@@ -350,7 +518,10 @@ impl<'a> Reduce<'a> {
 impl<'a> Map1 for Reduce<'a> {
     #[inline(always)]
     fn f<T: WithDType>(&self, src: &[T], src_l: &Layout) -> Result<Vec<T>> {
-        self.fold_impl(src, src_l, T::zero(), |x, y| x + y)
+        match self.op {
+            ReduceOp::Prod => self.fold_impl(src, src_l, T::one(), |x, y| x * y),
+            _ => self.fold_impl(src, src_l, T::zero(), |x, y| x + y),
+        }
     }
 }
 
@@ -633,39 +804,76 @@ impl Map1 for Affine {
     }
 }
 
-struct AvgPool2D((usize, usize), (usize, usize));
+// Moves `idx` to the next multi-index in row-major order where each component `i` ranges over
+// `ranges[i].0..ranges[i].1`, returns false once all the indexes have been visited.
+fn next_index(idx: &mut [usize], ranges: &[(usize, usize)]) -> bool {
+    for i in (0..idx.len()).rev() {
+        idx[i] += 1;
+        if idx[i] < ranges[i].1 {
+            return true;
+        }
+        idx[i] = ranges[i].0
+    }
+    false
+}
 
-impl Map1 for AvgPool2D {
+// Avg and max pooling over an arbitrary number of spatial dimensions.
+// https://pytorch.org/docs/stable/generated/torch.nn.AvgPool2d.html
+// https://pytorch.org/docs/stable/generated/torch.nn.MaxPool2d.html
+struct Pool<'a> {
+    params: &'a crate::pool::ParamsPool,
+    max: bool,
+}
+
+impl<'a> Map1 for Pool<'a> {
     fn f<T: WithDType>(&self, src: &[T], layout: &Layout) -> Result<Vec<T>> {
-        // https://pytorch.org/docs/stable/generated/torch.nn.AvgPool2d.html
-        let (k_h, k_w) = self.0;
-        let (s_h, s_w) = self.1;
-        let (b_sz, c, h, w) = layout.shape().dims4()?;
+        let p = self.params;
+        let dims = layout.dims();
         let stride = layout.stride();
-        let (stride_h, stride_w) = (stride[2], stride[3]);
-        let h_out = (h - k_h) / s_h + 1;
-        let w_out = (w - k_w) / s_w + 1;
-        let src_index = layout.start_offset();
-        let mut dst = vec![T::zero(); b_sz * c * h_out * w_out];
-        let scale = 1f64 / (k_h * k_w) as f64;
-        let scale = T::from_f64(scale);
-        for b_idx in 0..b_sz {
-            let dst = &mut dst[b_idx * c * h_out * w_out..];
-            let src_index = src_index + b_idx * stride[0];
-            for c_idx in 0..c {
-                let dst = &mut dst[c_idx * h_out * w_out..];
-                let src_index = src_index + c_idx * stride[1];
-                for h_idx in 0..h_out {
-                    for w_idx in 0..w_out {
-                        let mut sum = T::zero();
-                        for m in 0..k_h {
-                            for n in 0..k_w {
-                                let m = s_h * h_idx + m;
-                                let n = s_w * w_idx + n;
-                                sum += src[src_index + m * stride_h + n * stride_w]
+        let out_dims = p.out_dims(dims, if self.max { "max-pool" } else { "avg-pool" })?;
+        let (in_dims, out_dims) = (&dims[2..], &out_dims[2..]);
+        let out_ranges: Vec<_> = out_dims.iter().map(|&d| (0, d)).collect();
+        let n_out: usize = out_dims.iter().product();
+        let mut dst = Vec::with_capacity(dims[0] * dims[1] * n_out);
+        let mut out_idx = vec![0; out_dims.len()];
+        let mut ranges = vec![(0, 0); out_dims.len()];
+        let mut idx = vec![0; out_dims.len()];
+        for b_idx in 0..dims[0] {
+            for c_idx in 0..dims[1] {
+                let src_index = layout.start_offset() + b_idx * stride[0] + c_idx * stride[1];
+                loop {
+                    let mut divisor = 1;
+                    for (i, range) in ranges.iter_mut().enumerate() {
+                        let (start, end, d) = p.window(i, in_dims[i], out_idx[i]);
+                        *range = (start, end);
+                        divisor *= d;
+                    }
+                    for (i, r) in idx.iter_mut().zip(ranges.iter()) {
+                        *i = r.0
+                    }
+                    let offset = |idx: &[usize]| {
+                        let offsets = idx.iter().zip(stride[2..].iter()).map(|(i, s)| i * s);
+                        src_index + offsets.sum::<usize>()
+                    };
+                    let v = if self.max {
+                        let mut largest = src[offset(&idx)];
+                        while next_index(&mut idx, &ranges) {
+                            let v = src[offset(&idx)];
+                            if largest < v {
+                                largest = v
                             }
                         }
-                        dst[h_idx * w_out + w_idx] = sum * scale;
+                        largest
+                    } else {
+                        let mut sum = src[offset(&idx)];
+                        while next_index(&mut idx, &ranges) {
+                            sum += src[offset(&idx)]
+                        }
+                        sum * T::from_f64(1f64 / divisor as f64)
+                    };
+                    dst.push(v);
+                    if !next_index(&mut out_idx, &out_ranges) {
+                        break;
                     }
                 }
             }
@@ -674,40 +882,30 @@ impl Map1 for AvgPool2D {
     }
 }
 
-struct MaxPool2D((usize, usize), (usize, usize));
+struct Interpolate2D(usize, usize, crate::InterpolateMode);
 
-impl Map1 for MaxPool2D {
+impl Map1 for Interpolate2D {
     fn f<T: WithDType>(&self, src: &[T], layout: &Layout) -> Result<Vec<T>> {
-        // https://pytorch.org/docs/stable/generated/torch.nn.MaxPool2d.html
-        let (k_h, k_w) = self.0;
-        let (s_h, s_w) = self.1;
-        let (b_sz, c, h, w) = layout.shape().dims4()?;
+        let (dst_h, dst_w, mode) = (self.0, self.1, self.2);
+        let (b_sz, c, src_h, src_w) = layout.shape().dims4()?;
         let stride = layout.stride();
         let (stride_h, stride_w) = (stride[2], stride[3]);
-        let h_out = (h - k_h) / s_h + 1;
-        let w_out = (w - k_w) / s_w + 1;
-        let src_index = layout.start_offset();
-        let mut dst = vec![T::zero(); b_sz * c * h_out * w_out];
+        let taps_h = crate::interpolate::taps(src_h, dst_h, mode);
+        let taps_w = crate::interpolate::taps(src_w, dst_w, mode);
+        let mut dst = Vec::with_capacity(b_sz * c * dst_h * dst_w);
         for b_idx in 0..b_sz {
-            let dst = &mut dst[b_idx * c * h_out * w_out..];
-            let src_index = src_index + b_idx * stride[0];
             for c_idx in 0..c {
-                let dst = &mut dst[c_idx * h_out * w_out..];
-                let src_index = src_index + c_idx * stride[1];
-                for h_idx in 0..h_out {
-                    for w_idx in 0..w_out {
-                        let mut largest =
-                            src[src_index + s_h * h_idx * stride_h + s_w * w_idx * stride_w];
-                        for m in 0..k_h {
-                            for n in 0..k_w {
-                                let m = s_h * h_idx + m;
-                                let n = s_w * w_idx + n;
-                                if largest < src[src_index + m * stride_h + n * stride_w] {
-                                    largest = src[src_index + m * stride_h + n * stride_w]
-                                }
+                let src_index = layout.start_offset() + b_idx * stride[0] + c_idx * stride[1];
+                for taps_h in taps_h.iter() {
+                    for taps_w in taps_w.iter() {
+                        let mut v = 0f64;
+                        for &(h_idx, w_h) in taps_h.iter() {
+                            let src_index = src_index + h_idx * stride_h;
+                            for &(w_idx, w_w) in taps_w.iter() {
+                                v += w_h * w_w * src[src_index + w_idx * stride_w].to_f64()
                             }
                         }
-                        dst[h_idx * w_out + w_idx] = largest;
+                        dst.push(T::from_f64(v))
                     }
                 }
             }
@@ -716,6 +914,21 @@ impl Map1 for MaxPool2D {
     }
 }
 
+struct Fft(crate::fft::FftKind);
+
+impl Map1 for Fft {
+    fn f<T: WithDType>(&self, src: &[T], layout: &Layout) -> Result<Vec<T>> {
+        let src = match layout.contiguous_offsets() {
+            Some((o1, o2)) => &src[o1..o2],
+            None => Err(Error::RequiresContiguous { op: "fft" }.bt())?,
+        };
+        // The transforms are computed in f64 whatever the dtype.
+        let src: Vec<f64> = src.iter().map(|v| v.to_f64()).collect();
+        let dst = crate::fft::fft_rows(self.0, &src, layout.dims())?;
+        Ok(dst.into_iter().map(T::from_f64).collect())
+    }
+}
+
 struct UpsampleNearest2D(usize, usize);
 
 impl Map1 for UpsampleNearest2D {
@@ -729,10 +942,10 @@ impl Map1 for UpsampleNearest2D {
         let scale_h = src_h as f64 / dst_h as f64;
         let scale_w = src_w as f64 / dst_w as f64;
         let mut dst = vec![T::zero(); b_sz * c * dst_h * dst_w];
-        let src_h_idxs = (0..src_h)
+        let src_h_idxs = (0..dst_h)
             .map(|h_idx| usize::min(src_h - 1, (h_idx as f64 * scale_h) as usize))
             .collect::<Vec<_>>();
-        let src_w_idxs = (0..src_w)
+        let src_w_idxs = (0..dst_w)
             .map(|w_idx| usize::min(src_w - 1, (w_idx as f64 * scale_w) as usize))
             .collect::<Vec<_>>();
         for b_idx in 0..b_sz {
@@ -788,7 +1001,11 @@ impl<'a, I: IntDType> Map1 for Gather<'a, I> {
                 let start_dst_idx = start_dst_idx + i * dst_right_len;
                 for right_i in 0..dst_right_len {
                     let dst_idx = start_dst_idx + right_i;
-                    let index = ids[dst_idx].as_usize();
+                    let index = ids[dst_idx];
+                    if index < I::zero() {
+                        crate::bail!("gather: negative index {index}")
+                    }
+                    let index = index.as_usize();
                     if index >= src_dim_len {
                         Err(Error::InvalidIndex {
                             index,
@@ -840,7 +1057,11 @@ impl<'a, I: IntDType> Map1 for IndexSelect<'a, I> {
             let start_src_idx = left_i * right_len * src_dim;
             let start_dst_idx = left_i * right_len * n_ids;
             for i in 0..n_ids {
-                let index = self.ids[self.ids_l.start_offset() + stride_ids * i].as_usize();
+                let index = self.ids[self.ids_l.start_offset() + stride_ids * i];
+                if index < I::zero() {
+                    crate::bail!("index-select: negative index {index}")
+                }
+                let index = index.as_usize();
                 if index >= src_dim {
                     Err(Error::InvalidIndex {
                         index,
@@ -897,7 +1118,11 @@ impl<'a, I: IntDType> Map2 for ScatterAdd<'a, I> {
                 let start_ids_idx = start_ids_idx + i * ids_right_len;
                 for right_i in 0..dst_right_len {
                     let ids_idx = start_ids_idx + right_i;
-                    let index = ids[ids_idx].as_usize();
+                    let index = ids[ids_idx];
+                    if index < I::zero() {
+                        crate::bail!("scatter-add: negative index {index}")
+                    }
+                    let index = index.as_usize();
                     if index >= dst_dim_len {
                         Err(Error::InvalidIndex {
                             index,
@@ -939,7 +1164,10 @@ impl<'a, I: IntDType> Map2 for IndexAdd<'a, I> {
         let src_dim_sz = src_l.dims()[dim];
         let post_dim = src_l.dims()[dim + 1..].iter().product::<usize>();
         if dim == 0 {
-            for (src_idx, dst_idx) in self.ids.iter().enumerate() {
+            for (src_idx, &dst_idx) in self.ids.iter().enumerate() {
+                if dst_idx < I::zero() {
+                    crate::bail!("index-add: negative index {dst_idx}")
+                }
                 let dst_idx = dst_idx.as_usize();
                 if dst_idx >= max_idx {
                     Err(Error::InvalidIndex {
@@ -957,7 +1185,10 @@ impl<'a, I: IntDType> Map2 for IndexAdd<'a, I> {
                 }
             }
         } else {
-            for (src_idx, dst_idx) in self.ids.iter().enumerate() {
+            for (src_idx, &dst_idx) in self.ids.iter().enumerate() {
+                if dst_idx < I::zero() {
+                    crate::bail!("index-add: negative index {dst_idx}")
+                }
                 let dst_idx = dst_idx.as_usize();
                 if dst_idx >= max_idx {
                     Err(Error::InvalidIndex {
@@ -981,6 +1212,63 @@ impl<'a, I: IntDType> Map2 for IndexAdd<'a, I> {
     }
 }
 
+// The in place counterpart of IndexAdd, all the ids are validated before anything gets written
+// so that an error leaves the destination untouched.
+fn index_put_<I: IntDType>(
+    ids: &[I],
+    dim: usize,
+    dst: &mut CpuStorage,
+    dst_l: &Layout,
+    src: &CpuStorage,
+    src_l: &Layout,
+) -> Result<()> {
+    let max_idx = dst_l.dims()[dim];
+    let mut layouts = Vec::with_capacity(ids.len());
+    for (src_idx, &dst_idx) in ids.iter().enumerate() {
+        if dst_idx < I::zero() {
+            crate::bail!("index-put: negative index {dst_idx}")
+        }
+        let dst_idx = dst_idx.as_usize();
+        if dst_idx >= max_idx {
+            Err(Error::InvalidIndex {
+                index: dst_idx,
+                op: "index-put",
+                size: max_idx,
+            }
+            .bt())?
+        }
+        layouts.push((
+            dst_l.narrow(dim, dst_idx, 1)?,
+            src_l.narrow(dim, src_idx, 1)?,
+        ))
+    }
+    fn put<T: Copy>(dst: &mut [T], src: &[T], layouts: &[(Layout, Layout)]) {
+        for (dst_l, src_l) in layouts.iter() {
+            copy_strided_(src, dst, dst_l, src_l)
+        }
+    }
+    match (dst, src) {
+        (CpuStorage::U8(dst), CpuStorage::U8(src)) => put(dst, src, &layouts),
+        (CpuStorage::U32(dst), CpuStorage::U32(src)) => put(dst, src, &layouts),
+        (CpuStorage::I8(dst), CpuStorage::I8(src)) => put(dst, src, &layouts),
+        (CpuStorage::I16(dst), CpuStorage::I16(src)) => put(dst, src, &layouts),
+        (CpuStorage::I32(dst), CpuStorage::I32(src)) => put(dst, src, &layouts),
+        (CpuStorage::I64(dst), CpuStorage::I64(src)) => put(dst, src, &layouts),
+        (CpuStorage::BF16(dst), CpuStorage::BF16(src)) => put(dst, src, &layouts),
+        (CpuStorage::F16(dst), CpuStorage::F16(src)) => put(dst, src, &layouts),
+        (CpuStorage::F32(dst), CpuStorage::F32(src)) => put(dst, src, &layouts),
+        (CpuStorage::F64(dst), CpuStorage::F64(src)) => put(dst, src, &layouts),
+        (CpuStorage::Bool(dst), CpuStorage::Bool(src)) => put(dst, src, &layouts),
+        (dst, src) => Err(Error::DTypeMismatchBinaryOp {
+            lhs: dst.dtype(),
+            rhs: src.dtype(),
+            op: "index-put",
+        }
+        .bt())?,
+    }
+    Ok(())
+}
+
 fn copy_strided_src_<T: Copy>(src: &[T], dst: &mut [T], dst_offset: usize, src_l: &Layout) {
     match src_l.strided_blocks() {
         crate::StridedBlocks::SingleBlock { start_offset, len } => {
@@ -1019,6 +1307,265 @@ fn copy_strided_src_<T: Copy>(src: &[T], dst: &mut [T], dst_offset: usize, src_l
     }
 }
 
+// Calls `f` on each contiguous block of a layout with the storage offset of the block, the index
+// of its first element in the layout, and its length.
+fn for_each_block<F: FnMut(usize, usize, usize)>(layout: &Layout, mut f: F) {
+    match layout.strided_blocks() {
+        crate::StridedBlocks::SingleBlock { start_offset, len } => f(start_offset, 0, len),
+        crate::StridedBlocks::MultipleBlocks {
+            block_start_index,
+            block_len,
+        } => {
+            for (block_idx, start_offset) in block_start_index.enumerate() {
+                f(start_offset, block_idx * block_len, block_len)
+            }
+        }
+    }
+}
+
+fn copy_strided_<T: Copy>(src: &[T], dst: &mut [T], dst_l: &Layout, src_l: &Layout) {
+    match src_l.contiguous_offsets() {
+        Some((src_offset, _)) => for_each_block(dst_l, |dst_offset, index, len| {
+            let src_offset = src_offset + index;
+            dst[dst_offset..dst_offset + len].copy_from_slice(&src[src_offset..src_offset + len])
+        }),
+        None => {
+            let mut src_index = src_l.strided_index();
+            for_each_block(dst_l, |dst_offset, _, len| {
+                let dst = &mut dst[dst_offset..dst_offset + len];
+                for (d, src_index) in dst.iter_mut().zip(&mut src_index) {
+                    *d = src[src_index]
+                }
+            })
+        }
+    }
+}
+
+fn unary_inplace_<T: Copy, F: Fn(T) -> T>(vs: &mut [T], layout: &Layout, f: F) {
+    for_each_block(layout, |offset, _, len| {
+        vs[offset..offset + len].iter_mut().for_each(|v| *v = f(*v))
+    })
+}
+
+fn binary_inplace_<T: Copy, F: Fn(T, T) -> T>(
+    lhs: &mut [T],
+    lhs_l: &Layout,
+    rhs: &[T],
+    rhs_l: &Layout,
+    f: F,
+) {
+    match rhs_l.contiguous_offsets() {
+        Some((rhs_offset, _)) => for_each_block(lhs_l, |lhs_offset, index, len| {
+            let rhs = &rhs[rhs_offset + index..rhs_offset + index + len];
+            let lhs = &mut lhs[lhs_offset..lhs_offset + len];
+            lhs.iter_mut().zip(rhs).for_each(|(l, &r)| *l = f(*l, r))
+        }),
+        None => {
+            let mut rhs_index = rhs_l.strided_index();
+            for_each_block(lhs_l, |lhs_offset, _, len| {
+                let lhs = &mut lhs[lhs_offset..lhs_offset + len];
+                for (l, rhs_index) in lhs.iter_mut().zip(&mut rhs_index) {
+                    *l = f(*l, rhs[rhs_index])
+                }
+            })
+        }
+    }
+}
+
+// Float convolutions are computed by gathering the input windows in a matrix (im2col) and
+// running a single gemm, the direct kernels below are used for the other types as the gemm
+// kernels only exist for some float types.
+fn use_im2col(dtype: DType) -> bool {
+    if !crate::utils::use_im2col_conv() {
+        return false;
+    }
+    match dtype {
+        DType::F32 | DType::F64 => true,
+        DType::F16 => !cfg!(feature = "accelerate"),
+        _ => false,
+    }
+}
+
+struct Im2Col1D<'a>(&'a crate::conv::ParamsConv1D);
+
+impl<'a> Map1 for Im2Col1D<'a> {
+    // The output has shape [b_size, l_out, c_in * k_size] where c_in is the number of channels
+    // in the layout, i.e. the channels of a single group.
+    fn f<T: WithDType>(&self, vs: &[T], layout: &Layout) -> Result<Vec<T>> {
+        let p = self.0;
+        let (b_size, c_in, l_in) = layout.shape().dims3()?;
+        let (s0, s1, s2) = crate::shape::dims3(layout.stride())?;
+        let vs = &vs[layout.start_offset()..];
+        let l_out = p.l_out();
+        let k_size = p.k_size;
+        let mut dst = vec![T::zero(); b_size * l_out * c_in * k_size];
+        for b_idx in 0..b_size {
+            for l_idx in 0..l_out {
+                let dst_idx = (b_idx * l_out + l_idx) * c_in * k_size;
+                for k_idx in 0..k_size {
+                    let src_l = p.stride * l_idx + p.dilation * k_idx;
+                    if src_l < p.padding || src_l >= l_in + p.padding {
+                        continue;
+                    }
+                    let src_idx = b_idx * s0 + (src_l - p.padding) * s2;
+                    for c_idx in 0..c_in {
+                        dst[dst_idx + c_idx * k_size + k_idx] = vs[src_idx + c_idx * s1]
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
+struct Im2Col2D<'a>(&'a crate::conv::ParamsConv2D);
+
+impl<'a> Map1 for Im2Col2D<'a> {
+    // The output has shape [b_size, h_out * w_out, c_in * k_h * k_w].
+    fn f<T: WithDType>(&self, vs: &[T], layout: &Layout) -> Result<Vec<T>> {
+        let p = self.0;
+        let (b_size, c_in, i_h, i_w) = layout.shape().dims4()?;
+        let (s0, s1, s2, s3) = crate::shape::dims4(layout.stride())?;
+        let vs = &vs[layout.start_offset()..];
+        let (out_h, out_w) = (p.out_h(), p.out_w());
+        let (k_h, k_w) = (p.k_h, p.k_w);
+        let row_len = c_in * k_h * k_w;
+        let mut dst = vec![T::zero(); b_size * out_h * out_w * row_len];
+        for b_idx in 0..b_size {
+            for h_idx in 0..out_h {
+                for w_idx in 0..out_w {
+                    let dst_idx = ((b_idx * out_h + h_idx) * out_w + w_idx) * row_len;
+                    for k_h_idx in 0..k_h {
+                        let src_h = p.stride * h_idx + p.dilation * k_h_idx;
+                        if src_h < p.padding || src_h >= i_h + p.padding {
+                            continue;
+                        }
+                        for k_w_idx in 0..k_w {
+                            let src_w = p.stride * w_idx + p.dilation * k_w_idx;
+                            if src_w < p.padding || src_w >= i_w + p.padding {
+                                continue;
+                            }
+                            let src_idx =
+                                b_idx * s0 + (src_h - p.padding) * s2 + (src_w - p.padding) * s3;
+                            let dst_idx = dst_idx + k_h_idx * k_w + k_w_idx;
+                            for c_idx in 0..c_in {
+                                dst[dst_idx + c_idx * k_h * k_w] = vs[src_idx + c_idx * s1]
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
+struct Im2Col3D<'a>(&'a crate::conv::ParamsConv3D);
+
+impl<'a> Map1 for Im2Col3D<'a> {
+    // The output has shape [b_size, d_out * h_out * w_out, c_in * k_d * k_h * k_w].
+    fn f<T: WithDType>(&self, vs: &[T], layout: &Layout) -> Result<Vec<T>> {
+        let p = self.0;
+        let (b_size, c_in, i_d, i_h, i_w) = layout.shape().dims5()?;
+        let (s0, s1, s2, s3, s4) = crate::shape::dims5(layout.stride())?;
+        let vs = &vs[layout.start_offset()..];
+        let (out_d, out_h, out_w) = (p.out_d(), p.out_h(), p.out_w());
+        let k_elems = p.k_d * p.k_h * p.k_w;
+        let row_len = c_in * k_elems;
+        let mut dst = vec![T::zero(); b_size * out_d * out_h * out_w * row_len];
+        // Returns the unpadded source index if it lies within the input.
+        let src_index = |dst_idx: usize, k_idx: usize, i: usize| {
+            let src_idx = p.stride * dst_idx + p.dilation * k_idx;
+            if src_idx < p.padding || src_idx >= i + p.padding {
+                None
+            } else {
+                Some(src_idx - p.padding)
+            }
+        };
+        let mut dst_idx = 0;
+        for b_idx in 0..b_size {
+            for d_idx in 0..out_d {
+                for h_idx in 0..out_h {
+                    for w_idx in 0..out_w {
+                        let mut k_idx = 0;
+                        for k_d_idx in 0..p.k_d {
+                            let src_d = src_index(d_idx, k_d_idx, i_d);
+                            for k_h_idx in 0..p.k_h {
+                                let src_h = src_index(h_idx, k_h_idx, i_h);
+                                for k_w_idx in 0..p.k_w {
+                                    let src_w = src_index(w_idx, k_w_idx, i_w);
+                                    if let (Some(src_d), Some(src_h), Some(src_w)) =
+                                        (src_d, src_h, src_w)
+                                    {
+                                        let src_idx =
+                                            b_idx * s0 + src_d * s2 + src_h * s3 + src_w * s4;
+                                        for c_idx in 0..c_in {
+                                            dst[dst_idx + c_idx * k_elems + k_idx] =
+                                                vs[src_idx + c_idx * s1]
+                                        }
+                                    }
+                                    k_idx += 1;
+                                }
+                            }
+                        }
+                        dst_idx += row_len;
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
+impl CpuStorage {
+    // Runs a convolution as one matmul per group between the im2col matrix of shape
+    // [b_size * n_out, c_in_g * k_elems] and the kernel, the result of shape [b_size, n_out,
+    // c_out_g] is then transposed in the output of shape [b_size, c_out, n_out].
+    #[allow(clippy::too_many_arguments)]
+    fn conv_im2col<F: Fn(&Layout) -> Result<Self>>(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        (b_size, c_in, c_out, groups): (usize, usize, usize, usize),
+        n_out: usize,
+        k_elems: usize,
+        im2col: F,
+    ) -> Result<Self> {
+        let (c_in_g, c_out_g) = (c_in / groups, c_out / groups);
+        let (kernel, k_offset) = if kernel_l.is_contiguous() {
+            (std::borrow::Cow::Borrowed(kernel), kernel_l.start_offset())
+        } else {
+            let mut k = self.device().zeros_impl(kernel_l.shape(), kernel.dtype())?;
+            kernel.copy_strided_src(&mut k, 0, kernel_l)?;
+            (std::borrow::Cow::Owned(k), 0)
+        };
+        let row_len = c_in_g * k_elems;
+        let dst_shape = Shape::from((b_size, c_out, n_out));
+        let mut dst = self.device().zeros_impl(&dst_shape, self.dtype())?;
+        for g in 0..groups {
+            let col = im2col(&l.narrow(1, g * c_in_g, c_in_g)?)?;
+            // The batch dimension is merged with the output positions so that a single gemm is
+            // used, the kernel for this group is seen as a [row_len, c_out_g] matrix.
+            let m = b_size * n_out;
+            let col_l = Layout::contiguous((1, m, row_len));
+            let kernel_l = Layout::new(
+                (1, row_len, c_out_g).into(),
+                vec![c_out_g * row_len, 1, row_len],
+                k_offset + g * c_out_g * row_len,
+            );
+            let res = col.matmul(&kernel, (1, m, c_out_g, row_len), &col_l, &kernel_l)?;
+            let res_l = Layout::contiguous((b_size, n_out, c_out_g)).transpose(1, 2)?;
+            for b_idx in 0..b_size {
+                let res_l = res_l.narrow(0, b_idx, 1)?;
+                let dst_offset = (b_idx * c_out + g * c_out_g) * n_out;
+                res.copy_strided_src(&mut dst, dst_offset, &res_l)?;
+            }
+        }
+        Ok(dst)
+    }
+}
+
 struct Conv1D<'a>(&'a crate::conv::ParamsConv1D);
 
 impl<'a> Map2 for Conv1D<'a> {
@@ -1030,13 +1577,14 @@ impl<'a> Map2 for Conv1D<'a> {
         k: &[T],
         k_l: &Layout,
     ) -> Result<Vec<T>> {
-        // TODO: Optimize this (proper algorithm, simd, multithread, remove bound checks, etc).
         let p = self.0;
         let inp = &inp[inp_l.start_offset()..];
         let k = &k[k_l.start_offset()..];
         let (inp_s0, inp_s1, inp_s2) = crate::shape::dims3(inp_l.stride())?;
         let (k_s0, k_s1, k_s2) = crate::shape::dims3(k_l.stride())?;
         let l_out = p.l_out();
+        let c_in_per_group = p.c_in / p.groups;
+        let c_out_per_group = p.c_out / p.groups;
         let dst_elems = p.c_out * l_out * p.b_size;
         let mut dst = vec![T::zero(); dst_elems];
         // The output shape is [b_size, c_out, l_out]
@@ -1045,16 +1593,21 @@ impl<'a> Map2 for Conv1D<'a> {
             let dst_idx = b_idx * p.c_out * l_out;
             for dst_c_idx in 0..p.c_out {
                 let dst_idx = dst_idx + dst_c_idx * l_out;
+                let src_c_start = (dst_c_idx / c_out_per_group) * c_in_per_group;
                 for dst_l in 0..l_out {
                     let dst_idx = dst_idx + dst_l;
                     let mut d = T::zero();
                     for offset in 0..p.k_size {
-                        let src_l = (p.stride * dst_l + offset)
-                            .saturating_sub(p.padding)
-                            .min(p.l_in - 1);
-                        for src_c_idx in 0..p.c_in {
+                        // The positions that fall in the padding are zeros and are skipped.
+                        let src_l = p.stride * dst_l + p.dilation * offset;
+                        if src_l < p.padding || src_l >= p.l_in + p.padding {
+                            continue;
+                        }
+                        let src_l = src_l - p.padding;
+                        for k_c_idx in 0..c_in_per_group {
+                            let src_c_idx = src_c_start + k_c_idx;
                             let inp_idx = inp_idx + src_c_idx * inp_s1 + src_l * inp_s2;
-                            let k_idx = dst_c_idx * k_s0 + src_c_idx * k_s1 + offset * k_s2;
+                            let k_idx = dst_c_idx * k_s0 + k_c_idx * k_s1 + offset * k_s2;
                             d += inp[inp_idx] * k[k_idx]
                         }
                     }
@@ -1083,6 +1636,8 @@ impl<'a> Map2 for Conv2D<'a> {
         let k = &k[k_l.start_offset()..];
         let (k_s0, k_s1, k_s2, k_s3) = crate::shape::dims4(k_l.stride())?;
         let (out_h, out_w) = (p.out_h(), p.out_w());
+        let c_in_per_group = p.c_in / p.groups;
+        let c_out_per_group = p.c_out / p.groups;
 
         let mut dst = vec![T::zero(); p.b_size * p.c_out * out_h * out_w];
         for b_idx in 0..p.b_size {
@@ -1090,26 +1645,32 @@ impl<'a> Map2 for Conv2D<'a> {
             let dst_idx = b_idx * p.c_out * out_h * out_w;
             for dst_c_idx in 0..p.c_out {
                 let dst_idx = dst_idx + dst_c_idx * out_h * out_w;
+                let src_c_start = (dst_c_idx / c_out_per_group) * c_in_per_group;
                 for dst_h in 0..out_h {
                     let dst_idx = dst_idx + dst_h * out_w;
                     for dst_w in 0..out_w {
                         let dst_idx = dst_idx + dst_w;
                         let mut d = T::zero();
                         for offset_h in 0..p.k_h {
-                            let src_h = (p.stride * dst_h + offset_h)
-                                .saturating_sub(p.padding)
-                                .min(p.i_h - 1);
+                            let src_h = p.stride * dst_h + p.dilation * offset_h;
+                            if src_h < p.padding || src_h >= p.i_h + p.padding {
+                                continue;
+                            }
+                            let src_h = src_h - p.padding;
                             for offset_w in 0..p.k_w {
-                                let src_w = (p.stride * dst_w + offset_w)
-                                    .saturating_sub(p.padding)
-                                    .min(p.i_w - 1);
-                                for src_c_idx in 0..p.c_in {
+                                let src_w = p.stride * dst_w + p.dilation * offset_w;
+                                if src_w < p.padding || src_w >= p.i_w + p.padding {
+                                    continue;
+                                }
+                                let src_w = src_w - p.padding;
+                                for k_c_idx in 0..c_in_per_group {
+                                    let src_c_idx = src_c_start + k_c_idx;
                                     let inp_idx = inp_idx
                                         + src_c_idx * inp_s1
                                         + src_h * inp_s2
                                         + src_w * inp_s3;
                                     let k_idx = dst_c_idx * k_s0
-                                        + src_c_idx * k_s1
+                                        + k_c_idx * k_s1
                                         + offset_h * k_s2
                                         + offset_w * k_s3;
                                     d += inp[inp_idx] * k[k_idx]
@@ -1125,6 +1686,186 @@ impl<'a> Map2 for Conv2D<'a> {
     }
 }
 
+struct Conv3D<'a>(&'a crate::conv::ParamsConv3D);
+
+impl<'a> Map2 for Conv3D<'a> {
+    const OP: &'static str = "conv3d";
+    fn f<T: 'static + num_traits::NumAssign + Copy + std::fmt::Display>(
+        &self,
+        inp: &[T],
+        inp_l: &Layout,
+        k: &[T],
+        k_l: &Layout,
+    ) -> Result<Vec<T>> {
+        let p = self.0;
+        let inp = &inp[inp_l.start_offset()..];
+        let (inp_s0, inp_s1, inp_s2, inp_s3, inp_s4) = crate::shape::dims5(inp_l.stride())?;
+        let k = &k[k_l.start_offset()..];
+        let (k_s0, k_s1, k_s2, k_s3, k_s4) = crate::shape::dims5(k_l.stride())?;
+        let (out_d, out_h, out_w) = (p.out_d(), p.out_h(), p.out_w());
+        let c_in_per_group = p.c_in / p.groups;
+        let c_out_per_group = p.c_out / p.groups;
+        // Returns the unpadded source index if it lies within the input.
+        let src_index = |dst_idx: usize, k_idx: usize, i: usize| {
+            let src_idx = p.stride * dst_idx + p.dilation * k_idx;
+            if src_idx < p.padding || src_idx >= i + p.padding {
+                None
+            } else {
+                Some(src_idx - p.padding)
+            }
+        };
+
+        let mut dst = Vec::with_capacity(p.b_size * p.c_out * out_d * out_h * out_w);
+        for b_idx in 0..p.b_size {
+            let inp_idx = b_idx * inp_s0;
+            for dst_c_idx in 0..p.c_out {
+                let src_c_start = (dst_c_idx / c_out_per_group) * c_in_per_group;
+                for dst_d in 0..out_d {
+                    for dst_h in 0..out_h {
+                        for dst_w in 0..out_w {
+                            let mut d = T::zero();
+                            for offset_d in 0..p.k_d {
+                                let src_d = match src_index(dst_d, offset_d, p.i_d) {
+                                    Some(v) => v,
+                                    None => continue,
+                                };
+                                for offset_h in 0..p.k_h {
+                                    let src_h = match src_index(dst_h, offset_h, p.i_h) {
+                                        Some(v) => v,
+                                        None => continue,
+                                    };
+                                    for offset_w in 0..p.k_w {
+                                        let src_w = match src_index(dst_w, offset_w, p.i_w) {
+                                            Some(v) => v,
+                                            None => continue,
+                                        };
+                                        for k_c_idx in 0..c_in_per_group {
+                                            let src_c_idx = src_c_start + k_c_idx;
+                                            let inp_idx = inp_idx
+                                                + src_c_idx * inp_s1
+                                                + src_d * inp_s2
+                                                + src_h * inp_s3
+                                                + src_w * inp_s4;
+                                            let k_idx = dst_c_idx * k_s0
+                                                + k_c_idx * k_s1
+                                                + offset_d * k_s2
+                                                + offset_h * k_s3
+                                                + offset_w * k_s4;
+                                            d += inp[inp_idx] * k[k_idx]
+                                        }
+                                    }
+                                }
+                            }
+                            dst.push(d)
+                        }
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
+struct ConvTranspose1D<'a>(&'a crate::conv::ParamsConvTranspose1D);
+
+impl<'a> Map2 for ConvTranspose1D<'a> {
+    const OP: &'static str = "conv_transpose1d";
+    fn f<T: 'static + num_traits::NumAssign + Copy>(
+        &self,
+        inp: &[T],
+        inp_l: &Layout,
+        k: &[T],
+        k_l: &Layout,
+    ) -> Result<Vec<T>> {
+        let p = self.0;
+        let inp = &inp[inp_l.start_offset()..];
+        let k = &k[k_l.start_offset()..];
+        let (inp_s0, inp_s1, inp_s2) = crate::shape::dims3(inp_l.stride())?;
+        let (k_s0, k_s1, k_s2) = crate::shape::dims3(k_l.stride())?;
+        let l_out = p.l_out();
+        let mut dst = vec![T::zero(); p.b_size * p.c_out * l_out];
+        // Each input element is scattered over the output, the kernel has shape
+        // [c_in, c_out, k_size] and the output [b_size, c_out, l_out].
+        for b_idx in 0..p.b_size {
+            for src_c_idx in 0..p.c_in {
+                for src_l in 0..p.l_in {
+                    let v = inp[b_idx * inp_s0 + src_c_idx * inp_s1 + src_l * inp_s2];
+                    for offset in 0..p.k_size {
+                        let dst_l = p.stride * src_l + p.dilation * offset;
+                        if dst_l < p.padding || dst_l >= l_out + p.padding {
+                            continue;
+                        }
+                        let dst_l = dst_l - p.padding;
+                        for dst_c_idx in 0..p.c_out {
+                            let k_idx = src_c_idx * k_s0 + dst_c_idx * k_s1 + offset * k_s2;
+                            let dst_idx = (b_idx * p.c_out + dst_c_idx) * l_out + dst_l;
+                            dst[dst_idx] += v * k[k_idx]
+                        }
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
+struct ConvTranspose2D<'a>(&'a crate::conv::ParamsConvTranspose2D);
+
+impl<'a> Map2 for ConvTranspose2D<'a> {
+    const OP: &'static str = "conv_transpose2d";
+    fn f<T: 'static + num_traits::NumAssign + Copy>(
+        &self,
+        inp: &[T],
+        inp_l: &Layout,
+        k: &[T],
+        k_l: &Layout,
+    ) -> Result<Vec<T>> {
+        let p = self.0;
+        let inp = &inp[inp_l.start_offset()..];
+        let (inp_s0, inp_s1, inp_s2, inp_s3) = crate::shape::dims4(inp_l.stride())?;
+        let k = &k[k_l.start_offset()..];
+        let (k_s0, k_s1, k_s2, k_s3) = crate::shape::dims4(k_l.stride())?;
+        let (out_h, out_w) = (p.out_h(), p.out_w());
+        let mut dst = vec![T::zero(); p.b_size * p.c_out * out_h * out_w];
+        for b_idx in 0..p.b_size {
+            for src_c_idx in 0..p.c_in {
+                for src_h in 0..p.i_h {
+                    for src_w in 0..p.i_w {
+                        let inp_idx =
+                            b_idx * inp_s0 + src_c_idx * inp_s1 + src_h * inp_s2 + src_w * inp_s3;
+                        let v = inp[inp_idx];
+                        for offset_h in 0..p.k_h {
+                            let dst_h = p.stride * src_h + p.dilation * offset_h;
+                            if dst_h < p.padding || dst_h >= out_h + p.padding {
+                                continue;
+                            }
+                            let dst_h = dst_h - p.padding;
+                            for offset_w in 0..p.k_w {
+                                let dst_w = p.stride * src_w + p.dilation * offset_w;
+                                if dst_w < p.padding || dst_w >= out_w + p.padding {
+                                    continue;
+                                }
+                                let dst_w = dst_w - p.padding;
+                                for dst_c_idx in 0..p.c_out {
+                                    let k_idx = src_c_idx * k_s0
+                                        + dst_c_idx * k_s1
+                                        + offset_h * k_s2
+                                        + offset_w * k_s3;
+                                    let dst_idx = ((b_idx * p.c_out + dst_c_idx) * out_h + dst_h)
+                                        * out_w
+                                        + dst_w;
+                                    dst[dst_idx] += v * k[k_idx]
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(dst)
+    }
+}
+
 struct MatMul((usize, usize, usize, usize));
 
 impl MatMul {
@@ -1164,17 +1905,13 @@ impl Map2 for MatMul {
         let rhs_cs = rhs_stride[rank - 1];
         let rhs_rs = rhs_stride[rank - 2];
 
-        let a_skip: usize = match lhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * lhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => m * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
+        let a_skip: usize = match lhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
         };
-        let b_skip: usize = match rhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * rhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => n * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
+        let b_skip: usize = match rhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
         };
         let c_skip: usize = m * n;
 
@@ -1235,19 +1972,14 @@ impl Map2 for MatMul {
 
         let lhs_stride = lhs_l.stride();
         let rhs_stride = rhs_l.stride();
-        let rank = lhs_stride.len();
 
-        let a_skip: usize = match lhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * lhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => m * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
+        let a_skip: usize = match lhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
         };
-        let b_skip: usize = match rhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * rhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => n * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
+        let b_skip: usize = match rhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
         };
         let c_skip: usize = m * n;
 
@@ -1338,19 +2070,14 @@ impl Map2 for MatMul {
 
         let lhs_stride = lhs_l.stride();
         let rhs_stride = rhs_l.stride();
-        let rank = lhs_stride.len();
 
-        let a_skip: usize = match lhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * lhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => m * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
+        let a_skip: usize = match lhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous lhs"))?,
         };
-        let b_skip: usize = match rhs_stride[..rank - 2] {
-            [s1, stride] if s1 == stride * rhs_l.dims()[1] => stride,
-            [stride] => stride,
-            [] => n * k,
-            _ => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
+        let b_skip: usize = match rhs_l.matmul_batch_stride() {
+            Some(stride) => stride,
+            None => Err(self.striding_error(lhs_l, rhs_l, "non-contiguous rhs"))?,
         };
         let c_skip: usize = m * n;
 
@@ -1455,6 +2182,38 @@ impl Map2 for MatMul {
     }
 }
 
+fn cast_storage<T, U, F>(vs: &[T], layout: &Layout, dtype: DType, f: F) -> CpuStorage
+where
+    T: Copy,
+    U: num_traits::Zero
+        + PartialEq
+        + AsPrimitive<u8>
+        + AsPrimitive<u32>
+        + AsPrimitive<i8>
+        + AsPrimitive<i16>
+        + AsPrimitive<i32>
+        + AsPrimitive<i64>
+        + AsPrimitive<bf16>
+        + AsPrimitive<f16>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+    F: Fn(T) -> U,
+{
+    match dtype {
+        DType::U8 => CpuStorage::U8(unary_map(vs, layout, |v| f(v).as_())),
+        DType::U32 => CpuStorage::U32(unary_map(vs, layout, |v| f(v).as_())),
+        DType::I8 => CpuStorage::I8(unary_map(vs, layout, |v| f(v).as_())),
+        DType::I16 => CpuStorage::I16(unary_map(vs, layout, |v| f(v).as_())),
+        DType::I32 => CpuStorage::I32(unary_map(vs, layout, |v| f(v).as_())),
+        DType::I64 => CpuStorage::I64(unary_map(vs, layout, |v| f(v).as_())),
+        DType::BF16 => CpuStorage::BF16(unary_map(vs, layout, |v| f(v).as_())),
+        DType::F16 => CpuStorage::F16(unary_map(vs, layout, |v| f(v).as_())),
+        DType::F32 => CpuStorage::F32(unary_map(vs, layout, |v| f(v).as_())),
+        DType::F64 => CpuStorage::F64(unary_map(vs, layout, |v| f(v).as_())),
+        DType::Bool => CpuStorage::Bool(unary_map(vs, layout, |v| u8::from(!f(v).is_zero()))),
+    }
+}
+
 fn elu<T: num_traits::Float>(v: T, alpha: T) -> T {
     if v.is_sign_positive() {
         v
@@ -1467,6 +2226,14 @@ impl CpuStorage {
     pub fn as_slice<D: WithDType>(&self) -> Result<&[D]> {
         D::cpu_storage_as_slice(self)
     }
+
+    // Boolean storages share the u8 kernels, this is used to reject the arithmetic ones.
+    fn check_not_bool(&self, op: &'static str) -> Result<()> {
+        match self {
+            Self::Bool(_) => Err(Error::UnsupportedDTypeForOp(DType::Bool, op).bt()),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl BackendStorage for CpuStorage {
@@ -1476,166 +2243,40 @@ impl BackendStorage for CpuStorage {
         match self {
             Self::U8(_) => DType::U8,
             Self::U32(_) => DType::U32,
+            Self::I8(_) => DType::I8,
+            Self::I16(_) => DType::I16,
+            Self::I32(_) => DType::I32,
+            Self::I64(_) => DType::I64,
             Self::BF16(_) => DType::BF16,
             Self::F16(_) => DType::F16,
             Self::F32(_) => DType::F32,
             Self::F64(_) => DType::F64,
+            Self::Bool(_) => DType::Bool,
         }
     }
 
     fn to_dtype(&self, layout: &Layout, dtype: DType) -> Result<Self> {
-        // TODO: find a way around the quadratic number of cases below.
-        match (self, dtype) {
-            (Self::U8(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, |v| bf16::from_f32(v as f32));
-                Ok(Self::BF16(data))
-            }
-            (Self::U32(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, |v| bf16::from_f32(v as f32));
-                Ok(Self::BF16(data))
-            }
-            (Self::BF16(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::BF16(data))
-            }
-            (Self::F16(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, |v| bf16::from_f32(v.to_f32()));
-                Ok(Self::BF16(data))
-            }
-            (Self::F32(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, bf16::from_f32);
-                Ok(Self::BF16(data))
-            }
-            (Self::F64(storage), DType::BF16) => {
-                let data = unary_map(storage, layout, bf16::from_f64);
-                Ok(Self::BF16(data))
-            }
-            (Self::U8(storage), DType::F16) => {
-                let data = unary_map(storage, layout, |v| f16::from_f32(v as f32));
-                Ok(Self::F16(data))
-            }
-            (Self::U32(storage), DType::F16) => {
-                let data = unary_map(storage, layout, |v| f16::from_f32(v as f32));
-                Ok(Self::F16(data))
-            }
-            (Self::BF16(storage), DType::F16) => {
-                let data = unary_map(storage, layout, |v| f16::from_f32(v.to_f32()));
-                Ok(Self::F16(data))
-            }
-            (Self::F16(storage), DType::F16) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::F16(data))
-            }
-            (Self::F32(storage), DType::F16) => {
-                let data = unary_map(storage, layout, f16::from_f32);
-                Ok(Self::F16(data))
-            }
-            (Self::F64(storage), DType::F16) => {
-                let data = unary_map(storage, layout, f16::from_f64);
-                Ok(Self::F16(data))
-            }
-            (Self::U8(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v as f32);
-                Ok(Self::F32(data))
-            }
-            (Self::U32(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v as f32);
-                Ok(Self::F32(data))
-            }
-            (Self::BF16(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v.to_f32());
-                Ok(Self::F32(data))
-            }
-            (Self::F16(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v.to_f32());
-                Ok(Self::F32(data))
-            }
-            (Self::F32(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::F32(data))
-            }
-            (Self::F64(storage), DType::F32) => {
-                let data = unary_map(storage, layout, |v| v as f32);
-                Ok(Self::F32(data))
-            }
-            (Self::U8(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::U8(data))
-            }
-            (Self::BF16(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v.to_f32() as u8);
-                Ok(Self::U8(data))
-            }
-            (Self::F16(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v.to_f32() as u8);
-                Ok(Self::U8(data))
-            }
-            (Self::F32(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v as u8);
-                Ok(Self::U8(data))
-            }
-            (Self::F64(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v as u8);
-                Ok(Self::U8(data))
-            }
-            (Self::U8(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v as u32);
-                Ok(Self::U32(data))
-            }
-            (Self::U32(storage), DType::U8) => {
-                let data = unary_map(storage, layout, |v| v as u8);
-                Ok(Self::U8(data))
-            }
-            (Self::U32(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::U32(data))
-            }
-            (Self::BF16(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v.to_f32() as u32);
-                Ok(Self::U32(data))
-            }
-            (Self::F16(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v.to_f32() as u32);
-                Ok(Self::U32(data))
-            }
-            (Self::F32(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v as u32);
-                Ok(Self::U32(data))
-            }
-            (Self::F64(storage), DType::U32) => {
-                let data = unary_map(storage, layout, |v| v as u32);
-                Ok(Self::U32(data))
-            }
-            (Self::U8(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v as f64);
-                Ok(Self::F64(data))
-            }
-            (Self::U32(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v as f64);
-                Ok(Self::F64(data))
-            }
-            (Self::BF16(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v.to_f64());
-                Ok(Self::F64(data))
-            }
-            (Self::F16(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v.to_f64());
-                Ok(Self::F64(data))
-            }
-            (Self::F32(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v as f64);
-                Ok(Self::F64(data))
-            }
-            (Self::F64(storage), DType::F64) => {
-                let data = unary_map(storage, layout, |v| v);
-                Ok(Self::F64(data))
-            }
-        }
+        // The half precision floats are converted via f32, the other types use `as` casts.
+        let storage = match self {
+            Self::U8(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::U32(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::I8(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::I16(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::I32(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::I64(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::BF16(storage) => cast_storage(storage, layout, dtype, |v| v.to_f32()),
+            Self::F16(storage) => cast_storage(storage, layout, dtype, |v| v.to_f32()),
+            Self::F32(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::F64(storage) => cast_storage(storage, layout, dtype, |v| v),
+            Self::Bool(storage) => cast_storage(storage, layout, dtype, |v| v),
+        };
+        Ok(storage)
     }
 
     fn reduce_op(&self, op: ReduceOp, layout: &Layout, reduce_dims: &[usize]) -> Result<Self> {
         match op {
-            ReduceOp::Sum => {
+            ReduceOp::Sum | ReduceOp::Prod => {
+                self.check_not_bool(op.name())?;
                 let src_dims = layout.dims();
                 let mut dst_dims = src_dims.to_vec();
                 for &dim in reduce_dims.iter() {
@@ -1651,6 +2292,7 @@ impl BackendStorage for CpuStorage {
                     .map(|&d| (src_dims[d], src_dims[d + 1..].iter().product::<usize>()))
                     .collect();
                 Reduce {
+                    op,
                     dst_shape: &dst_shape,
                     reduce_dims: &reduce_dims,
                     reduce_dims_and_stride,
@@ -1689,34 +2331,59 @@ impl BackendStorage for CpuStorage {
         }
     }
 
+    fn scan(&self, op: ScanOp, layout: &Layout, dim: usize) -> Result<Self> {
+        match op {
+            ScanOp::Sum | ScanOp::Prod => self.check_not_bool(op.name())?,
+            ScanOp::LogSumExp if !self.dtype().is_float() => {
+                Err(Error::UnsupportedDTypeForOp(self.dtype(), op.name()).bt())?
+            }
+            ScanOp::ArgMax | ScanOp::LogSumExp => {}
+        }
+        Scan { op, dim }.map(self, layout)
+    }
+
+    fn arg_sort(&self, layout: &Layout, dim: usize, asc: bool) -> Result<Self> {
+        ArgSort { dim, asc }.map(self, layout)
+    }
+
     fn cmp(&self, op: CmpOp, rhs: &Self, lhs_l: &Layout, rhs_l: &Layout) -> Result<Self> {
         Cmp(op).map(self, lhs_l, rhs, rhs_l)
     }
 
     fn affine(&self, layout: &Layout, mul: f64, add: f64) -> Result<Self> {
+        self.check_not_bool("affine")?;
         Affine(mul, add).map(self, layout)
     }
 
-    fn avg_pool2d(
-        &self,
-        layout: &Layout,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
-    ) -> Result<Self> {
-        AvgPool2D(kernel_size, stride).map(self, layout)
+    fn avg_pool(&self, layout: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        self.check_not_bool("avg-pool")?;
+        Pool { params, max: false }.map(self, layout)
+    }
+
+    fn max_pool(&self, layout: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        Pool { params, max: true }.map(self, layout)
     }
 
-    fn max_pool2d(
+    fn upsample_nearest2d(&self, layout: &Layout, h: usize, w: usize) -> Result<Self> {
+        UpsampleNearest2D(h, w).map(self, layout)
+    }
+
+    fn interpolate2d(
         &self,
         layout: &Layout,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
+        h: usize,
+        w: usize,
+        mode: crate::InterpolateMode,
     ) -> Result<Self> {
-        MaxPool2D(kernel_size, stride).map(self, layout)
+        self.check_not_bool("interpolate2d")?;
+        Interpolate2D(h, w, mode).map(self, layout)
     }
 
-    fn upsample_nearest2d(&self, layout: &Layout, h: usize, w: usize) -> Result<Self> {
-        UpsampleNearest2D(h, w).map(self, layout)
+    fn fft(&self, layout: &Layout, kind: crate::fft::FftKind) -> Result<Self> {
+        match self.dtype() {
+            DType::BF16 | DType::F16 | DType::F32 | DType::F64 => Fft(kind).map(self, layout),
+            dtype => Err(Error::UnsupportedDTypeForOp(dtype, "fft").bt()),
+        }
     }
 
     fn elu(&self, layout: &Layout, alpha: f64) -> Result<Self> {
@@ -1738,8 +2405,13 @@ impl BackendStorage for CpuStorage {
                 let data = unary_map(storage, layout, |v| elu(v, alpha));
                 Ok(Self::F64(data))
             }
-            Self::U8(_) => Err(Error::UnsupportedDTypeForOp(DType::U8, "elu").bt()),
-            Self::U32(_) => Err(Error::UnsupportedDTypeForOp(DType::U32, "elu").bt()),
+            Self::U8(_)
+            | Self::U32(_)
+            | Self::I8(_)
+            | Self::I16(_)
+            | Self::I32(_)
+            | Self::I64(_)
+            | Self::Bool(_) => Err(Error::UnsupportedDTypeForOp(self.dtype(), "elu").bt()),
         }
     }
 
@@ -1789,6 +2461,23 @@ impl BackendStorage for CpuStorage {
                 let data = unary_map(storage, layout, B::u32);
                 Ok(Self::U32(data))
             }
+            Self::I8(storage) => {
+                let data = unary_map(storage, layout, B::i8);
+                Ok(Self::I8(data))
+            }
+            Self::I16(storage) => {
+                let data = unary_map(storage, layout, B::i16);
+                Ok(Self::I16(data))
+            }
+            Self::I32(storage) => {
+                let data = unary_map(storage, layout, B::i32);
+                Ok(Self::I32(data))
+            }
+            Self::I64(storage) => {
+                let data = unary_map(storage, layout, B::i64);
+                Ok(Self::I64(data))
+            }
+            Self::Bool(_) => Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt()),
         }
     }
 
@@ -1847,6 +2536,25 @@ impl BackendStorage for CpuStorage {
                 };
                 Ok(Self::U8(data))
             }
+            (Self::I8(lhs), Self::I8(rhs)) => {
+                let data = binary_map(lhs_l, rhs_l, lhs, rhs, B::i8);
+                Ok(Self::I8(data))
+            }
+            (Self::I16(lhs), Self::I16(rhs)) => {
+                let data = binary_map(lhs_l, rhs_l, lhs, rhs, B::i16);
+                Ok(Self::I16(data))
+            }
+            (Self::I32(lhs), Self::I32(rhs)) => {
+                let data = binary_map(lhs_l, rhs_l, lhs, rhs, B::i32);
+                Ok(Self::I32(data))
+            }
+            (Self::I64(lhs), Self::I64(rhs)) => {
+                let data = binary_map(lhs_l, rhs_l, lhs, rhs, B::i64);
+                Ok(Self::I64(data))
+            }
+            (Self::Bool(_), Self::Bool(_)) => {
+                Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt())
+            }
             _ => {
                 // This should be covered by the dtype check above.
                 Err(Error::DTypeMismatchBinaryOp {
@@ -1859,14 +2567,93 @@ impl BackendStorage for CpuStorage {
         }
     }
 
+    fn unary_inplace<B: UnaryOpT>(&mut self, layout: &Layout) -> Result<()> {
+        match self {
+            Self::U8(vs) => unary_inplace_(vs, layout, B::u8),
+            Self::U32(vs) => unary_inplace_(vs, layout, B::u32),
+            Self::I8(vs) => unary_inplace_(vs, layout, B::i8),
+            Self::I16(vs) => unary_inplace_(vs, layout, B::i16),
+            Self::I32(vs) => unary_inplace_(vs, layout, B::i32),
+            Self::I64(vs) => unary_inplace_(vs, layout, B::i64),
+            Self::BF16(vs) => unary_inplace_(vs, layout, B::bf16),
+            Self::F16(vs) => unary_inplace_(vs, layout, B::f16),
+            Self::F32(vs) => unary_inplace_(vs, layout, B::f32),
+            Self::F64(vs) => unary_inplace_(vs, layout, B::f64),
+            Self::Bool(_) => Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt())?,
+        }
+        Ok(())
+    }
+
+    fn binary_inplace<B: BinaryOpT>(
+        &mut self,
+        rhs: &Self,
+        lhs_l: &Layout,
+        rhs_l: &Layout,
+    ) -> Result<()> {
+        let lhs_dtype = self.dtype();
+        match (self, rhs) {
+            (Self::U8(lhs), Self::U8(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::u8),
+            (Self::U32(lhs), Self::U32(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::u32),
+            (Self::I8(lhs), Self::I8(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::i8),
+            (Self::I16(lhs), Self::I16(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::i16),
+            (Self::I32(lhs), Self::I32(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::i32),
+            (Self::I64(lhs), Self::I64(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::i64),
+            (Self::BF16(lhs), Self::BF16(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::bf16),
+            (Self::F16(lhs), Self::F16(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::f16),
+            (Self::F32(lhs), Self::F32(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::f32),
+            (Self::F64(lhs), Self::F64(rhs)) => binary_inplace_(lhs, lhs_l, rhs, rhs_l, B::f64),
+            (Self::Bool(_), Self::Bool(_)) => {
+                Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt())?
+            }
+            _ => Err(Error::DTypeMismatchBinaryOp {
+                lhs: lhs_dtype,
+                rhs: rhs.dtype(),
+                op: B::NAME,
+            }
+            .bt())?,
+        }
+        Ok(())
+    }
+
+    fn index_put(
+        &mut self,
+        l: &Layout,
+        ids: &Self,
+        ids_l: &Layout,
+        src: &Self,
+        src_l: &Layout,
+        dim: usize,
+    ) -> Result<()> {
+        fn contiguous_ids<'a, I>(ids: &'a [I], ids_l: &Layout) -> Result<&'a [I]> {
+            match ids_l.contiguous_offsets() {
+                Some((a, b)) => Ok(&ids[a..b]),
+                None => Err(Error::RequiresContiguous { op: "index-put" })?,
+            }
+        }
+        match ids {
+            Self::U8(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            Self::U32(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            Self::I8(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            Self::I16(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            Self::I32(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            Self::I64(ids) => index_put_(contiguous_ids(ids, ids_l)?, dim, self, l, src, src_l),
+            _ => Err(Error::UnsupportedDTypeForOp(ids.dtype(), "index-put").bt()),
+        }
+    }
+
     fn copy_strided_src(&self, dst: &mut Self, dst_offset: usize, src_l: &Layout) -> Result<()> {
         match (self, dst) {
             (Self::U8(src), Self::U8(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (Self::U32(src), Self::U32(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
+            (Self::I8(src), Self::I8(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
+            (Self::I16(src), Self::I16(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
+            (Self::I32(src), Self::I32(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
+            (Self::I64(src), Self::I64(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (Self::BF16(src), Self::BF16(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (Self::F16(src), Self::F16(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (Self::F32(src), Self::F32(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (Self::F64(src), Self::F64(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
+            (Self::Bool(src), Self::Bool(dst)) => copy_strided_src_(src, dst, dst_offset, src_l),
             (_, dst) => {
                 // This should be covered by the dtype check above.
                 return Err(Error::DTypeMismatchBinaryOp {
@@ -1880,6 +2667,31 @@ impl BackendStorage for CpuStorage {
         Ok(())
     }
 
+    fn copy_strided(&self, dst: &mut Self, dst_l: &Layout, src_l: &Layout) -> Result<()> {
+        match (self, dst) {
+            (Self::U8(src), Self::U8(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::U32(src), Self::U32(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::I8(src), Self::I8(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::I16(src), Self::I16(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::I32(src), Self::I32(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::I64(src), Self::I64(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::BF16(src), Self::BF16(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::F16(src), Self::F16(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::F32(src), Self::F32(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::F64(src), Self::F64(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (Self::Bool(src), Self::Bool(dst)) => copy_strided_(src, dst, dst_l, src_l),
+            (_, dst) => {
+                return Err(Error::DTypeMismatchBinaryOp {
+                    lhs: self.dtype(),
+                    rhs: dst.dtype(),
+                    op: "copy_strided",
+                }
+                .bt());
+            }
+        }
+        Ok(())
+    }
+
     fn where_cond(
         &self,
         layout: &Layout,
@@ -1889,8 +2701,12 @@ impl BackendStorage for CpuStorage {
         f_l: &Layout,
     ) -> Result<Self> {
         match self {
-            Self::U8(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
+            Self::U8(pred) | Self::Bool(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
             Self::U32(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
+            Self::I8(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
+            Self::I16(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
+            Self::I32(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
+            Self::I64(pred) => WCond(pred, layout).map(t, t_l, f, f_l),
             _ => Err(Error::UnsupportedDTypeForOp(self.dtype(), "where-cond")),
         }
     }
@@ -1902,7 +2718,15 @@ impl BackendStorage for CpuStorage {
         kernel_l: &Layout,
         params: &crate::conv::ParamsConv1D,
     ) -> Result<Self> {
-        Conv1D(params).map(self, l, kernel, kernel_l)
+        self.check_not_bool("conv1d")?;
+        if !use_im2col(self.dtype()) {
+            return Conv1D(params).map(self, l, kernel, kernel_l);
+        }
+        let p = params;
+        let dims = (p.b_size, p.c_in, p.c_out, p.groups);
+        self.conv_im2col(l, kernel, kernel_l, dims, p.l_out(), p.k_size, |l| {
+            Im2Col1D(p).map(self, l)
+        })
     }
 
     fn conv2d(
@@ -1912,13 +2736,68 @@ impl BackendStorage for CpuStorage {
         kernel_l: &Layout,
         params: &crate::conv::ParamsConv2D,
     ) -> Result<Self> {
-        Conv2D(params).map(self, l, kernel, kernel_l)
+        self.check_not_bool("conv2d")?;
+        if !use_im2col(self.dtype()) {
+            return Conv2D(params).map(self, l, kernel, kernel_l);
+        }
+        let p = params;
+        let dims = (p.b_size, p.c_in, p.c_out, p.groups);
+        let n_out = p.out_h() * p.out_w();
+        self.conv_im2col(l, kernel, kernel_l, dims, n_out, p.k_h * p.k_w, |l| {
+            Im2Col2D(p).map(self, l)
+        })
+    }
+
+    fn conv3d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv3D,
+    ) -> Result<Self> {
+        self.check_not_bool("conv3d")?;
+        if !use_im2col(self.dtype()) {
+            return Conv3D(params).map(self, l, kernel, kernel_l);
+        }
+        let p = params;
+        let dims = (p.b_size, p.c_in, p.c_out, p.groups);
+        let n_out = p.out_d() * p.out_h() * p.out_w();
+        let k_elems = p.k_d * p.k_h * p.k_w;
+        self.conv_im2col(l, kernel, kernel_l, dims, n_out, k_elems, |l| {
+            Im2Col3D(p).map(self, l)
+        })
+    }
+
+    fn conv_transpose1d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose1D,
+    ) -> Result<Self> {
+        self.check_not_bool("conv_transpose1d")?;
+        ConvTranspose1D(params).map(self, l, kernel, kernel_l)
+    }
+
+    fn conv_transpose2d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose2D,
+    ) -> Result<Self> {
+        self.check_not_bool("conv_transpose2d")?;
+        ConvTranspose2D(params).map(self, l, kernel, kernel_l)
     }
 
     fn index_select(&self, ids: &Self, l: &Layout, ids_l: &Layout, dim: usize) -> Result<Self> {
         match ids {
             Self::U8(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
             Self::U32(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
+            Self::I8(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
+            Self::I16(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
+            Self::I32(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
+            Self::I64(ids) => IndexSelect { ids, ids_l, dim }.map(self, l),
             _ => Err(Error::UnsupportedDTypeForOp(self.dtype(), "index-select")),
         }
     }
@@ -1927,6 +2806,10 @@ impl BackendStorage for CpuStorage {
         match ids {
             Self::U8(ids) => Gather { ids, ids_l, dim }.map(self, l),
             Self::U32(ids) => Gather { ids, ids_l, dim }.map(self, l),
+            Self::I8(ids) => Gather { ids, ids_l, dim }.map(self, l),
+            Self::I16(ids) => Gather { ids, ids_l, dim }.map(self, l),
+            Self::I32(ids) => Gather { ids, ids_l, dim }.map(self, l),
+            Self::I64(ids) => Gather { ids, ids_l, dim }.map(self, l),
             _ => Err(Error::UnsupportedDTypeForOp(self.dtype(), "gather")),
         }
     }
@@ -1943,6 +2826,10 @@ impl BackendStorage for CpuStorage {
         match ids {
             Self::U8(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
             Self::U32(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
+            Self::I8(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
+            Self::I16(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
+            Self::I32(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
+            Self::I64(ids) => ScatterAdd { ids, ids_l, dim }.map(self, l, src, src_l),
             _ => Err(Error::UnsupportedDTypeForOp(self.dtype(), "scatter-add")),
         }
     }
@@ -1956,19 +2843,35 @@ impl BackendStorage for CpuStorage {
         src_l: &Layout,
         dim: usize,
     ) -> Result<Self> {
+        fn contiguous_ids<'a, I>(ids: &'a [I], ids_l: &Layout) -> Result<&'a [I]> {
+            match ids_l.contiguous_offsets() {
+                Some((a, b)) => Ok(&ids[a..b]),
+                None => Err(Error::RequiresContiguous { op: "index-add" })?,
+            }
+        }
         match ids {
             Self::U8(ids) => {
-                let ids = match ids_l.contiguous_offsets() {
-                    Some((a, b)) => &ids[a..b],
-                    None => Err(Error::RequiresContiguous { op: "index-add" })?,
-                };
+                let ids = contiguous_ids(ids, ids_l)?;
                 IndexAdd { ids, dim }.map(self, l, src, src_l)
             }
             Self::U32(ids) => {
-                let ids = match ids_l.contiguous_offsets() {
-                    Some((a, b)) => &ids[a..b],
-                    None => Err(Error::RequiresContiguous { op: "index-add" })?,
-                };
+                let ids = contiguous_ids(ids, ids_l)?;
+                IndexAdd { ids, dim }.map(self, l, src, src_l)
+            }
+            Self::I8(ids) => {
+                let ids = contiguous_ids(ids, ids_l)?;
+                IndexAdd { ids, dim }.map(self, l, src, src_l)
+            }
+            Self::I16(ids) => {
+                let ids = contiguous_ids(ids, ids_l)?;
+                IndexAdd { ids, dim }.map(self, l, src, src_l)
+            }
+            Self::I32(ids) => {
+                let ids = contiguous_ids(ids, ids_l)?;
+                IndexAdd { ids, dim }.map(self, l, src, src_l)
+            }
+            Self::I64(ids) => {
+                let ids = contiguous_ids(ids, ids_l)?;
                 IndexAdd { ids, dim }.map(self, l, src, src_l)
             }
             _ => Err(Error::UnsupportedDTypeForOp(self.dtype(), "index-add")),
@@ -1982,6 +2885,7 @@ impl BackendStorage for CpuStorage {
         lhs_l: &Layout,
         rhs_l: &Layout,
     ) -> Result<Self> {
+        self.check_not_bool("matmul")?;
         MatMul(bmnk).map(self, lhs_l, rhs, rhs_l)
     }
 
@@ -2023,7 +2927,13 @@ impl BackendDevice for CpuDevice {
         let elem_count = shape.elem_count();
         let mut rng = rand::thread_rng();
         match dtype {
-            DType::U8 | DType::U32 => Err(Error::UnsupportedDTypeForOp(dtype, "rand_uniform").bt()),
+            DType::U8
+            | DType::U32
+            | DType::I8
+            | DType::I16
+            | DType::I32
+            | DType::I64
+            | DType::Bool => Err(Error::UnsupportedDTypeForOp(dtype, "rand_uniform").bt()),
             DType::BF16 => {
                 let mut data = Vec::with_capacity(elem_count);
                 let uniform =
@@ -2067,7 +2977,13 @@ impl BackendDevice for CpuDevice {
         let elem_count = shape.elem_count();
         let mut rng = rand::thread_rng();
         match dtype {
-            DType::U8 | DType::U32 => Err(Error::UnsupportedDTypeForOp(dtype, "rand_normal").bt()),
+            DType::U8
+            | DType::U32
+            | DType::I8
+            | DType::I16
+            | DType::I32
+            | DType::I64
+            | DType::Bool => Err(Error::UnsupportedDTypeForOp(dtype, "rand_normal").bt()),
             DType::BF16 => {
                 let mut data = Vec::with_capacity(elem_count);
                 let normal = rand_distr::Normal::new(bf16::from_f64(mean), bf16::from_f64(std))
@@ -2111,10 +3027,15 @@ impl BackendDevice for CpuDevice {
         let storage = match dtype {
             DType::U8 => CpuStorage::U8(vec![1u8; elem_count]),
             DType::U32 => CpuStorage::U32(vec![1u32; elem_count]),
+            DType::I8 => CpuStorage::I8(vec![1i8; elem_count]),
+            DType::I16 => CpuStorage::I16(vec![1i16; elem_count]),
+            DType::I32 => CpuStorage::I32(vec![1i32; elem_count]),
+            DType::I64 => CpuStorage::I64(vec![1i64; elem_count]),
             DType::BF16 => CpuStorage::BF16(vec![bf16::ONE; elem_count]),
             DType::F16 => CpuStorage::F16(vec![f16::ONE; elem_count]),
             DType::F32 => CpuStorage::F32(vec![1f32; elem_count]),
             DType::F64 => CpuStorage::F64(vec![1f64; elem_count]),
+            DType::Bool => CpuStorage::Bool(vec![1u8; elem_count]),
         };
         Ok(storage)
     }
@@ -2124,10 +3045,15 @@ impl BackendDevice for CpuDevice {
         let storage = match dtype {
             DType::U8 => CpuStorage::U8(vec![0u8; elem_count]),
             DType::U32 => CpuStorage::U32(vec![0u32; elem_count]),
+            DType::I8 => CpuStorage::I8(vec![0i8; elem_count]),
+            DType::I16 => CpuStorage::I16(vec![0i16; elem_count]),
+            DType::I32 => CpuStorage::I32(vec![0i32; elem_count]),
+            DType::I64 => CpuStorage::I64(vec![0i64; elem_count]),
             DType::BF16 => CpuStorage::BF16(vec![bf16::ZERO; elem_count]),
             DType::F16 => CpuStorage::F16(vec![f16::ZERO; elem_count]),
             DType::F32 => CpuStorage::F32(vec![0f32; elem_count]),
             DType::F64 => CpuStorage::F64(vec![0f64; elem_count]),
+            DType::Bool => CpuStorage::Bool(vec![0u8; elem_count]),
         };
         Ok(storage)
     }
@@ -1,5 +1,5 @@
 use crate::backend::{BackendDevice, BackendStorage};
-use crate::op::{BinaryOpT, CmpOp, ReduceOp, UnaryOpT};
+use crate::op::{BinaryOpT, CmpOp, ReduceOp, ScanOp, UnaryOpT};
 use crate::{CpuStorage, DType, Layout, Result, Shape, WithDType};
 use candle_kernels as kernels;
 pub use cudarc;
@@ -115,13 +115,17 @@ impl CudaDevice {
         let elem_count = shape.elem_count();
         let cfg = LaunchConfig::for_num_elems(elem_count as u32);
         let slice = match dtype {
-            DType::U8 => {
+            DType::U8 | DType::Bool => {
                 // SAFETY: Set later by running the fill kernel.
                 let data = unsafe { self.alloc::<u8>(elem_count) }.w()?;
                 let func = self.get_or_load_func("fill_u8", kernels::FILL)?;
                 let params = (&data, v as u8, elem_count);
                 unsafe { func.launch(cfg, params) }.w()?;
-                CudaStorageSlice::U8(data)
+                if dtype == DType::Bool {
+                    CudaStorageSlice::Bool(data)
+                } else {
+                    CudaStorageSlice::U8(data)
+                }
             }
             DType::U32 => {
                 // SAFETY: Set later by running the fill kernel.
@@ -163,6 +167,9 @@ impl CudaDevice {
                 unsafe { func.launch(cfg, params) }.w()?;
                 CudaStorageSlice::F64(data)
             }
+            DType::I8 | DType::I16 | DType::I32 | DType::I64 => {
+                Err(CudaError::UnsupportedDtype { dtype, op: "fill" }).w()?
+            }
         };
         Ok(CudaStorage {
             slice,
@@ -224,6 +231,10 @@ impl BackendDevice for CudaDevice {
                 let data = self.alloc_zeros::<u8>(elem_count).w()?;
                 CudaStorageSlice::U8(data)
             }
+            DType::Bool => {
+                let data = self.alloc_zeros::<u8>(elem_count).w()?;
+                CudaStorageSlice::Bool(data)
+            }
             DType::U32 => {
                 let data = self.alloc_zeros::<u32>(elem_count).w()?;
                 CudaStorageSlice::U32(data)
@@ -244,6 +255,9 @@ impl BackendDevice for CudaDevice {
                 let data = self.alloc_zeros::<f64>(elem_count).w()?;
                 CudaStorageSlice::F64(data)
             }
+            DType::I8 | DType::I16 | DType::I32 | DType::I64 => {
+                Err(CudaError::UnsupportedDtype { dtype, op: "zeros" }).w()?
+            }
         };
         Ok(CudaStorage {
             slice,
@@ -257,7 +271,15 @@ impl BackendDevice for CudaDevice {
         let slice = match dtype {
             // TODO: Add support for F16 and BF16 though this is likely to require some upstream
             // cudarc changes.
-            DType::U8 | DType::U32 | DType::F16 | DType::BF16 => Err(CudaError::UnsupportedDtype {
+            DType::U8
+            | DType::U32
+            | DType::I8
+            | DType::I16
+            | DType::I32
+            | DType::I64
+            | DType::Bool
+            | DType::F16
+            | DType::BF16 => Err(CudaError::UnsupportedDtype {
                 dtype,
                 op: "rand_uniform",
             })
@@ -289,7 +311,15 @@ impl BackendDevice for CudaDevice {
         let elem_count = shape.elem_count();
         let curand = self.curand.lock().unwrap();
         let slice = match dtype {
-            DType::U8 | DType::U32 | DType::F16 | DType::BF16 => Err(CudaError::UnsupportedDtype {
+            DType::U8
+            | DType::U32
+            | DType::I8
+            | DType::I16
+            | DType::I32
+            | DType::I64
+            | DType::Bool
+            | DType::F16
+            | DType::BF16 => Err(CudaError::UnsupportedDtype {
                 dtype,
                 op: "rand_normal",
             })
@@ -324,6 +354,10 @@ impl BackendDevice for CudaDevice {
                 let data = self.htod_sync_copy(storage).w()?;
                 CudaStorageSlice::U8(data)
             }
+            CpuStorage::Bool(storage) => {
+                let data = self.htod_sync_copy(storage).w()?;
+                CudaStorageSlice::Bool(data)
+            }
             CpuStorage::U32(storage) => {
                 let data = self.htod_sync_copy(storage).w()?;
                 CudaStorageSlice::U32(data)
@@ -344,6 +378,13 @@ impl BackendDevice for CudaDevice {
                 let data = self.htod_sync_copy(storage).w()?;
                 CudaStorageSlice::F64(data)
             }
+            CpuStorage::I8(_) | CpuStorage::I16(_) | CpuStorage::I32(_) | CpuStorage::I64(_) => {
+                Err(CudaError::UnsupportedDtype {
+                    dtype: storage.dtype(),
+                    op: "storage_from_cpu_storage",
+                })
+                .w()?
+            }
         };
         Ok(CudaStorage {
             slice,
@@ -355,6 +396,8 @@ impl BackendDevice for CudaDevice {
 #[derive(Debug)]
 enum CudaStorageSlice {
     U8(CudaSlice<u8>),
+    // Booleans are stored as `u8` values that are either 0 or 1 and share the u8 kernels.
+    Bool(CudaSlice<u8>),
     U32(CudaSlice<u32>),
     BF16(CudaSlice<bf16>),
     F16(CudaSlice<f16>),
@@ -374,6 +417,7 @@ trait Map1 {
     fn map(&self, s: &S, d: &CudaDevice, l: &Layout) -> Result<S> {
         let out = match s {
             S::U8(s) => S::U8(self.f(s, d, l)?),
+            S::Bool(s) => S::Bool(self.f(s, d, l)?),
             S::U32(s) => S::U32(self.f(s, d, l)?),
             S::BF16(s) => S::BF16(self.f(s, d, l)?),
             S::F16(s) => S::F16(self.f(s, d, l)?),
@@ -397,6 +441,7 @@ trait Map2 {
     fn map(&self, s1: &S, l1: &Layout, s2: &S, l2: &Layout, d: &CudaDevice) -> Result<S> {
         let out = match (s1, s2) {
             (S::U8(s1), S::U8(s2)) => S::U8(self.f(s1, l1, s2, l2, d)?),
+            (S::Bool(s1), S::Bool(s2)) => S::Bool(self.f(s1, l1, s2, l2, d)?),
             (S::U32(s1), S::U32(s2)) => S::U32(self.f(s1, l1, s2, l2, d)?),
             (S::BF16(s1), S::BF16(s2)) => S::BF16(self.f(s1, l1, s2, l2, d)?),
             (S::F16(s1), S::F16(s2)) => S::F16(self.f(s1, l1, s2, l2, d)?),
@@ -427,7 +472,9 @@ trait Map2InPlace {
         d: &CudaDevice,
     ) -> Result<()> {
         match (dst, src) {
-            (S::U8(dst), S::U8(src)) => self.f(dst, dst_s, src, src_l, d),
+            (S::U8(dst), S::U8(src)) | (S::Bool(dst), S::Bool(src)) => {
+                self.f(dst, dst_s, src, src_l, d)
+            }
             (S::U32(dst), S::U32(src)) => self.f(dst, dst_s, src, src_l, d),
             (S::BF16(dst), S::BF16(src)) => self.f(dst, dst_s, src, src_l, d),
             (S::F16(dst), S::F16(src)) => self.f(dst, dst_s, src, src_l, d),
@@ -450,6 +497,7 @@ trait Map1Any {
     fn map(&self, s: &S, d: &CudaDevice, l: &Layout) -> Result<S> {
         let out = match s {
             S::U8(s) => self.f(s, d, l, S::U8)?,
+            S::Bool(s) => self.f(s, d, l, S::Bool)?,
             S::U32(s) => self.f(s, d, l, S::U32)?,
             S::BF16(s) => self.f(s, d, l, S::BF16)?,
             S::F16(s) => self.f(s, d, l, S::F16)?,
@@ -472,7 +520,7 @@ trait Map2Any {
 
     fn map(&self, s1: &S, l1: &Layout, s2: &S, l2: &Layout, d: &CudaDevice) -> Result<S> {
         let out = match (s1, s2) {
-            (S::U8(s1), S::U8(s2)) => self.f(s1, l1, s2, l2, d)?,
+            (S::U8(s1), S::U8(s2)) | (S::Bool(s1), S::Bool(s2)) => self.f(s1, l1, s2, l2, d)?,
             (S::U32(s1), S::U32(s2)) => self.f(s1, l1, s2, l2, d)?,
             (S::BF16(s1), S::BF16(s2)) => self.f(s1, l1, s2, l2, d)?,
             (S::F16(s1), S::F16(s2)) => self.f(s1, l1, s2, l2, d)?,
@@ -644,6 +692,7 @@ impl<'a> Map1Any for FastReduce<'a> {
             ReduceOp::Max => ("fast_max", true, false),
             ReduceOp::ArgMin => ("fast_argmin", true, true),
             ReduceOp::ArgMax => ("fast_argmax", true, true),
+            ReduceOp::Prod => crate::bail!("no cuda kernel for prod"),
         };
         if check_empty && layout.shape().elem_count() == 0 {
             Err(crate::Error::EmptyTensor { op: "reduce" }.bt())?
@@ -894,7 +943,7 @@ impl<'a> Map2 for Conv1D<'a> {
         k_l: &Layout,
         dev: &CudaDevice,
     ) -> Result<CudaSlice<T>> {
-        // Kernel shape: (c_out, c_in_k, k_size)
+        // Kernel shape: (c_out, c_in / groups, k_size)
         // Input shape: (b_size, c_in, l_in) or (c_in, l_in)
         let p = &self.0;
 
@@ -917,7 +966,9 @@ impl<'a> Map2 for Conv1D<'a> {
             panic!("unexpected input shape for conv1d {dims:?}")
         };
         let ds = dev.htod_copy(ds).w()?;
-        let params = (el, l_out, p.stride, &ds, inp, k, &out);
+        let params = (
+            el, l_out, p.stride, p.padding, p.dilation, p.groups, &ds, inp, k, &out,
+        );
         // SAFETY: ffi.
         unsafe { func.launch(cfg, params) }.w()?;
         Ok(out)
@@ -936,7 +987,7 @@ impl<'a> Map2 for WhereCond<'a> {
     ) -> Result<CudaSlice<T>> {
         let ids_l = &self.1;
         let (ids, name) = match &self.0.slice {
-            CudaStorageSlice::U8(slice) => {
+            CudaStorageSlice::U8(slice) | CudaStorageSlice::Bool(slice) => {
                 let ptr = *slice.slice(ids_l.start_offset()..).device_ptr();
                 (ptr, "where_u8")
             }
@@ -1031,7 +1082,7 @@ impl Map2Any for Cmp {
         let params = (elem_count, dims.len(), &dims_and_strides, lhs, rhs, &out);
         // SAFETY: ffi
         unsafe { func.launch(cfg, params) }.w()?;
-        Ok(S::U8(out))
+        Ok(S::Bool(out))
     }
 }
 
@@ -1054,6 +1105,35 @@ fn slice_src_and_dst<'a, T>(
     (src, dst)
 }
 
+// Copies a contiguous source to the blocks of a strided destination.
+fn copy_blocks<T: DeviceRepr>(
+    dev: &CudaDevice,
+    src: &CudaSlice<T>,
+    src_offset: usize,
+    dst: &mut CudaSlice<T>,
+    dst_l: &Layout,
+) -> Result<()> {
+    match dst_l.strided_blocks() {
+        crate::StridedBlocks::SingleBlock { start_offset, len } => {
+            let src = src.slice(src_offset..src_offset + len);
+            let mut dst = dst.slice_mut(start_offset..start_offset + len);
+            dev.dtod_copy(&src, &mut dst).w()?
+        }
+        crate::StridedBlocks::MultipleBlocks {
+            block_start_index,
+            block_len,
+        } => {
+            for (block_idx, dst_offset) in block_start_index.enumerate() {
+                let src_offset = src_offset + block_idx * block_len;
+                let src = src.slice(src_offset..src_offset + block_len);
+                let mut dst = dst.slice_mut(dst_offset..dst_offset + block_len);
+                dev.dtod_copy(&src, &mut dst).w()?
+            }
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct CudaStorage {
     slice: CudaStorageSlice,
@@ -1102,6 +1182,16 @@ impl CudaStorage {
     pub fn as_cuda_slice<T: CudaDType>(&self) -> Result<&CudaSlice<T>> {
         T::as_cuda_slice(self)
     }
+
+    // Boolean storages share the u8 kernels, this is used to reject the arithmetic ones.
+    fn check_not_bool(&self, op: &'static str) -> Result<()> {
+        match self.slice {
+            CudaStorageSlice::Bool(_) => {
+                Err(crate::Error::UnsupportedDTypeForOp(DType::Bool, op).bt())
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 fn gemm_config<T>(
@@ -1159,21 +1249,17 @@ fn gemm_config<T>(
         transb,
     };
 
-    let stride_b: usize = match lhs_stride[..lhs_stride.len() - 2] {
-        [s1, stride] if s1 == stride * lhs_l.dims()[1] => stride,
-        [stride] => stride,
-        [] => m * k,
-        _ => Err(CudaError::MatMulNonContiguous {
+    let stride_b: usize = match lhs_l.matmul_batch_stride() {
+        Some(stride) => stride,
+        None => Err(CudaError::MatMulNonContiguous {
             lhs_stride: lhs_stride.to_vec(),
             rhs_stride: rhs_stride.to_vec(),
             mnk: (m, n, k),
         })?,
     };
-    let stride_a: usize = match rhs_stride[..rhs_stride.len() - 2] {
-        [s1, stride] if s1 == stride * rhs_l.dims()[1] => stride,
-        [stride] => stride,
-        [] => n * k,
-        _ => Err(CudaError::MatMulNonContiguous {
+    let stride_a: usize = match rhs_l.matmul_batch_stride() {
+        Some(stride) => stride,
+        None => Err(CudaError::MatMulNonContiguous {
             lhs_stride: lhs_stride.to_vec(),
             rhs_stride: rhs_stride.to_vec(),
             mnk: (m, n, k),
@@ -1201,6 +1287,7 @@ impl BackendStorage for CudaStorage {
     fn dtype(&self) -> DType {
         match self.slice {
             CudaStorageSlice::U8(_) => DType::U8,
+            CudaStorageSlice::Bool(_) => DType::Bool,
             CudaStorageSlice::U32(_) => DType::U32,
             CudaStorageSlice::BF16(_) => DType::BF16,
             CudaStorageSlice::F16(_) => DType::F16,
@@ -1214,6 +1301,14 @@ impl BackendStorage for CudaStorage {
     }
 
     fn to_dtype(&self, layout: &Layout, dtype: DType) -> Result<Self> {
+        if dtype == DType::Bool {
+            // Any non-zero value maps to true, as on the cpu.
+            let zeros = self
+                .device()
+                .zeros_impl(&crate::shape::SCALAR, self.dtype())?;
+            let zeros_l = Layout::contiguous(crate::shape::SCALAR).broadcast_as(layout.shape())?;
+            return self.cmp(CmpOp::Ne, &zeros, layout, &zeros_l);
+        }
         let shape = layout.shape();
         let dims = shape.dims();
         let el = shape.elem_count();
@@ -1225,7 +1320,9 @@ impl BackendStorage for CudaStorage {
         // lifetime issue and is safe as long as self.slice does not go out of scope before inp
         // is used.
         let inp = match &self.slice {
-            CudaStorageSlice::U8(inp) => *inp.slice(start_o..).device_ptr(),
+            CudaStorageSlice::U8(inp) | CudaStorageSlice::Bool(inp) => {
+                *inp.slice(start_o..).device_ptr()
+            }
             CudaStorageSlice::U32(inp) => *inp.slice(start_o..).device_ptr(),
             CudaStorageSlice::BF16(inp) => *inp.slice(start_o..).device_ptr(),
             CudaStorageSlice::F16(inp) => *inp.slice(start_o..).device_ptr(),
@@ -1234,7 +1331,12 @@ impl BackendStorage for CudaStorage {
         };
         let inp = &inp;
 
-        let kernel_name = format!("cast_{}_{}", self.dtype().as_str(), dtype.as_str());
+        // Booleans share the u8 kernels.
+        let src_dtype = match self.dtype() {
+            DType::Bool => DType::U8,
+            src_dtype => src_dtype,
+        };
+        let kernel_name = format!("cast_{}_{}", src_dtype.as_str(), dtype.as_str());
         let func = dev.get_or_load_func(&kernel_name, kernels::CAST)?;
         let slice = match dtype {
             DType::U8 => {
@@ -1273,6 +1375,13 @@ impl BackendStorage for CudaStorage {
                 unsafe { func.launch(cfg, params) }.w()?;
                 CudaStorageSlice::F64(out)
             }
+            DType::I8 | DType::I16 | DType::I32 | DType::I64 | DType::Bool => {
+                Err(CudaError::UnsupportedDtype {
+                    dtype,
+                    op: "to_dtype",
+                })
+                .w()?
+            }
         };
         Ok(Self {
             slice,
@@ -1281,23 +1390,48 @@ impl BackendStorage for CudaStorage {
     }
 
     fn affine(&self, layout: &Layout, mul: f64, add: f64) -> Result<Self> {
+        self.check_not_bool("affine")?;
         let device = self.device().clone();
         let slice = Affine(mul, add).map(&self.slice, &device, layout)?;
         Ok(Self { slice, device })
     }
 
     fn elu(&self, layout: &Layout, alpha: f64) -> Result<Self> {
+        self.check_not_bool("elu")?;
         let device = self.device().clone();
         let slice = Elu(alpha).map(&self.slice, &device, layout)?;
         Ok(Self { slice, device })
     }
 
     fn reduce_op(&self, op: ReduceOp, layout: &Layout, sum_dims: &[usize]) -> Result<Self> {
+        if op == ReduceOp::Prod {
+            // There is no product kernel yet so the reduction runs on the host.
+            let cpu_storage = self.to_cpu_storage()?;
+            let cpu_storage = cpu_storage.reduce_op(op, layout, sum_dims)?;
+            return self.device().storage_from_cpu_storage(&cpu_storage);
+        }
+        if op == ReduceOp::Sum {
+            self.check_not_bool(op.name())?;
+        }
         let device = self.device().clone();
         let slice = FastReduce(sum_dims, op).map(&self.slice, &device, layout)?;
         Ok(Self { slice, device })
     }
 
+    fn scan(&self, op: ScanOp, layout: &Layout, dim: usize) -> Result<Self> {
+        // There is no scan kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?;
+        let cpu_storage = cpu_storage.scan(op, layout, dim)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
+    fn arg_sort(&self, layout: &Layout, dim: usize, asc: bool) -> Result<Self> {
+        // There is no sorting kernel yet so the sort happens on the host.
+        let cpu_storage = self.to_cpu_storage()?;
+        let cpu_storage = cpu_storage.arg_sort(layout, dim, asc)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
     fn cmp(&self, op: CmpOp, rhs: &Self, lhs_l: &Layout, rhs_l: &Layout) -> Result<Self> {
         let device = self.device().clone();
         let slice = Cmp(op).map(&self.slice, lhs_l, &rhs.slice, rhs_l, &device)?;
@@ -1305,6 +1439,7 @@ impl BackendStorage for CudaStorage {
     }
 
     fn unary_impl<U: UnaryOpT>(&self, layout: &Layout) -> Result<Self> {
+        self.check_not_bool(U::NAME)?;
         let device = self.device().clone();
         let slice = U::V.map(&self.slice, &device, layout)?;
         Ok(Self { slice, device })
@@ -1316,11 +1451,46 @@ impl BackendStorage for CudaStorage {
         lhs_l: &Layout,
         rhs_l: &Layout,
     ) -> Result<Self> {
+        self.check_not_bool(B::NAME)?;
         let device = self.device().clone();
         let slice = B::V.map(&self.slice, lhs_l, &rhs.slice, rhs_l, &device)?;
         Ok(Self { slice, device })
     }
 
+    // The kernels cannot write to their input so the in place ops compute their result in a
+    // new buffer that is then copied back.
+    fn unary_inplace<U: UnaryOpT>(&mut self, layout: &Layout) -> Result<()> {
+        let res = self.unary_impl::<U>(layout)?;
+        res.copy_strided(self, layout, &Layout::contiguous(layout.shape()))
+    }
+
+    fn binary_inplace<B: BinaryOpT>(
+        &mut self,
+        rhs: &Self,
+        lhs_l: &Layout,
+        rhs_l: &Layout,
+    ) -> Result<()> {
+        let res = self.binary_impl::<B>(rhs, lhs_l, rhs_l)?;
+        res.copy_strided(self, lhs_l, &Layout::contiguous(lhs_l.shape()))
+    }
+
+    fn index_put(
+        &mut self,
+        l: &Layout,
+        ids: &Self,
+        ids_l: &Layout,
+        src: &Self,
+        src_l: &Layout,
+        dim: usize,
+    ) -> Result<()> {
+        // There is no index-put kernel yet so the update runs on the host.
+        let mut cpu_storage = self.to_cpu_storage()?;
+        let (ids, src) = (ids.to_cpu_storage()?, src.to_cpu_storage()?);
+        cpu_storage.index_put(l, &ids, ids_l, &src, src_l, dim)?;
+        *self = self.device().storage_from_cpu_storage(&cpu_storage)?;
+        Ok(())
+    }
+
     fn to_cpu_storage(&self) -> Result<CpuStorage> {
         match &self.slice {
             CudaStorageSlice::U8(slice) => {
@@ -1328,6 +1498,11 @@ impl BackendStorage for CudaStorage {
                 let cpu_storage = dev.dtoh_sync_copy(slice).w()?;
                 Ok(CpuStorage::U8(cpu_storage))
             }
+            CudaStorageSlice::Bool(slice) => {
+                let dev = slice.device();
+                let cpu_storage = dev.dtoh_sync_copy(slice).w()?;
+                Ok(CpuStorage::Bool(cpu_storage))
+            }
             CudaStorageSlice::U32(slice) => {
                 let dev = slice.device();
                 let cpu_storage = dev.dtoh_sync_copy(slice).w()?;
@@ -1376,6 +1551,7 @@ impl BackendStorage for CudaStorage {
         kernel_l: &Layout,
         params: &crate::conv::ParamsConv1D,
     ) -> Result<Self> {
+        self.check_not_bool("conv1d")?;
         let device = self.device().clone();
         let slice = Conv1D(params).map(&self.slice, l, &kernel.slice, kernel_l, &device)?;
         Ok(Self { slice, device })
@@ -1391,18 +1567,81 @@ impl BackendStorage for CudaStorage {
         todo!()
     }
 
-    fn avg_pool2d(&self, _: &Layout, _: (usize, usize), _: (usize, usize)) -> Result<Self> {
-        todo!()
+    fn conv3d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv3D,
+    ) -> Result<Self> {
+        // There is no 3d convolution kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?;
+        let cpu_kernel = kernel.to_cpu_storage()?;
+        let cpu_storage = cpu_storage.conv3d(l, &cpu_kernel, kernel_l, params)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
     }
 
-    fn max_pool2d(&self, _: &Layout, _: (usize, usize), _: (usize, usize)) -> Result<Self> {
-        todo!()
+    fn conv_transpose1d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose1D,
+    ) -> Result<Self> {
+        // There is no transposed convolution kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?;
+        let cpu_kernel = kernel.to_cpu_storage()?;
+        let cpu_storage = cpu_storage.conv_transpose1d(l, &cpu_kernel, kernel_l, params)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
+    fn conv_transpose2d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose2D,
+    ) -> Result<Self> {
+        // There is no transposed convolution kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?;
+        let cpu_kernel = kernel.to_cpu_storage()?;
+        let cpu_storage = cpu_storage.conv_transpose2d(l, &cpu_kernel, kernel_l, params)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
+    fn avg_pool(&self, l: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        // There is no pooling kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?.avg_pool(l, params)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
+    fn max_pool(&self, l: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        let cpu_storage = self.to_cpu_storage()?.max_pool(l, params)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
     }
 
     fn upsample_nearest2d(&self, _: &Layout, _: usize, _: usize) -> Result<Self> {
         todo!()
     }
 
+    fn interpolate2d(
+        &self,
+        l: &Layout,
+        h: usize,
+        w: usize,
+        mode: crate::InterpolateMode,
+    ) -> Result<Self> {
+        // There is no interpolation kernel yet so the op runs on the host.
+        let cpu_storage = self.to_cpu_storage()?.interpolate2d(l, h, w, mode)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
+    fn fft(&self, l: &Layout, kind: crate::fft::FftKind) -> Result<Self> {
+        // There is no cufft binding yet so the transforms run on the host.
+        let cpu_storage = self.to_cpu_storage()?.fft(l, kind)?;
+        self.device().storage_from_cpu_storage(&cpu_storage)
+    }
+
     fn index_select(&self, ids: &Self, l: &Layout, ids_l: &Layout, dim: usize) -> Result<Self> {
         let device = self.device().clone();
         let slice = IndexSelect(ids, ids_l, dim).map(&self.slice, &device, l)?;
@@ -1556,7 +1795,8 @@ impl BackendStorage for CudaStorage {
                     unsafe { func.launch(cfg, params) }.w()?
                 }
             }
-            (CudaStorageSlice::U8(src), CudaStorageSlice::U8(dst)) => {
+            (CudaStorageSlice::U8(src), CudaStorageSlice::U8(dst))
+            | (CudaStorageSlice::Bool(src), CudaStorageSlice::Bool(dst)) => {
                 let (src, mut dst) = slice_src_and_dst(src, src_l, dst, dst_offset);
                 if src_l.is_contiguous() {
                     dev.dtod_copy(&src, &mut dst).w()?
@@ -1598,4 +1838,47 @@ impl BackendStorage for CudaStorage {
         }
         Ok(())
     }
+
+    fn copy_strided(&self, dst: &mut Self, dst_l: &Layout, src_l: &Layout) -> Result<()> {
+        if let Some((dst_offset, _)) = dst_l.contiguous_offsets() {
+            return self.copy_strided_src(dst, dst_offset, src_l);
+        }
+        // The source is made contiguous first so that it can be copied block by block.
+        let contiguous;
+        let (src, src_offset) = match src_l.contiguous_offsets() {
+            Some((src_offset, _)) => (self, src_offset),
+            None => {
+                let mut src = self.device.zeros_impl(src_l.shape(), self.dtype())?;
+                self.copy_strided_src(&mut src, 0, src_l)?;
+                contiguous = src;
+                (&contiguous, 0)
+            }
+        };
+        let dev = &self.device;
+        match (&src.slice, &mut dst.slice) {
+            (CudaStorageSlice::U8(src), CudaStorageSlice::U8(dst))
+            | (CudaStorageSlice::Bool(src), CudaStorageSlice::Bool(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            (CudaStorageSlice::U32(src), CudaStorageSlice::U32(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            (CudaStorageSlice::BF16(src), CudaStorageSlice::BF16(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            (CudaStorageSlice::F16(src), CudaStorageSlice::F16(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            (CudaStorageSlice::F32(src), CudaStorageSlice::F32(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            (CudaStorageSlice::F64(src), CudaStorageSlice::F64(dst)) => {
+                copy_blocks(dev, src, src_offset, dst, dst_l)?
+            }
+            _ => Err(CudaError::InternalError(
+                "dtype mismatch in copy_strided op",
+            ))?,
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
 use crate::backend::BackendDevice;
 use crate::cpu_backend::CpuDevice;
-use crate::{CpuStorage, DType, Result, Shape, Storage, WithDType};
+use crate::lazy_backend::LazyDevice;
+use crate::{CpuStorage, DType, LazyStorage, Result, Shape, Storage, WithDType};
 
 /// A `DeviceLocation` represents a physical device whereas multiple `Device`
 /// can live on the same location (typically for cuda devices).
@@ -8,12 +9,16 @@ use crate::{CpuStorage, DType, Result, Shape, Storage, WithDType};
 pub enum DeviceLocation {
     Cpu,
     Cuda { gpu_id: usize },
+    Lazy,
 }
 
 #[derive(Debug, Clone)]
 pub enum Device {
     Cpu,
     Cuda(crate::CudaDevice),
+    /// Operations are recorded in a graph and only run on the cpu when the data is needed, see
+    /// [`crate::lazy_backend`].
+    Lazy,
 }
 
 // TODO: Should we back the cpu implementation using the NdArray crate or similar?
@@ -90,6 +95,7 @@ impl Device {
         match (self, rhs) {
             (Self::Cpu, Self::Cpu) => true,
             (Self::Cuda(lhs), Self::Cuda(rhs)) => lhs.same_device(rhs),
+            (Self::Lazy, Self::Lazy) => true,
             _ => false,
         }
     }
@@ -98,23 +104,28 @@ impl Device {
         match self {
             Self::Cpu => DeviceLocation::Cpu,
             Self::Cuda(device) => device.location(),
+            Self::Lazy => DeviceLocation::Lazy,
         }
     }
 
     pub fn is_cpu(&self) -> bool {
         match self {
             Self::Cpu => true,
-            Self::Cuda(_) => false,
+            Self::Cuda(_) | Self::Lazy => false,
         }
     }
 
     pub fn is_cuda(&self) -> bool {
         match self {
-            Self::Cpu => false,
+            Self::Cpu | Self::Lazy => false,
             Self::Cuda(_) => true,
         }
     }
 
+    pub fn is_lazy(&self) -> bool {
+        matches!(self, Self::Lazy)
+    }
+
     pub fn cuda_if_available(ordinal: usize) -> Result<Self> {
         if crate::utils::cuda_is_available() {
             Self::new_cuda(ordinal)
@@ -139,6 +150,10 @@ impl Device {
                 let storage = device.rand_uniform(shape, dtype, lo, up)?;
                 Ok(Storage::Cuda(storage))
             }
+            Device::Lazy => {
+                let storage = LazyDevice.rand_uniform(shape, dtype, lo, up)?;
+                Ok(Storage::Lazy(storage))
+            }
         }
     }
 
@@ -167,6 +182,10 @@ impl Device {
                 let storage = device.rand_normal(shape, dtype, mean, std)?;
                 Ok(Storage::Cuda(storage))
             }
+            Device::Lazy => {
+                let storage = LazyDevice.rand_normal(shape, dtype, mean, std)?;
+                Ok(Storage::Lazy(storage))
+            }
         }
     }
 
@@ -189,6 +208,10 @@ impl Device {
                 let storage = device.ones_impl(shape, dtype)?;
                 Ok(Storage::Cuda(storage))
             }
+            Device::Lazy => {
+                let storage = LazyDevice.ones_impl(shape, dtype)?;
+                Ok(Storage::Lazy(storage))
+            }
         }
     }
 
@@ -202,12 +225,17 @@ impl Device {
                 let storage = device.zeros_impl(shape, dtype)?;
                 Ok(Storage::Cuda(storage))
             }
+            Device::Lazy => {
+                let storage = LazyDevice.zeros_impl(shape, dtype)?;
+                Ok(Storage::Lazy(storage))
+            }
         }
     }
 
     pub(crate) fn storage<A: NdArray>(&self, array: A) -> Result<Storage> {
         match self {
             Device::Cpu => Ok(Storage::Cpu(array.to_cpu_storage())),
+            Device::Lazy => Ok(Storage::Lazy(LazyStorage::ready(array.to_cpu_storage()))),
             Device::Cuda(device) => {
                 let storage = array.to_cpu_storage();
                 let storage = device.storage_from_cpu_storage(&storage)?;
@@ -219,6 +247,9 @@ impl Device {
     pub(crate) fn storage_owned<S: WithDType>(&self, data: Vec<S>) -> Result<Storage> {
         match self {
             Device::Cpu => Ok(Storage::Cpu(S::to_cpu_storage_owned(data))),
+            Device::Lazy => Ok(Storage::Lazy(LazyStorage::ready(S::to_cpu_storage_owned(
+                data,
+            )))),
             Device::Cuda(device) => {
                 let storage = S::to_cpu_storage_owned(data);
                 let storage = device.storage_from_cpu_storage(&storage)?;
@@ -8,16 +8,25 @@ impl Tensor {
     fn fmt_dt<T: WithDType + std::fmt::Display>(
         &self,
         f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        self.fmt_dt_with::<T, T>(f, |v| v)
+    }
+
+    fn fmt_dt_with<T: WithDType, D: std::fmt::Display>(
+        &self,
+        f: &mut std::fmt::Formatter,
+        to_display: impl Fn(T) -> D,
     ) -> std::fmt::Result {
         let prefix = match self.device() {
             crate::Device::Cpu => "Cpu",
             crate::Device::Cuda(_) => "Cuda",
+            crate::Device::Lazy => "Lazy",
         };
         write!(f, "{prefix}Tensor[")?;
         match self.dims() {
             [] => {
                 if let Ok(v) = self.to_scalar::<T>() {
-                    write!(f, "{v}")?
+                    write!(f, "{}", to_display(v))?
                 }
             }
             [s] if *s < 10 => {
@@ -26,7 +35,7 @@ impl Tensor {
                         if i > 0 {
                             write!(f, ", ")?;
                         }
-                        write!(f, "{v}")?;
+                        write!(f, "{}", to_display(*v))?;
                     }
                 }
             }
@@ -49,10 +58,15 @@ impl std::fmt::Debug for Tensor {
         match self.dtype() {
             DType::U8 => self.fmt_dt::<u8>(f),
             DType::U32 => self.fmt_dt::<u32>(f),
+            DType::I8 => self.fmt_dt::<i8>(f),
+            DType::I16 => self.fmt_dt::<i16>(f),
+            DType::I32 => self.fmt_dt::<i32>(f),
+            DType::I64 => self.fmt_dt::<i64>(f),
             DType::BF16 => self.fmt_dt::<bf16>(f),
             DType::F16 => self.fmt_dt::<f16>(f),
             DType::F32 => self.fmt_dt::<f32>(f),
             DType::F64 => self.fmt_dt::<f64>(f),
+            DType::Bool => self.fmt_dt_with::<u8, bool>(f, |v| v != 0),
         }
     }
 }
@@ -374,6 +388,17 @@ where
     }
 }
 
+struct BoolFormatter;
+
+impl TensorFormatter for BoolFormatter {
+    // Booleans are stored as u8 values.
+    type Elem = u8;
+
+    fn fmt<T: std::fmt::Write>(&self, v: Self::Elem, max_w: usize, f: &mut T) -> std::fmt::Result {
+        write!(f, "{:>max_w$}", v != 0)
+    }
+}
+
 fn get_summarized_data(t: &Tensor, edge_items: usize) -> Result<Tensor> {
     let dims = t.dims();
     if dims.is_empty() {
@@ -431,6 +456,36 @@ impl std::fmt::Display for Tensor {
                 tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
                 writeln!(f)?;
             }
+            DType::I8 => {
+                let tf: IntFormatter<i8> = IntFormatter::new();
+                let max_w = tf.max_width(&to_display);
+                tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
+                writeln!(f)?;
+            }
+            DType::I16 => {
+                let tf: IntFormatter<i16> = IntFormatter::new();
+                let max_w = tf.max_width(&to_display);
+                tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
+                writeln!(f)?;
+            }
+            DType::I32 => {
+                let tf: IntFormatter<i32> = IntFormatter::new();
+                let max_w = tf.max_width(&to_display);
+                tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
+                writeln!(f)?;
+            }
+            DType::I64 => {
+                let tf: IntFormatter<i64> = IntFormatter::new();
+                let max_w = tf.max_width(&to_display);
+                tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
+                writeln!(f)?;
+            }
+            DType::Bool => {
+                let tf = BoolFormatter;
+                let max_w = tf.max_width(&to_display);
+                tf.fmt_tensor(self, 1, max_w, summarize, &po, f)?;
+                writeln!(f)?;
+            }
             DType::BF16 => {
                 if let Ok(tf) = FloatFormatter::<bf16>::new(&to_display, &po) {
                     let max_w = tf.max_width(&to_display);
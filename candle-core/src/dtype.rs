@@ -5,10 +5,17 @@ use crate::{CpuStorage, Error, Result};
 pub enum DType {
     U8,
     U32,
+    I8,
+    I16,
+    I32,
+    I64,
     BF16,
     F16,
     F32,
     F64,
+    /// Booleans, stored using one byte per element. The underlying data can be accessed as `u8`
+    /// values that are either 0 or 1.
+    Bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -20,10 +27,15 @@ impl std::str::FromStr for DType {
         match s {
             "u8" => Ok(Self::U8),
             "u32" => Ok(Self::U32),
+            "i8" => Ok(Self::I8),
+            "i16" => Ok(Self::I16),
+            "i32" => Ok(Self::I32),
+            "i64" => Ok(Self::I64),
             "bf16" => Ok(Self::BF16),
             "f16" => Ok(Self::F16),
             "f32" => Ok(Self::F32),
             "f64" => Ok(Self::F64),
+            "bool" => Ok(Self::Bool),
             _ => Err(DTypeParseError),
         }
     }
@@ -34,10 +46,15 @@ impl DType {
         match self {
             Self::U8 => "u8",
             Self::U32 => "u32",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
             Self::BF16 => "bf16",
             Self::F16 => "f16",
             Self::F32 => "f32",
             Self::F64 => "f64",
+            Self::Bool => "bool",
         }
     }
 
@@ -45,10 +62,31 @@ impl DType {
         match self {
             Self::U8 => 1,
             Self::U32 => 4,
+            Self::I8 => 1,
+            Self::I16 => 2,
+            Self::I32 => 4,
+            Self::I64 => 8,
             Self::BF16 => 2,
             Self::F16 => 2,
             Self::F32 => 4,
             Self::F64 => 8,
+            Self::Bool => 1,
+        }
+    }
+
+    pub fn is_int(&self) -> bool {
+        match self {
+            Self::U8 | Self::U32 | Self::I8 | Self::I16 | Self::I32 | Self::I64 => true,
+            Self::BF16 | Self::F16 | Self::F32 | Self::F64 | Self::Bool => false,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        match self {
+            Self::BF16 | Self::F16 | Self::F32 | Self::F64 => true,
+            Self::U8 | Self::U32 | Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::Bool => {
+                false
+            }
         }
     }
 }
@@ -72,6 +110,11 @@ pub trait WithDType:
 
 macro_rules! with_dtype {
     ($ty:ty, $dtype:ident, $from_f64:expr, $to_f64:expr) => {
+        with_dtype!($ty, $dtype, $from_f64, $to_f64, $dtype);
+    };
+    // The additional storage variants can also be read as this type, e.g. booleans are stored as
+    // `u8` values.
+    ($ty:ty, $dtype:ident, $from_f64:expr, $to_f64:expr, $($variant:ident),+) => {
         impl WithDType for $ty {
             const DTYPE: DType = DType::$dtype;
 
@@ -89,7 +132,7 @@ macro_rules! with_dtype {
 
             fn cpu_storage_data(s: CpuStorage) -> Result<Vec<Self>> {
                 match s {
-                    CpuStorage::$dtype(data) => Ok(data),
+                    $(CpuStorage::$variant(data) => Ok(data),)+
                     _ => Err(Error::UnexpectedDType {
                         expected: DType::$dtype,
                         got: s.dtype(),
@@ -101,7 +144,7 @@ macro_rules! with_dtype {
 
             fn cpu_storage_as_slice(s: &CpuStorage) -> Result<&[Self]> {
                 match s {
-                    CpuStorage::$dtype(data) => Ok(data),
+                    $(CpuStorage::$variant(data) => Ok(data),)+
                     _ => Err(Error::UnexpectedDType {
                         expected: DType::$dtype,
                         got: s.dtype(),
@@ -115,8 +158,12 @@ macro_rules! with_dtype {
 }
 use half::{bf16, f16};
 
-with_dtype!(u8, U8, |v: f64| v as u8, |v: u8| v as f64);
+with_dtype!(u8, U8, |v: f64| v as u8, |v: u8| v as f64, U8, Bool);
 with_dtype!(u32, U32, |v: f64| v as u32, |v: u32| v as f64);
+with_dtype!(i8, I8, |v: f64| v as i8, |v: i8| v as f64);
+with_dtype!(i16, I16, |v: f64| v as i16, |v: i16| v as f64);
+with_dtype!(i32, I32, |v: f64| v as i32, |v: i32| v as f64);
+with_dtype!(i64, I64, |v: f64| v as i64, |v: i64| v as f64);
 with_dtype!(f16, F16, f16::from_f64, f16::to_f64);
 with_dtype!(bf16, BF16, bf16::from_f64, bf16::to_f64);
 with_dtype!(f32, F32, |v: f64| v as f32, |v: f32| v as f64);
@@ -127,23 +174,25 @@ pub trait IntDType: WithDType {
     fn as_usize(&self) -> usize;
 }
 
-impl IntDType for u32 {
-    fn is_true(&self) -> bool {
-        *self != 0
-    }
-    fn as_usize(&self) -> usize {
-        *self as usize
-    }
+macro_rules! int_dtype {
+    ($ty:ty) => {
+        impl IntDType for $ty {
+            fn is_true(&self) -> bool {
+                *self != 0
+            }
+            fn as_usize(&self) -> usize {
+                *self as usize
+            }
+        }
+    };
 }
 
-impl IntDType for u8 {
-    fn is_true(&self) -> bool {
-        *self != 0
-    }
-    fn as_usize(&self) -> usize {
-        *self as usize
-    }
-}
+int_dtype!(u8);
+int_dtype!(u32);
+int_dtype!(i8);
+int_dtype!(i16);
+int_dtype!(i32);
+int_dtype!(i64);
 
 pub trait FloatDType: WithDType {}
 
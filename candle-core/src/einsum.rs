@@ -0,0 +1,346 @@
+use crate::{Result, Tensor};
+use std::collections::HashMap;
+
+// The dimensions covered by an ellipsis get labels that cannot clash with the user provided
+// ones, these are aligned on the right across operands as in numpy broadcasting.
+fn ellipsis_label(i: usize) -> char {
+    char::from_u32(0xE000 + i as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Subscript {
+    Label(char),
+    Ellipsis,
+}
+
+fn parse_term(term: &str, equation: &str) -> Result<Vec<Subscript>> {
+    let mut subscripts = vec![];
+    let mut chars = term.chars();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_alphabetic() {
+            subscripts.push(Subscript::Label(c))
+        } else if c == '.' && chars.next() == Some('.') && chars.next() == Some('.') {
+            if subscripts.contains(&Subscript::Ellipsis) {
+                crate::bail!("einsum: more than one ellipsis in {term:?} for {equation:?}")
+            }
+            subscripts.push(Subscript::Ellipsis)
+        } else {
+            crate::bail!("einsum: unexpected character {c:?} in {equation:?}")
+        }
+    }
+    Ok(subscripts)
+}
+
+// Replaces the ellipsis in `subscripts` with the labels of the last `n_ellipsis_dims` dimensions
+// out of `max_ellipsis_dims`.
+fn expand_ellipsis(
+    subscripts: &[Subscript],
+    n_ellipsis_dims: usize,
+    max_ellipsis_dims: usize,
+) -> Vec<char> {
+    let mut labels = vec![];
+    for s in subscripts.iter() {
+        match s {
+            Subscript::Label(c) => labels.push(*c),
+            Subscript::Ellipsis => {
+                for i in max_ellipsis_dims - n_ellipsis_dims..max_ellipsis_dims {
+                    labels.push(ellipsis_label(i))
+                }
+            }
+        }
+    }
+    labels
+}
+
+// A tensor together with a label for each of its dimensions.
+struct Term {
+    tensor: Tensor,
+    labels: Vec<char>,
+}
+
+impl Term {
+    // Sums over the dimensions which labels do not satisfy `keep`.
+    fn sum_unused<F: Fn(char) -> bool>(self, keep: F) -> Result<Self> {
+        let (kept, summed): (Vec<_>, Vec<_>) =
+            self.labels.iter().enumerate().partition(|(_, &c)| keep(c));
+        if summed.is_empty() {
+            return Ok(self);
+        }
+        let summed: Vec<usize> = summed.iter().map(|(i, _)| *i).collect();
+        let tensor = self.tensor.sum(summed)?;
+        let labels = kept.iter().map(|(_, &c)| c).collect();
+        Ok(Self { tensor, labels })
+    }
+
+    // Extracts the diagonal for each label that appears more than once, e.g. for "ii->i".
+    fn take_diagonals(mut self) -> Result<Self> {
+        loop {
+            let labels = &self.labels;
+            let repeated = (0..labels.len()).find_map(|i| {
+                (i + 1..labels.len()).find_map(|j| (labels[i] == labels[j]).then_some((i, j)))
+            });
+            let (i, j) = match repeated {
+                None => return Ok(self),
+                Some(ij) => ij,
+            };
+            let dims = self.tensor.dims();
+            let n = dims[i];
+            if dims[j] != n {
+                crate::bail!(
+                    "einsum: label {:?} is repeated with different sizes {} and {}",
+                    labels[i],
+                    n,
+                    dims[j]
+                )
+            }
+            let others: Vec<usize> = (0..dims.len()).filter(|&d| d != i && d != j).collect();
+            let mut perm = others.clone();
+            perm.extend([i, j]);
+            let mut flat_dims: Vec<usize> = others.iter().map(|&d| dims[d]).collect();
+            flat_dims.push(n * n);
+            let tensor = self.tensor.permute(perm)?.reshape(flat_dims)?;
+            let ids = Tensor::arange_step(0u32, (n * n) as u32, n as u32 + 1, tensor.device())?;
+            let tensor = tensor.index_select(&ids, others.len())?;
+            let mut labels: Vec<char> = others.iter().map(|&d| labels[d]).collect();
+            labels.push(self.labels[i]);
+            self = Self { tensor, labels }
+        }
+    }
+
+    // Permutes the dimensions in the `groups` order, broadcasts them to their full size and
+    // flattens each group into a single dimension.
+    fn to_matrix(&self, groups: [&[char]; 3], sizes: &HashMap<char, usize>) -> Result<Tensor> {
+        let order: Vec<char> = groups.concat();
+        let perm: Vec<usize> = order
+            .iter()
+            .map(|c| self.labels.iter().position(|l| l == c).unwrap_or(0))
+            .collect();
+        let full_dims: Vec<usize> = order.iter().map(|c| sizes[c]).collect();
+        let mut tensor = self.tensor.permute(perm)?;
+        if tensor.dims() != full_dims {
+            tensor = tensor.broadcast_as(full_dims)?
+        }
+        let [d0, d1, d2] = groups.map(|g| g.iter().map(|c| sizes[c]).product::<usize>());
+        tensor.contiguous()?.reshape((d0, d1, d2))
+    }
+}
+
+// Contracts two terms using a batched matmul, only the labels in `keep` are preserved in the
+// result.
+fn contract(a: Term, b: Term, keep: &[char], sizes: &HashMap<char, usize>) -> Result<Term> {
+    // The labels that appear in a single operand and are not kept get summed first.
+    let a = a.sum_unused(|c| keep.contains(&c) || b.labels.contains(&c))?;
+    let b = b.sum_unused(|c| keep.contains(&c) || a.labels.contains(&c))?;
+    let (shared, a_only): (Vec<char>, Vec<char>) =
+        a.labels.iter().partition(|c| b.labels.contains(c));
+    let (batch, contracted): (Vec<char>, Vec<char>) = shared.iter().partition(|c| keep.contains(c));
+    let b_only: Vec<char> = b
+        .labels
+        .iter()
+        .filter(|c| !a.labels.contains(c))
+        .copied()
+        .collect();
+    let lhs = a.to_matrix([&batch, &a_only, &contracted], sizes)?;
+    let rhs = b.to_matrix([&batch, &contracted, &b_only], sizes)?;
+    let labels = [batch, a_only, b_only].concat();
+    let dims: Vec<usize> = labels.iter().map(|c| sizes[c]).collect();
+    let tensor = lhs.matmul(&rhs)?.reshape(dims)?;
+    Ok(Term { tensor, labels })
+}
+
+impl Tensor {
+    /// Evaluates the Einstein summation convention on the operands, e.g. `"bhqd,bhkd->bhqk"`
+    /// computes the attention scores from queries and keys.
+    ///
+    /// The subscripts are ascii letters, `...` stands for the dimensions that are not explicitly
+    /// labeled and these are broadcasted across operands. Without `->`, the output labels are the
+    /// ellipsis ones followed by the labels appearing once in alphabetical order. A label that is
+    /// repeated in an operand selects the diagonal. Labels missing from the output are summed
+    /// over.
+    ///
+    /// The computation is lowered to `permute`, `reshape`, `broadcast_as`, `sum` and batched
+    /// `matmul` ops so the result supports backpropagation. With more than two operands, the
+    /// contractions are done pairwise, greedily picking the pair that produces the smallest
+    /// intermediate result.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[1f32, 2.], [3., 4.]], &Device::Cpu)?;
+    /// let b = Tensor::new(&[[1f32, 0.], [1., 1.]], &Device::Cpu)?;
+    /// let c = Tensor::einsum("ij,jk->ik", &[&a, &b])?;
+    /// assert_eq!(c.to_vec2::<f32>()?, &[[3., 2.], [7., 4.]]);
+    /// let trace = Tensor::einsum("ii", &[&a])?;
+    /// assert_eq!(trace.to_scalar::<f32>()?, 5.);
+    /// let t = Tensor::einsum("ij->ji", &[&a])?;
+    /// assert_eq!(t.to_vec2::<f32>()?, &[[1., 3.], [2., 4.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn einsum(equation: &str, operands: &[&Tensor]) -> Result<Tensor> {
+        let eq: String = equation.chars().filter(|c| !c.is_whitespace()).collect();
+        let (inputs, output) = match eq.split_once("->") {
+            Some((inputs, output)) => (inputs, Some(output)),
+            None => (eq.as_str(), None),
+        };
+        let inputs = inputs
+            .split(',')
+            .map(|term| parse_term(term, equation))
+            .collect::<Result<Vec<_>>>()?;
+        if inputs.len() != operands.len() {
+            crate::bail!(
+                "einsum: {equation:?} expects {} operands, got {}",
+                inputs.len(),
+                operands.len()
+            )
+        }
+
+        let mut n_ellipsis_dims = Vec::with_capacity(inputs.len());
+        for (subscripts, operand) in inputs.iter().zip(operands.iter()) {
+            let n_labels = subscripts
+                .iter()
+                .filter(|s| **s != Subscript::Ellipsis)
+                .count();
+            let has_ellipsis = subscripts.contains(&Subscript::Ellipsis);
+            let rank = operand.rank();
+            if rank < n_labels || (!has_ellipsis && rank != n_labels) {
+                crate::bail!(
+                    "einsum: subscripts {subscripts:?} do not match the operand shape {:?} for {equation:?}",
+                    operand.shape()
+                )
+            }
+            n_ellipsis_dims.push(rank - n_labels)
+        }
+        let max_ellipsis_dims = n_ellipsis_dims.iter().copied().max().unwrap_or(0);
+
+        let mut terms = Vec::with_capacity(operands.len());
+        let mut sizes: HashMap<char, usize> = HashMap::new();
+        for ((subscripts, &n), operand) in inputs.iter().zip(n_ellipsis_dims.iter()).zip(operands) {
+            let labels = expand_ellipsis(subscripts, n, max_ellipsis_dims);
+            for (&c, &dim) in labels.iter().zip(operand.dims().iter()) {
+                let size = sizes.entry(c).or_insert(dim);
+                // Dimensions of size 1 are broadcasted.
+                if *size == 1 {
+                    *size = dim
+                } else if dim != 1 && dim != *size {
+                    crate::bail!(
+                        "einsum: label {c:?} has inconsistent sizes {size} and {dim} in {equation:?}"
+                    )
+                }
+            }
+            let tensor = (*operand).clone();
+            terms.push(Term { tensor, labels }.take_diagonals()?)
+        }
+
+        let output: Vec<char> = match output {
+            Some(output) => {
+                let output = parse_term(output, equation)?;
+                let output = expand_ellipsis(&output, max_ellipsis_dims, max_ellipsis_dims);
+                for (i, c) in output.iter().enumerate() {
+                    if output[..i].contains(c) {
+                        crate::bail!("einsum: output label {c:?} is repeated in {equation:?}")
+                    }
+                    if !sizes.contains_key(c) {
+                        crate::bail!(
+                            "einsum: output label {c:?} is not used in the inputs of {equation:?}"
+                        )
+                    }
+                }
+                output
+            }
+            None => {
+                let mut counts: HashMap<char, usize> = HashMap::new();
+                for subscripts in inputs.iter() {
+                    for s in subscripts.iter() {
+                        if let Subscript::Label(c) = s {
+                            *counts.entry(*c).or_default() += 1
+                        }
+                    }
+                }
+                let mut once: Vec<char> = counts
+                    .into_iter()
+                    .filter_map(|(c, count)| (count == 1).then_some(c))
+                    .collect();
+                once.sort();
+                let mut output: Vec<char> = (0..max_ellipsis_dims).map(ellipsis_label).collect();
+                output.extend(once);
+                output
+            }
+        };
+
+        // The labels needed once the terms `skip` have been combined, i.e. the output ones and
+        // the ones from the other terms.
+        let needed = |terms: &[Term], skip: &[usize]| -> Vec<char> {
+            let mut needed = output.clone();
+            for (k, term) in terms.iter().enumerate() {
+                if !skip.contains(&k) {
+                    needed.extend(
+                        term.labels
+                            .iter()
+                            .filter(|c| !needed.contains(c))
+                            .collect::<Vec<_>>(),
+                    )
+                }
+            }
+            needed
+        };
+
+        // Sum the dimensions that are only used by a single operand upfront.
+        let terms_ = std::mem::take(&mut terms);
+        for (i, term) in terms_.iter().enumerate() {
+            let keep = needed(&terms_, &[i]);
+            let tensor = term.tensor.clone();
+            let labels = term.labels.clone();
+            terms.push(Term { tensor, labels }.sum_unused(|c| keep.contains(&c))?)
+        }
+
+        while terms.len() > 1 {
+            let mut best: Option<(usize, usize, usize, Vec<char>)> = None;
+            for i in 0..terms.len() {
+                for j in i + 1..terms.len() {
+                    let keep = needed(&terms, &[i, j]);
+                    let size: usize = terms[i]
+                        .labels
+                        .iter()
+                        .chain(
+                            terms[j]
+                                .labels
+                                .iter()
+                                .filter(|c| !terms[i].labels.contains(c)),
+                        )
+                        .filter(|c| keep.contains(c))
+                        .map(|c| sizes[c])
+                        .product();
+                    let is_better = match &best {
+                        None => true,
+                        Some((_, _, best_size, _)) => size < *best_size,
+                    };
+                    if is_better {
+                        best = Some((i, j, size, keep))
+                    }
+                }
+            }
+            let (i, j, _, keep) = match best {
+                Some(best) => best,
+                None => break,
+            };
+            let b = terms.remove(j);
+            let a = terms.remove(i);
+            terms.push(contract(a, b, &keep, &sizes)?)
+        }
+
+        let term = match terms.pop() {
+            Some(term) => term.sum_unused(|c| output.contains(&c))?,
+            None => crate::bail!("einsum: no operands provided for {equation:?}"),
+        };
+        let perm: Vec<usize> = output
+            .iter()
+            .map(|c| term.labels.iter().position(|l| l == c).unwrap_or(0))
+            .collect();
+        let tensor = term.tensor.permute(perm)?;
+        let dims: Vec<usize> = output.iter().map(|c| sizes[c]).collect();
+        if tensor.dims() != dims {
+            tensor.broadcast_as(dims)?.contiguous()
+        } else {
+            Ok(tensor)
+        }
+    }
+}
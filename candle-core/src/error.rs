@@ -132,6 +132,9 @@ pub enum Error {
     #[error("cannot set variable {msg}")]
     CannotSetVar { msg: &'static str },
 
+    #[error("{op} cannot be performed in place: {msg}")]
+    InplaceNotSupported { op: &'static str, msg: &'static str },
+
     // Box indirection to avoid large variant.
     #[error("{0:?}")]
     MatMulUnexpectedStriding(Box<MatMulUnexpectedStriding>),
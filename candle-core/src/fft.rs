@@ -0,0 +1,490 @@
+use crate::{DType, Result, Tensor};
+
+/// The transform applied by the fft op. Complex values are stored as (re, im) pairs along a
+/// trailing dimension of size 2, the transform applies to the last dimension of real inputs and
+/// to the dimension before the (re, im) one for complex inputs.
+///
+/// As in PyTorch, the forward transforms are not normalized and the inverse ones are scaled by
+/// `1/n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FftKind {
+    /// Real to complex, only the `n/2 + 1` non-redundant frequencies are returned.
+    Rfft,
+    /// Complex to real, the inverse of `Rfft` for a signal of length `n`.
+    Irfft {
+        n: usize,
+    },
+    Fft,
+    Ifft,
+}
+
+impl FftKind {
+    // Returns the length of the transform together with the output dims.
+    pub(crate) fn dims(&self, dims: &[usize], op: &'static str) -> Result<(usize, Vec<usize>)> {
+        let complex_dims = || match dims {
+            [.., m, 2] => Ok((*m, &dims[..dims.len() - 2])),
+            _ => crate::bail!("{op}: expected a trailing dimension of size 2, got {dims:?}"),
+        };
+        let (n, out_dims) = match *self {
+            Self::Rfft => match dims.split_last() {
+                Some((&n, batch)) => (n, [batch, &[n / 2 + 1, 2]].concat()),
+                None => crate::bail!("{op}: the input should have at least one dimension"),
+            },
+            Self::Irfft { n } => {
+                let (m, batch) = complex_dims()?;
+                if m != n / 2 + 1 {
+                    crate::bail!(
+                        "{op}: expected {} frequencies for n={n}, got {dims:?}",
+                        n / 2 + 1
+                    )
+                }
+                (n, [batch, &[n]].concat())
+            }
+            Self::Fft | Self::Ifft => (complex_dims()?.0, dims.to_vec()),
+        };
+        if n == 0 {
+            crate::bail!("{op}: cannot transform an empty dimension {dims:?}")
+        }
+        Ok((n, out_dims))
+    }
+
+    // The number of input and output values for each transformed row.
+    fn row_lens(&self, n: usize) -> (usize, usize) {
+        match self {
+            Self::Rfft => (n, 2 * (n / 2 + 1)),
+            Self::Irfft { .. } => (2 * (n / 2 + 1), n),
+            Self::Fft | Self::Ifft => (2 * n, 2 * n),
+        }
+    }
+}
+
+type Complex = (f64, f64);
+
+fn mul(a: Complex, b: Complex) -> Complex {
+    (a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+}
+
+// A mixed-radix Cooley-Tukey fft for a given length, the length is split in its prime factors
+// and prime lengths fall back to a plain dft so any length is supported.
+struct FftPlan {
+    n: usize,
+    factors: Vec<usize>,
+    // twiddles[j] = exp(-2i.pi.j/n)
+    twiddles: Vec<Complex>,
+}
+
+impl FftPlan {
+    fn new(n: usize) -> Self {
+        let mut factors = vec![];
+        let mut rem = n;
+        for p in [4, 2, 3, 5] {
+            while rem.is_multiple_of(p) {
+                factors.push(p);
+                rem /= p;
+            }
+        }
+        let mut p = 7;
+        while rem > 1 {
+            if p * p > rem {
+                p = rem
+            }
+            while rem.is_multiple_of(p) {
+                factors.push(p);
+                rem /= p;
+            }
+            p += 2;
+        }
+        if factors.is_empty() {
+            factors.push(1)
+        }
+        let twiddles = (0..n)
+            .map(|j| {
+                // Quarter turns use exact values so that e.g. the imaginary part of the nyquist
+                // frequency of a real signal is exactly zero.
+                match (4 * j % n == 0).then_some(4 * j / n) {
+                    Some(0) => (1., 0.),
+                    Some(1) => (0., -1.),
+                    Some(2) => (-1., 0.),
+                    Some(3) => (0., 1.),
+                    _ => {
+                        let theta = -2. * std::f64::consts::PI * j as f64 / n as f64;
+                        (theta.cos(), theta.sin())
+                    }
+                }
+            })
+            .collect();
+        Self {
+            n,
+            factors,
+            twiddles,
+        }
+    }
+
+    // Decimation in time: `out` receives the transform of x[0], x[stride], x[2 stride], ...
+    // computed by splitting it in factors[0] interleaved sub-sequences.
+    fn work(&self, out: &mut [Complex], x: &[Complex], stride: usize, factors: &[usize]) {
+        let p = factors[0];
+        let m = out.len() / p;
+        if factors.len() == 1 {
+            for (q, out) in out.iter_mut().enumerate() {
+                *out = x[q * stride]
+            }
+        } else {
+            for (q, out) in out.chunks_exact_mut(m).enumerate() {
+                self.work(out, &x[q * stride..], stride * p, &factors[1..])
+            }
+        }
+        if p == 1 {
+            return;
+        }
+        let mut tmp = vec![(0., 0.); p];
+        for k in 0..m {
+            for (q, tmp) in tmp.iter_mut().enumerate() {
+                *tmp = out[q * m + k]
+            }
+            for q1 in 0..p {
+                let idx = k + q1 * m;
+                let mut v = tmp[0];
+                for (q, &t) in tmp.iter().enumerate().skip(1) {
+                    let w = self.twiddles[(q * idx * stride) % self.n];
+                    let t = mul(t, w);
+                    v = (v.0 + t.0, v.1 + t.1)
+                }
+                out[idx] = v
+            }
+        }
+    }
+
+    fn fft(&self, out: &mut [Complex], x: &[Complex]) {
+        self.work(out, x, 1, &self.factors)
+    }
+
+    // Transforms a single row, `x` and `out` are scratch buffers of length n.
+    fn process_row(
+        &self,
+        kind: FftKind,
+        src: &[f64],
+        dst: &mut [f64],
+        x: &mut [Complex],
+        out: &mut [Complex],
+    ) {
+        let n = self.n;
+        match kind {
+            FftKind::Rfft => {
+                for (x, &v) in x.iter_mut().zip(src.iter()) {
+                    *x = (v, 0.)
+                }
+                self.fft(out, x);
+                for (dst, out) in dst.chunks_exact_mut(2).zip(out.iter()) {
+                    dst[0] = out.0;
+                    dst[1] = out.1;
+                }
+            }
+            FftKind::Irfft { .. } => {
+                // Rebuild the full hermitian spectrum, the imaginary parts of the dc and nyquist
+                // frequencies are ignored. The inverse uses ifft(x) = conj(fft(conj(x))) / n.
+                for (k, x) in x.iter_mut().enumerate() {
+                    *x = if k <= n / 2 {
+                        (src[2 * k], -src[2 * k + 1])
+                    } else {
+                        (src[2 * (n - k)], src[2 * (n - k) + 1])
+                    }
+                }
+                x[0].1 = 0.;
+                if n.is_multiple_of(2) {
+                    x[n / 2].1 = 0.
+                }
+                self.fft(out, x);
+                for (dst, out) in dst.iter_mut().zip(out.iter()) {
+                    *dst = out.0 / n as f64
+                }
+            }
+            FftKind::Fft | FftKind::Ifft => {
+                let inverse = kind == FftKind::Ifft;
+                let sign = if inverse { -1. } else { 1. };
+                for (x, src) in x.iter_mut().zip(src.chunks_exact(2)) {
+                    *x = (src[0], sign * src[1])
+                }
+                self.fft(out, x);
+                let scale = if inverse { 1. / n as f64 } else { 1. };
+                for (dst, out) in dst.chunks_exact_mut(2).zip(out.iter()) {
+                    dst[0] = out.0 * scale;
+                    dst[1] = sign * out.1 * scale;
+                }
+            }
+        }
+    }
+}
+
+// Applies the transform to each row of a contiguous input with shape `dims`, the rows are split
+// between multiple threads.
+pub(crate) fn fft_rows(kind: FftKind, src: &[f64], dims: &[usize]) -> Result<Vec<f64>> {
+    let (n, _) = kind.dims(dims, "fft")?;
+    let (in_len, out_len) = kind.row_lens(n);
+    let rows = src.len() / in_len;
+    let mut dst = vec![0f64; rows * out_len];
+    if rows == 0 {
+        return Ok(dst);
+    }
+    let plan = FftPlan::new(n);
+    let compute = |src: &[f64], dst: &mut [f64]| {
+        let mut x = vec![(0., 0.); n];
+        let mut out = vec![(0., 0.); n];
+        for (src, dst) in src.chunks_exact(in_len).zip(dst.chunks_exact_mut(out_len)) {
+            plan.process_row(kind, src, dst, &mut x, &mut out)
+        }
+    };
+    // Avoid spawning threads for small transforms.
+    let num_threads = crate::utils::get_num_threads().min(rows * n / 4096).max(1);
+    if num_threads == 1 {
+        compute(src, &mut dst);
+        return Ok(dst);
+    }
+    let chunk_rows = rows.div_ceil(num_threads);
+    std::thread::scope(|s| {
+        for (src, dst) in src
+            .chunks(chunk_rows * in_len)
+            .zip(dst.chunks_mut(chunk_rows * out_len))
+        {
+            s.spawn(move || compute(src, dst));
+        }
+    });
+    Ok(dst)
+}
+
+// Reflects the indexes that fall out of [0, len), as with PyTorch's reflect padding.
+fn reflect(idx: isize, len: usize) -> usize {
+    let len = len as isize;
+    let idx = if idx < 0 { -idx } else { idx };
+    let idx = if idx >= len { 2 * (len - 1) - idx } else { idx };
+    idx as usize
+}
+
+fn check_window(window: &Tensor, n_fft: usize, op: &'static str) -> Result<()> {
+    if window.dims() != [n_fft] {
+        crate::bail!(
+            "{op}: expected a window of size {n_fft}, got {:?}",
+            window.dims()
+        )
+    }
+    Ok(())
+}
+
+impl Tensor {
+    // Moves `dim` to the position the fft op transforms, `pos` being the last dimension for real
+    // inputs and the one before the trailing (re, im) dimension for complex inputs. Swapping the
+    // two dimensions is its own inverse so the same call moves the result back.
+    fn fft_along(&self, dim: usize, pos: usize, kind: FftKind) -> Result<Self> {
+        if dim == pos {
+            self.fft_op(kind)
+        } else {
+            self.transpose(dim, pos)?.fft_op(kind)?.transpose(dim, pos)
+        }
+    }
+
+    fn complex_dim<D: crate::shape::Dim>(&self, dim: D, op: &'static str) -> Result<usize> {
+        let dims = self.dims();
+        if dims.len() < 2 || dims[dims.len() - 1] != 2 {
+            crate::bail!("{op}: expected a trailing dimension of size 2, got {dims:?}")
+        }
+        let dim = dim.to_index(self.shape(), op)?;
+        if dim == dims.len() - 1 {
+            crate::bail!("{op}: cannot transform the (re, im) dimension of {dims:?}")
+        }
+        Ok(dim)
+    }
+
+    /// The discrete Fourier transform of a real signal along dimension `dim`. For an input of
+    /// size `n` along `dim`, the `n/2 + 1` non-redundant frequencies are returned as (re, im)
+    /// pairs along a new trailing dimension of size 2.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[1f32, 2., 3., 4.], &Device::Cpu)?;
+    /// let f = t.rfft(0)?;
+    /// assert_eq!(f.to_vec2::<f32>()?, &[[10., 0.], [-2., 2.], [-2., 0.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn rfft<D: crate::shape::Dim>(&self, dim: D) -> Result<Self> {
+        let dim = dim.to_index(self.shape(), "rfft")?;
+        self.fft_along(dim, self.rank() - 1, FftKind::Rfft)
+    }
+
+    /// The inverse of [`Tensor::rfft`], `self` holds the `n/2 + 1` first frequencies along `dim`
+    /// as (re, im) pairs along its trailing dimension and the result is the real signal of length
+    /// `n`.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[1f32, 2., 3., 4., 5.], &Device::Cpu)?;
+    /// let t = t.rfft(0)?.irfft(0, 5)?;
+    /// let t: Vec<f32> = t.to_vec1::<f32>()?.iter().map(|v| v.round()).collect();
+    /// assert_eq!(t, &[1., 2., 3., 4., 5.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn irfft<D: crate::shape::Dim>(&self, dim: D, n: usize) -> Result<Self> {
+        let dim = self.complex_dim(dim, "irfft")?;
+        self.fft_along(dim, self.rank() - 2, FftKind::Irfft { n })
+    }
+
+    /// The discrete Fourier transform of a complex signal along dimension `dim`, the values are
+    /// stored as (re, im) pairs along the trailing dimension which must have size 2.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[0f32, 1.], [1., 0.]], &Device::Cpu)?;
+    /// let f = t.fft(0)?;
+    /// assert_eq!(f.to_vec2::<f32>()?, &[[1., 1.], [-1., 1.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn fft<D: crate::shape::Dim>(&self, dim: D) -> Result<Self> {
+        let dim = self.complex_dim(dim, "fft")?;
+        self.fft_along(dim, self.rank() - 2, FftKind::Fft)
+    }
+
+    /// The inverse of [`Tensor::fft`], the result is scaled by `1/n`.
+    pub fn ifft<D: crate::shape::Dim>(&self, dim: D) -> Result<Self> {
+        let dim = self.complex_dim(dim, "ifft")?;
+        self.fft_along(dim, self.rank() - 2, FftKind::Ifft)
+    }
+
+    /// The short-time Fourier transform of a real signal of shape `(..., t)`. Frames of `n_fft`
+    /// samples are taken every `hop_length` samples, multiplied by `window` (rectangular by
+    /// default) and transformed with [`Tensor::rfft`]. The result has shape
+    /// `(..., n_fft/2 + 1, n_frames, 2)`.
+    ///
+    /// With `center`, the signal is reflect-padded by `n_fft/2` on both sides so that frame `i`
+    /// is centered on sample `i * hop_length`. This matches `torch.stft` with
+    /// `return_complex=False`.
+    pub fn stft(
+        &self,
+        n_fft: usize,
+        hop_length: usize,
+        window: Option<&Tensor>,
+        center: bool,
+    ) -> Result<Self> {
+        if n_fft == 0 || hop_length == 0 {
+            crate::bail!("stft: n_fft {n_fft} and hop_length {hop_length} should be positive")
+        }
+        let dims = self.dims();
+        let t = match dims.last() {
+            Some(&t) => t,
+            None => crate::bail!("stft: the input should have at least one dimension"),
+        };
+        let pad = if center { n_fft / 2 } else { 0 };
+        if center && t <= pad {
+            crate::bail!("stft: the signal length {t} should be larger than the padding {pad}")
+        }
+        if t + 2 * pad < n_fft {
+            crate::bail!("stft: the signal length {t} is smaller than n_fft {n_fft}")
+        }
+        let n_frames = 1 + (t + 2 * pad - n_fft) / hop_length;
+        let ids: Vec<u32> = (0..n_frames)
+            .flat_map(|f| {
+                (0..n_fft).map(move |k| reflect((f * hop_length + k) as isize - pad as isize, t))
+            })
+            .map(|i| i as u32)
+            .collect();
+        let ids = Tensor::from_vec(ids, n_frames * n_fft, self.device())?;
+        let batch = &dims[..dims.len() - 1];
+        let frames = self
+            .index_select(&ids, dims.len() - 1)?
+            .reshape([batch, &[n_frames, n_fft]].concat())?;
+        let frames = match window {
+            None => frames,
+            Some(window) => {
+                check_window(window, n_fft, "stft")?;
+                frames.broadcast_mul(window)?
+            }
+        };
+        let rank = frames.rank();
+        frames.rfft(rank - 1)?.transpose(rank - 2, rank - 1)
+    }
+
+    /// The inverse of [`Tensor::stft`], `self` has shape `(..., n_fft/2 + 1, n_frames, 2)` and the
+    /// frames are recombined with a windowed overlap-add. The output is trimmed or zero-padded to
+    /// `length` samples when specified. This matches `torch.istft`.
+    pub fn istft(
+        &self,
+        n_fft: usize,
+        hop_length: usize,
+        window: Option<&Tensor>,
+        center: bool,
+        length: Option<usize>,
+    ) -> Result<Self> {
+        if n_fft == 0 || hop_length == 0 {
+            crate::bail!("istft: n_fft {n_fft} and hop_length {hop_length} should be positive")
+        }
+        let dims = self.dims();
+        if dims.len() < 3 || dims[dims.len() - 1] != 2 || dims[dims.len() - 3] != n_fft / 2 + 1 {
+            crate::bail!(
+                "istft: expected a shape (..., {}, n_frames, 2), got {dims:?}",
+                n_fft / 2 + 1
+            )
+        }
+        let rank = dims.len();
+        let n_frames = dims[rank - 2];
+        if n_frames == 0 {
+            crate::bail!("istft: the input has no frames {dims:?}")
+        }
+        // frames has shape (..., n_frames, n_fft)
+        let frames = self.transpose(rank - 3, rank - 2)?.irfft(rank - 2, n_fft)?;
+        let window = match window {
+            None => Tensor::ones(n_fft, frames.dtype(), self.device())?,
+            Some(window) => {
+                check_window(window, n_fft, "istft")?;
+                window.clone()
+            }
+        };
+        let frames = frames.broadcast_mul(&window)?;
+
+        // Overlap-add the frames and normalize by the sum of the squared windows.
+        let expected_len = n_fft + hop_length * (n_frames - 1);
+        let batch = &dims[..rank - 3];
+        let ids: Vec<u32> = (0..n_frames)
+            .flat_map(|f| (0..n_fft).map(move |k| (f * hop_length + k) as u32))
+            .collect();
+        let ids = Tensor::from_vec(ids, n_frames * n_fft, self.device())?;
+        let frames = frames.reshape([batch, &[n_frames * n_fft]].concat())?;
+        let signal = Tensor::zeros(
+            [batch, &[expected_len]].concat(),
+            frames.dtype(),
+            self.device(),
+        )?
+        .index_add(&ids, &frames, batch.len())?;
+        let window = window.to_dtype(DType::F64)?.to_vec1::<f64>()?;
+        let mut envelope = vec![0f64; expected_len];
+        for f in 0..n_frames {
+            for (k, w) in window.iter().enumerate() {
+                envelope[f * hop_length + k] += w * w
+            }
+        }
+
+        let start = if center { n_fft / 2 } else { 0 };
+        let end = match length {
+            Some(length) => start + length,
+            None if center => expected_len - n_fft / 2,
+            None => expected_len,
+        };
+        let kept = usize::min(end, expected_len);
+        if kept < start {
+            crate::bail!(
+                "istft: the signal of length {expected_len} is too short for n_fft {n_fft}"
+            )
+        }
+        let envelope = &envelope[start..kept];
+        if envelope.iter().any(|&e| e < 1e-11) {
+            crate::bail!("istft: the window overlap-add is zero at some positions (NOLA condition)")
+        }
+        let envelope = Tensor::from_slice(envelope, envelope.len(), self.device())?
+            .to_dtype(signal.dtype())?;
+        let signal = signal
+            .narrow(batch.len(), start, kept - start)?
+            .broadcast_div(&envelope)?;
+        if end > kept {
+            signal.pad_with_zeros(batch.len(), 0, end - kept)
+        } else {
+            Ok(signal)
+        }
+    }
+}
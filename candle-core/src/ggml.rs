@@ -1,287 +1,9 @@
 //! Support for the GGML file format.
 
-use crate::{DType, Device, Result, Tensor};
+use crate::quantized::{k_quants, GgmlDType, QTensor};
+use crate::Result;
 use byteorder::{LittleEndian, ReadBytesExt};
-use half::f16;
-
-// Default to QK_K 256 rather than 64.
-pub const QK_K: usize = 256;
-pub const K_SCALE_SIZE: usize = 12;
-
-pub const QK4_0: usize = 32;
-pub const QK4_1: usize = 32;
-pub const QK5_0: usize = 32;
-pub const QK5_1: usize = 32;
-pub const QK8_0: usize = 32;
-pub const QK8_1: usize = 32;
-
-#[repr(C)]
-struct BlockQ4_0 {
-    d: f16,
-    qs: [u8; QK4_0 / 2],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ4_0>() == 18);
-
-#[repr(C)]
-struct BlockQ4_1 {
-    d: f16,
-    m: f16,
-    qs: [u8; QK4_1 / 2],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ4_1>() == 20);
-
-#[repr(C)]
-struct BlockQ5_0 {
-    d: f16,
-    qh: [u8; 4],
-    qs: [u8; QK5_0 / 2],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ5_0>() == 22);
-
-#[repr(C)]
-struct BlockQ5_1 {
-    d: f16,
-    m: f16,
-    qh: [u8; 4],
-    qs: [u8; QK5_1 / 2],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ5_1>() == 24);
-
-#[repr(C)]
-struct BlockQ8_0 {
-    d: f16,
-    qs: [u8; QK8_0],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ8_0>() == 34);
-
-#[repr(C)]
-struct BlockQ8_1 {
-    d: f16,
-    s: f16,
-    qs: [u8; QK8_1],
-}
-const _: () = assert!(std::mem::size_of::<BlockQ8_1>() == 36);
-
-#[repr(C)]
-struct BlockQ2K {
-    scales: [u8; QK_K / 16],
-    qs: [u8; QK_K / 4],
-    d: f16,
-    dmin: f16,
-}
-const _: () = assert!(QK_K / 16 + QK_K / 4 + 2 * 2 == std::mem::size_of::<BlockQ2K>());
-
-#[repr(C)]
-struct BlockQ3K {
-    hmask: [u8; QK_K / 8],
-    qs: [u8; QK_K / 4],
-    scales: [u8; 12],
-    d: f16,
-}
-const _: () = assert!(QK_K / 8 + QK_K / 4 + 12 + 2 == std::mem::size_of::<BlockQ3K>());
-
-// https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/k_quants.h#L82
-#[repr(C)]
-struct BlockQ4K {
-    d: f16,
-    dmin: f16,
-    scales: [u8; K_SCALE_SIZE],
-    qs: [u8; QK_K / 2],
-}
-const _: () = assert!(QK_K / 2 + K_SCALE_SIZE + 2 * 2 == std::mem::size_of::<BlockQ4K>());
-
-#[repr(C)]
-struct BlockQ5K {
-    d: f16,
-    dmin: f16,
-    scales: [u8; K_SCALE_SIZE],
-    qh: [u8; QK_K / 8],
-    qs: [u8; QK_K / 2],
-}
-const _: () =
-    assert!(QK_K / 8 + QK_K / 2 + 2 * 2 + K_SCALE_SIZE == std::mem::size_of::<BlockQ5K>());
-
-#[repr(C)]
-struct BlockQ6K {
-    ql: [u8; QK_K / 2],
-    qh: [u8; QK_K / 4],
-    scales: [i8; QK_K / 16],
-    d: f16,
-}
-const _: () = assert!(3 * QK_K / 4 + QK_K / 16 + 2 == std::mem::size_of::<BlockQ6K>());
-
-// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L354
-fn dequantize_row_q2k(xs: &[BlockQ2K], ys: &mut [f32]) -> Result<()> {
-    let k = ys.len();
-    if k % QK_K != 0 {
-        crate::bail!("dequantize_row_q2k: {k} is not divisible by {QK_K}")
-    }
-    let mut ys_index = 0;
-    for x in xs {
-        let d = x.d.to_f32();
-        let min = x.dmin.to_f32();
-        let q = &x.qs;
-
-        let mut is = 0;
-        for n in (0..QK_K).step_by(128) {
-            // Step by 32 over q.
-            let q = &q[n / 4..];
-            let mut shift = 0;
-            for _j in 0..4 {
-                let sc = x.scales[is];
-                is += 1;
-                let dl = d * (sc & 0xF) as f32;
-                let ml = min * (sc >> 4) as f32;
-                for q in &q[..16] {
-                    let y = dl * ((q >> shift) & 3) as i8 as f32 - ml;
-                    ys[ys_index] = y;
-                    ys_index += 1;
-                }
-
-                let sc = x.scales[is];
-                is += 1;
-                let dl = d * (sc & 0xF) as f32;
-                let ml = min * (sc >> 4) as f32;
-                for q in &q[16..32] {
-                    let y = dl * ((q >> shift) & 3) as i8 as f32 - ml;
-                    ys[ys_index] = y;
-                    ys_index += 1;
-                }
-
-                shift += 2;
-            }
-        }
-    }
-    Ok(())
-}
-
-fn get_scale_min_k4(j: usize, q: &[u8]) -> (u8, u8) {
-    if j < 4 {
-        let d = q[j] & 63;
-        let m = q[j + 4] & 63;
-        (d, m)
-    } else {
-        let d = (q[j + 4] & 0xF) | ((q[j - 4] >> 6) << 4);
-        let m = (q[j + 4] >> 4) | ((q[j] >> 6) << 4);
-        (d, m)
-    }
-}
-// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L735
-fn dequantize_row_q4k(xs: &[BlockQ4K], ys: &mut [f32]) -> Result<()> {
-    let k = ys.len();
-    if k % QK_K != 0 {
-        crate::bail!("dequantize_row_q4k: {k} is not divisible by {QK_K}")
-    }
-    let mut ys_index = 0;
-    for x in xs.iter() {
-        let d = x.d.to_f32();
-        let min = x.dmin.to_f32();
-        let q = &x.qs;
-        let mut is = 0;
-        for j in (0..QK_K).step_by(64) {
-            let q = &q[j / 2..j / 2 + 32];
-            let (sc, m) = get_scale_min_k4(is, &x.scales);
-            let d1 = d * sc as f32;
-            let m1 = min * m as f32;
-            let (sc, m) = get_scale_min_k4(is + 1, &x.scales);
-            let d2 = d * sc as f32;
-            let m2 = min * m as f32;
-            for q in q {
-                let y = d1 * (q & 0xF) as f32 - m1;
-                ys[ys_index] = y;
-                ys_index += 1;
-            }
-            for q in q {
-                let y = d2 * (q >> 4) as f32 - m2;
-                ys[ys_index] = y;
-                ys_index += 1;
-            }
-            is += 2;
-        }
-    }
-    Ok(())
-}
-
-// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L533
-fn dequantize_row_q3k(_xs: &[BlockQ3K], _ys: &mut [f32]) -> Result<()> {
-    todo!()
-}
-
-// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L928
-fn dequantize_row_q5k(xs: &[BlockQ5K], ys: &mut [f32]) -> Result<()> {
-    let k = ys.len();
-    if k % QK_K != 0 {
-        crate::bail!("dequantize_row_q5k: {k} is not divisible by {QK_K}")
-    }
-    let mut ys_index = 0;
-    for x in xs.iter() {
-        let d = x.d.to_f32();
-        let min = x.dmin.to_f32();
-        let ql = &x.qs;
-        let qh = &x.qh;
-        let mut is = 0;
-        let mut u1 = 1;
-        let mut u2 = 2;
-        for j in (0..QK_K).step_by(64) {
-            let ql = &ql[j / 2..j / 2 + 32];
-            let (sc, m) = get_scale_min_k4(is, &x.scales);
-            let d1 = d * sc as f32;
-            let m1 = min * m as f32;
-            let (sc, m) = get_scale_min_k4(is + 1, &x.scales);
-            let d2 = d * sc as f32;
-            let m2 = min * m as f32;
-            for (ql, qh) in ql.iter().zip(qh) {
-                let to_add = if qh & u1 != 0 { 16 } else { 1 };
-                let y = d1 * ((ql & 0xF) + to_add) as f32 - m1;
-                ys[ys_index] = y;
-                ys_index += 1;
-            }
-            for (ql, qh) in ql.iter().zip(qh) {
-                let to_add = if qh & u2 != 0 { 16 } else { 1 };
-                let y = d2 * ((ql >> 4) + to_add) as f32 - m2;
-                ys[ys_index] = y;
-                ys_index += 1;
-            }
-            is += 2;
-            u1 <<= 2;
-            u2 <<= 2;
-        }
-    }
-    Ok(())
-}
-
-// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L1067
-fn dequantize_row_q6k(xs: &[BlockQ6K], ys: &mut [f32]) -> Result<()> {
-    let k = ys.len();
-    if k % QK_K != 0 {
-        crate::bail!("dequantize_row_q6k: {k} is not divisible by {QK_K}")
-    }
-    for x in xs.iter() {
-        let d = x.d.to_f32();
-        let ql = &x.ql;
-        let qh = &x.qh;
-        let sc = &x.scales;
-        for n in (0..QK_K).step_by(128) {
-            let idx = n / 128;
-            let ys = &mut ys[n..];
-            let sc = &sc[8 * idx..];
-            let ql = &ql[64 * idx..];
-            let qh = &qh[32 * idx..];
-            for l in 0..32 {
-                let is = l / 16;
-                let q1 = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
-                let q2 = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
-                let q3 = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
-                let q4 = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
-                ys[l] = d * sc[is] as f32 * q1 as f32;
-                ys[l + 32] = d * sc[is + 2] as f32 * q2 as f32;
-                ys[l + 64] = d * sc[is + 4] as f32 * q3 as f32;
-                ys[l + 96] = d * sc[is + 6] as f32 * q4 as f32;
-            }
-        }
-    }
-    Ok(())
-}
+use std::collections::HashMap;
 
 // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/llama.h#L37
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -395,97 +117,80 @@ impl Vocab {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GgmlDType {
-    F32,
-    F16,
-    Q4_0,
-    Q4_1,
-    Q5_0,
-    Q5_1,
-    Q8_0,
-    Q8_1,
-    Q2K,
-    Q3K,
-    Q4K,
-    Q5K,
-    Q6K,
+fn from_raw_data<T: k_quants::GgmlType + 'static, R: std::io::Read>(
+    reader: &mut R,
+    size_in_bytes: usize,
+    dims: Vec<usize>,
+) -> Result<QTensor> {
+    let n_blocks = size_in_bytes / std::mem::size_of::<T>();
+    let mut data = vec![T::zeros(); n_blocks];
+    // Read the raw bytes directly in the block buffer, the blocks are `repr(C)` so this matches
+    // the on-disk layout.
+    let raw_data =
+        unsafe { std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, size_in_bytes) };
+    reader.read_exact(raw_data)?;
+    QTensor::new(data, dims)
 }
 
-impl GgmlDType {
-    fn from_u32(u: u32) -> Result<Self> {
-        let dtype = match u {
-            0 => Self::F32,
-            1 => Self::F16,
-            2 => Self::Q4_0,
-            3 => Self::Q4_1,
-            6 => Self::Q5_0,
-            7 => Self::Q5_1,
-            8 => Self::Q8_0,
-            9 => Self::Q8_1,
-            10 => Self::Q2K,
-            11 => Self::Q3K,
-            12 => Self::Q4K,
-            13 => Self::Q5K,
-            14 => Self::Q6K,
-            _ => crate::bail!("unknown dtype for tensor {u}"),
-        };
-        Ok(dtype)
+/// Reads the raw data for a tensor of the given dtype and dimensions, the dimensions are expected
+/// in the usual order, i.e. the last one being the fastest changing.
+pub(crate) fn qtensor_from_reader<R: std::io::Read>(
+    reader: &mut R,
+    dtype: GgmlDType,
+    dims: Vec<usize>,
+) -> Result<QTensor> {
+    let tensor_elems = dims.iter().product::<usize>();
+    let blck_size = dtype.blck_size();
+    if tensor_elems % blck_size != 0 {
+        crate::bail!(
+            "the number of elements {tensor_elems} is not divisible by the block size {blck_size}"
+        )
     }
-
-    fn type_size(&self) -> usize {
-        match self {
-            Self::F32 => 4,
-            Self::F16 => 2,
-            Self::Q4_0 => std::mem::size_of::<BlockQ4_0>(),
-            Self::Q4_1 => std::mem::size_of::<BlockQ4_1>(),
-            Self::Q5_0 => std::mem::size_of::<BlockQ5_0>(),
-            Self::Q5_1 => std::mem::size_of::<BlockQ5_1>(),
-            // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c#L932
-            Self::Q8_0 => std::mem::size_of::<BlockQ8_0>(),
-            Self::Q8_1 => std::mem::size_of::<BlockQ8_1>(),
-            Self::Q2K => std::mem::size_of::<BlockQ2K>(),
-            Self::Q3K => std::mem::size_of::<BlockQ3K>(),
-            Self::Q4K => std::mem::size_of::<BlockQ4K>(),
-            Self::Q5K => std::mem::size_of::<BlockQ5K>(),
-            Self::Q6K => std::mem::size_of::<BlockQ6K>(),
-        }
+    let size_in_bytes = tensor_elems / blck_size * dtype.type_size();
+    match dtype {
+        GgmlDType::F32 => from_raw_data::<f32, _>(reader, size_in_bytes, dims),
+        GgmlDType::F16 => from_raw_data::<half::f16, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q4_0 => from_raw_data::<k_quants::BlockQ4_0, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q4_1 => from_raw_data::<k_quants::BlockQ4_1, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q5_0 => from_raw_data::<k_quants::BlockQ5_0, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q5_1 => from_raw_data::<k_quants::BlockQ5_1, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q8_0 => from_raw_data::<k_quants::BlockQ8_0, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q8_1 => from_raw_data::<k_quants::BlockQ8_1, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q2K => from_raw_data::<k_quants::BlockQ2K, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q3K => from_raw_data::<k_quants::BlockQ3K, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q4K => from_raw_data::<k_quants::BlockQ4K, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q5K => from_raw_data::<k_quants::BlockQ5K, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q6K => from_raw_data::<k_quants::BlockQ6K, _>(reader, size_in_bytes, dims),
+        GgmlDType::Q8K => from_raw_data::<k_quants::BlockQ8K, _>(reader, size_in_bytes, dims),
     }
+}
 
-    fn blck_size(&self) -> usize {
-        match self {
-            Self::F32 => 1,
-            Self::F16 => 1,
-            Self::Q4_0 => QK4_0,
-            Self::Q4_1 => QK4_1,
-            Self::Q5_0 => QK5_0,
-            Self::Q5_1 => QK5_1,
-            Self::Q8_0 => QK8_0,
-            Self::Q8_1 => QK8_1,
-            Self::Q2K | Self::Q3K | Self::Q4K | Self::Q5K | Self::Q6K => QK_K,
-        }
-    }
+#[derive(Debug, Clone, PartialEq)]
+pub struct TensorInfo {
+    pub ggml_dtype: GgmlDType,
+    pub shape: crate::Shape,
+    /// The offset of the tensor data from the start of the file.
+    pub offset: usize,
 }
 
-#[derive(Debug)]
-pub struct Content {
-    pub magic: VersionedMagic,
-    pub hparams: HParams,
-    pub vocab: Vocab,
-    pub tensors: Vec<(String, Tensor)>,
+impl TensorInfo {
+    pub fn size_in_bytes(&self) -> usize {
+        self.shape.elem_count() / self.ggml_dtype.blck_size() * self.ggml_dtype.type_size()
+    }
 }
 
-fn read_one_tensor<R: std::io::Seek + std::io::Read>(
+fn read_one_tensor_info<R: std::io::Seek + std::io::Read>(
     reader: &mut R,
     magic: VersionedMagic,
-    device: &Device,
-) -> Result<(String, Tensor)> {
+) -> Result<(String, TensorInfo)> {
     let n_dims = reader.read_u32::<LittleEndian>()?;
     let name_len = reader.read_u32::<LittleEndian>()?;
     let dtype = reader.read_u32::<LittleEndian>()?;
-    let dtype = GgmlDType::from_u32(dtype)?;
+    let ggml_dtype = GgmlDType::from_u32(dtype)?;
     let mut dims = vec![0u32; n_dims as usize];
     reader.read_u32_into::<LittleEndian>(&mut dims)?;
+    // The dimensions are stored in reverse order, the first one being the fastest changing.
+    dims.reverse();
     let mut name = vec![0u8; name_len as usize];
     reader.read_exact(&mut name)?;
     let name = String::from_utf8_lossy(&name).into_owned();
@@ -495,88 +200,134 @@ fn read_one_tensor<R: std::io::Seek + std::io::Read>(
         reader.seek(std::io::SeekFrom::Current(((32 - pos % 32) % 32) as i64))?;
     }
     let dims = dims.iter().map(|&u| u as usize).collect::<Vec<_>>();
-    let tensor_elems = dims.iter().product::<usize>();
-    let size_in_bytes = tensor_elems * dtype.type_size() / dtype.blck_size();
-    println!("{name} {dtype:?} {dims:?}");
-    // TODO: Mmap version to avoid copying the data around?
-    let mut raw_data = vec![0u8; size_in_bytes];
-    reader.read_exact(&mut raw_data)?;
-    let tensor = match dtype {
-        GgmlDType::F32 => Tensor::from_raw_buffer(&raw_data, DType::F32, &dims, device)?,
-        GgmlDType::F16 => Tensor::from_raw_buffer(&raw_data, DType::F16, &dims, device)?,
-        GgmlDType::Q2K => {
-            let mut f32_data = vec![0f32; tensor_elems];
-            let raw_data_ptr = raw_data.as_ptr();
-            let n_blocks = size_in_bytes / std::mem::size_of::<BlockQ2K>();
-            let raw_data =
-                unsafe { std::slice::from_raw_parts(raw_data_ptr as *const BlockQ2K, n_blocks) };
-            dequantize_row_q2k(raw_data, &mut f32_data)?;
-            // Maybe we should use bf16 instead?
-            Tensor::from_vec(f32_data, dims, device)?
-        }
-        GgmlDType::Q3K => {
-            let mut f32_data = vec![0f32; tensor_elems];
-            let raw_data_ptr = raw_data.as_ptr();
-            let n_blocks = size_in_bytes / std::mem::size_of::<BlockQ3K>();
-            let raw_data =
-                unsafe { std::slice::from_raw_parts(raw_data_ptr as *const BlockQ3K, n_blocks) };
-            dequantize_row_q3k(raw_data, &mut f32_data)?;
-            Tensor::from_vec(f32_data, dims, device)?
-        }
-        GgmlDType::Q4K => {
-            let mut f32_data = vec![0f32; tensor_elems];
-            let raw_data_ptr = raw_data.as_ptr();
-            let n_blocks = size_in_bytes / std::mem::size_of::<BlockQ4K>();
-            let raw_data =
-                unsafe { std::slice::from_raw_parts(raw_data_ptr as *const BlockQ4K, n_blocks) };
-            dequantize_row_q4k(raw_data, &mut f32_data)?;
-            Tensor::from_vec(f32_data, dims, device)?
-        }
-        GgmlDType::Q5K => {
-            let mut f32_data = vec![0f32; tensor_elems];
-            let raw_data_ptr = raw_data.as_ptr();
-            let n_blocks = size_in_bytes / std::mem::size_of::<BlockQ5K>();
-            let raw_data =
-                unsafe { std::slice::from_raw_parts(raw_data_ptr as *const BlockQ5K, n_blocks) };
-            dequantize_row_q5k(raw_data, &mut f32_data)?;
-            Tensor::from_vec(f32_data, dims, device)?
-        }
-        GgmlDType::Q6K => {
-            let mut f32_data = vec![0f32; tensor_elems];
-            let raw_data_ptr = raw_data.as_ptr();
-            let n_blocks = size_in_bytes / std::mem::size_of::<BlockQ6K>();
-            let raw_data =
-                unsafe { std::slice::from_raw_parts(raw_data_ptr as *const BlockQ6K, n_blocks) };
-            dequantize_row_q6k(raw_data, &mut f32_data)?;
-            Tensor::from_vec(f32_data, dims, device)?
-        }
-        _ => crate::bail!("quantized type {dtype:?} used in {name} is not supported yet"),
+    let blck_size = ggml_dtype.blck_size();
+    let elem_count = dims.iter().product::<usize>();
+    if elem_count % blck_size != 0 {
+        crate::bail!(
+            "ggml: {name} has {elem_count} elements which is not divisible by the block size {blck_size}"
+        )
+    }
+    let tensor_info = TensorInfo {
+        ggml_dtype,
+        shape: crate::Shape::from(dims),
+        offset: reader.stream_position()? as usize,
     };
-    Ok((name, tensor))
+    tracing::debug!(
+        "ggml: {name} {:?} {:?}",
+        tensor_info.ggml_dtype,
+        tensor_info.shape
+    );
+    // Skip the tensor data, it only gets read when the tensor is requested.
+    let size_in_bytes = tensor_info.size_in_bytes();
+    reader.seek(std::io::SeekFrom::Current(size_in_bytes as i64))?;
+    Ok((name, tensor_info))
+}
+
+enum Data {
+    Mmap(memmap2::Mmap),
+    Owned(Vec<u8>),
+}
+
+impl std::ops::Deref for Data {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Mmap(mmap) => mmap,
+            Self::Owned(data) => data,
+        }
+    }
+}
+
+/// The content of a ggml file. Only the header is parsed when building it, the tensors data stays
+/// in the backing buffer (usually a memory mapped file) until [`Content::tensor`] is called.
+pub struct Content {
+    pub magic: VersionedMagic,
+    pub hparams: HParams,
+    pub vocab: Vocab,
+    pub tensor_infos: HashMap<String, TensorInfo>,
+    data: Data,
+}
+
+impl std::fmt::Debug for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Content")
+            .field("magic", &self.magic)
+            .field("hparams", &self.hparams)
+            .field("tensor_infos", &self.tensor_infos)
+            .field("data_len", &self.data.len())
+            .finish()
+    }
 }
 
 impl Content {
-    pub fn read<R: std::io::Seek + std::io::Read>(
-        reader: &mut R,
-        device: &Device,
-    ) -> Result<Content> {
+    fn from_data(data: Data) -> Result<Self> {
         // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/llama.cpp#L505
-        let last_position = reader.seek(std::io::SeekFrom::End(0))?;
-        reader.seek(std::io::SeekFrom::Start(0))?;
-        let magic = VersionedMagic::read(reader)?;
-        let hparams = HParams::read(reader)?;
-        let vocab = Vocab::read(reader, hparams.n_vocab as usize)?;
-        let mut tensors = vec![];
-
-        while reader.stream_position()? != last_position {
-            let (name, tensor) = read_one_tensor(reader, magic, device)?;
-            tensors.push((name, tensor))
+        let last_position = data.len() as u64;
+        let mut reader = std::io::Cursor::new(&*data);
+        let magic = VersionedMagic::read(&mut reader)?;
+        let hparams = HParams::read(&mut reader)?;
+        let vocab = Vocab::read(&mut reader, hparams.n_vocab as usize)?;
+        let mut tensor_infos = HashMap::new();
+
+        while reader.position() < last_position {
+            let (name, tensor_info) = read_one_tensor_info(&mut reader, magic)?;
+            tensor_infos.insert(name, tensor_info);
+        }
+        if reader.position() != last_position {
+            crate::bail!("ggml: the tensor data extends past the end of the file")
         }
         Ok(Self {
             magic,
             hparams,
             vocab,
-            tensors,
+            tensor_infos,
+            data,
         })
     }
+
+    /// Memory maps the given ggml file and parses its header.
+    ///
+    /// # Safety
+    ///
+    /// The unsafe is inherited from [`memmap2::MmapOptions`].
+    pub unsafe fn from_mmaped_file<P: AsRef<std::path::Path>>(p: P) -> Result<Self> {
+        let p = p.as_ref();
+        let file = std::fs::File::open(p).map_err(|e| crate::Error::from(e).with_path(p))?;
+        let mmap = memmap2::MmapOptions::new()
+            .map(&file)
+            .map_err(|e| crate::Error::from(e).with_path(p))?;
+        Self::from_data(Data::Mmap(mmap))
+    }
+
+    /// Parses a ggml file that has already been loaded in memory.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        Self::from_data(Data::Owned(bytes))
+    }
+
+    /// Reads the whole content of the reader in memory, use [`Content::from_mmaped_file`] to avoid
+    /// this for large files.
+    pub fn read<R: std::io::Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Materializes the tensor with the given name, copying its data out of the backing buffer.
+    pub fn tensor(&self, name: &str) -> Result<QTensor> {
+        let tensor_info = match self.tensor_infos.get(name) {
+            Some(tensor_info) => tensor_info,
+            None => Err(crate::Error::CannotFindTensor {
+                path: name.to_string(),
+            }
+            .bt())?,
+        };
+        let start = tensor_info.offset;
+        let mut data = &self.data[start..start + tensor_info.size_in_bytes()];
+        qtensor_from_reader(
+            &mut data,
+            tensor_info.ggml_dtype,
+            tensor_info.shape.dims().to_vec(),
+        )
+    }
 }
@@ -29,27 +29,50 @@ impl Tensor {
         let dims = self.shape().dims();
         let mut current_dim = 0;
         for (i, indexer) in indexers.iter().enumerate() {
-            x = match indexer {
-                TensorIndexer::Select(n) => x.narrow(current_dim, *n, 1)?.squeeze(current_dim)?,
-                TensorIndexer::Narrow(left_bound, right_bound) => {
-                    let start = match left_bound {
-                        Bound::Included(n) => *n,
-                        Bound::Excluded(n) => *n + 1,
-                        Bound::Unbounded => 0,
-                    };
-                    let stop = match right_bound {
-                        Bound::Included(n) => *n + 1,
-                        Bound::Excluded(n) => *n,
-                        Bound::Unbounded => dims[i],
-                    };
-                    let out = x.narrow(current_dim, start, stop.saturating_sub(start))?;
-                    current_dim += 1;
-                    out
-                }
-            };
+            let (start, len) = indexer.start_and_len(dims[i]);
+            x = x.narrow(current_dim, start, len)?;
+            match indexer {
+                TensorIndexer::Select(_) => x = x.squeeze(current_dim)?,
+                TensorIndexer::Narrow(_, _) => current_dim += 1,
+            }
         }
         Ok(x)
     }
+
+    /// Intended to be use by the trait `.slice_assign()`, the selected dimensions are narrowed
+    /// rather than squeezed so that the destination is always a view on the storage of `self`.
+    fn index_assign(&self, indexers: &[TensorIndexer], src: &Tensor) -> Result<(), Error> {
+        let dims = self.shape().dims();
+        if indexers.len() > dims.len() {
+            Err(Error::UnexpectedNumberOfDims {
+                expected: dims.len(),
+                got: indexers.len(),
+                shape: self.shape().clone(),
+            }
+            .bt())?
+        }
+        let mut dst = self.clone();
+        let mut src_dims = vec![];
+        for (i, &dim) in dims.iter().enumerate() {
+            match indexers.get(i) {
+                None => src_dims.push(dim),
+                Some(indexer) => {
+                    let (start, len) = indexer.start_and_len(dim);
+                    dst = dst.narrow(i, start, len)?;
+                    if let TensorIndexer::Narrow(_, _) = indexer {
+                        src_dims.push(len)
+                    }
+                }
+            }
+        }
+        let src = src.broadcast_as(src_dims)?;
+        let src = if src.shape() == dst.shape() {
+            src
+        } else {
+            src.reshape(dst.shape())?
+        };
+        dst.assign(&src, "slice-assign")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +84,27 @@ pub enum TensorIndexer {
     Narrow(Bound<usize>, Bound<usize>),
 }
 
+impl TensorIndexer {
+    fn start_and_len(&self, dim: usize) -> (usize, usize) {
+        match self {
+            TensorIndexer::Select(n) => (*n, 1),
+            TensorIndexer::Narrow(left_bound, right_bound) => {
+                let start = match left_bound {
+                    Bound::Included(n) => *n,
+                    Bound::Excluded(n) => *n + 1,
+                    Bound::Unbounded => 0,
+                };
+                let stop = match right_bound {
+                    Bound::Included(n) => *n + 1,
+                    Bound::Excluded(n) => *n,
+                    Bound::Unbounded => dim,
+                };
+                (start, stop.saturating_sub(start))
+            }
+        }
+    }
+}
+
 impl From<usize> for TensorIndexer {
     fn from(index: usize) -> Self {
         TensorIndexer::Select(index)
@@ -104,6 +148,24 @@ pub trait IndexOp<T> {
     /// Returns a slicing iterator which are the chunks of data necessary to
     /// reconstruct the desired tensor.
     fn i(&self, index: T) -> Result<Tensor, Error>;
+
+    /// Writes `src` in place to the sub-tensor that would be returned by `.i(index)`, `src` is
+    /// broadcasted to the shape of this sub-tensor. The write goes through the storage so it is
+    /// visible to all the tensors sharing it, this is only available on tensors that are not
+    /// tracked by the computation graph. If an existing graph has captured one of these tensors,
+    /// its backward pass returns an error. Tensors created with `Tensor::zeros` broadcast a single
+    /// value and have to be made contiguous first.
+    ///
+    /// ```
+    /// # use candle_core::{Tensor, DType, Device, IndexOp};
+    /// let a = Tensor::zeros((2, 3), DType::F32, &Device::Cpu)?.contiguous()?;
+    /// let b = Tensor::new(&[[1f32, 2.], [3., 4.]], &Device::Cpu)?;
+    /// a.slice_assign((.., 1..), &b)?;
+    /// a.slice_assign((1, 0), &Tensor::new(5f32, &Device::Cpu)?)?;
+    /// assert_eq!(a.to_vec2::<f32>()?, &[[0., 1., 2.], [5., 3., 4.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    fn slice_assign(&self, index: T, src: &Tensor) -> Result<(), Error>;
 }
 
 impl<T> IndexOp<T> for Tensor
@@ -113,6 +175,10 @@ where
     fn i(&self, index: T) -> Result<Tensor, Error> {
         self.index(&[index.into()])
     }
+
+    fn slice_assign(&self, index: T, src: &Tensor) -> Result<(), Error> {
+        self.index_assign(&[index.into()], src)
+    }
 }
 
 macro_rules! index_op_tuple {
@@ -125,6 +191,10 @@ macro_rules! index_op_tuple {
             fn i(&self, ($($t,)*): ($($t,)*)) -> Result<Tensor, Error> {
                 self.index(&[$($t.into(),)*])
             }
+
+            fn slice_assign(&self, ($($t,)*): ($($t,)*), src: &Tensor) -> Result<(), Error> {
+                self.index_assign(&[$($t.into(),)*], src)
+            }
         }
     };
 }
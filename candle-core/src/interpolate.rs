@@ -0,0 +1,101 @@
+use crate::{Device, Result, Tensor};
+
+/// The interpolation method used by [`Tensor::interpolate2d`], these follow the PyTorch
+/// conventions of `torch.nn.functional.interpolate`.
+///
+/// With `align_corners`, the corner pixels of the input and output are aligned and the values at
+/// the corners are preserved, otherwise the pixels are treated as areas and the input and output
+/// edges are aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolateMode {
+    Nearest,
+    Bilinear { align_corners: bool },
+    Bicubic { align_corners: bool },
+}
+
+// https://github.com/pytorch/pytorch/blob/main/aten/src/ATen/native/UpSample.h
+fn source_index(in_size: usize, out_size: usize, dst_idx: usize, align_corners: bool) -> f64 {
+    if align_corners {
+        if out_size > 1 {
+            (in_size - 1) as f64 / (out_size - 1) as f64 * dst_idx as f64
+        } else {
+            0.
+        }
+    } else {
+        in_size as f64 / out_size as f64 * (dst_idx as f64 + 0.5) - 0.5
+    }
+}
+
+const CUBIC_A: f64 = -0.75;
+
+fn cubic_convolution1(x: f64) -> f64 {
+    ((CUBIC_A + 2.) * x - (CUBIC_A + 3.)) * x * x + 1.
+}
+
+fn cubic_convolution2(x: f64) -> f64 {
+    ((CUBIC_A * x - 5. * CUBIC_A) * x + 8. * CUBIC_A) * x - 4. * CUBIC_A
+}
+
+// For each output position along a dimension, the input positions that contribute to it and
+// their weights. The interpolation modes are separable so a 2d interpolation combines the taps
+// of the two spatial dimensions.
+pub(crate) fn taps(
+    in_size: usize,
+    out_size: usize,
+    mode: InterpolateMode,
+) -> Vec<Vec<(usize, f64)>> {
+    (0..out_size)
+        .map(|dst_idx| match mode {
+            InterpolateMode::Nearest => {
+                let scale = in_size as f64 / out_size as f64;
+                let src_idx = usize::min(in_size - 1, (dst_idx as f64 * scale) as usize);
+                vec![(src_idx, 1.)]
+            }
+            InterpolateMode::Bilinear { align_corners } => {
+                let src = source_index(in_size, out_size, dst_idx, align_corners).max(0.);
+                let i0 = usize::min(src as usize, in_size - 1);
+                let i1 = usize::min(i0 + 1, in_size - 1);
+                let l1 = src - i0 as f64;
+                vec![(i0, 1. - l1), (i1, l1)]
+            }
+            InterpolateMode::Bicubic { align_corners } => {
+                let src = source_index(in_size, out_size, dst_idx, align_corners);
+                let i0 = src.floor();
+                let t = src - i0;
+                let weights = [
+                    cubic_convolution2(t + 1.),
+                    cubic_convolution1(t),
+                    cubic_convolution1(1. - t),
+                    cubic_convolution2(2. - t),
+                ];
+                // Out of bounds accesses are clamped to the input edges.
+                let max_idx = (in_size - 1) as f64;
+                weights
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &w)| {
+                        let idx = (i0 - 1. + i as f64).clamp(0., max_idx);
+                        (idx as usize, w)
+                    })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+// The taps as a dense matrix of shape (out_size, in_size) so that interpolating along a dimension
+// is a matmul with this matrix.
+pub(crate) fn taps_matrix(
+    in_size: usize,
+    out_size: usize,
+    mode: InterpolateMode,
+    device: &Device,
+) -> Result<Tensor> {
+    let mut m = vec![0f64; out_size * in_size];
+    for (dst_idx, taps) in taps(in_size, out_size, mode).iter().enumerate() {
+        for &(src_idx, w) in taps.iter() {
+            m[dst_idx * in_size + src_idx] += w
+        }
+    }
+    Tensor::from_vec(m, (out_size, in_size), device)
+}
@@ -9,6 +9,14 @@ pub struct Layout {
 }
 
 impl Layout {
+    pub fn new(shape: Shape, stride: Vec<usize>, start_offset: usize) -> Self {
+        Self {
+            shape,
+            stride,
+            start_offset,
+        }
+    }
+
     pub fn contiguous_with_offset<S: Into<Shape>>(shape: S, start_offset: usize) -> Self {
         let shape = shape.into();
         let stride = shape.stride_contiguous();
@@ -62,6 +70,35 @@ impl Layout {
         self.shape.is_fortran_contiguous(&self.stride)
     }
 
+    /// The offset between two consecutive matrices when all the dimensions but the last two are
+    /// flattened in a single batch dimension, `None` if the matrices are not evenly spaced. This
+    /// is 0 when the matrix is broadcasted over the batch dimensions.
+    pub(crate) fn matmul_batch_stride(&self) -> Option<usize> {
+        let rank = self.dims().len();
+        if rank < 2 {
+            return None;
+        }
+        let dims = self.dims();
+        // The batch dimensions of size 1 can have any stride.
+        let mut batch = dims[..rank - 2]
+            .iter()
+            .zip(self.stride[..rank - 2].iter())
+            .filter(|(&d, _)| d > 1)
+            .rev();
+        let (dim, batch_stride) = match batch.next() {
+            None => return Some(dims[rank - 2] * dims[rank - 1]),
+            Some((&d, &s)) => (d, s),
+        };
+        let mut expected_stride = batch_stride * dim;
+        for (&d, &s) in batch {
+            if s != expected_stride {
+                return None;
+            }
+            expected_stride = s * d
+        }
+        Some(batch_stride)
+    }
+
     pub(crate) fn narrow(&self, dim: usize, start: usize, len: usize) -> Result<Self> {
         let dims = self.shape().dims();
         if dim >= dims.len() {
@@ -112,6 +149,18 @@ impl Layout {
         })
     }
 
+    // The caller is responsible for checking that `idxs` is a permutation of the dimensions.
+    pub(crate) fn permute(&self, idxs: &[usize]) -> Self {
+        let dims = self.shape().dims();
+        let stride = idxs.iter().map(|&i| self.stride[i]).collect();
+        let dims: Vec<_> = idxs.iter().map(|&i| dims[i]).collect();
+        Self {
+            shape: Shape::from(dims),
+            stride,
+            start_offset: self.start_offset,
+        }
+    }
+
     pub fn broadcast_as<S: Into<Shape>>(&self, shape: S) -> Result<Self> {
         let shape = shape.into();
         if shape.rank() < self.shape().rank() {
@@ -0,0 +1,1362 @@
+//! A lazy backend that records operations in a graph and runs them on the cpu on demand.
+//!
+//! Operations on lazy storages do not compute anything, instead they add a node to a graph. The
+//! graph gets evaluated when the data is actually needed, e.g. when calling `to_vec1` or when
+//! moving a tensor to another device. Before running the graph, the executor:
+//! - only keeps the nodes that contribute to the requested result, the other nodes are dead and
+//!   are never evaluated,
+//! - fuses chains of elementwise operations (unary, binary, affine) so that they run block by
+//!   block in a single buffer,
+//! - plans buffer reuse: the buffers of intermediary values are released as soon as their last
+//!   consumer has run and get recycled for later nodes or updated in place.
+//!
+//! The values of nodes that are still referenced from outside of the evaluated graph, e.g. by a
+//! tensor kept in a cache, are stored so that they are only computed once.
+use crate::backend::{BackendDevice, BackendStorage};
+use crate::cpu_backend::CpuDevice;
+use crate::op::{BinaryOpT, CmpOp, ReduceOp, ScanOp, UnaryOpT};
+use crate::{CpuStorage, DType, Error, Layout, Result, Shape, StridedIndex, WithDType};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+// The number of elements processed at once by fused elementwise kernels, this is small enough for
+// a block to remain in cache while all the ops of the chain are applied to it.
+const BLOCK_SIZE: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct LazyDevice;
+
+/// A storage whose content is defined by a node of the lazy graph.
+#[derive(Debug, Clone)]
+pub struct LazyStorage {
+    node: Arc<Node>,
+}
+
+type UnaryFn = fn(&mut CpuStorage, Range<usize>) -> Result<()>;
+type BinaryFn = fn(&mut CpuStorage, Range<usize>, &CpuStorage, &mut Offsets, bool) -> Result<()>;
+type OpaqueFn = Box<dyn Fn(&[&CpuStorage]) -> Result<CpuStorage> + Send + Sync>;
+type UpdateFn = Box<dyn Fn(&mut CpuStorage, &[&CpuStorage]) -> Result<()> + Send + Sync>;
+
+// A node used as the argument of an op, together with the way it is read. The layout is only
+// kept when the node is not read contiguously so that most ops do not have to clone it.
+struct Input {
+    node: Arc<Node>,
+    start: usize,
+    elem_count: usize,
+    strided: Option<Layout>,
+}
+
+impl Input {
+    fn offsets(&self) -> Offsets<'_> {
+        match &self.strided {
+            None => Offsets::Contiguous(self.start),
+            Some(layout) => Offsets::new(layout),
+        }
+    }
+
+    // Returns true if this reads the whole buffer of the node in order.
+    fn is_identity(&self, elem_count: usize) -> bool {
+        self.strided.is_none() && self.start == 0 && self.elem_count == elem_count
+    }
+}
+
+enum LazyOp {
+    Fill {
+        value: f64,
+        elem_count: usize,
+    },
+    Unary {
+        arg: Input,
+        name: &'static str,
+        f: UnaryFn,
+    },
+    Binary {
+        lhs: Input,
+        rhs: Input,
+        name: &'static str,
+        f: BinaryFn,
+    },
+    Affine {
+        arg: Input,
+        mul: f64,
+        add: f64,
+    },
+    Copy {
+        dst: Arc<Node>,
+        src: Arc<Node>,
+        src_l: Layout,
+        dst_offset: usize,
+    },
+    // Any other operation, these are run using the cpu backend and cannot be fused.
+    Opaque {
+        args: Vec<Arc<Node>>,
+        name: &'static str,
+        f: OpaqueFn,
+    },
+    // An operation that modifies the value of `dst` in place, e.g. a strided copy.
+    Update {
+        dst: Arc<Node>,
+        args: Vec<Arc<Node>>,
+        name: &'static str,
+        f: UpdateFn,
+    },
+}
+
+impl LazyOp {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Fill { .. } => "fill",
+            Self::Unary { name, .. }
+            | Self::Binary { name, .. }
+            | Self::Opaque { name, .. }
+            | Self::Update { name, .. } => name,
+            Self::Affine { .. } => "affine",
+            Self::Copy { .. } => "copy",
+        }
+    }
+
+    // The nodes used by this op, a node appears as many times as it is used.
+    fn inputs(&self) -> impl Iterator<Item = &Arc<Node>> {
+        let (pair, args): ([Option<&Arc<Node>>; 2], &[Arc<Node>]) = match self {
+            Self::Fill { .. } => ([None, None], &[]),
+            Self::Unary { arg, .. } | Self::Affine { arg, .. } => ([Some(&arg.node), None], &[]),
+            Self::Binary { lhs, rhs, .. } => ([Some(&lhs.node), Some(&rhs.node)], &[]),
+            Self::Copy { dst, src, .. } => ([Some(dst), Some(src)], &[]),
+            Self::Opaque { args, .. } => ([None, None], args.as_slice()),
+            Self::Update { dst, args, .. } => ([Some(dst), None], args.as_slice()),
+        };
+        pair.into_iter().flatten().chain(args.iter())
+    }
+
+    // The arguments of an elementwise op.
+    fn elementwise_args(&self) -> [Option<&Input>; 2] {
+        match self {
+            Self::Unary { arg, .. } | Self::Affine { arg, .. } => [Some(arg), None],
+            Self::Binary { lhs, rhs, .. } => [Some(lhs), Some(rhs)],
+            _ => [None, None],
+        }
+    }
+
+    fn is_elementwise(&self) -> bool {
+        matches!(
+            self,
+            Self::Unary { .. } | Self::Binary { .. } | Self::Affine { .. }
+        )
+    }
+
+    // The number of elements of the result when it can be known without running the op.
+    fn elem_count(&self) -> Option<usize> {
+        match self {
+            Self::Fill { elem_count, .. } => Some(*elem_count),
+            Self::Unary { arg, .. } | Self::Affine { arg, .. } => Some(arg.elem_count),
+            Self::Binary { lhs, .. } => Some(lhs.elem_count),
+            Self::Copy { .. } | Self::Opaque { .. } | Self::Update { .. } => None,
+        }
+    }
+}
+
+enum State {
+    Pending(Arc<LazyOp>),
+    Ready(Arc<CpuStorage>),
+}
+
+struct Node {
+    id: usize,
+    dtype: DType,
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state.try_lock().as_deref() {
+            Ok(State::Pending(op)) => op.name(),
+            Ok(State::Ready(_)) => "ready",
+            Err(_) => "locked",
+        };
+        write!(f, "Node({}, {:?}, {state})", self.id, self.dtype)
+    }
+}
+
+impl Node {
+    fn new(dtype: DType, state: State) -> Arc<Self> {
+        use std::sync::atomic;
+        static COUNTER: atomic::AtomicUsize = atomic::AtomicUsize::new(1);
+        let id = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+        Arc::new(Self {
+            id,
+            dtype,
+            state: Mutex::new(state),
+        })
+    }
+}
+
+impl LazyStorage {
+    pub(crate) fn ready(storage: CpuStorage) -> Self {
+        let node = Node::new(storage.dtype(), State::Ready(Arc::new(storage)));
+        Self { node }
+    }
+
+    fn pending(dtype: DType, op: LazyOp) -> Self {
+        let node = Node::new(dtype, State::Pending(Arc::new(op)));
+        Self { node }
+    }
+
+    fn input(&self, layout: &Layout) -> Input {
+        let (start, strided) = match layout.contiguous_offsets() {
+            Some((start, _)) => (start, None),
+            None => (0, Some(layout.clone())),
+        };
+        Input {
+            node: self.node.clone(),
+            start,
+            elem_count: layout.shape().elem_count(),
+            strided,
+        }
+    }
+
+    fn update<F>(&self, name: &'static str, args: &[&Self], f: F) -> Self
+    where
+        F: Fn(&mut CpuStorage, &[&CpuStorage]) -> Result<()> + Send + Sync + 'static,
+    {
+        let op = LazyOp::Update {
+            dst: self.node.clone(),
+            args: args.iter().map(|a| a.node.clone()).collect(),
+            name,
+            f: Box::new(f),
+        };
+        Self::pending(self.dtype(), op)
+    }
+
+    fn opaque<F>(name: &'static str, args: &[&Self], dtype: DType, f: F) -> Self
+    where
+        F: Fn(&[&CpuStorage]) -> Result<CpuStorage> + Send + Sync + 'static,
+    {
+        let args = args.iter().map(|a| a.node.clone()).collect();
+        let op = LazyOp::Opaque {
+            args,
+            name,
+            f: Box::new(f),
+        };
+        Self::pending(dtype, op)
+    }
+
+    /// Returns true if the value of this storage has already been computed.
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.node.state.lock().unwrap(), State::Ready(_))
+    }
+
+    /// Evaluates the graph that this storage depends on and returns the resulting cpu storage.
+    /// The result is cached so calling this again does not run anything.
+    pub fn realize(&self) -> Result<Arc<CpuStorage>> {
+        if let State::Ready(storage) = &*self.node.state.lock().unwrap() {
+            return Ok(storage.clone());
+        }
+        let mut executor = Executor::new(&self.node);
+        executor.run()?;
+        match &*self.node.state.lock().unwrap() {
+            State::Ready(storage) => Ok(storage.clone()),
+            State::Pending(_) => crate::bail!("lazy node {} has not been evaluated", self.node.id),
+        }
+    }
+}
+
+// Storage offsets of the elements of an op argument, these are consumed block by block in the
+// same order as the elements of the result.
+enum Offsets<'a> {
+    Contiguous(usize),
+    Broadcast(BroadcastIndex),
+    Strided(StridedIndex<'a>),
+}
+
+impl<'a> Offsets<'a> {
+    fn new(layout: &'a Layout) -> Self {
+        if let Some((start, _)) = layout.contiguous_offsets() {
+            return Self::Contiguous(start);
+        }
+        match layout.offsets_b() {
+            Some(ob) => Self::Broadcast(BroadcastIndex {
+                start: ob.start,
+                len: ob.len,
+                right_broadcast: ob.right_broadcast,
+                i_in_block: 0,
+                i_right_broadcast: 0,
+            }),
+            None => Self::Strided(layout.strided_index()),
+        }
+    }
+}
+
+// Offsets of a contiguous block of data that is broadcasted on the left and on the right, this
+// avoids going through the generic strided index for the common broadcasting cases.
+struct BroadcastIndex {
+    start: usize,
+    len: usize,
+    right_broadcast: usize,
+    i_in_block: usize,
+    i_right_broadcast: usize,
+}
+
+impl Iterator for BroadcastIndex {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let index = self.start + self.i_in_block;
+        self.i_right_broadcast += 1;
+        if self.i_right_broadcast >= self.right_broadcast {
+            self.i_in_block += 1;
+            self.i_right_broadcast = 0;
+        }
+        if self.i_in_block >= self.len {
+            self.i_in_block = 0
+        }
+        Some(index)
+    }
+}
+
+fn load_<T: Copy>(dst: &mut [T], src: &[T], offsets: &mut Offsets) {
+    fn load_indexed<T: Copy>(dst: &mut [T], src: &[T], index: impl Iterator<Item = usize>) {
+        for (d, i) in dst.iter_mut().zip(index) {
+            *d = src[i]
+        }
+    }
+    match offsets {
+        Offsets::Contiguous(start) => {
+            dst.copy_from_slice(&src[*start..*start + dst.len()]);
+            *start += dst.len()
+        }
+        Offsets::Broadcast(index) => load_indexed(dst, src, index),
+        Offsets::Strided(index) => load_indexed(dst, src, index),
+    }
+}
+
+fn binary_<T: Copy, F: Fn(T, T) -> T>(
+    acc: &mut [T],
+    other: &[T],
+    offsets: &mut Offsets,
+    acc_is_lhs: bool,
+    f: F,
+) {
+    fn binary_indexed<T: Copy, F: Fn(T, T) -> T>(
+        acc: &mut [T],
+        other: &[T],
+        index: impl Iterator<Item = usize>,
+        acc_is_lhs: bool,
+        f: F,
+    ) {
+        if acc_is_lhs {
+            acc.iter_mut()
+                .zip(index)
+                .for_each(|(a, i)| *a = f(*a, other[i]))
+        } else {
+            acc.iter_mut()
+                .zip(index)
+                .for_each(|(a, i)| *a = f(other[i], *a))
+        }
+    }
+    match offsets {
+        Offsets::Contiguous(start) => {
+            let other = &other[*start..*start + acc.len()];
+            *start += acc.len();
+            if acc_is_lhs {
+                acc.iter_mut().zip(other).for_each(|(a, &o)| *a = f(*a, o))
+            } else {
+                acc.iter_mut().zip(other).for_each(|(a, &o)| *a = f(o, *a))
+            }
+        }
+        Offsets::Broadcast(index) => binary_indexed(acc, other, index, acc_is_lhs, f),
+        Offsets::Strided(index) => binary_indexed(acc, other, index, acc_is_lhs, f),
+    }
+}
+
+fn load_block(
+    dst: &mut CpuStorage,
+    r: Range<usize>,
+    src: &CpuStorage,
+    offsets: &mut Offsets,
+) -> Result<()> {
+    use CpuStorage as C;
+    match (dst, src) {
+        (C::U8(d), C::U8(s)) | (C::Bool(d), C::Bool(s)) => load_(&mut d[r], s, offsets),
+        (C::U32(d), C::U32(s)) => load_(&mut d[r], s, offsets),
+        (C::I8(d), C::I8(s)) => load_(&mut d[r], s, offsets),
+        (C::I16(d), C::I16(s)) => load_(&mut d[r], s, offsets),
+        (C::I32(d), C::I32(s)) => load_(&mut d[r], s, offsets),
+        (C::I64(d), C::I64(s)) => load_(&mut d[r], s, offsets),
+        (C::BF16(d), C::BF16(s)) => load_(&mut d[r], s, offsets),
+        (C::F16(d), C::F16(s)) => load_(&mut d[r], s, offsets),
+        (C::F32(d), C::F32(s)) => load_(&mut d[r], s, offsets),
+        (C::F64(d), C::F64(s)) => load_(&mut d[r], s, offsets),
+        (d, s) => Err(Error::DTypeMismatchBinaryOp {
+            lhs: d.dtype(),
+            rhs: s.dtype(),
+            op: "load",
+        }
+        .bt())?,
+    }
+    Ok(())
+}
+
+fn unary_block<B: UnaryOpT>(acc: &mut CpuStorage, r: Range<usize>) -> Result<()> {
+    use CpuStorage as C;
+    match acc {
+        C::U8(vs) => vs[r].iter_mut().for_each(|v| *v = B::u8(*v)),
+        C::U32(vs) => vs[r].iter_mut().for_each(|v| *v = B::u32(*v)),
+        C::I8(vs) => vs[r].iter_mut().for_each(|v| *v = B::i8(*v)),
+        C::I16(vs) => vs[r].iter_mut().for_each(|v| *v = B::i16(*v)),
+        C::I32(vs) => vs[r].iter_mut().for_each(|v| *v = B::i32(*v)),
+        C::I64(vs) => vs[r].iter_mut().for_each(|v| *v = B::i64(*v)),
+        C::BF16(vs) => vs[r].iter_mut().for_each(|v| *v = B::bf16(*v)),
+        C::F16(vs) => vs[r].iter_mut().for_each(|v| *v = B::f16(*v)),
+        C::F32(vs) => vs[r].iter_mut().for_each(|v| *v = B::f32(*v)),
+        C::F64(vs) => vs[r].iter_mut().for_each(|v| *v = B::f64(*v)),
+        C::Bool(_) => Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt())?,
+    }
+    Ok(())
+}
+
+fn binary_block<B: BinaryOpT>(
+    acc: &mut CpuStorage,
+    r: Range<usize>,
+    other: &CpuStorage,
+    offsets: &mut Offsets,
+    acc_is_lhs: bool,
+) -> Result<()> {
+    use CpuStorage as C;
+    match (acc, other) {
+        (C::U8(a), C::U8(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::u8),
+        (C::U32(a), C::U32(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::u32),
+        (C::I8(a), C::I8(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::i8),
+        (C::I16(a), C::I16(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::i16),
+        (C::I32(a), C::I32(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::i32),
+        (C::I64(a), C::I64(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::i64),
+        (C::BF16(a), C::BF16(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::bf16),
+        (C::F16(a), C::F16(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::f16),
+        (C::F32(a), C::F32(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::f32),
+        (C::F64(a), C::F64(o)) => binary_(&mut a[r], o, offsets, acc_is_lhs, B::f64),
+        (C::Bool(_), C::Bool(_)) => Err(Error::UnsupportedDTypeForOp(DType::Bool, B::NAME).bt())?,
+        (a, o) => Err(Error::DTypeMismatchBinaryOp {
+            lhs: a.dtype(),
+            rhs: o.dtype(),
+            op: B::NAME,
+        }
+        .bt())?,
+    }
+    Ok(())
+}
+
+fn affine_block(acc: &mut CpuStorage, r: Range<usize>, mul: f64, add: f64) -> Result<()> {
+    fn affine_<T: WithDType>(vs: &mut [T], mul: f64, add: f64) {
+        let (mul, add) = (T::from_f64(mul), T::from_f64(add));
+        vs.iter_mut().for_each(|v| *v = *v * mul + add)
+    }
+    use CpuStorage as C;
+    match acc {
+        C::U8(vs) => affine_(&mut vs[r], mul, add),
+        C::U32(vs) => affine_(&mut vs[r], mul, add),
+        C::I8(vs) => affine_(&mut vs[r], mul, add),
+        C::I16(vs) => affine_(&mut vs[r], mul, add),
+        C::I32(vs) => affine_(&mut vs[r], mul, add),
+        C::I64(vs) => affine_(&mut vs[r], mul, add),
+        C::BF16(vs) => affine_(&mut vs[r], mul, add),
+        C::F16(vs) => affine_(&mut vs[r], mul, add),
+        C::F32(vs) => affine_(&mut vs[r], mul, add),
+        C::F64(vs) => affine_(&mut vs[r], mul, add),
+        C::Bool(_) => Err(Error::UnsupportedDTypeForOp(DType::Bool, "affine").bt())?,
+    }
+    Ok(())
+}
+
+fn fill(storage: &mut CpuStorage, value: f64) {
+    fn fill_<T: WithDType>(vs: &mut [T], value: f64) {
+        vs.fill(T::from_f64(value))
+    }
+    use CpuStorage as C;
+    match storage {
+        C::U8(vs) | C::Bool(vs) => fill_(vs, value),
+        C::U32(vs) => fill_(vs, value),
+        C::I8(vs) => fill_(vs, value),
+        C::I16(vs) => fill_(vs, value),
+        C::I32(vs) => fill_(vs, value),
+        C::I64(vs) => fill_(vs, value),
+        C::BF16(vs) => fill_(vs, value),
+        C::F16(vs) => fill_(vs, value),
+        C::F32(vs) => fill_(vs, value),
+        C::F64(vs) => fill_(vs, value),
+    }
+}
+
+fn storage_len(storage: &CpuStorage) -> usize {
+    use CpuStorage as C;
+    match storage {
+        C::U8(vs) | C::Bool(vs) => vs.len(),
+        C::U32(vs) => vs.len(),
+        C::I8(vs) => vs.len(),
+        C::I16(vs) => vs.len(),
+        C::I32(vs) => vs.len(),
+        C::I64(vs) => vs.len(),
+        C::BF16(vs) => vs.len(),
+        C::F16(vs) => vs.len(),
+        C::F32(vs) => vs.len(),
+        C::F64(vs) => vs.len(),
+    }
+}
+
+enum Value {
+    // A value that is shared with the graph, it cannot be modified.
+    Shared(Arc<CpuStorage>),
+    // An intermediary value only used by the executor, its buffer can be reused.
+    Owned(CpuStorage),
+}
+
+impl Value {
+    fn as_storage(&self) -> &CpuStorage {
+        match self {
+            Self::Shared(s) => s.as_ref(),
+            Self::Owned(s) => s,
+        }
+    }
+}
+
+enum Step<'a> {
+    Load(&'a CpuStorage, Offsets<'a>),
+    Unary(UnaryFn),
+    Affine(f64, f64),
+    Binary {
+        f: BinaryFn,
+        other: &'a CpuStorage,
+        offsets: Offsets<'a>,
+        acc_is_lhs: bool,
+    },
+}
+
+impl<'a> Step<'a> {
+    fn apply(&mut self, acc: &mut CpuStorage, r: Range<usize>) -> Result<()> {
+        match self {
+            Self::Load(src, offsets) => load_block(acc, r, src, offsets),
+            Self::Unary(f) => f(acc, r),
+            Self::Affine(mul, add) => affine_block(acc, r, *mul, *add),
+            Self::Binary {
+                f,
+                other,
+                offsets,
+                acc_is_lhs,
+            } => f(acc, r, other, offsets, *acc_is_lhs),
+        }
+    }
+}
+
+struct Executor {
+    // The nodes reached from the target in topological order, the nodes that are already ready
+    // have no op and are only used as inputs.
+    nodes: Vec<Arc<Node>>,
+    ops: Vec<Option<Arc<LazyOp>>>,
+    // The positions in `nodes` of the inputs of each op, `inputs[i]` is the range of
+    // `input_indexes` used by the op at position `i`.
+    inputs: Vec<Range<usize>>,
+    input_indexes: Vec<usize>,
+    // The number of consumers that have not run yet for each node.
+    uses: Vec<usize>,
+    // The nodes whose value has to be stored in the graph once computed.
+    retain: Vec<bool>,
+    // The chains of fused elementwise nodes and for each node the chain that it belongs to.
+    groups: Vec<Vec<usize>>,
+    group_of: Vec<Option<usize>>,
+    values: Vec<Option<Value>>,
+    // Buffers that are not used anymore, indexed by dtype and number of elements.
+    pool: HashMap<(DType, usize), Vec<CpuStorage>>,
+    allocated: usize,
+    reused: usize,
+}
+
+impl Executor {
+    fn new(target: &Arc<Node>) -> Self {
+        let mut executor = Self {
+            nodes: vec![],
+            ops: vec![],
+            inputs: vec![],
+            input_indexes: vec![],
+            uses: vec![],
+            retain: vec![],
+            groups: vec![],
+            group_of: vec![],
+            values: vec![],
+            pool: HashMap::new(),
+            allocated: 0,
+            reused: 0,
+        };
+        executor.collect(target);
+        executor
+    }
+
+    // Walks the graph from the target and lists the nodes in topological order. Nodes that do
+    // not contribute to the target are never reached and so are not evaluated.
+    fn collect(&mut self, target: &Arc<Node>) {
+        let mut positions = HashMap::new();
+        let mut stack = vec![(target.clone(), None)];
+        while let Some((node, op)) = stack.pop() {
+            if let Some(op) = op {
+                positions.insert(node.id, self.nodes.len());
+                self.nodes.push(node);
+                self.ops.push(Some(op));
+                continue;
+            }
+            if positions.contains_key(&node.id) {
+                continue;
+            }
+            let op = match &*node.state.lock().unwrap() {
+                State::Ready(storage) => Err(storage.clone()),
+                State::Pending(op) => Ok(op.clone()),
+            };
+            match op {
+                Err(storage) => {
+                    positions.insert(node.id, self.nodes.len());
+                    self.values.resize_with(self.nodes.len(), || None);
+                    self.values.push(Some(Value::Shared(storage)));
+                    self.nodes.push(node);
+                    self.ops.push(None);
+                }
+                Ok(op) => {
+                    let len = stack.len();
+                    for input in op.inputs() {
+                        if !positions.contains_key(&input.id) {
+                            stack.push((input.clone(), None))
+                        }
+                    }
+                    // Inputs are popped in order so that the nodes are evaluated in the order in
+                    // which they have been created.
+                    stack[len..].reverse();
+                    stack.insert(len, (node, Some(op)));
+                }
+            }
+        }
+        let n = self.nodes.len();
+        self.values.resize_with(n, || None);
+        self.uses = vec![0; n];
+        for op in self.ops.iter() {
+            let start = self.input_indexes.len();
+            if let Some(op) = op {
+                for input in op.inputs() {
+                    let index = positions[&input.id];
+                    self.uses[index] += 1;
+                    self.input_indexes.push(index)
+                }
+            }
+            self.inputs.push(start..self.input_indexes.len())
+        }
+        // A node that is referenced from outside of the evaluated graph has its value stored.
+        // The references that we know of are the one from `self.nodes` and the ones from the ops
+        // of the graph.
+        self.retain = (0..n)
+            .map(|i| i + 1 == n || Arc::strong_count(&self.nodes[i]) > 1 + self.uses[i])
+            .collect();
+        self.group_of = vec![None; n];
+    }
+
+    // Groups the elementwise nodes in chains: a node is added to the chain of its argument when
+    // it is the only consumer of this argument and reads it in order.
+    fn fuse(&mut self) {
+        for (index, op) in self.ops.iter().enumerate() {
+            let op = match op {
+                Some(op) if op.is_elementwise() => op,
+                _ => continue,
+            };
+            let args = op.elementwise_args().into_iter().flatten();
+            let arg_indexes = self.input_indexes[self.inputs[index].clone()].iter();
+            let group = args.zip(arg_indexes).find_map(|(arg, &arg_index)| {
+                let group = self.group_of[arg_index]?;
+                let elem_count = self.ops[arg_index].as_ref()?.elem_count()?;
+                let fusable = self.groups[group].last() == Some(&arg_index)
+                    && self.uses[arg_index] == 1
+                    && !self.retain[arg_index]
+                    && arg.is_identity(elem_count);
+                fusable.then_some(group)
+            });
+            let group = match group {
+                Some(group) => {
+                    self.groups[group].push(index);
+                    group
+                }
+                None => {
+                    self.groups.push(vec![index]);
+                    self.groups.len() - 1
+                }
+            };
+            self.group_of[index] = Some(group);
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        self.fuse();
+        let fused = self
+            .groups
+            .iter()
+            .filter(|g| g.len() > 1)
+            .map(|g| g.len())
+            .sum::<usize>();
+        for index in 0..self.nodes.len() {
+            if self.ops[index].is_none() {
+                continue;
+            }
+            match self.group_of[index] {
+                None => self.run_node(index)?,
+                Some(group) => {
+                    if self.groups[group].last() == Some(&index) {
+                        self.run_group(group)?
+                    }
+                }
+            }
+        }
+        tracing::debug!(
+            nodes = self.nodes.len(),
+            fused,
+            allocated = self.allocated,
+            reused = self.reused,
+            "lazy graph evaluated"
+        );
+        Ok(())
+    }
+
+    fn value(&self, index: usize) -> Result<&CpuStorage> {
+        match &self.values[index] {
+            Some(value) => Ok(value.as_storage()),
+            None => crate::bail!("lazy node {} has no value", self.nodes[index].id),
+        }
+    }
+
+    fn input(&self, index: usize, i: usize) -> usize {
+        self.input_indexes[self.inputs[index].start + i]
+    }
+
+    fn alloc(&mut self, dtype: DType, elem_count: usize) -> Result<CpuStorage> {
+        let buffer = self
+            .pool
+            .get_mut(&(dtype, elem_count))
+            .and_then(|buffers| buffers.pop());
+        match buffer {
+            Some(buffer) => {
+                self.reused += 1;
+                Ok(buffer)
+            }
+            None => {
+                self.allocated += 1;
+                CpuDevice.zeros_impl(&Shape::from(elem_count), dtype)
+            }
+        }
+    }
+
+    // Returns true if the buffer of an argument can be updated in place. This is only possible
+    // when this is the last use of the argument and its value is not stored in the graph.
+    fn can_take(&self, index: usize, input: Option<&Input>) -> bool {
+        self.uses[index] == 1
+            && match &self.values[index] {
+                Some(Value::Owned(s)) => input.is_none_or(|i| i.is_identity(storage_len(s))),
+                _ => false,
+            }
+    }
+
+    fn take(&mut self, index: usize, input: Option<&Input>) -> Option<CpuStorage> {
+        if !self.can_take(index, input) {
+            return None;
+        }
+        match self.values[index].take() {
+            Some(Value::Owned(storage)) => {
+                self.reused += 1;
+                Some(storage)
+            }
+            _ => None,
+        }
+    }
+
+    // Returns the buffer of a node that is about to be modified, this is a copy if the buffer
+    // cannot be reused.
+    fn take_or_clone(&mut self, index: usize) -> Result<CpuStorage> {
+        match self.take(index, None) {
+            Some(storage) => Ok(storage),
+            None => {
+                self.allocated += 1;
+                Ok(self.value(index)?.clone())
+            }
+        }
+    }
+
+    // Marks a use of a node as done, once there are no uses left its buffer is recycled.
+    fn release(&mut self, index: usize) {
+        self.uses[index] = self.uses[index].saturating_sub(1);
+        if self.uses[index] == 0 {
+            if let Some(Value::Owned(storage)) = self.values[index].take() {
+                let key = (storage.dtype(), storage_len(&storage));
+                self.pool.entry(key).or_default().push(storage)
+            }
+        }
+    }
+
+    fn release_inputs(&mut self, index: usize) {
+        for i in self.inputs[index].clone() {
+            self.release(self.input_indexes[i])
+        }
+    }
+
+    fn store(&mut self, index: usize, storage: CpuStorage) {
+        if self.retain[index] {
+            let storage = Arc::new(storage);
+            *self.nodes[index].state.lock().unwrap() = State::Ready(storage.clone());
+            self.values[index] = Some(Value::Shared(storage));
+        } else {
+            self.values[index] = Some(Value::Owned(storage));
+        }
+    }
+
+    fn run_node(&mut self, index: usize) -> Result<()> {
+        let op = match &self.ops[index] {
+            Some(op) => op.clone(),
+            None => return Ok(()),
+        };
+        let storage = match op.as_ref() {
+            LazyOp::Fill { value, elem_count } => {
+                let reused = self.reused;
+                let mut storage = self.alloc(self.nodes[index].dtype, *elem_count)?;
+                // Freshly allocated buffers are already zeroed.
+                if *value != 0. || self.reused != reused {
+                    fill(&mut storage, *value)
+                }
+                storage
+            }
+            LazyOp::Copy {
+                src_l, dst_offset, ..
+            } => {
+                let mut dst = self.take_or_clone(self.input(index, 0))?;
+                self.value(self.input(index, 1))?
+                    .copy_strided_src(&mut dst, *dst_offset, src_l)?;
+                dst
+            }
+            LazyOp::Update { f, .. } => {
+                let mut dst = self.take_or_clone(self.input(index, 0))?;
+                let args = self.input_indexes[self.inputs[index].clone()][1..]
+                    .iter()
+                    .map(|&arg| self.value(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                f(&mut dst, &args)?;
+                dst
+            }
+            LazyOp::Opaque { f, .. } => {
+                self.allocated += 1;
+                let args = self.input_indexes[self.inputs[index].clone()]
+                    .iter()
+                    .map(|&arg| self.value(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                f(&args)?
+            }
+            LazyOp::Unary { .. } | LazyOp::Binary { .. } | LazyOp::Affine { .. } => {
+                crate::bail!("elementwise lazy node outside of a fused group")
+            }
+        };
+        self.store(index, storage);
+        self.release_inputs(index);
+        Ok(())
+    }
+
+    fn run_group(&mut self, group: usize) -> Result<()> {
+        let members = std::mem::take(&mut self.groups[group]);
+        let first_index = members[0];
+        let first_op = match &self.ops[first_index] {
+            Some(op) => op.clone(),
+            None => crate::bail!("non elementwise lazy node in a fused group"),
+        };
+        let elem_count = match first_op.elem_count() {
+            Some(elem_count) => elem_count,
+            None => crate::bail!("unknown size for lazy elementwise op"),
+        };
+        // The first op of the chain reads one of its arguments, if possible this is done in
+        // place. For binary ops, the accumulator is the rhs if only this one can be reused.
+        let [first, other] = first_op.elementwise_args();
+        let first = match first {
+            Some(first) => first,
+            None => crate::bail!("non elementwise lazy node in a fused group"),
+        };
+        let (first, first_is_lhs) = match other {
+            Some(rhs)
+                if self.can_take(self.input(first_index, 1), Some(rhs))
+                    && !self.can_take(self.input(first_index, 0), Some(first)) =>
+            {
+                ((rhs, 1), false)
+            }
+            _ => ((first, 0), true),
+        };
+        let (first, first_pos) = first;
+        let first_arg = self.input(first_index, first_pos);
+        let (mut acc, load) = match self.take(first_arg, Some(first)) {
+            Some(acc) => (acc, false),
+            None => (self.alloc(self.nodes[first_index].dtype, elem_count)?, true),
+        };
+
+        let mut steps = Vec::with_capacity(members.len() + 1);
+        if load {
+            steps.push(Step::Load(self.value(first_arg)?, first.offsets()))
+        }
+        for (i, &member) in members.iter().enumerate() {
+            let step = match self.ops[member].as_deref() {
+                Some(LazyOp::Unary { f, .. }) => Step::Unary(*f),
+                Some(LazyOp::Affine { mul, add, .. }) => Step::Affine(*mul, *add),
+                Some(LazyOp::Binary { lhs, rhs, f, .. }) => {
+                    let acc_is_lhs = if i == 0 {
+                        first_is_lhs
+                    } else {
+                        self.input(member, 0) == members[i - 1]
+                    };
+                    let (other, other_pos) = if acc_is_lhs { (rhs, 1) } else { (lhs, 0) };
+                    Step::Binary {
+                        f: *f,
+                        other: self.value(self.input(member, other_pos))?,
+                        offsets: other.offsets(),
+                        acc_is_lhs,
+                    }
+                }
+                _ => crate::bail!("non elementwise lazy node in a fused group"),
+            };
+            steps.push(step)
+        }
+        for start in (0..elem_count).step_by(BLOCK_SIZE) {
+            let r = start..usize::min(start + BLOCK_SIZE, elem_count);
+            for step in steps.iter_mut() {
+                step.apply(&mut acc, r.clone())?
+            }
+        }
+        drop(steps);
+
+        self.store(*members.last().unwrap(), acc);
+        for (i, &member) in members.iter().enumerate() {
+            for j in self.inputs[member].clone() {
+                let input = self.input_indexes[j];
+                // The intermediary values of the chain are never materialized.
+                if i > 0 && input == members[i - 1] {
+                    continue;
+                }
+                self.release(input)
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BackendStorage for LazyStorage {
+    type Device = LazyDevice;
+
+    fn try_clone(&self, _: &Layout) -> Result<Self> {
+        // Nodes are never modified so the clone can share the node.
+        Ok(self.clone())
+    }
+
+    fn dtype(&self) -> DType {
+        self.node.dtype
+    }
+
+    fn device(&self) -> &Self::Device {
+        &LazyDevice
+    }
+
+    fn to_cpu_storage(&self) -> Result<CpuStorage> {
+        Ok(self.realize()?.as_ref().clone())
+    }
+
+    fn affine(&self, layout: &Layout, mul: f64, add: f64) -> Result<Self> {
+        let arg = self.input(layout);
+        Ok(Self::pending(
+            self.dtype(),
+            LazyOp::Affine { arg, mul, add },
+        ))
+    }
+
+    fn elu(&self, layout: &Layout, alpha: f64) -> Result<Self> {
+        let layout = layout.clone();
+        Ok(Self::opaque("elu", &[self], self.dtype(), move |s| {
+            s[0].elu(&layout, alpha)
+        }))
+    }
+
+    fn reduce_op(&self, op: ReduceOp, layout: &Layout, dims: &[usize]) -> Result<Self> {
+        let dtype = match op {
+            ReduceOp::ArgMin | ReduceOp::ArgMax => DType::U32,
+            ReduceOp::Sum | ReduceOp::Prod | ReduceOp::Min | ReduceOp::Max => self.dtype(),
+        };
+        let (layout, dims) = (layout.clone(), dims.to_vec());
+        Ok(Self::opaque("reduce", &[self], dtype, move |s| {
+            s[0].reduce_op(op, &layout, &dims)
+        }))
+    }
+
+    fn scan(&self, op: ScanOp, layout: &Layout, dim: usize) -> Result<Self> {
+        let dtype = match op {
+            ScanOp::ArgMax => DType::U32,
+            ScanOp::Sum | ScanOp::Prod | ScanOp::LogSumExp => self.dtype(),
+        };
+        let layout = layout.clone();
+        Ok(Self::opaque(op.name(), &[self], dtype, move |s| {
+            s[0].scan(op, &layout, dim)
+        }))
+    }
+
+    fn arg_sort(&self, layout: &Layout, dim: usize, asc: bool) -> Result<Self> {
+        let layout = layout.clone();
+        Ok(Self::opaque("arg-sort", &[self], DType::U32, move |s| {
+            s[0].arg_sort(&layout, dim, asc)
+        }))
+    }
+
+    fn cmp(&self, op: CmpOp, rhs: &Self, lhs_l: &Layout, rhs_l: &Layout) -> Result<Self> {
+        let (lhs_l, rhs_l) = (lhs_l.clone(), rhs_l.clone());
+        Ok(Self::opaque("cmp", &[self, rhs], DType::Bool, move |s| {
+            s[0].cmp(op, s[1], &lhs_l, &rhs_l)
+        }))
+    }
+
+    fn to_dtype(&self, layout: &Layout, dtype: DType) -> Result<Self> {
+        let layout = layout.clone();
+        Ok(Self::opaque("to-dtype", &[self], dtype, move |s| {
+            s[0].to_dtype(&layout, dtype)
+        }))
+    }
+
+    fn unary_impl<B: UnaryOpT>(&self, layout: &Layout) -> Result<Self> {
+        let op = LazyOp::Unary {
+            arg: self.input(layout),
+            name: B::NAME,
+            f: unary_block::<B>,
+        };
+        Ok(Self::pending(self.dtype(), op))
+    }
+
+    fn binary_impl<B: BinaryOpT>(
+        &self,
+        rhs: &Self,
+        lhs_l: &Layout,
+        rhs_l: &Layout,
+    ) -> Result<Self> {
+        let op = LazyOp::Binary {
+            lhs: self.input(lhs_l),
+            rhs: rhs.input(rhs_l),
+            name: B::NAME,
+            f: binary_block::<B>,
+        };
+        Ok(Self::pending(self.dtype(), op))
+    }
+
+    fn unary_inplace<B: UnaryOpT>(&mut self, layout: &Layout) -> Result<()> {
+        let layout = layout.clone();
+        let f: fn(&mut CpuStorage, &Layout) -> Result<()> = CpuStorage::unary_inplace::<B>;
+        *self = self.update(B::NAME, &[], move |dst, _| f(dst, &layout));
+        Ok(())
+    }
+
+    fn binary_inplace<B: BinaryOpT>(
+        &mut self,
+        rhs: &Self,
+        lhs_l: &Layout,
+        rhs_l: &Layout,
+    ) -> Result<()> {
+        let (lhs_l, rhs_l) = (lhs_l.clone(), rhs_l.clone());
+        let f: fn(&mut CpuStorage, &CpuStorage, &Layout, &Layout) -> Result<()> =
+            CpuStorage::binary_inplace::<B>;
+        *self = self.update(B::NAME, &[rhs], move |dst, s| f(dst, s[0], &lhs_l, &rhs_l));
+        Ok(())
+    }
+
+    fn index_put(
+        &mut self,
+        l: &Layout,
+        ids: &Self,
+        ids_l: &Layout,
+        src: &Self,
+        src_l: &Layout,
+        dim: usize,
+    ) -> Result<()> {
+        let (l, ids_l, src_l) = (l.clone(), ids_l.clone(), src_l.clone());
+        *self = self.update("index-put", &[ids, src], move |dst, s| {
+            dst.index_put(&l, s[0], &ids_l, s[1], &src_l, dim)
+        });
+        Ok(())
+    }
+
+    fn where_cond(
+        &self,
+        layout: &Layout,
+        t: &Self,
+        t_l: &Layout,
+        f: &Self,
+        f_l: &Layout,
+    ) -> Result<Self> {
+        let (layout, t_l, f_l) = (layout.clone(), t_l.clone(), f_l.clone());
+        Ok(Self::opaque(
+            "where-cond",
+            &[self, t, f],
+            t.dtype(),
+            move |s| s[0].where_cond(&layout, s[1], &t_l, s[2], &f_l),
+        ))
+    }
+
+    fn conv1d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv1D,
+    ) -> Result<Self> {
+        let (l, kernel_l, params) = (l.clone(), kernel_l.clone(), params.clone());
+        Ok(Self::opaque(
+            "conv1d",
+            &[self, kernel],
+            self.dtype(),
+            move |s| s[0].conv1d(&l, s[1], &kernel_l, &params),
+        ))
+    }
+
+    fn conv2d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv2D,
+    ) -> Result<Self> {
+        let (l, kernel_l, params) = (l.clone(), kernel_l.clone(), params.clone());
+        Ok(Self::opaque(
+            "conv2d",
+            &[self, kernel],
+            self.dtype(),
+            move |s| s[0].conv2d(&l, s[1], &kernel_l, &params),
+        ))
+    }
+
+    fn conv3d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv3D,
+    ) -> Result<Self> {
+        let (l, kernel_l, params) = (l.clone(), kernel_l.clone(), params.clone());
+        Ok(Self::opaque(
+            "conv3d",
+            &[self, kernel],
+            self.dtype(),
+            move |s| s[0].conv3d(&l, s[1], &kernel_l, &params),
+        ))
+    }
+
+    fn conv_transpose1d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose1D,
+    ) -> Result<Self> {
+        let (l, kernel_l, params) = (l.clone(), kernel_l.clone(), params.clone());
+        Ok(Self::opaque(
+            "conv_transpose1d",
+            &[self, kernel],
+            self.dtype(),
+            move |s| s[0].conv_transpose1d(&l, s[1], &kernel_l, &params),
+        ))
+    }
+
+    fn conv_transpose2d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose2D,
+    ) -> Result<Self> {
+        let (l, kernel_l, params) = (l.clone(), kernel_l.clone(), params.clone());
+        Ok(Self::opaque(
+            "conv_transpose2d",
+            &[self, kernel],
+            self.dtype(),
+            move |s| s[0].conv_transpose2d(&l, s[1], &kernel_l, &params),
+        ))
+    }
+
+    fn avg_pool(&self, l: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        let (l, params) = (l.clone(), params.clone());
+        Ok(Self::opaque("avg-pool", &[self], self.dtype(), move |s| {
+            s[0].avg_pool(&l, &params)
+        }))
+    }
+
+    fn max_pool(&self, l: &Layout, params: &crate::pool::ParamsPool) -> Result<Self> {
+        let (l, params) = (l.clone(), params.clone());
+        Ok(Self::opaque("max-pool", &[self], self.dtype(), move |s| {
+            s[0].max_pool(&l, &params)
+        }))
+    }
+
+    fn upsample_nearest2d(&self, l: &Layout, h: usize, w: usize) -> Result<Self> {
+        let l = l.clone();
+        Ok(Self::opaque(
+            "upsample-nearest2d",
+            &[self],
+            self.dtype(),
+            move |s| s[0].upsample_nearest2d(&l, h, w),
+        ))
+    }
+
+    fn interpolate2d(
+        &self,
+        l: &Layout,
+        h: usize,
+        w: usize,
+        mode: crate::InterpolateMode,
+    ) -> Result<Self> {
+        let l = l.clone();
+        Ok(Self::opaque(
+            "interpolate2d",
+            &[self],
+            self.dtype(),
+            move |s| s[0].interpolate2d(&l, h, w, mode),
+        ))
+    }
+
+    fn fft(&self, l: &Layout, kind: crate::fft::FftKind) -> Result<Self> {
+        let l = l.clone();
+        Ok(Self::opaque("fft", &[self], self.dtype(), move |s| {
+            s[0].fft(&l, kind)
+        }))
+    }
+
+    fn gather(&self, l: &Layout, ids: &Self, ids_l: &Layout, dim: usize) -> Result<Self> {
+        let (l, ids_l) = (l.clone(), ids_l.clone());
+        Ok(Self::opaque(
+            "gather",
+            &[self, ids],
+            self.dtype(),
+            move |s| s[0].gather(&l, s[1], &ids_l, dim),
+        ))
+    }
+
+    fn scatter_add(
+        &self,
+        l: &Layout,
+        ids: &Self,
+        ids_l: &Layout,
+        src: &Self,
+        src_l: &Layout,
+        dim: usize,
+    ) -> Result<Self> {
+        let (l, ids_l, src_l) = (l.clone(), ids_l.clone(), src_l.clone());
+        Ok(Self::opaque(
+            "scatter-add",
+            &[self, ids, src],
+            self.dtype(),
+            move |s| s[0].scatter_add(&l, s[1], &ids_l, s[2], &src_l, dim),
+        ))
+    }
+
+    fn index_select(&self, ids: &Self, l: &Layout, ids_l: &Layout, dim: usize) -> Result<Self> {
+        let (l, ids_l) = (l.clone(), ids_l.clone());
+        Ok(Self::opaque(
+            "index-select",
+            &[self, ids],
+            self.dtype(),
+            move |s| s[0].index_select(s[1], &l, &ids_l, dim),
+        ))
+    }
+
+    fn index_add(
+        &self,
+        l: &Layout,
+        ids: &Self,
+        ids_l: &Layout,
+        src: &Self,
+        src_l: &Layout,
+        dim: usize,
+    ) -> Result<Self> {
+        let (l, ids_l, src_l) = (l.clone(), ids_l.clone(), src_l.clone());
+        Ok(Self::opaque(
+            "index-add",
+            &[self, ids, src],
+            self.dtype(),
+            move |s| s[0].index_add(&l, s[1], &ids_l, s[2], &src_l, dim),
+        ))
+    }
+
+    fn matmul(
+        &self,
+        rhs: &Self,
+        bmnk: (usize, usize, usize, usize),
+        lhs_l: &Layout,
+        rhs_l: &Layout,
+    ) -> Result<Self> {
+        let (lhs_l, rhs_l) = (lhs_l.clone(), rhs_l.clone());
+        Ok(Self::opaque(
+            "matmul",
+            &[self, rhs],
+            self.dtype(),
+            move |s| s[0].matmul(s[1], bmnk, &lhs_l, &rhs_l),
+        ))
+    }
+
+    fn copy_strided_src(&self, dst: &mut Self, dst_offset: usize, src_l: &Layout) -> Result<()> {
+        let op = LazyOp::Copy {
+            dst: dst.node.clone(),
+            src: self.node.clone(),
+            src_l: src_l.clone(),
+            dst_offset,
+        };
+        *dst = Self::pending(dst.dtype(), op);
+        Ok(())
+    }
+
+    fn copy_strided(&self, dst: &mut Self, dst_l: &Layout, src_l: &Layout) -> Result<()> {
+        let (dst_l, src_l) = (dst_l.clone(), src_l.clone());
+        *dst = dst.update("copy", &[self], move |dst, s| {
+            s[0].copy_strided(dst, &dst_l, &src_l)
+        });
+        Ok(())
+    }
+}
+
+impl BackendDevice for LazyDevice {
+    type Storage = LazyStorage;
+
+    fn new(_: usize) -> Result<Self> {
+        Ok(Self)
+    }
+
+    fn location(&self) -> crate::DeviceLocation {
+        crate::DeviceLocation::Lazy
+    }
+
+    fn same_device(&self, _: &Self) -> bool {
+        true
+    }
+
+    fn zeros_impl(&self, shape: &Shape, dtype: DType) -> Result<LazyStorage> {
+        let elem_count = shape.elem_count();
+        let op = LazyOp::Fill {
+            value: 0.,
+            elem_count,
+        };
+        Ok(LazyStorage::pending(dtype, op))
+    }
+
+    fn ones_impl(&self, shape: &Shape, dtype: DType) -> Result<LazyStorage> {
+        let elem_count = shape.elem_count();
+        let op = LazyOp::Fill {
+            value: 1.,
+            elem_count,
+        };
+        Ok(LazyStorage::pending(dtype, op))
+    }
+
+    fn storage_from_cpu_storage(&self, storage: &CpuStorage) -> Result<LazyStorage> {
+        Ok(LazyStorage::ready(storage.clone()))
+    }
+
+    fn rand_uniform(&self, shape: &Shape, dtype: DType, lo: f64, up: f64) -> Result<LazyStorage> {
+        let storage = CpuDevice.rand_uniform(shape, dtype, lo, up)?;
+        Ok(LazyStorage::ready(storage))
+    }
+
+    fn rand_normal(&self, shape: &Shape, dtype: DType, mean: f64, std: f64) -> Result<LazyStorage> {
+        let storage = CpuDevice.rand_normal(shape, dtype, mean, std)?;
+        Ok(LazyStorage::ready(storage))
+    }
+}
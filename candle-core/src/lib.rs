@@ -15,7 +15,7 @@
 //! ## Features
 //!
 //! - Simple syntax (looks and like PyTorch)
-//! - CPU and Cuda backends (and M1 support)
+//! - CPU and Cuda backends (and M1 support), plus a lazy CPU backend with graph optimizations
 //! - Enable serverless (CPU) small and fast deployments
 //! - Model training
 //! - Distributed computing (NCCL).
@@ -46,15 +46,25 @@ mod device;
 pub mod display;
 mod dtype;
 mod dummy_cuda_backend;
+mod einsum;
 pub mod error;
+mod fft;
 pub mod ggml;
+pub mod gguf;
 mod indexer;
+mod interpolate;
 pub mod layout;
+pub mod lazy_backend;
+pub mod linalg;
 #[cfg(feature = "mkl")]
 mod mkl;
 pub mod npy;
 mod op;
+pub mod pickle;
+mod pool;
+pub mod quantized;
 pub mod safetensors;
+pub mod scalar;
 pub mod shape;
 mod storage;
 mod strided_index;
@@ -62,12 +72,15 @@ mod tensor;
 pub mod utils;
 mod variable;
 
+pub use conv::ConvPadding;
 pub use cpu_backend::CpuStorage;
 pub use device::{Device, DeviceLocation};
 pub use dtype::{DType, FloatDType, IntDType, WithDType};
 pub use error::{Error, Result};
 pub use indexer::IndexOp;
+pub use interpolate::InterpolateMode;
 pub use layout::Layout;
+pub use lazy_backend::{LazyDevice, LazyStorage};
 pub use op::{CustomOp1, CustomOp2, CustomOp3};
 pub use shape::{Shape, D};
 pub use storage::Storage;
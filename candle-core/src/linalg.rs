@@ -0,0 +1,703 @@
+//! Linear algebra routines on batches of matrices.
+//!
+//! The matrices are stored in the last two dimensions of the tensors and the leading dimensions
+//! are batch dimensions. These routines support f32 and f64 tensors, the computations are carried
+//! out in f64 on the host using LAPACK when the `mkl` or `accelerate` feature is enabled and a pure
+//! Rust implementation otherwise. The results are not tracked for backpropagation.
+use crate::{DType, Error, Result, Tensor};
+
+#[cfg(any(feature = "mkl", feature = "accelerate"))]
+use lapack as kernels;
+#[cfg(not(any(feature = "mkl", feature = "accelerate")))]
+use native as kernels;
+
+// Returns the transpose of the row-major matrix `a` of shape (rows, cols). This also converts a
+// row-major matrix to the column-major layout used by LAPACK and back.
+fn transpose(a: &[f64], rows: usize, cols: usize) -> Vec<f64> {
+    let mut t = vec![0.; rows * cols];
+    for i in 0..rows {
+        for j in 0..cols {
+            t[j * rows + i] = a[i * cols + j]
+        }
+    }
+    t
+}
+
+// All the matrices are row-major and contiguous.
+#[cfg_attr(any(feature = "mkl", feature = "accelerate"), allow(dead_code))]
+mod native {
+    // LU decomposition with partial pivoting of the (n, n) matrix `a`, done in place. Returns the
+    // row permutation and its sign, or `None` if the matrix is singular.
+    fn lu(a: &mut [f64], n: usize) -> Option<(Vec<usize>, f64)> {
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut sign = 1.;
+        for k in 0..n {
+            let mut p = k;
+            for i in k + 1..n {
+                if a[i * n + k].abs() > a[p * n + k].abs() {
+                    p = i
+                }
+            }
+            if a[p * n + k] == 0. {
+                return None;
+            }
+            if p != k {
+                for j in 0..n {
+                    a.swap(k * n + j, p * n + j)
+                }
+                perm.swap(k, p);
+                sign = -sign
+            }
+            let pivot = a[k * n + k];
+            for i in k + 1..n {
+                let f = a[i * n + k] / pivot;
+                a[i * n + k] = f;
+                for j in k + 1..n {
+                    a[i * n + j] -= f * a[k * n + j]
+                }
+            }
+        }
+        Some((perm, sign))
+    }
+
+    pub(super) fn solve(a: &[f64], b: &[f64], n: usize, k: usize) -> Option<Vec<f64>> {
+        let mut lu_ = a.to_vec();
+        let (perm, _) = lu(&mut lu_, n)?;
+        let mut x = vec![0.; n * k];
+        for c in 0..k {
+            // Forward substitution with the unit lower triangle then backward substitution with
+            // the upper triangle.
+            let mut y: Vec<f64> = perm.iter().map(|&p| b[p * k + c]).collect();
+            for i in 0..n {
+                for j in 0..i {
+                    y[i] -= lu_[i * n + j] * y[j]
+                }
+            }
+            for i in (0..n).rev() {
+                for j in i + 1..n {
+                    y[i] -= lu_[i * n + j] * y[j]
+                }
+                y[i] /= lu_[i * n + i]
+            }
+            for i in 0..n {
+                x[i * k + c] = y[i]
+            }
+        }
+        Some(x)
+    }
+
+    pub(super) fn det(a: &[f64], n: usize) -> f64 {
+        let mut lu_ = a.to_vec();
+        match lu(&mut lu_, n) {
+            None => 0.,
+            Some((_, sign)) => (0..n).fold(sign, |acc, i| acc * lu_[i * n + i]),
+        }
+    }
+
+    // Only the lower triangle of `a` is used.
+    pub(super) fn cholesky(a: &[f64], n: usize) -> Option<Vec<f64>> {
+        let mut l = vec![0.; n * n];
+        for j in 0..n {
+            let d = a[j * n + j] - (0..j).map(|k| l[j * n + k] * l[j * n + k]).sum::<f64>();
+            if d.is_nan() || d <= 0. {
+                return None;
+            }
+            let d = d.sqrt();
+            l[j * n + j] = d;
+            for i in j + 1..n {
+                let s: f64 = (0..j).map(|k| l[i * n + k] * l[j * n + k]).sum();
+                l[i * n + j] = (a[i * n + j] - s) / d
+            }
+        }
+        Some(l)
+    }
+
+    // Householder QR, returns q of shape (m, k) and r of shape (k, n) with k = min(m, n). The
+    // reflections follow the LAPACK conventions so that both implementations return the same
+    // signs.
+    pub(super) fn qr(a: &[f64], m: usize, n: usize) -> (Vec<f64>, Vec<f64>) {
+        let k = usize::min(m, n);
+        let mut r = a.to_vec();
+        let mut reflectors = Vec::with_capacity(k);
+        for j in 0..k {
+            let sub_norm2: f64 = (j + 1..m).map(|i| r[i * n + j] * r[i * n + j]).sum();
+            if sub_norm2 == 0. {
+                reflectors.push(None);
+                continue;
+            }
+            let ajj = r[j * n + j];
+            let norm = (ajj * ajj + sub_norm2).sqrt();
+            let beta = if ajj >= 0. { -norm } else { norm };
+            let mut v = vec![0.; m];
+            v[j] = ajj - beta;
+            for i in j + 1..m {
+                v[i] = r[i * n + j]
+            }
+            let v_norm2 = v[j] * v[j] + sub_norm2;
+            for c in j..n {
+                let s: f64 = (j..m).map(|i| v[i] * r[i * n + c]).sum();
+                let f = 2. * s / v_norm2;
+                for i in j..m {
+                    r[i * n + c] -= f * v[i]
+                }
+            }
+            reflectors.push(Some((v, v_norm2)))
+        }
+        let mut q = vec![0.; m * k];
+        for i in 0..k {
+            q[i * k + i] = 1.
+        }
+        for (j, reflector) in reflectors.iter().enumerate().rev() {
+            if let Some((v, v_norm2)) = reflector {
+                for c in 0..k {
+                    let s: f64 = (j..m).map(|i| v[i] * q[i * k + c]).sum();
+                    let f = 2. * s / v_norm2;
+                    for i in j..m {
+                        q[i * k + c] -= f * v[i]
+                    }
+                }
+            }
+        }
+        let mut r_ = vec![0.; k * n];
+        for i in 0..k {
+            for c in i..n {
+                r_[i * n + c] = r[i * n + c]
+            }
+        }
+        (q, r_)
+    }
+
+    const MAX_SWEEPS: usize = 100;
+
+    // One-sided Jacobi SVD, returns u of shape (m, k), s of size k in descending order and vt of
+    // shape (k, n) with k = min(m, n).
+    pub(super) fn svd(a: &[f64], m: usize, n: usize) -> Option<(Vec<f64>, Vec<f64>, Vec<f64>)> {
+        if m < n {
+            // a = v s u^t where u s v^t is the decomposition of a^t.
+            let (u, s, vt) = svd(&super::transpose(a, m, n), n, m)?;
+            return Some((super::transpose(&vt, m, m), s, super::transpose(&u, n, m)));
+        }
+        // The columns of u are rotated until they are orthogonal, v accumulates the rotations.
+        let mut u = a.to_vec();
+        let mut v = vec![0.; n * n];
+        for i in 0..n {
+            v[i * n + i] = 1.
+        }
+        let rotate = |x: &mut [f64], rows: usize, p: usize, q: usize, c: f64, s: f64| {
+            for i in 0..rows {
+                let (xp, xq) = (x[i * n + p], x[i * n + q]);
+                x[i * n + p] = c * xp - s * xq;
+                x[i * n + q] = s * xp + c * xq;
+            }
+        };
+        let mut converged = false;
+        for _sweep in 0..MAX_SWEEPS {
+            let mut rotated = false;
+            for p in 0..n {
+                for q in p + 1..n {
+                    let (mut alpha, mut beta, mut gamma) = (0., 0., 0.);
+                    for i in 0..m {
+                        let (up, uq) = (u[i * n + p], u[i * n + q]);
+                        alpha += up * up;
+                        beta += uq * uq;
+                        gamma += up * uq;
+                    }
+                    if gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() {
+                        continue;
+                    }
+                    rotated = true;
+                    let zeta = (beta - alpha) / (2. * gamma);
+                    let t = zeta.signum() / (zeta.abs() + (1. + zeta * zeta).sqrt());
+                    let c = 1. / (1. + t * t).sqrt();
+                    let s = c * t;
+                    rotate(&mut u, m, p, q, c, s);
+                    rotate(&mut v, n, p, q, c, s);
+                }
+            }
+            if !rotated {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            return None;
+        }
+        let norms: Vec<f64> = (0..n)
+            .map(|j| {
+                (0..m)
+                    .map(|i| u[i * n + j] * u[i * n + j])
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+        let s: Vec<f64> = order.iter().map(|&j| norms[j]).collect();
+        let tol = s.first().copied().unwrap_or(0.) * m as f64 * f64::EPSILON;
+        let mut u_ = vec![0.; m * n];
+        let mut vt = vec![0.; n * n];
+        for (dst, &src) in order.iter().enumerate() {
+            for i in 0..n {
+                vt[dst * n + i] = v[i * n + src]
+            }
+            if s[dst] > tol {
+                for i in 0..m {
+                    u_[i * n + dst] = u[i * n + src] / s[dst]
+                }
+            } else {
+                complete_orthonormal_column(&mut u_, m, n, dst)
+            }
+        }
+        Some((u_, s, vt))
+    }
+
+    // Sets column `col` of the (m, n) matrix `u` to a unit vector orthogonal to the previous
+    // columns, this is used for the left singular vectors of null singular values.
+    fn complete_orthonormal_column(u: &mut [f64], m: usize, n: usize, col: usize) {
+        for e in 0..m {
+            let mut x = vec![0.; m];
+            x[e] = 1.;
+            for c in 0..col {
+                let dot: f64 = (0..m).map(|i| x[i] * u[i * n + c]).sum();
+                for i in 0..m {
+                    x[i] -= dot * u[i * n + c]
+                }
+            }
+            let norm = x.iter().map(|x| x * x).sum::<f64>().sqrt();
+            if norm > 0.5 {
+                for i in 0..m {
+                    u[i * n + col] = x[i] / norm
+                }
+                return;
+            }
+        }
+    }
+
+    // Cyclic Jacobi eigenvalue algorithm using the lower triangle of `a`, returns the eigenvalues
+    // in ascending order and the eigenvectors as the columns of a (n, n) matrix.
+    pub(super) fn eigh(a: &[f64], n: usize) -> Option<(Vec<f64>, Vec<f64>)> {
+        let mut a: Vec<f64> = (0..n * n)
+            .map(|idx| {
+                let (i, j) = (idx / n, idx % n);
+                if i >= j {
+                    a[idx]
+                } else {
+                    a[j * n + i]
+                }
+            })
+            .collect();
+        let mut v = vec![0.; n * n];
+        for i in 0..n {
+            v[i * n + i] = 1.
+        }
+        let norm2: f64 = a.iter().map(|x| x * x).sum();
+        // The matrix is diagonal up to rounding errors once the off-diagonal part is small enough.
+        let converged = |a: &[f64]| {
+            let off2: f64 = (0..n)
+                .flat_map(|i| (0..i).map(move |j| (i, j)))
+                .map(|(i, j)| a[i * n + j] * a[i * n + j])
+                .sum();
+            off2 <= f64::EPSILON * f64::EPSILON * norm2
+        };
+        for _sweep in 0..MAX_SWEEPS {
+            if converged(&a) {
+                break;
+            }
+            for p in 0..n {
+                for q in p + 1..n {
+                    let apq = a[p * n + q];
+                    if apq == 0. {
+                        continue;
+                    }
+                    let theta = (a[q * n + q] - a[p * n + p]) / (2. * apq);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                    let c = 1. / (t * t + 1.).sqrt();
+                    let s = t * c;
+                    for k in 0..n {
+                        let (akp, akq) = (a[k * n + p], a[k * n + q]);
+                        a[k * n + p] = c * akp - s * akq;
+                        a[k * n + q] = s * akp + c * akq;
+                    }
+                    for k in 0..n {
+                        let (apk, aqk) = (a[p * n + k], a[q * n + k]);
+                        a[p * n + k] = c * apk - s * aqk;
+                        a[q * n + k] = s * apk + c * aqk;
+                    }
+                    for k in 0..n {
+                        let (vkp, vkq) = (v[k * n + p], v[k * n + q]);
+                        v[k * n + p] = c * vkp - s * vkq;
+                        v[k * n + q] = s * vkp + c * vkq;
+                    }
+                }
+            }
+        }
+        if !converged(&a) {
+            return None;
+        }
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&i, &j| a[i * n + i].total_cmp(&a[j * n + j]));
+        let w = order.iter().map(|&i| a[i * n + i]).collect();
+        let mut v_ = vec![0.; n * n];
+        for (dst, &src) in order.iter().enumerate() {
+            for k in 0..n {
+                v_[k * n + dst] = v[k * n + src]
+            }
+        }
+        Some((w, v_))
+    }
+}
+
+// The same routines as the native module using LAPACK. The inputs and outputs are row-major and
+// get transposed to and from the column-major layout expected by LAPACK.
+#[cfg(any(feature = "mkl", feature = "accelerate"))]
+mod lapack {
+    use super::transpose;
+    #[cfg(all(feature = "accelerate", not(feature = "mkl")))]
+    use crate::accelerate as ffi;
+    #[cfg(feature = "mkl")]
+    use crate::mkl as ffi;
+
+    pub(super) fn solve(a: &[f64], b: &[f64], n: usize, k: usize) -> Option<Vec<f64>> {
+        let mut a = transpose(a, n, n);
+        let mut b = transpose(b, n, k);
+        let mut ipiv = vec![0i32; n];
+        let ld = n as i32;
+        let info = unsafe { ffi::dgesv(ld, k as i32, &mut a, ld, &mut ipiv, &mut b, ld) };
+        if info != 0 {
+            return None;
+        }
+        Some(transpose(&b, k, n))
+    }
+
+    pub(super) fn det(a: &[f64], n: usize) -> f64 {
+        // det(a^t) = det(a) so there is no need to transpose the input.
+        let mut a = a.to_vec();
+        let mut ipiv = vec![0i32; n];
+        let info = unsafe { ffi::dgetrf(n as i32, n as i32, &mut a, n as i32, &mut ipiv) };
+        if info != 0 {
+            return 0.;
+        }
+        (0..n).fold(1., |acc, i| {
+            // The pivot indexes are 1-based.
+            let sign = if ipiv[i] as usize != i + 1 { -1. } else { 1. };
+            acc * sign * a[i * n + i]
+        })
+    }
+
+    pub(super) fn cholesky(a: &[f64], n: usize) -> Option<Vec<f64>> {
+        let mut a = transpose(a, n, n);
+        let info = unsafe { ffi::dpotrf(b'L', n as i32, &mut a, n as i32) };
+        if info != 0 {
+            return None;
+        }
+        // The upper triangle is left untouched by dpotrf.
+        let mut l = transpose(&a, n, n);
+        for i in 0..n {
+            for j in i + 1..n {
+                l[i * n + j] = 0.
+            }
+        }
+        Some(l)
+    }
+
+    pub(super) fn qr(a: &[f64], m: usize, n: usize) -> (Vec<f64>, Vec<f64>) {
+        let k = usize::min(m, n);
+        let (m_, n_, k_) = (m as i32, n as i32, k as i32);
+        let mut a = transpose(a, m, n);
+        let mut tau = vec![0.; k];
+        let mut work = vec![0.; 1];
+        unsafe {
+            ffi::dgeqrf(m_, n_, &mut a, m_, &mut tau, &mut work, -1);
+            let lwork = usize::max(work[0] as usize, 1);
+            let mut work = vec![0.; lwork];
+            ffi::dgeqrf(m_, n_, &mut a, m_, &mut tau, &mut work, lwork as i32);
+        }
+        let mut r = vec![0.; k * n];
+        for i in 0..k {
+            for j in i..n {
+                r[i * n + j] = a[j * m + i]
+            }
+        }
+        unsafe {
+            ffi::dorgqr(m_, k_, k_, &mut a, m_, &tau, &mut work, -1);
+            let lwork = usize::max(work[0] as usize, 1);
+            let mut work = vec![0.; lwork];
+            ffi::dorgqr(m_, k_, k_, &mut a, m_, &tau, &mut work, lwork as i32);
+        }
+        (transpose(&a[..m * k], k, m), r)
+    }
+
+    pub(super) fn svd(a: &[f64], m: usize, n: usize) -> Option<(Vec<f64>, Vec<f64>, Vec<f64>)> {
+        let k = usize::min(m, n);
+        let (m_, n_, k_) = (m as i32, n as i32, k as i32);
+        let mut a = transpose(a, m, n);
+        let mut s = vec![0.; k];
+        let mut u = vec![0.; m * k];
+        let mut vt = vec![0.; k * n];
+        let mut work = vec![0.; 1];
+        let mut iwork = vec![0i32; 8 * k];
+        let info = unsafe {
+            ffi::dgesdd(
+                b'S', m_, n_, &mut a, m_, &mut s, &mut u, m_, &mut vt, k_, &mut work, -1,
+                &mut iwork,
+            );
+            let lwork = usize::max(work[0] as usize, 1);
+            let mut work = vec![0.; lwork];
+            ffi::dgesdd(
+                b'S',
+                m_,
+                n_,
+                &mut a,
+                m_,
+                &mut s,
+                &mut u,
+                m_,
+                &mut vt,
+                k_,
+                &mut work,
+                lwork as i32,
+                &mut iwork,
+            )
+        };
+        if info != 0 {
+            return None;
+        }
+        Some((transpose(&u, k, m), s, transpose(&vt, n, k)))
+    }
+
+    pub(super) fn eigh(a: &[f64], n: usize) -> Option<(Vec<f64>, Vec<f64>)> {
+        let n_ = n as i32;
+        let mut a = transpose(a, n, n);
+        let mut w = vec![0.; n];
+        let mut work = vec![0.; 1];
+        let mut iwork = vec![0i32; 1];
+        let info = unsafe {
+            ffi::dsyevd(
+                b'V', b'L', n_, &mut a, n_, &mut w, &mut work, -1, &mut iwork, -1,
+            );
+            let lwork = usize::max(work[0] as usize, 1);
+            let liwork = usize::max(iwork[0] as usize, 1);
+            let mut work = vec![0.; lwork];
+            let mut iwork = vec![0i32; liwork];
+            ffi::dsyevd(
+                b'V',
+                b'L',
+                n_,
+                &mut a,
+                n_,
+                &mut w,
+                &mut work,
+                lwork as i32,
+                &mut iwork,
+                liwork as i32,
+            )
+        };
+        if info != 0 {
+            return None;
+        }
+        Some((w, transpose(&a, n, n)))
+    }
+}
+
+// Checks that `t` is a batch of non-empty matrices and returns the batch dims and the matrix
+// dims.
+fn matrix_dims(t: &Tensor, op: &'static str) -> Result<(Vec<usize>, usize, usize)> {
+    match t.dtype() {
+        DType::F32 | DType::F64 => {}
+        dtype => Err(Error::UnsupportedDTypeForOp(dtype, op).bt())?,
+    }
+    let dims = t.dims();
+    if dims.len() < 2 {
+        Err(Error::UnexpectedNumberOfDims {
+            expected: 2,
+            got: dims.len(),
+            shape: t.shape().clone(),
+        }
+        .bt())?
+    }
+    let (batch, mat) = dims.split_at(dims.len() - 2);
+    if mat.contains(&0) {
+        crate::bail!(
+            "{op}: the matrices should not be empty, got {:?}",
+            t.shape()
+        )
+    }
+    Ok((batch.to_vec(), mat[0], mat[1]))
+}
+
+fn square_dims(t: &Tensor, op: &'static str) -> Result<(Vec<usize>, usize)> {
+    let (batch, m, n) = matrix_dims(t, op)?;
+    if m != n {
+        crate::bail!("{op}: expected square matrices, got {:?}", t.shape())
+    }
+    Ok((batch, n))
+}
+
+fn to_host(t: &Tensor) -> Result<Vec<f64>> {
+    t.to_dtype(DType::F64)?.flatten_all()?.to_vec1::<f64>()
+}
+
+// Builds a tensor of shape `batch` + `dims` with the same device and dtype as `like`.
+fn from_host(data: Vec<f64>, batch: &[usize], dims: &[usize], like: &Tensor) -> Result<Tensor> {
+    let shape = [batch, dims].concat();
+    Tensor::from_vec(data, shape, like.device())?.to_dtype(like.dtype())
+}
+
+impl Tensor {
+    /// Solves the linear systems `self · x = rhs` where `self` has shape `(.., n, n)` and `rhs`
+    /// has shape `(.., n, k)` with the same batch dimensions. An error is returned if one of the
+    /// matrices is singular.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[3f32, 1.], [1., 2.]], &Device::Cpu)?;
+    /// let b = Tensor::new(&[[9f32], [8.]], &Device::Cpu)?;
+    /// let x = a.solve(&b)?;
+    /// assert_eq!(x.to_vec2::<f32>()?, &[[2.], [3.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn solve(&self, rhs: &Self) -> Result<Self> {
+        let (batch, n) = square_dims(self, "solve")?;
+        let (rhs_batch, rhs_n, k) = matrix_dims(rhs, "solve")?;
+        if self.dtype() != rhs.dtype() {
+            Err(Error::DTypeMismatchBinaryOp {
+                lhs: self.dtype(),
+                rhs: rhs.dtype(),
+                op: "solve",
+            }
+            .bt())?
+        }
+        if rhs_batch != batch || rhs_n != n {
+            Err(Error::ShapeMismatchBinaryOp {
+                lhs: self.shape().clone(),
+                rhs: rhs.shape().clone(),
+                op: "solve",
+            }
+            .bt())?
+        }
+        let (a, b) = (to_host(self)?, to_host(rhs)?);
+        let mut x = Vec::with_capacity(b.len());
+        for (a, b) in a.chunks_exact(n * n).zip(b.chunks_exact(n * k)) {
+            match kernels::solve(a, b, n, k) {
+                Some(v) => x.extend(v),
+                None => crate::bail!("solve: the matrix is singular"),
+            }
+        }
+        from_host(x, &batch, &[n, k], self)
+    }
+
+    /// The inverse of the square matrices in the last two dimensions. An error is returned if
+    /// one of the matrices is singular.
+    pub fn inv(&self) -> Result<Self> {
+        let (batch, n) = square_dims(self, "inv")?;
+        let mut eye = vec![0.; n * n];
+        for i in 0..n {
+            eye[i * n + i] = 1.
+        }
+        let a = to_host(self)?;
+        let mut inv = Vec::with_capacity(a.len());
+        for a in a.chunks_exact(n * n) {
+            match kernels::solve(a, &eye, n, n) {
+                Some(v) => inv.extend(v),
+                None => crate::bail!("inv: the matrix is singular"),
+            }
+        }
+        from_host(inv, &batch, &[n, n], self)
+    }
+
+    /// The determinant of the square matrices in the last two dimensions, the result has the
+    /// batch dimensions as shape.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[[3f32, 1.], [1., 2.]], [[1., 2.], [2., 4.]]], &Device::Cpu)?;
+    /// assert_eq!(a.det()?.to_vec1::<f32>()?, &[5., 0.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn det(&self) -> Result<Self> {
+        let (batch, n) = square_dims(self, "det")?;
+        let a = to_host(self)?;
+        let det = a.chunks_exact(n * n).map(|a| kernels::det(a, n)).collect();
+        from_host(det, &batch, &[], self)
+    }
+
+    /// The Cholesky decomposition `self = l · l^t` of symmetric positive-definite matrices, the
+    /// lower triangular `l` is returned and only the lower triangle of `self` is used.
+    pub fn cholesky(&self) -> Result<Self> {
+        let (batch, n) = square_dims(self, "cholesky")?;
+        let a = to_host(self)?;
+        let mut l = Vec::with_capacity(a.len());
+        for a in a.chunks_exact(n * n) {
+            match kernels::cholesky(a, n) {
+                Some(v) => l.extend(v),
+                None => crate::bail!("cholesky: the matrix is not positive-definite"),
+            }
+        }
+        from_host(l, &batch, &[n, n], self)
+    }
+
+    /// The reduced QR decomposition `self = q · r` of matrices of shape `(.., m, n)`. With
+    /// `k = min(m, n)`, `q` has shape `(.., m, k)` and orthonormal columns, `r` has shape
+    /// `(.., k, n)` and is upper triangular.
+    pub fn qr(&self) -> Result<(Self, Self)> {
+        let (batch, m, n) = matrix_dims(self, "qr")?;
+        let k = usize::min(m, n);
+        let a = to_host(self)?;
+        let mut q = Vec::with_capacity(a.len() / n * k);
+        let mut r = Vec::with_capacity(a.len() / m * k);
+        for a in a.chunks_exact(m * n) {
+            let (q_, r_) = kernels::qr(a, m, n);
+            q.extend(q_);
+            r.extend(r_);
+        }
+        let q = from_host(q, &batch, &[m, k], self)?;
+        let r = from_host(r, &batch, &[k, n], self)?;
+        Ok((q, r))
+    }
+
+    /// The reduced singular value decomposition `self = u · diag(s) · vt` of matrices of shape
+    /// `(.., m, n)`. With `k = min(m, n)`, `u` has shape `(.., m, k)`, `s` has shape `(.., k)` and
+    /// is sorted in descending order, `vt` has shape `(.., k, n)`. The columns of `u` and rows of
+    /// `vt` are orthonormal.
+    pub fn svd(&self) -> Result<(Self, Self, Self)> {
+        let (batch, m, n) = matrix_dims(self, "svd")?;
+        let k = usize::min(m, n);
+        let a = to_host(self)?;
+        let (mut u, mut s, mut vt) = (vec![], vec![], vec![]);
+        for a in a.chunks_exact(m * n) {
+            match kernels::svd(a, m, n) {
+                Some((u_, s_, vt_)) => {
+                    u.extend(u_);
+                    s.extend(s_);
+                    vt.extend(vt_);
+                }
+                None => crate::bail!("svd: the decomposition did not converge"),
+            }
+        }
+        let u = from_host(u, &batch, &[m, k], self)?;
+        let s = from_host(s, &batch, &[k], self)?;
+        let vt = from_host(vt, &batch, &[k, n], self)?;
+        Ok((u, s, vt))
+    }
+
+    /// The eigenvalues and eigenvectors of symmetric matrices, only the lower triangle of `self`
+    /// is used. The eigenvalues have shape `(.., n)` and are sorted in ascending order, the
+    /// eigenvectors are the columns of the returned `(.., n, n)` matrices.
+    pub fn eigh(&self) -> Result<(Self, Self)> {
+        let (batch, n) = square_dims(self, "eigh")?;
+        let a = to_host(self)?;
+        let (mut w, mut v) = (vec![], vec![]);
+        for a in a.chunks_exact(n * n) {
+            match kernels::eigh(a, n) {
+                Some((w_, v_)) => {
+                    w.extend(w_);
+                    v.extend(v_);
+                }
+                None => crate::bail!("eigh: the decomposition did not converge"),
+            }
+        }
+        let w = from_host(w, &batch, &[n], self)?;
+        let v = from_host(v, &batch, &[n, n], self)?;
+        Ok((w, v))
+    }
+}
@@ -71,6 +71,82 @@ mod ffi {
             c: *mut half::f16,
             ldc: *const c_int,
         );
+
+        pub fn dgesv_(
+            n: *const c_int,
+            nrhs: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            ipiv: *mut c_int,
+            b: *mut c_double,
+            ldb: *const c_int,
+            info: *mut c_int,
+        );
+        pub fn dgetrf_(
+            m: *const c_int,
+            n: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            ipiv: *mut c_int,
+            info: *mut c_int,
+        );
+        pub fn dpotrf_(
+            uplo: *const c_char,
+            n: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            info: *mut c_int,
+        );
+        pub fn dgeqrf_(
+            m: *const c_int,
+            n: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            tau: *mut c_double,
+            work: *mut c_double,
+            lwork: *const c_int,
+            info: *mut c_int,
+        );
+        pub fn dorgqr_(
+            m: *const c_int,
+            n: *const c_int,
+            k: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            tau: *const c_double,
+            work: *mut c_double,
+            lwork: *const c_int,
+            info: *mut c_int,
+        );
+        pub fn dgesdd_(
+            jobz: *const c_char,
+            m: *const c_int,
+            n: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            s: *mut c_double,
+            u: *mut c_double,
+            ldu: *const c_int,
+            vt: *mut c_double,
+            ldvt: *const c_int,
+            work: *mut c_double,
+            lwork: *const c_int,
+            iwork: *mut c_int,
+            info: *mut c_int,
+        );
+        pub fn dsyevd_(
+            jobz: *const c_char,
+            uplo: *const c_char,
+            n: *const c_int,
+            a: *mut c_double,
+            lda: *const c_int,
+            w: *mut c_double,
+            work: *mut c_double,
+            lwork: *const c_int,
+            iwork: *mut c_int,
+            liwork: *const c_int,
+            info: *mut c_int,
+        );
     }
 }
 
@@ -376,3 +452,164 @@ binary_op!(vs_mul, f32, vsMul);
 binary_op!(vd_mul, f64, vdMul);
 binary_op!(vs_div, f32, vsDiv);
 binary_op!(vd_div, f64, vdDiv);
+
+// LAPACK routines used by the linalg module, all the matrices are stored in column-major order
+// and the returned value is the LAPACK `info` status, 0 on success.
+
+#[inline]
+pub unsafe fn dgesv(
+    n: i32,
+    nrhs: i32,
+    a: &mut [f64],
+    lda: i32,
+    ipiv: &mut [i32],
+    b: &mut [f64],
+    ldb: i32,
+) -> i32 {
+    let mut info = 0;
+    ffi::dgesv_(
+        &n,
+        &nrhs,
+        a.as_mut_ptr(),
+        &lda,
+        ipiv.as_mut_ptr(),
+        b.as_mut_ptr(),
+        &ldb,
+        &mut info,
+    );
+    info
+}
+
+#[inline]
+pub unsafe fn dgetrf(m: i32, n: i32, a: &mut [f64], lda: i32, ipiv: &mut [i32]) -> i32 {
+    let mut info = 0;
+    ffi::dgetrf_(&m, &n, a.as_mut_ptr(), &lda, ipiv.as_mut_ptr(), &mut info);
+    info
+}
+
+#[inline]
+pub unsafe fn dpotrf(uplo: u8, n: i32, a: &mut [f64], lda: i32) -> i32 {
+    let mut info = 0;
+    ffi::dpotrf_(&(uplo as c_char), &n, a.as_mut_ptr(), &lda, &mut info);
+    info
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline]
+pub unsafe fn dgeqrf(
+    m: i32,
+    n: i32,
+    a: &mut [f64],
+    lda: i32,
+    tau: &mut [f64],
+    work: &mut [f64],
+    lwork: i32,
+) -> i32 {
+    let mut info = 0;
+    ffi::dgeqrf_(
+        &m,
+        &n,
+        a.as_mut_ptr(),
+        &lda,
+        tau.as_mut_ptr(),
+        work.as_mut_ptr(),
+        &lwork,
+        &mut info,
+    );
+    info
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline]
+pub unsafe fn dorgqr(
+    m: i32,
+    n: i32,
+    k: i32,
+    a: &mut [f64],
+    lda: i32,
+    tau: &[f64],
+    work: &mut [f64],
+    lwork: i32,
+) -> i32 {
+    let mut info = 0;
+    ffi::dorgqr_(
+        &m,
+        &n,
+        &k,
+        a.as_mut_ptr(),
+        &lda,
+        tau.as_ptr(),
+        work.as_mut_ptr(),
+        &lwork,
+        &mut info,
+    );
+    info
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline]
+pub unsafe fn dgesdd(
+    jobz: u8,
+    m: i32,
+    n: i32,
+    a: &mut [f64],
+    lda: i32,
+    s: &mut [f64],
+    u: &mut [f64],
+    ldu: i32,
+    vt: &mut [f64],
+    ldvt: i32,
+    work: &mut [f64],
+    lwork: i32,
+    iwork: &mut [i32],
+) -> i32 {
+    let mut info = 0;
+    ffi::dgesdd_(
+        &(jobz as c_char),
+        &m,
+        &n,
+        a.as_mut_ptr(),
+        &lda,
+        s.as_mut_ptr(),
+        u.as_mut_ptr(),
+        &ldu,
+        vt.as_mut_ptr(),
+        &ldvt,
+        work.as_mut_ptr(),
+        &lwork,
+        iwork.as_mut_ptr(),
+        &mut info,
+    );
+    info
+}
+
+#[allow(clippy::too_many_arguments)]
+#[inline]
+pub unsafe fn dsyevd(
+    jobz: u8,
+    uplo: u8,
+    n: i32,
+    a: &mut [f64],
+    lda: i32,
+    w: &mut [f64],
+    work: &mut [f64],
+    lwork: i32,
+    iwork: &mut [i32],
+    liwork: i32,
+) -> i32 {
+    let mut info = 0;
+    ffi::dsyevd_(
+        &(jobz as c_char),
+        &(uplo as c_char),
+        &n,
+        a.as_mut_ptr(),
+        &lda,
+        w.as_mut_ptr(),
+        work.as_mut_ptr(),
+        &lwork,
+        iwork.as_mut_ptr(),
+        &liwork,
+        &mut info,
+    );
+    info
+}
@@ -1,6 +1,8 @@
 use crate::{CpuStorage, CudaStorage, Layout, Result, Shape, Tensor};
 use half::{bf16, f16};
 use num_traits::float::Float;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CmpOp {
@@ -15,6 +17,7 @@ pub enum CmpOp {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReduceOp {
     Sum,
+    Prod,
     Min,
     Max,
     ArgMin,
@@ -29,6 +32,27 @@ impl ReduceOp {
             Self::Min => "min",
             Self::Max => "max",
             Self::Sum => "sum",
+            Self::Prod => "prod",
+        }
+    }
+}
+
+// Cumulative ops along a dimension, `ArgMax` returns the u32 index of the running maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanOp {
+    Sum,
+    Prod,
+    ArgMax,
+    LogSumExp,
+}
+
+impl ScanOp {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Sum => "cumsum",
+            Self::Prod => "cumprod",
+            Self::ArgMax => "cummax",
+            Self::LogSumExp => "logcumsumexp",
         }
     }
 }
@@ -40,6 +64,11 @@ pub enum BinaryOp {
     Mul,
     Sub,
     Div,
+    Maximum,
+    Minimum,
+    Pow,
+    Atan2,
+    Rem,
 }
 
 // Unary ops with no argument
@@ -55,7 +84,16 @@ pub enum UnaryOp {
     Sqr,
     Sqrt,
     Gelu,
+    GeluErf,
     Relu,
+    Tanh,
+    Erf,
+    Floor,
+    Ceil,
+    Round,
+    Sigmoid,
+    Softplus,
+    Log1p,
 }
 
 #[derive(Clone)]
@@ -65,6 +103,7 @@ pub enum Op {
     Cmp(Tensor, CmpOp),
     // The third argument is the reduced shape with `keepdim=true`.
     Reduce(Tensor, ReduceOp, Vec<usize>),
+    Scan(Tensor, ScanOp, usize),
     Matmul(Tensor, Tensor),
     Gather(Tensor, Tensor, usize),
     ScatterAdd(Tensor, Tensor, Tensor, usize),
@@ -72,36 +111,75 @@ pub enum Op {
     IndexAdd(Tensor, Tensor, Tensor, usize),
     WhereCond(Tensor, Tensor, Tensor),
 
-    #[allow(dead_code)]
     Conv1D {
         arg: Tensor,
         kernel: Tensor,
         padding: usize,
         stride: usize,
+        dilation: usize,
+        groups: usize,
     },
 
-    #[allow(dead_code)]
     Conv2D {
         arg: Tensor,
         kernel: Tensor,
         padding: usize,
         stride: usize,
+        dilation: usize,
+        groups: usize,
+    },
+
+    Conv3D {
+        arg: Tensor,
+        kernel: Tensor,
+        padding: usize,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    },
+
+    #[allow(dead_code)]
+    ConvTranspose1D {
+        arg: Tensor,
+        kernel: Tensor,
+        padding: usize,
+        output_padding: usize,
+        stride: usize,
+        dilation: usize,
+    },
+
+    #[allow(dead_code)]
+    ConvTranspose2D {
+        arg: Tensor,
+        kernel: Tensor,
+        padding: usize,
+        output_padding: usize,
+        stride: usize,
+        dilation: usize,
     },
 
-    AvgPool2D {
+    AvgPool {
         arg: Tensor,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
+        params: crate::pool::ParamsPool,
     },
 
-    MaxPool2D {
+    MaxPool {
         arg: Tensor,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
+        params: crate::pool::ParamsPool,
     },
 
     UpsampleNearest2D(Tensor),
 
+    Interpolate2D {
+        arg: Tensor,
+        mode: crate::InterpolateMode,
+    },
+
+    Fft {
+        arg: Tensor,
+        kind: crate::fft::FftKind,
+    },
+
     Cat(Vec<Tensor>, usize),
 
     #[allow(dead_code)] // add is currently unused.
@@ -117,6 +195,8 @@ pub enum Op {
     Reshape(Tensor),
     ToDevice(Tensor),
     Transpose(Tensor, usize, usize),
+    Permute(Tensor, Vec<usize>),
+    AsStrided(Tensor),
     Elu(Tensor, f64),
     CustomOp1(Tensor, std::sync::Arc<Box<dyn CustomOp1>>),
     CustomOp2(Tensor, Tensor, std::sync::Arc<Box<dyn CustomOp2>>),
@@ -239,6 +319,24 @@ pub trait UnaryOpT {
     fn f64(v1: f64) -> f64;
     fn u8(v1: u8) -> u8;
     fn u32(v1: u32) -> u32;
+    fn i8(v1: i8) -> i8;
+    fn i16(v1: i16) -> i16;
+    fn i32(v1: i32) -> i32;
+    fn i64(v1: i64) -> i64;
+
+    // Most ops have no integer version, these flags mark the ones for which the integer functions
+    // above are implemented. They are checked before dispatching to a backend.
+    const UNSIGNED_INT: bool = false;
+    const SIGNED_INT: bool = false;
+
+    fn supports_dtype(dtype: crate::DType) -> bool {
+        use crate::DType;
+        match dtype {
+            DType::U8 | DType::U32 => Self::UNSIGNED_INT,
+            DType::I8 | DType::I16 | DType::I32 | DType::I64 => Self::SIGNED_INT,
+            DType::BF16 | DType::F16 | DType::F32 | DType::F64 | DType::Bool => true,
+        }
+    }
 
     // There is no very good way to represent optional function in traits so we go for an explicit
     // boolean flag to mark the function as existing.
@@ -262,6 +360,10 @@ pub trait BinaryOpT {
     fn f64(v1: f64, v2: f64) -> f64;
     fn u8(v1: u8, v2: u8) -> u8;
     fn u32(v1: u32, v2: u32) -> u32;
+    fn i8(v1: i8, v2: i8) -> i8;
+    fn i16(v1: i16, v2: i16) -> i16;
+    fn i32(v1: i32, v2: i32) -> i32;
+    fn i64(v1: i64, v2: i64) -> i64;
 
     const BF16_VEC: bool = false;
     fn bf16_vec(_xs1: &[bf16], _xs2: &[bf16], _ys: &mut [bf16]) {}
@@ -281,6 +383,11 @@ pub(crate) struct Add;
 pub(crate) struct Div;
 pub(crate) struct Mul;
 pub(crate) struct Sub;
+pub(crate) struct Maximum;
+pub(crate) struct Minimum;
+pub(crate) struct Pow;
+pub(crate) struct Atan2;
+pub(crate) struct Rem;
 pub(crate) struct Exp;
 pub(crate) struct Log;
 pub(crate) struct Sin;
@@ -292,6 +399,15 @@ pub(crate) struct Sqr;
 pub(crate) struct Sqrt;
 pub(crate) struct Gelu;
 pub(crate) struct Relu;
+pub(crate) struct GeluErf;
+pub(crate) struct Tanh;
+pub(crate) struct Erf;
+pub(crate) struct Floor;
+pub(crate) struct Ceil;
+pub(crate) struct Round;
+pub(crate) struct Sigmoid;
+pub(crate) struct Softplus;
+pub(crate) struct Log1p;
 
 macro_rules! bin_op {
     ($op:ident, $name: literal, $e: expr, $f32_vec: ident, $f64_vec: ident) => {
@@ -323,6 +439,22 @@ macro_rules! bin_op {
             fn u32(v1: u32, v2: u32) -> u32 {
                 $e(v1, v2)
             }
+            #[inline(always)]
+            fn i8(v1: i8, v2: i8) -> i8 {
+                $e(v1, v2)
+            }
+            #[inline(always)]
+            fn i16(v1: i16, v2: i16) -> i16 {
+                $e(v1, v2)
+            }
+            #[inline(always)]
+            fn i32(v1: i32, v2: i32) -> i32 {
+                $e(v1, v2)
+            }
+            #[inline(always)]
+            fn i64(v1: i64, v2: i64) -> i64 {
+                $e(v1, v2)
+            }
 
             #[cfg(feature = "mkl")]
             const F32_VEC: bool = true;
@@ -347,8 +479,127 @@ bin_op!(Sub, "sub", |v1, v2| v1 - v2, vs_sub, vd_sub);
 bin_op!(Mul, "mul", |v1, v2| v1 * v2, vs_mul, vd_mul);
 bin_op!(Div, "div", |v1, v2| v1 / v2, vs_div, vd_div);
 
+// Binary ops where the float and integer versions differ. Half precision values are computed
+// using f32.
+macro_rules! bin_op_fns {
+    (half, $v1: ident, $v2: ident, $e: expr, $($ty:ident),*) => {
+        $(
+            #[inline(always)]
+            fn $ty($v1: $ty, $v2: $ty) -> $ty {
+                let ($v1, $v2) = ($v1.to_f32(), $v2.to_f32());
+                $ty::from_f32($e)
+            }
+        )*
+    };
+    ($v1: ident, $v2: ident, $e: expr, $($ty:ident),*) => {
+        $(
+            #[inline(always)]
+            fn $ty($v1: $ty, $v2: $ty) -> $ty {
+                $e
+            }
+        )*
+    };
+}
+
+macro_rules! bin_op_typed {
+    ($op:ident, $name: literal, |$v1: ident, $v2: ident| $float: expr, $int: expr) => {
+        impl BinaryOpT for $op {
+            const NAME: &'static str = $name;
+            const KERNEL: &'static str = concat!("b", $name);
+            const V: Self = $op;
+            bin_op_fns!(half, $v1, $v2, $float, bf16, f16);
+            bin_op_fns!($v1, $v2, $float, f32, f64);
+            bin_op_fns!($v1, $v2, $int, u8, u32, i8, i16, i32, i64);
+        }
+    };
+}
+
+// The maximum and minimum propagate NaN values.
+bin_op_typed!(
+    Maximum,
+    "maximum",
+    |v1, v2| if v1.is_nan() || v1 > v2 { v1 } else { v2 },
+    v1.max(v2)
+);
+bin_op_typed!(
+    Minimum,
+    "minimum",
+    |v1, v2| if v1.is_nan() || v1 < v2 { v1 } else { v2 },
+    v1.min(v2)
+);
+bin_op_typed!(Pow, "pow", |v1, v2| v1.powf(v2), int_pow(v1, v2));
+bin_op_typed!(
+    Atan2,
+    "atan2",
+    |v1, v2| v1.atan2(v2),
+    (v1 as f64).atan2(v2 as f64) as _
+);
+bin_op_typed!(Rem, "rem", |v1, v2| py_rem(v1, v2), py_rem(v1, v2));
+
+// Integer powers are computed with wrapping multiplications. Negative exponents truncate towards
+// zero as for the integer division, e.g. `2 ^ -1` is 0 and `-1 ^ -3` is -1.
+fn int_pow<T: num_traits::PrimInt + num_traits::WrappingMul>(base: T, exp: T) -> T {
+    let two = T::one() + T::one();
+    if exp < T::zero() {
+        let minus_one = T::zero().checked_sub(&T::one());
+        return if base == T::one() {
+            T::one()
+        } else if Some(base) == minus_one {
+            if exp % two == T::zero() {
+                T::one()
+            } else {
+                base
+            }
+        } else {
+            T::zero()
+        };
+    }
+    let (mut base, mut exp, mut acc) = (base, exp, T::one());
+    while exp > T::zero() {
+        if exp % two == T::one() {
+            acc = acc.wrapping_mul(&base)
+        }
+        base = base.wrapping_mul(&base);
+        exp = exp / two;
+    }
+    acc
+}
+
+// The remainder has the same sign as the divisor, as for the python `%` operator.
+fn py_rem<T: num_traits::Num + PartialOrd + Copy>(v1: T, v2: T) -> T {
+    let r = v1 % v2;
+    if r != T::zero() && ((r < T::zero()) != (v2 < T::zero())) {
+        r + v2
+    } else {
+        r
+    }
+}
+
+// Most unary ops have no integer version, calling these on integer tensors returns an error
+// before reaching the functions below. The ones that make sense for signed integers, e.g. `neg`,
+// use a wrapping version of the float expression so that `MIN` values do not overflow, e.g.
+// `-i8::MIN` is `i8::MIN` as in pytorch.
+macro_rules! unary_int_fns {
+    (unsupported, $($ty:ident),*) => {
+        $(
+            #[inline(always)]
+            fn $ty(_: $ty) -> $ty {
+                unreachable!(concat!("no unary function for ", stringify!($ty)))
+            }
+        )*
+    };
+    ($a: ident, $e: expr, $($ty:ident),*) => {
+        $(
+            #[inline(always)]
+            fn $ty($a: $ty) -> $ty {
+                $e
+            }
+        )*
+    };
+}
+
 macro_rules! unary_op {
-    ($op: ident, $name: literal, $a: ident, $e: expr) => {
+    ($op: ident, $name: literal, $a: ident, $e: expr, signed $e_int: expr) => {
         impl UnaryOpT for $op {
             const NAME: &'static str = $name;
             const KERNEL: &'static str = concat!("u", $name);
@@ -369,14 +620,34 @@ macro_rules! unary_op {
             fn f64($a: f64) -> f64 {
                 $e
             }
+            const SIGNED_INT: bool = true;
+            unary_int_fns!(unsupported, u8, u32);
+            unary_int_fns!($a, $e_int, i8, i16, i32, i64);
+        }
+    };
+
+    ($op: ident, $name: literal, $a: ident, $e: expr) => {
+        impl UnaryOpT for $op {
+            const NAME: &'static str = $name;
+            const KERNEL: &'static str = concat!("u", $name);
+            const V: Self = $op;
+            #[inline(always)]
+            fn bf16($a: bf16) -> bf16 {
+                $e
+            }
+            #[inline(always)]
+            fn f16($a: f16) -> f16 {
+                $e
+            }
             #[inline(always)]
-            fn u8(_: u8) -> u8 {
-                todo!("no unary function for u8")
+            fn f32($a: f32) -> f32 {
+                $e
             }
             #[inline(always)]
-            fn u32(_: u32) -> u32 {
-                todo!("no unary function for u32")
+            fn f64($a: f64) -> f64 {
+                $e
             }
+            unary_int_fns!(unsupported, u8, u32, i8, i16, i32, i64);
         }
     };
 
@@ -401,14 +672,7 @@ macro_rules! unary_op {
             fn f64($a: f64) -> f64 {
                 $e
             }
-            #[inline(always)]
-            fn u8(_: u8) -> u8 {
-                todo!("no unary function for u8")
-            }
-            #[inline(always)]
-            fn u32(_: u32) -> u32 {
-                todo!("no unary function for u32")
-            }
+            unary_int_fns!(unsupported, u8, u32, i8, i16, i32, i64);
 
             #[cfg(feature = "mkl")]
             const F32_VEC: bool = true;
@@ -432,12 +696,115 @@ unary_op!(Exp, "exp", v, v.exp(), vs_exp, vd_exp);
 unary_op!(Log, "log", v, v.ln(), vs_ln, vd_ln);
 unary_op!(Sin, "sin", v, v.sin(), vs_sin, vd_sin);
 unary_op!(Cos, "cos", v, v.cos(), vs_cos, vd_cos);
-unary_op!(Abs, "abs", v, v.abs());
-unary_op!(Neg, "neg", v, -v);
+unary_op!(Abs, "abs", v, v.abs(), signed v.wrapping_abs());
+unary_op!(Neg, "neg", v, -v, signed v.wrapping_neg());
 unary_op!(Recip, "recip", v, v.recip());
 unary_op!(Sqr, "sqr", v, v * v, vs_sqr, vd_sqr);
 unary_op!(Sqrt, "sqrt", v, v.sqrt(), vs_sqrt, vd_sqrt);
 
+// Unary ops where the half precision versions are computed using f32. Rounding ops are the
+// identity on integers, the other ones have no integer version.
+macro_rules! unary_op_via_f32 {
+    (@impl $op: ident, $name: literal, $a: ident, $e: expr, $int_ok: literal, $($int: tt)*) => {
+        impl UnaryOpT for $op {
+            const NAME: &'static str = $name;
+            const KERNEL: &'static str = concat!("u", $name);
+            const V: Self = $op;
+            const UNSIGNED_INT: bool = $int_ok;
+            const SIGNED_INT: bool = $int_ok;
+            #[inline(always)]
+            fn bf16($a: bf16) -> bf16 {
+                let $a = $a.to_f32();
+                bf16::from_f32($e)
+            }
+            #[inline(always)]
+            fn f16($a: f16) -> f16 {
+                let $a = $a.to_f32();
+                f16::from_f32($e)
+            }
+            #[inline(always)]
+            fn f32($a: f32) -> f32 {
+                $e
+            }
+            #[inline(always)]
+            fn f64($a: f64) -> f64 {
+                $e
+            }
+            unary_int_fns!($($int)*, u8, u32, i8, i16, i32, i64);
+        }
+    };
+    ($op: ident, $name: literal, $a: ident, $e: expr, unsupported) => {
+        unary_op_via_f32!(@impl $op, $name, $a, $e, false, unsupported);
+    };
+    ($op: ident, $name: literal, $a: ident, $e: expr, $a_int: ident, $e_int: expr) => {
+        unary_op_via_f32!(@impl $op, $name, $a, $e, true, $a_int, $e_int);
+    };
+}
+
+unary_op_via_f32!(Tanh, "tanh", v, v.tanh(), unsupported);
+unary_op_via_f32!(Floor, "floor", v, v.floor(), v, v);
+unary_op_via_f32!(Ceil, "ceil", v, v.ceil(), v, v);
+// Values halfway between two integers are rounded to the even one.
+unary_op_via_f32!(Round, "round", v, v.round_ties_even(), v, v);
+unary_op_via_f32!(Sigmoid, "sigmoid", v, 1. / (1. + (-v).exp()), unsupported);
+// log(1 + exp(v)) written so that exp does not overflow for large inputs.
+unary_op_via_f32!(
+    Softplus,
+    "softplus",
+    v,
+    v.max(0.) + (-v.abs()).exp().ln_1p(),
+    unsupported
+);
+unary_op_via_f32!(Log1p, "log1p", v, v.ln_1p(), unsupported);
+
+impl UnaryOpT for Erf {
+    const NAME: &'static str = "erf";
+    const KERNEL: &'static str = "uerf";
+    const V: Self = Erf;
+    #[inline(always)]
+    fn bf16(v: bf16) -> bf16 {
+        bf16::from_f32(libm::erff(v.to_f32()))
+    }
+    #[inline(always)]
+    fn f16(v: f16) -> f16 {
+        f16::from_f32(libm::erff(v.to_f32()))
+    }
+    #[inline(always)]
+    fn f32(v: f32) -> f32 {
+        libm::erff(v)
+    }
+    #[inline(always)]
+    fn f64(v: f64) -> f64 {
+        libm::erf(v)
+    }
+    unary_int_fns!(unsupported, u8, u32, i8, i16, i32, i64);
+}
+
+/// `gelu` operation using the exact formulation `0.5 * x * (1 + erf(x / sqrt(2)))`, `Gelu` uses
+/// the tanh approximation.
+impl UnaryOpT for GeluErf {
+    const NAME: &'static str = "gelu_erf";
+    const KERNEL: &'static str = "ugelu_erf";
+    const V: Self = GeluErf;
+    #[inline(always)]
+    fn bf16(v: bf16) -> bf16 {
+        bf16::from_f32(Self::f32(v.to_f32()))
+    }
+    #[inline(always)]
+    fn f16(v: f16) -> f16 {
+        f16::from_f32(Self::f32(v.to_f32()))
+    }
+    #[inline(always)]
+    fn f32(v: f32) -> f32 {
+        0.5 * v * (1. + libm::erff(v * std::f32::consts::FRAC_1_SQRT_2))
+    }
+    #[inline(always)]
+    fn f64(v: f64) -> f64 {
+        0.5 * v * (1. + libm::erf(v * std::f64::consts::FRAC_1_SQRT_2))
+    }
+    unary_int_fns!(unsupported, u8, u32, i8, i16, i32, i64);
+}
+
 /// `gelu` operation
 /// <https://en.wikipedia.org/wiki/Activation_function#Comparison_of_activation_functions>
 impl UnaryOpT for Gelu {
@@ -477,14 +844,7 @@ impl UnaryOpT for Gelu {
             * (1.0
                 + f64::tanh((2.0f64 / std::f64::consts::PI).sqrt() * v * (1.0 + 0.044715 * v * v)))
     }
-    #[inline(always)]
-    fn u8(_: u8) -> u8 {
-        0
-    }
-    #[inline(always)]
-    fn u32(_: u32) -> u32 {
-        0
-    }
+    unary_int_fns!(unsupported, u8, u32, i8, i16, i32, i64);
     const KERNEL: &'static str = "ugelu";
 
     #[cfg(feature = "mkl")]
@@ -510,6 +870,8 @@ impl UnaryOpT for Relu {
     const NAME: &'static str = "relu";
     const KERNEL: &'static str = "urelu";
     const V: Self = Relu;
+    const UNSIGNED_INT: bool = true;
+    const SIGNED_INT: bool = true;
     #[inline(always)]
     fn bf16(v: bf16) -> bf16 {
         v.max(bf16::ZERO)
@@ -534,34 +896,54 @@ impl UnaryOpT for Relu {
     fn u32(v: u32) -> u32 {
         v
     }
+    #[inline(always)]
+    fn i8(v: i8) -> i8 {
+        v.max(0)
+    }
+    #[inline(always)]
+    fn i16(v: i16) -> i16 {
+        v.max(0)
+    }
+    #[inline(always)]
+    fn i32(v: i32) -> i32 {
+        v.max(0)
+    }
+    #[inline(always)]
+    fn i64(v: i64) -> i64 {
+        v.max(0)
+    }
 }
 
 /// `BackpropOp` is a wrapper around `Option<Op>`. The main goal is to ensure that dependencies are
-/// properly checked when creating a new value
+/// properly checked when creating a new value. The versions of the arguments are recorded so that
+/// in place modifications of these after the op creation can be detected.
 #[derive(Clone)]
-pub struct BackpropOp(Option<Op>);
+pub struct BackpropOp(Option<Op>, Vec<(Arc<AtomicUsize>, usize)>);
 
 impl BackpropOp {
     pub(crate) fn none() -> Self {
-        BackpropOp(None)
+        BackpropOp(None, vec![])
+    }
+
+    fn with_versions(op: Op, args: &[&Tensor]) -> Self {
+        let versions = args.iter().map(|arg| arg.version()).collect();
+        Self(Some(op), versions)
     }
 
     pub(crate) fn new1(arg: &Tensor, f: impl Fn(Tensor) -> Op) -> Self {
-        let op = if arg.track_op() {
-            Some(f(arg.clone()))
+        if arg.track_op() {
+            Self::with_versions(f(arg.clone()), &[arg])
         } else {
-            None
-        };
-        Self(op)
+            Self::none()
+        }
     }
 
     pub(crate) fn new2(arg1: &Tensor, arg2: &Tensor, f: impl Fn(Tensor, Tensor) -> Op) -> Self {
-        let op = if arg1.track_op() || arg2.track_op() {
-            Some(f(arg1.clone(), arg2.clone()))
+        if arg1.track_op() || arg2.track_op() {
+            Self::with_versions(f(arg1.clone(), arg2.clone()), &[arg1, arg2])
         } else {
-            None
-        };
-        Self(op)
+            Self::none()
+        }
     }
 
     pub(crate) fn new3(
@@ -570,22 +952,29 @@ impl BackpropOp {
         arg3: &Tensor,
         f: impl Fn(Tensor, Tensor, Tensor) -> Op,
     ) -> Self {
-        let op = if arg1.track_op() || arg2.track_op() || arg3.track_op() {
-            Some(f(arg1.clone(), arg2.clone(), arg3.clone()))
+        if arg1.track_op() || arg2.track_op() || arg3.track_op() {
+            let op = f(arg1.clone(), arg2.clone(), arg3.clone());
+            Self::with_versions(op, &[arg1, arg2, arg3])
         } else {
-            None
-        };
-        Self(op)
+            Self::none()
+        }
     }
 
     pub(crate) fn new<A: AsRef<Tensor>>(args: &[A], f: impl Fn(Vec<Tensor>) -> Op) -> Self {
-        let op = if args.iter().any(|arg| arg.as_ref().track_op()) {
-            let args: Vec<Tensor> = args.iter().map(|arg| arg.as_ref().clone()).collect();
-            Some(f(args))
+        if args.iter().any(|arg| arg.as_ref().track_op()) {
+            let args: Vec<&Tensor> = args.iter().map(|arg| arg.as_ref()).collect();
+            let op = f(args.iter().map(|&arg| arg.clone()).collect());
+            Self::with_versions(op, &args)
         } else {
-            None
-        };
-        Self(op)
+            Self::none()
+        }
+    }
+
+    // Returns true if one of the arguments has been modified in place since the op creation.
+    pub(crate) fn versions_changed(&self) -> bool {
+        self.1
+            .iter()
+            .any(|(version, saved)| version.load(Ordering::SeqCst) != *saved)
     }
 }
 
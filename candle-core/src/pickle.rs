@@ -0,0 +1,731 @@
+//! Support for reading PyTorch checkpoints, i.e. the `.pt` and `.pth` files produced by
+//! `torch.save`.
+//!
+//! These files are zip archives containing a pickled object in `data.pkl` and the raw tensor
+//! storages in `data/<key>`. Only the subset of the pickle protocol that is used by torch is
+//! supported.
+use crate::{DType, Device, Error, Layout, Result, Shape, Tensor};
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+// https://docs.juliahub.com/Pickle/LAUNc/0.1.0/opcode/
+#[repr(u8)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum OpCode {
+    Proto = 0x80,
+    Global = b'c',
+    BinPut = b'q',
+    LongBinPut = b'r',
+    EmptyTuple = b')',
+    Reduce = b'R',
+    Mark = b'(',
+    BinUnicode = b'X',
+    ShortBinUnicode = 0x8c,
+    BinInt = b'J',
+    BinInt1 = b'K',
+    BinInt2 = b'M',
+    Long1 = 0x8a,
+    Tuple = b't',
+    Tuple1 = 0x85,
+    Tuple2 = 0x86,
+    Tuple3 = 0x87,
+    BinPersId = b'Q',
+    BinFloat = b'G',
+    NewTrue = 0x88,
+    NewFalse = 0x89,
+    None = b'N',
+    EmptyDict = b'}',
+    EmptyList = b']',
+    SetItem = b's',
+    SetItems = b'u',
+    Append = b'a',
+    Appends = b'e',
+    BinGet = b'h',
+    LongBinGet = b'j',
+    Build = b'b',
+    Memoize = 0x94,
+    Frame = 0x95,
+    Pop = b'0',
+    PopMark = b'1',
+    Dup = b'2',
+    Stop = b'.',
+}
+
+impl TryFrom<u8> for OpCode {
+    type Error = u8;
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        let op_code = match value {
+            0x80 => Self::Proto,
+            b'c' => Self::Global,
+            b'q' => Self::BinPut,
+            b'r' => Self::LongBinPut,
+            b')' => Self::EmptyTuple,
+            b'R' => Self::Reduce,
+            b'(' => Self::Mark,
+            b'X' => Self::BinUnicode,
+            0x8c => Self::ShortBinUnicode,
+            b'J' => Self::BinInt,
+            b'K' => Self::BinInt1,
+            b'M' => Self::BinInt2,
+            0x8a => Self::Long1,
+            b't' => Self::Tuple,
+            0x85 => Self::Tuple1,
+            0x86 => Self::Tuple2,
+            0x87 => Self::Tuple3,
+            b'Q' => Self::BinPersId,
+            b'G' => Self::BinFloat,
+            0x88 => Self::NewTrue,
+            0x89 => Self::NewFalse,
+            b'N' => Self::None,
+            b'}' => Self::EmptyDict,
+            b']' => Self::EmptyList,
+            b's' => Self::SetItem,
+            b'u' => Self::SetItems,
+            b'a' => Self::Append,
+            b'e' => Self::Appends,
+            b'h' => Self::BinGet,
+            b'j' => Self::LongBinGet,
+            b'b' => Self::Build,
+            0x94 => Self::Memoize,
+            0x95 => Self::Frame,
+            b'0' => Self::Pop,
+            b'1' => Self::PopMark,
+            b'2' => Self::Dup,
+            b'.' => Self::Stop,
+            value => return Err(value),
+        };
+        Ok(op_code)
+    }
+}
+
+fn read_to_newline<R: BufRead>(r: &mut R) -> Result<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::with_capacity(32);
+    r.read_until(b'\n', &mut data)?;
+    data.pop();
+    if data.last() == Some(&b'\r') {
+        data.pop();
+    }
+    Ok(data)
+}
+
+/// The objects that can be built when unpickling a torch checkpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Class {
+        module_name: String,
+        class_name: String,
+    },
+    Int(i64),
+    Float(f64),
+    Unicode(String),
+    Bool(bool),
+    None,
+    Tuple(Vec<Object>),
+    List(Vec<Object>),
+    Mark,
+    Dict(Vec<(Object, Object)>),
+    Reduce {
+        callable: Box<Object>,
+        args: Box<Object>,
+    },
+    Build {
+        callable: Box<Object>,
+        args: Box<Object>,
+    },
+    PersistentLoad(Box<Object>),
+}
+
+type OResult<T> = std::result::Result<T, Object>;
+
+impl Object {
+    pub fn unicode(self) -> OResult<String> {
+        match self {
+            Self::Unicode(t) => Ok(t),
+            _ => Err(self),
+        }
+    }
+
+    pub fn int(self) -> OResult<i64> {
+        match self {
+            Self::Int(t) => Ok(t),
+            _ => Err(self),
+        }
+    }
+
+    pub fn tuple(self) -> OResult<Vec<Self>> {
+        match self {
+            Self::Tuple(t) => Ok(t),
+            _ => Err(self),
+        }
+    }
+
+    pub fn dict(self) -> OResult<Vec<(Self, Self)>> {
+        match self {
+            Self::Dict(t) => Ok(t),
+            _ => Err(self),
+        }
+    }
+
+    pub fn class(self) -> OResult<(String, String)> {
+        match self {
+            Self::Class {
+                module_name,
+                class_name,
+            } => Ok((module_name, class_name)),
+            _ => Err(self),
+        }
+    }
+
+    pub fn reduce(self) -> OResult<(Self, Self)> {
+        match self {
+            Self::Reduce { callable, args } => Ok((*callable, *args)),
+            _ => Err(self),
+        }
+    }
+
+    pub fn persistent_load(self) -> OResult<Self> {
+        match self {
+            Self::PersistentLoad(t) => Ok(*t),
+            _ => Err(self),
+        }
+    }
+
+    fn is_class(&self, module: &str, class: &str) -> bool {
+        match self {
+            Self::Class {
+                module_name,
+                class_name,
+            } => module_name == module && class_name == class,
+            _ => false,
+        }
+    }
+
+    fn usize_tuple(self) -> OResult<Vec<usize>> {
+        let values = self.tuple()?;
+        values
+            .into_iter()
+            .map(|v| match v {
+                Self::Int(i) if i >= 0 => Ok(i as usize),
+                v => Err(v),
+            })
+            .collect()
+    }
+}
+
+/// The unpickling stack machine.
+#[derive(Debug, Default)]
+pub struct Stack {
+    stack: Vec<Object>,
+    memo: HashMap<u32, Object>,
+}
+
+impl Stack {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn stack(&self) -> &[Object] {
+        &self.stack
+    }
+
+    /// Runs the pickle opcodes from the reader until the STOP opcode is reached.
+    pub fn read_loop<R: BufRead>(&mut self, r: &mut R) -> Result<()> {
+        loop {
+            if self.read(r)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the object at the top of the stack once unpickling is over.
+    pub fn finalize(mut self) -> Result<Object> {
+        self.pop()
+    }
+
+    fn push(&mut self, obj: Object) {
+        self.stack.push(obj)
+    }
+
+    fn pop(&mut self) -> Result<Object> {
+        match self.stack.pop() {
+            None => crate::bail!("pickle: unexpected empty stack"),
+            Some(obj) => Ok(obj),
+        }
+    }
+
+    fn top(&mut self) -> Result<&mut Object> {
+        match self.stack.last_mut() {
+            None => crate::bail!("pickle: unexpected empty stack"),
+            Some(obj) => Ok(obj),
+        }
+    }
+
+    fn pop_to_marker(&mut self) -> Result<Vec<Object>> {
+        let marker = self
+            .stack
+            .iter()
+            .rposition(|o| matches!(o, Object::Mark))
+            .ok_or_else(|| Error::Msg("pickle: marker object not found".to_string()))?;
+        let objs = self.stack.split_off(marker + 1);
+        self.stack.pop();
+        Ok(objs)
+    }
+
+    fn memo_get(&self, id: u32) -> Result<Object> {
+        match self.memo.get(&id) {
+            None => crate::bail!("pickle: missing object in memo {id}"),
+            Some(obj) => Ok(obj.clone()),
+        }
+    }
+
+    fn memo_put(&mut self, id: u32) -> Result<()> {
+        let obj = self.top()?.clone();
+        self.memo.insert(id, obj);
+        Ok(())
+    }
+
+    // Only a couple callables are evaluated, the other ones are kept as is so that they can be
+    // interpreted when extracting the tensors.
+    fn reduce(&mut self, callable: Object, args: Object) -> Object {
+        if callable.is_class("collections", "OrderedDict") {
+            return Object::Dict(vec![]);
+        }
+        if callable.is_class("torch._utils", "_rebuild_parameter") {
+            if let Object::Tuple(args) = &args {
+                if let Some(data) = args.first() {
+                    return data.clone();
+                }
+            }
+        }
+        Object::Reduce {
+            callable: Box::new(callable),
+            args: Box::new(args),
+        }
+    }
+
+    fn set_items(&mut self, items: Vec<Object>) -> Result<()> {
+        if !items.len().is_multiple_of(2) {
+            crate::bail!("pickle: setitems with an odd number of objects")
+        }
+        match self.top()? {
+            Object::Dict(dict) => {
+                let mut items = items.into_iter();
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    dict.push((key, value))
+                }
+            }
+            obj => crate::bail!("pickle: setitems on a non-dict object {obj:?}"),
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, objs: Vec<Object>) -> Result<()> {
+        match self.top()? {
+            Object::List(list) => list.extend(objs),
+            obj => crate::bail!("pickle: append on a non-list object {obj:?}"),
+        }
+        Ok(())
+    }
+
+    // Returns true once the STOP opcode has been reached.
+    fn read<R: BufRead>(&mut self, r: &mut R) -> Result<bool> {
+        let op_code = match OpCode::try_from(r.read_u8()?) {
+            Ok(op_code) => op_code,
+            Err(op_code) => crate::bail!("pickle: unsupported op-code {op_code:#04x}"),
+        };
+        match op_code {
+            OpCode::Proto => {
+                let version = r.read_u8()?;
+                if version > 5 {
+                    crate::bail!("pickle: unsupported protocol version {version}")
+                }
+            }
+            OpCode::Frame => {
+                // Frames are only a hint for buffering, the content is read as usual.
+                let _frame_len = r.read_u64::<LittleEndian>()?;
+            }
+            OpCode::Global => {
+                let module_name = read_to_newline(r)?;
+                let class_name = read_to_newline(r)?;
+                let module_name = String::from_utf8_lossy(&module_name).to_string();
+                let class_name = String::from_utf8_lossy(&class_name).to_string();
+                self.push(Object::Class {
+                    module_name,
+                    class_name,
+                })
+            }
+            OpCode::BinInt1 => {
+                let arg = r.read_u8()?;
+                self.push(Object::Int(arg as i64))
+            }
+            OpCode::BinInt2 => {
+                let arg = r.read_u16::<LittleEndian>()?;
+                self.push(Object::Int(arg as i64))
+            }
+            OpCode::BinInt => {
+                let arg = r.read_i32::<LittleEndian>()?;
+                self.push(Object::Int(arg as i64))
+            }
+            OpCode::Long1 => {
+                let len = r.read_u8()? as usize;
+                if len > 8 {
+                    crate::bail!("pickle: unsupported long of {len} bytes")
+                }
+                let mut bytes = [0u8; 8];
+                r.read_exact(&mut bytes[..len])?;
+                // Sign extend from the most significant byte that has been read.
+                if len > 0 && len < 8 && bytes[len - 1] & 0x80 != 0 {
+                    bytes[len..].fill(0xff)
+                }
+                self.push(Object::Int(i64::from_le_bytes(bytes)))
+            }
+            OpCode::BinFloat => {
+                // Floats are stored in big-endian order.
+                let arg = r.read_f64::<byteorder::BigEndian>()?;
+                self.push(Object::Float(arg))
+            }
+            OpCode::BinUnicode => {
+                let len = r.read_u32::<LittleEndian>()?;
+                let mut data = vec![0u8; len as usize];
+                r.read_exact(&mut data)?;
+                let data = String::from_utf8(data).map_err(Error::wrap)?;
+                self.push(Object::Unicode(data))
+            }
+            OpCode::ShortBinUnicode => {
+                let len = r.read_u8()?;
+                let mut data = vec![0u8; len as usize];
+                r.read_exact(&mut data)?;
+                let data = String::from_utf8(data).map_err(Error::wrap)?;
+                self.push(Object::Unicode(data))
+            }
+            OpCode::BinPut => {
+                let arg = r.read_u8()?;
+                self.memo_put(arg as u32)?
+            }
+            OpCode::LongBinPut => {
+                let arg = r.read_u32::<LittleEndian>()?;
+                self.memo_put(arg)?
+            }
+            OpCode::Memoize => {
+                let id = self.memo.len() as u32;
+                self.memo_put(id)?
+            }
+            OpCode::BinGet => {
+                let arg = r.read_u8()?;
+                let obj = self.memo_get(arg as u32)?;
+                self.push(obj)
+            }
+            OpCode::LongBinGet => {
+                let arg = r.read_u32::<LittleEndian>()?;
+                let obj = self.memo_get(arg)?;
+                self.push(obj)
+            }
+            OpCode::Mark => self.push(Object::Mark),
+            OpCode::None => self.push(Object::None),
+            OpCode::NewTrue => self.push(Object::Bool(true)),
+            OpCode::NewFalse => self.push(Object::Bool(false)),
+            OpCode::EmptyTuple => self.push(Object::Tuple(vec![])),
+            OpCode::EmptyList => self.push(Object::List(vec![])),
+            OpCode::EmptyDict => self.push(Object::Dict(vec![])),
+            OpCode::Tuple => {
+                let objs = self.pop_to_marker()?;
+                self.push(Object::Tuple(objs))
+            }
+            OpCode::Tuple1 => {
+                let obj = self.pop()?;
+                self.push(Object::Tuple(vec![obj]))
+            }
+            OpCode::Tuple2 => {
+                let obj2 = self.pop()?;
+                let obj1 = self.pop()?;
+                self.push(Object::Tuple(vec![obj1, obj2]))
+            }
+            OpCode::Tuple3 => {
+                let obj3 = self.pop()?;
+                let obj2 = self.pop()?;
+                let obj1 = self.pop()?;
+                self.push(Object::Tuple(vec![obj1, obj2, obj3]))
+            }
+            OpCode::BinPersId => {
+                let id = self.pop()?;
+                self.push(Object::PersistentLoad(Box::new(id)))
+            }
+            OpCode::Reduce => {
+                let args = self.pop()?;
+                let callable = self.pop()?;
+                let obj = self.reduce(callable, args);
+                self.push(obj)
+            }
+            OpCode::Build => {
+                let args = self.pop()?;
+                let obj = self.pop()?;
+                let obj = match obj {
+                    // The state of an OrderedDict only contains attributes like `_metadata` that
+                    // are not needed here.
+                    Object::Dict(_) => obj,
+                    obj => Object::Build {
+                        callable: Box::new(obj),
+                        args: Box::new(args),
+                    },
+                };
+                self.push(obj)
+            }
+            OpCode::SetItem => {
+                let value = self.pop()?;
+                let key = self.pop()?;
+                self.set_items(vec![key, value])?
+            }
+            OpCode::SetItems => {
+                let objs = self.pop_to_marker()?;
+                self.set_items(objs)?
+            }
+            OpCode::Append => {
+                let obj = self.pop()?;
+                self.append(vec![obj])?
+            }
+            OpCode::Appends => {
+                let objs = self.pop_to_marker()?;
+                self.append(objs)?
+            }
+            OpCode::Pop => {
+                self.pop()?;
+            }
+            OpCode::PopMark => {
+                self.pop_to_marker()?;
+            }
+            OpCode::Dup => {
+                let obj = self.top()?.clone();
+                self.push(obj)
+            }
+            OpCode::Stop => return Ok(true),
+        }
+        Ok(false)
+    }
+}
+
+fn storage_dtype(module_name: &str, class_name: &str) -> Option<DType> {
+    if module_name != "torch" {
+        return None;
+    }
+    let dtype = match class_name {
+        "FloatStorage" => DType::F32,
+        "DoubleStorage" => DType::F64,
+        "HalfStorage" => DType::F16,
+        "BFloat16Storage" => DType::BF16,
+        "ByteStorage" => DType::U8,
+        "CharStorage" => DType::I8,
+        "ShortStorage" => DType::I16,
+        "IntStorage" => DType::I32,
+        "LongStorage" => DType::I64,
+        "BoolStorage" => DType::Bool,
+        _ => return None,
+    };
+    Some(dtype)
+}
+
+/// The location of a tensor within a PyTorch checkpoint.
+#[derive(Debug, Clone)]
+pub struct TensorInfo {
+    pub name: String,
+    pub dtype: DType,
+    pub layout: Layout,
+    /// The name of the zip entry holding the tensor storage.
+    pub path: String,
+}
+
+// Extracts the tensor info out of a `torch._utils._rebuild_tensor_v2` call, returns `Ok(None)` if
+// the object is not a tensor or if its storage type is not supported.
+fn rebuild_tensor_v2(name: &str, obj: Object, dir_name: &str) -> OResult<Option<TensorInfo>> {
+    let (callable, args) = obj.reduce()?;
+    if !callable.is_class("torch._utils", "_rebuild_tensor_v2") {
+        return Ok(None);
+    }
+    let mut args = args.tuple()?.into_iter();
+    let (storage, offset, size, stride) = match (args.next(), args.next(), args.next(), args.next())
+    {
+        (Some(storage), Some(offset), Some(size), Some(stride)) => (storage, offset, size, stride),
+        _ => return Ok(None),
+    };
+    // The persistent id is ('storage', storage_type, key, location, numel).
+    let storage = storage.persistent_load()?.tuple()?;
+    let (storage_type, key) = match storage.as_slice() {
+        [_, storage_type, key, _, _] => (storage_type.clone(), key.clone()),
+        _ => return Err(Object::Tuple(storage)),
+    };
+    let (module_name, class_name) = storage_type.class()?;
+    let dtype = match storage_dtype(&module_name, &class_name) {
+        Some(dtype) => dtype,
+        None => {
+            tracing::warn!(
+                "pth: skipping {name} with unsupported storage {module_name}.{class_name}"
+            );
+            return Ok(None);
+        }
+    };
+    let key = key.unicode()?;
+    let offset = offset.int()?;
+    let size = size.usize_tuple()?;
+    let stride = stride.usize_tuple()?;
+    Ok(Some(TensorInfo {
+        name: name.to_string(),
+        dtype,
+        layout: Layout::new(Shape::from(size), stride, offset as usize),
+        path: format!("{dir_name}data/{key}"),
+    }))
+}
+
+// Walks the nested dictionaries, the keys of the nested entries are joined with a dot so that the
+// names match the paths used by `VarBuilder`.
+fn collect_tensor_infos(
+    prefix: &str,
+    obj: Object,
+    dir_name: &str,
+    tensor_infos: &mut Vec<TensorInfo>,
+) -> Result<()> {
+    match obj {
+        Object::Dict(dict) => {
+            for (key, value) in dict {
+                let key = match key {
+                    Object::Unicode(key) => key,
+                    Object::Int(key) => key.to_string(),
+                    _ => continue,
+                };
+                let name = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_tensor_infos(&name, value, dir_name, tensor_infos)?
+            }
+        }
+        obj @ Object::Reduce { .. } => match rebuild_tensor_v2(prefix, obj, dir_name) {
+            Ok(Some(tensor_info)) => tensor_infos.push(tensor_info),
+            Ok(None) => {}
+            Err(obj) => crate::bail!("pth: unexpected object for {prefix}: {obj:?}"),
+        },
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Reads the names, dtypes and layouts of the tensors stored in a PyTorch checkpoint without
+/// loading their data. Tensors stored in nested dictionaries get their names prefixed with the
+/// dictionary keys, e.g. `model_state_dict.encoder.conv1.weight`.
+pub fn read_pth_tensor_info<P: AsRef<Path>>(file: P) -> Result<Vec<TensorInfo>> {
+    let file = std::fs::File::open(file)?;
+    let zip_reader = BufReader::new(file);
+    let mut zip = zip::ZipArchive::new(zip_reader)?;
+    let pkl_file = zip
+        .file_names()
+        .find(|f| f.ends_with("data.pkl"))
+        .map(|f| f.to_string());
+    let pkl_file = match pkl_file {
+        Some(pkl_file) => pkl_file,
+        None => crate::bail!("pth: no data.pkl file in the archive"),
+    };
+    let dir_name = pkl_file.strip_suffix("data.pkl").unwrap_or_default();
+    let reader = zip.by_name(&pkl_file)?;
+    let mut reader = BufReader::new(reader);
+    let mut stack = Stack::empty();
+    stack.read_loop(&mut reader)?;
+    let obj = stack.finalize()?;
+    let mut tensor_infos = vec![];
+    collect_tensor_infos("", obj, dir_name, &mut tensor_infos)?;
+    Ok(tensor_infos)
+}
+
+/// Lazy tensor loader for PyTorch checkpoints.
+pub struct PthTensors {
+    tensor_infos: HashMap<String, TensorInfo>,
+    path: std::path::PathBuf,
+    // We do not store a zip reader as it needs mutable access to extract data. Instead we
+    // re-create a zip reader for each tensor.
+}
+
+impl PthTensors {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let tensor_infos = read_pth_tensor_info(path.as_ref())?;
+        let tensor_infos = tensor_infos
+            .into_iter()
+            .map(|ti| (ti.name.to_string(), ti))
+            .collect();
+        let path = path.as_ref().to_owned();
+        Ok(Self { tensor_infos, path })
+    }
+
+    pub fn tensor_infos(&self) -> &HashMap<String, TensorInfo> {
+        &self.tensor_infos
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Tensor>> {
+        let tensor_info = match self.tensor_infos.get(name) {
+            None => return Ok(None),
+            Some(tensor_info) => tensor_info,
+        };
+        // We hope that the file has not changed since first reading it.
+        let zip_reader = BufReader::new(std::fs::File::open(&self.path)?);
+        let mut zip = zip::ZipArchive::new(zip_reader)?;
+        let reader = zip.by_name(&tensor_info.path)?;
+        let tensor = load_tensor(reader, tensor_info)?;
+        Ok(Some(tensor))
+    }
+}
+
+fn load_tensor<R: Read>(mut reader: R, tensor_info: &TensorInfo) -> Result<Tensor> {
+    let layout = &tensor_info.layout;
+    let dims = layout.dims();
+    let stride = layout.stride();
+    // The tensor can be a view on its storage, e.g. a transposed tensor. Only views that are
+    // permutations of a contiguous layout are supported, the dimensions are sorted by decreasing
+    // strides to recover the contiguous layout.
+    let mut perm = (0..dims.len()).collect::<Vec<_>>();
+    if !layout.is_contiguous() {
+        perm.sort_by(|&i, &j| stride[j].cmp(&stride[i]));
+    }
+    let perm_dims = perm.iter().map(|&i| dims[i]).collect::<Vec<_>>();
+    let perm_stride = perm.iter().map(|&i| stride[i]).collect::<Vec<_>>();
+    if !Shape::from(perm_dims.as_slice()).is_contiguous(&perm_stride) {
+        crate::bail!(
+            "pth: unsupported strides {stride:?} for {} {dims:?}",
+            tensor_info.name
+        )
+    }
+    let elem_size = tensor_info.dtype.size_in_bytes();
+    let skip = (layout.start_offset() * elem_size) as u64;
+    std::io::copy(&mut reader.by_ref().take(skip), &mut std::io::sink())?;
+    let mut data = vec![0u8; layout.shape().elem_count() * elem_size];
+    reader.read_exact(&mut data)?;
+    let mut tensor = Tensor::from_raw_buffer(&data, tensor_info.dtype, &perm_dims, &Device::Cpu)?;
+    // Undo the permutation, `perm[i]` is the target dimension of the current dimension `i`.
+    for i in 0..perm.len() {
+        if let Some(j) = perm.iter().position(|&p| p == i) {
+            if i != j {
+                tensor = tensor.transpose(i, j)?;
+                perm.swap(i, j);
+            }
+        }
+    }
+    if layout.is_contiguous() {
+        Ok(tensor)
+    } else {
+        tensor.contiguous()
+    }
+}
+
+/// Reads all the tensors from a PyTorch checkpoint, in the order in which they appear in the
+/// pickled object.
+pub fn read_all<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Tensor)>> {
+    let tensor_infos = read_pth_tensor_info(path.as_ref())?;
+    let zip_reader = BufReader::new(std::fs::File::open(path.as_ref())?);
+    let mut zip = zip::ZipArchive::new(zip_reader)?;
+    let mut tensors = Vec::with_capacity(tensor_infos.len());
+    for tensor_info in tensor_infos.iter() {
+        let reader = zip.by_name(&tensor_info.path)?;
+        let tensor = load_tensor(reader, tensor_info)?;
+        tensors.push((tensor_info.name.to_string(), tensor))
+    }
+    Ok(tensors)
+}
@@ -0,0 +1,101 @@
+use crate::{Error, Result};
+
+/// The parameters of a pooling op, these apply to the spatial dimensions of the input i.e. to all
+/// the dimensions after the batch and channel ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsPool {
+    pub(crate) kernel_size: Vec<usize>,
+    pub(crate) stride: Vec<usize>,
+    // Avg pooling treats padded positions as zeros, max pooling ignores them.
+    pub(crate) padding: Vec<usize>,
+    // Use a ceil rather than a floor when computing the output size so that the last window can
+    // go past the end of the padded input.
+    pub(crate) ceil_mode: bool,
+}
+
+impl ParamsPool {
+    pub(crate) fn new(
+        kernel_size: &[usize],
+        stride: &[usize],
+        padding: &[usize],
+        ceil_mode: bool,
+        op: &'static str,
+    ) -> Result<Self> {
+        let nd = kernel_size.len();
+        if stride.len() != nd || padding.len() != nd {
+            crate::bail!(
+                "{op}: kernel_size {kernel_size:?}, stride {stride:?} and padding {padding:?} should have the same length"
+            )
+        }
+        if kernel_size.contains(&0) || stride.contains(&0) {
+            crate::bail!(
+                "{op}: kernel_size {kernel_size:?} and stride {stride:?} should be positive"
+            )
+        }
+        for (&k, &p) in kernel_size.iter().zip(padding.iter()) {
+            if 2 * p > k {
+                crate::bail!("{op}: padding {padding:?} should be at most half of the kernel size {kernel_size:?}")
+            }
+        }
+        Ok(Self {
+            kernel_size: kernel_size.to_vec(),
+            stride: stride.to_vec(),
+            padding: padding.to_vec(),
+            ceil_mode,
+        })
+    }
+
+    // The output size along the spatial dimension `i` for an input of size `in_size`.
+    pub(crate) fn out_size(&self, i: usize, in_size: usize) -> usize {
+        let (k, s, p) = (self.kernel_size[i], self.stride[i], self.padding[i]);
+        let span = in_size + 2 * p - k;
+        if self.ceil_mode {
+            let out = span.div_ceil(s) + 1;
+            // The last window has to start inside the input or the left padding.
+            if (out - 1) * s >= in_size + p {
+                out - 1
+            } else {
+                out
+            }
+        } else {
+            span / s + 1
+        }
+    }
+
+    // Checks the input dims and returns the output dims, batch and channel dims included.
+    pub(crate) fn out_dims(&self, dims: &[usize], op: &'static str) -> Result<Vec<usize>> {
+        let nd = self.kernel_size.len();
+        if dims.len() != nd + 2 {
+            Err(Error::UnexpectedNumberOfDims {
+                expected: nd + 2,
+                got: dims.len(),
+                shape: dims.into(),
+            }
+            .bt())?
+        }
+        let mut out_dims = dims[..2].to_vec();
+        for (i, &in_size) in dims[2..].iter().enumerate() {
+            if in_size + 2 * self.padding[i] < self.kernel_size[i] {
+                crate::bail!(
+                    "{op}: the kernel size {:?} is larger than the padded input {dims:?}",
+                    self.kernel_size
+                )
+            }
+            out_dims.push(self.out_size(i, in_size))
+        }
+        Ok(out_dims)
+    }
+
+    // For each spatial dimension, the range of the input covered by the window at `out_idx` once
+    // clipped to the input and the number of elements used as divisor by avg pooling. The divisor
+    // includes the padding but not the positions past the padded input in ceil mode.
+    pub(crate) fn window(&self, i: usize, in_size: usize, out_idx: usize) -> (usize, usize, usize) {
+        let (k, s, p) = (self.kernel_size[i], self.stride[i], self.padding[i]);
+        let start = out_idx * s;
+        let end = usize::min(start + k, in_size + 2 * p);
+        let divisor = end - start;
+        let start = start.saturating_sub(p);
+        let end = usize::min(end - p, in_size);
+        (start, end, divisor)
+    }
+}
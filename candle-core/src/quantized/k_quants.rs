@@ -0,0 +1,1653 @@
+use super::GgmlDType;
+use crate::Result;
+use half::f16;
+
+// Default to QK_K 256 rather than 64.
+pub const QK_K: usize = 256;
+pub const K_SCALE_SIZE: usize = 12;
+
+pub const QK4_0: usize = 32;
+pub const QK4_1: usize = 32;
+pub const QK5_0: usize = 32;
+pub const QK5_1: usize = 32;
+pub const QK8_0: usize = 32;
+pub const QK8_1: usize = 32;
+
+pub trait GgmlType: Sized + Clone + Send + Sync {
+    const DTYPE: GgmlDType;
+    const BLCK_SIZE: usize;
+    // The block type used to quantize the other operand of `vec_dot`, usually some 8 bits format.
+    type VecDotType: GgmlType;
+
+    fn zeros() -> Self;
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()>;
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()>;
+    // The dot product of the `n` elements of `xs` with the `n` elements of `ys`, this is computed
+    // block by block without dequantizing `xs`.
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ4_0 {
+    pub(crate) d: f16,
+    pub(crate) qs: [u8; QK4_0 / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ4_0>() == 18);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ4_1 {
+    pub(crate) d: f16,
+    pub(crate) m: f16,
+    pub(crate) qs: [u8; QK4_1 / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ4_1>() == 20);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ5_0 {
+    pub(crate) d: f16,
+    pub(crate) qh: [u8; 4],
+    pub(crate) qs: [u8; QK5_0 / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ5_0>() == 22);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ5_1 {
+    pub(crate) d: f16,
+    pub(crate) m: f16,
+    pub(crate) qh: [u8; 4],
+    pub(crate) qs: [u8; QK5_1 / 2],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ5_1>() == 24);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ8_0 {
+    pub(crate) d: f16,
+    pub(crate) qs: [i8; QK8_0],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ8_0>() == 34);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ8_1 {
+    pub(crate) d: f16,
+    pub(crate) s: f16,
+    pub(crate) qs: [i8; QK8_1],
+}
+const _: () = assert!(std::mem::size_of::<BlockQ8_1>() == 36);
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ2K {
+    pub(crate) scales: [u8; QK_K / 16],
+    pub(crate) qs: [u8; QK_K / 4],
+    pub(crate) d: f16,
+    pub(crate) dmin: f16,
+}
+const _: () = assert!(QK_K / 16 + QK_K / 4 + 2 * 2 == std::mem::size_of::<BlockQ2K>());
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ3K {
+    pub(crate) hmask: [u8; QK_K / 8],
+    pub(crate) qs: [u8; QK_K / 4],
+    pub(crate) scales: [u8; 12],
+    pub(crate) d: f16,
+}
+const _: () = assert!(QK_K / 8 + QK_K / 4 + 12 + 2 == std::mem::size_of::<BlockQ3K>());
+
+// https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/k_quants.h#L82
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ4K {
+    pub(crate) d: f16,
+    pub(crate) dmin: f16,
+    pub(crate) scales: [u8; K_SCALE_SIZE],
+    pub(crate) qs: [u8; QK_K / 2],
+}
+const _: () = assert!(QK_K / 2 + K_SCALE_SIZE + 2 * 2 == std::mem::size_of::<BlockQ4K>());
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ5K {
+    pub(crate) d: f16,
+    pub(crate) dmin: f16,
+    pub(crate) scales: [u8; K_SCALE_SIZE],
+    pub(crate) qh: [u8; QK_K / 8],
+    pub(crate) qs: [u8; QK_K / 2],
+}
+const _: () =
+    assert!(QK_K / 8 + QK_K / 2 + 2 * 2 + K_SCALE_SIZE == std::mem::size_of::<BlockQ5K>());
+
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ6K {
+    pub(crate) ql: [u8; QK_K / 2],
+    pub(crate) qh: [u8; QK_K / 4],
+    pub(crate) scales: [i8; QK_K / 16],
+    pub(crate) d: f16,
+}
+const _: () = assert!(3 * QK_K / 4 + QK_K / 16 + 2 == std::mem::size_of::<BlockQ6K>());
+
+// The format used to quantize the activations for the k-quants dot products, `bsums` holds the
+// sums of the quants over groups of 16.
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub struct BlockQ8K {
+    pub(crate) d: f32,
+    pub(crate) qs: [i8; QK_K],
+    pub(crate) bsums: [i16; QK_K / 16],
+}
+const _: () = assert!(4 + QK_K + QK_K / 16 * 2 == std::mem::size_of::<BlockQ8K>());
+
+fn check_len<T: GgmlType>(xs: &[T], ys: &[f32]) -> Result<()> {
+    let k = ys.len();
+    if !k.is_multiple_of(T::BLCK_SIZE) {
+        crate::bail!("{:?}: {k} is not divisible by {}", T::DTYPE, T::BLCK_SIZE)
+    }
+    if xs.len() * T::BLCK_SIZE != k {
+        crate::bail!(
+            "{:?}: size mismatch, {} blocks for {k} elements",
+            T::DTYPE,
+            xs.len()
+        )
+    }
+    Ok(())
+}
+
+fn check_vec_dot_len<T: GgmlType>(n: usize, xs: &[T], ys: &[T::VecDotType]) -> Result<()> {
+    if !n.is_multiple_of(T::BLCK_SIZE) {
+        crate::bail!("{:?}: {n} is not divisible by {}", T::DTYPE, T::BLCK_SIZE)
+    }
+    if xs.len() * T::BLCK_SIZE != n || ys.len() * T::VecDotType::BLCK_SIZE != n {
+        crate::bail!(
+            "{:?}: size mismatch in vec-dot, {} and {} blocks for {n} elements",
+            T::DTYPE,
+            xs.len(),
+            ys.len()
+        )
+    }
+    Ok(())
+}
+
+fn dot_i8(xs: &[i8], ys: &[i8]) -> i32 {
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(&x, &y)| x as i32 * y as i32)
+        .sum()
+}
+
+// Returns the value with the largest magnitude, keeping its sign.
+fn signed_amax(xs: &[f32]) -> f32 {
+    let mut amax = 0f32;
+    let mut max = 0f32;
+    for &x in xs {
+        if amax < x.abs() {
+            amax = x.abs();
+            max = x;
+        }
+    }
+    max
+}
+
+fn min_max(xs: &[f32]) -> (f32, f32) {
+    xs.iter().fold((f32::MAX, f32::MIN), |(min, max), &x| {
+        (min.min(x), max.max(x))
+    })
+}
+
+// Rounds to the nearest integer, ties to even, using the same bit trick as llama.cpp.
+fn nearest_int(v: f32) -> i32 {
+    debug_assert!(v.abs() <= 4194303.);
+    let val = v + 12582912.;
+    (val.to_bits() & 0x007fffff) as i32 - 0x00400000
+}
+
+// Port of `make_qx_quants` from
+// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+fn make_qx_quants(nmax: i32, xs: &[f32], ls: &mut [i8], rmse_type: i32) -> f32 {
+    let n = xs.len();
+    let max = signed_amax(xs);
+    if max == 0. {
+        ls.fill(0);
+        return 0.;
+    }
+    let quantize = |iscale: f32, x: f32| nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+    let weight = |x: f32| if rmse_type % 2 == 1 { x * x } else { 1. };
+    let mut iscale = -(nmax as f32) / max;
+    if rmse_type == 0 {
+        for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+            *l = (nmax + quantize(iscale, x)) as i8;
+        }
+        return 1. / iscale;
+    }
+    let mut sumlx = 0f32;
+    let mut suml2 = 0f32;
+    for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+        let q = quantize(iscale, x);
+        *l = (q + nmax) as i8;
+        let w = weight(x);
+        sumlx += w * x * q as f32;
+        suml2 += w * (q * q) as f32;
+    }
+    let mut scale = sumlx / suml2;
+    let mut best = scale * sumlx;
+    for _itry in 0..3 {
+        iscale = 1. / scale;
+        let mut slx = 0f32;
+        let mut sl2 = 0f32;
+        let mut changed = false;
+        for (l, &x) in ls.iter().zip(xs.iter()) {
+            let q = quantize(iscale, x);
+            if q + nmax != *l as i32 {
+                changed = true;
+            }
+            let w = weight(x);
+            slx += w * x * q as f32;
+            sl2 += w * (q * q) as f32;
+        }
+        if !changed || sl2 == 0. || slx * slx <= best * sl2 {
+            break;
+        }
+        for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+            *l = (nmax + quantize(iscale, x)) as i8;
+        }
+        sumlx = slx;
+        suml2 = sl2;
+        scale = sumlx / suml2;
+        best = scale * sumlx;
+    }
+    for _itry in 0..5 {
+        let mut n_changed = 0;
+        for i in 0..n {
+            let x = xs[i];
+            let w = weight(x);
+            let l = ls[i] as i32 - nmax;
+            let mut slx = sumlx - w * x * l as f32;
+            if slx > 0. {
+                let mut sl2 = suml2 - w * (l * l) as f32;
+                let new_l = nearest_int(x * sl2 / slx).clamp(-nmax, nmax - 1);
+                if new_l != l {
+                    slx += w * x * new_l as f32;
+                    sl2 += w * (new_l * new_l) as f32;
+                    if sl2 > 0. && slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        ls[i] = (nmax + new_l) as i8;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        scale = sumlx / suml2;
+                        best = scale * sumlx;
+                        n_changed += 1;
+                    }
+                }
+            }
+        }
+        if n_changed == 0 {
+            break;
+        }
+    }
+    if rmse_type < 3 {
+        return scale;
+    }
+    for is in -4..4 {
+        if is == 0 {
+            continue;
+        }
+        iscale = -(nmax as f32 + 0.1 * is as f32) / max;
+        let mut sumlx = 0f32;
+        let mut suml2 = 0f32;
+        for &x in xs.iter() {
+            let q = quantize(iscale, x);
+            let w = weight(x);
+            sumlx += w * x * q as f32;
+            suml2 += w * (q * q) as f32;
+        }
+        if suml2 > 0. && sumlx * sumlx > best * suml2 {
+            for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+                *l = (nmax + quantize(iscale, x)) as i8;
+            }
+            scale = sumlx / suml2;
+            best = scale * sumlx;
+        }
+    }
+    scale
+}
+
+// Port of `make_qkx1_quants` from
+// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+fn make_qkx1_quants(nmax: i32, ntry: usize, xs: &[f32], ls: &mut [u8]) -> (f32, f32) {
+    let n = xs.len();
+    let (mut min, max) = min_max(xs);
+    if max == min {
+        ls.fill(0);
+        return (0., 0.);
+    }
+    if min > 0. {
+        min = 0.
+    }
+    let mut iscale = nmax as f32 / (max - min);
+    let mut scale = 1. / iscale;
+    for _itry in 0..ntry {
+        let mut sumlx = 0f32;
+        let mut suml2 = 0i32;
+        let mut did_change = false;
+        for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+            let q = nearest_int(iscale * (x - min)).clamp(0, nmax);
+            if q as u8 != *l {
+                *l = q as u8;
+                did_change = true;
+            }
+            sumlx += (x - min) * q as f32;
+            suml2 += q * q;
+        }
+        scale = sumlx / suml2 as f32;
+        let sum: f32 = xs
+            .iter()
+            .zip(ls.iter())
+            .map(|(&x, &l)| x - scale * l as f32)
+            .sum();
+        min = sum / n as f32;
+        if min > 0. {
+            min = 0.
+        }
+        iscale = 1. / scale;
+        if !did_change {
+            break;
+        }
+    }
+    (scale, -min)
+}
+
+// Port of `make_q3_quants` from
+// https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+fn make_q3_quants(nmax: i32, xs: &[f32], ls: &mut [i8]) -> f32 {
+    let max = signed_amax(xs);
+    if max == 0. {
+        ls.fill(0);
+        return 0.;
+    }
+    let iscale = -(nmax as f32) / max;
+    let mut sumlx = 0f32;
+    let mut suml2 = 0f32;
+    for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+        let q = nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+        *l = q as i8;
+        let w = x * x;
+        sumlx += w * x * q as f32;
+        suml2 += w * (q * q) as f32;
+    }
+    for _itry in 0..5 {
+        let mut n_changed = 0;
+        for (l, &x) in ls.iter_mut().zip(xs.iter()) {
+            let w = x * x;
+            let q = *l as i32;
+            let mut slx = sumlx - w * x * q as f32;
+            if slx > 0. {
+                let mut sl2 = suml2 - w * (q * q) as f32;
+                let new_q = nearest_int(x * sl2 / slx).clamp(-nmax, nmax - 1);
+                if new_q != q {
+                    slx += w * x * new_q as f32;
+                    sl2 += w * (new_q * new_q) as f32;
+                    if sl2 > 0. && slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        *l = new_q as i8;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        n_changed += 1;
+                    }
+                }
+            }
+        }
+        if n_changed == 0 {
+            break;
+        }
+    }
+    for l in ls.iter_mut() {
+        *l += nmax as i8;
+    }
+    sumlx / suml2
+}
+
+impl GgmlType for BlockQ4_0 {
+    const DTYPE: GgmlDType = GgmlDType::Q4_0;
+    const BLCK_SIZE: usize = QK4_0;
+    type VecDotType = BlockQ8_0;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            qs: [0; QK4_0 / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK4_0)) {
+            let d = x.d.to_f32();
+            for (j, q) in x.qs.iter().enumerate() {
+                let x0 = (q & 0x0F) as i16 - 8;
+                let x1 = (q >> 4) as i16 - 8;
+                ys[j] = x0 as f32 * d;
+                ys[j + QK4_0 / 2] = x1 as f32 * d;
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK4_0).zip(ys.iter_mut()) {
+            let d = signed_amax(xs) / -8.;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            for (j, q) in y.qs.iter_mut().enumerate() {
+                let x0 = xs[j] * id;
+                let x1 = xs[j + QK4_0 / 2] * id;
+                let xi0 = u8::min(15, (x0 + 8.5) as i8 as u8);
+                let xi1 = u8::min(15, (x1 + 8.5) as i8 as u8);
+                *q = xi0 | (xi1 << 4)
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let mut sumi = 0i32;
+            for (j, q) in x.qs.iter().enumerate() {
+                let v0 = (q & 0x0F) as i32 - 8;
+                let v1 = (q >> 4) as i32 - 8;
+                sumi += v0 * y.qs[j] as i32 + v1 * y.qs[j + QK4_0 / 2] as i32;
+            }
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ4_1 {
+    const DTYPE: GgmlDType = GgmlDType::Q4_1;
+    const BLCK_SIZE: usize = QK4_1;
+    type VecDotType = BlockQ8_1;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            m: f16::ZERO,
+            qs: [0; QK4_1 / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK4_1)) {
+            let d = x.d.to_f32();
+            let m = x.m.to_f32();
+            for (j, q) in x.qs.iter().enumerate() {
+                ys[j] = (q & 0x0F) as f32 * d + m;
+                ys[j + QK4_1 / 2] = (q >> 4) as f32 * d + m;
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK4_1).zip(ys.iter_mut()) {
+            let (min, max) = min_max(xs);
+            let d = (max - min) / ((1 << 4) - 1) as f32;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            y.m = f16::from_f32(min);
+            for (j, q) in y.qs.iter_mut().enumerate() {
+                let x0 = (xs[j] - min) * id;
+                let x1 = (xs[j + QK4_1 / 2] - min) * id;
+                let xi0 = u8::min(15, (x0 + 0.5) as i8 as u8);
+                let xi1 = u8::min(15, (x1 + 0.5) as i8 as u8);
+                *q = xi0 | (xi1 << 4)
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let mut sumi = 0i32;
+            for (j, q) in x.qs.iter().enumerate() {
+                let v0 = (q & 0x0F) as i32;
+                let v1 = (q >> 4) as i32;
+                sumi += v0 * y.qs[j] as i32 + v1 * y.qs[j + QK4_1 / 2] as i32;
+            }
+            // The min contributes `m` times the sum of the activations which is stored in `s`.
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32() + x.m.to_f32() * y.s.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ5_0 {
+    const DTYPE: GgmlDType = GgmlDType::Q5_0;
+    const BLCK_SIZE: usize = QK5_0;
+    type VecDotType = BlockQ8_0;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            qh: [0; 4],
+            qs: [0; QK5_0 / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK5_0)) {
+            let d = x.d.to_f32();
+            let qh = u32::from_le_bytes(x.qh);
+            for (j, q) in x.qs.iter().enumerate() {
+                let xh_0 = (((qh >> j) << 4) & 0x10) as u8;
+                let xh_1 = ((qh >> (j + 12)) & 0x10) as u8;
+                let x0 = ((q & 0x0F) | xh_0) as i16 - 16;
+                let x1 = ((q >> 4) | xh_1) as i16 - 16;
+                ys[j] = x0 as f32 * d;
+                ys[j + QK5_0 / 2] = x1 as f32 * d;
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK5_0).zip(ys.iter_mut()) {
+            let d = signed_amax(xs) / -16.;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            let mut qh = 0u32;
+            for (j, q) in y.qs.iter_mut().enumerate() {
+                let x0 = xs[j] * id;
+                let x1 = xs[j + QK5_0 / 2] * id;
+                let xi0 = u8::min(31, (x0 + 16.5) as i8 as u8);
+                let xi1 = u8::min(31, (x1 + 16.5) as i8 as u8);
+                *q = (xi0 & 0x0F) | ((xi1 & 0x0F) << 4);
+                qh |= ((xi0 as u32 & 0x10) >> 4) << j;
+                qh |= ((xi1 as u32 & 0x10) >> 4) << (j + QK5_0 / 2);
+            }
+            y.qh = qh.to_le_bytes();
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let qh = u32::from_le_bytes(x.qh);
+            let mut sumi = 0i32;
+            for (j, q) in x.qs.iter().enumerate() {
+                let xh_0 = (((qh >> j) << 4) & 0x10) as u8;
+                let xh_1 = ((qh >> (j + 12)) & 0x10) as u8;
+                let v0 = ((q & 0x0F) | xh_0) as i32 - 16;
+                let v1 = ((q >> 4) | xh_1) as i32 - 16;
+                sumi += v0 * y.qs[j] as i32 + v1 * y.qs[j + QK5_0 / 2] as i32;
+            }
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ5_1 {
+    const DTYPE: GgmlDType = GgmlDType::Q5_1;
+    const BLCK_SIZE: usize = QK5_1;
+    type VecDotType = BlockQ8_1;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            m: f16::ZERO,
+            qh: [0; 4],
+            qs: [0; QK5_1 / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK5_1)) {
+            let d = x.d.to_f32();
+            let m = x.m.to_f32();
+            let qh = u32::from_le_bytes(x.qh);
+            for (j, q) in x.qs.iter().enumerate() {
+                let xh_0 = (((qh >> j) << 4) & 0x10) as u8;
+                let xh_1 = ((qh >> (j + 12)) & 0x10) as u8;
+                let x0 = (q & 0x0F) | xh_0;
+                let x1 = (q >> 4) | xh_1;
+                ys[j] = x0 as f32 * d + m;
+                ys[j + QK5_1 / 2] = x1 as f32 * d + m;
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK5_1).zip(ys.iter_mut()) {
+            let (min, max) = min_max(xs);
+            let d = (max - min) / ((1 << 5) - 1) as f32;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            y.m = f16::from_f32(min);
+            let mut qh = 0u32;
+            for (j, q) in y.qs.iter_mut().enumerate() {
+                let x0 = (xs[j] - min) * id;
+                let x1 = (xs[j + QK5_1 / 2] - min) * id;
+                let xi0 = (x0 + 0.5) as u8;
+                let xi1 = (x1 + 0.5) as u8;
+                *q = (xi0 & 0x0F) | ((xi1 & 0x0F) << 4);
+                qh |= ((xi0 as u32 & 0x10) >> 4) << j;
+                qh |= ((xi1 as u32 & 0x10) >> 4) << (j + QK5_1 / 2);
+            }
+            y.qh = qh.to_le_bytes();
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let qh = u32::from_le_bytes(x.qh);
+            let mut sumi = 0i32;
+            for (j, q) in x.qs.iter().enumerate() {
+                let xh_0 = (((qh >> j) << 4) & 0x10) as u8;
+                let xh_1 = ((qh >> (j + 12)) & 0x10) as u8;
+                let v0 = ((q & 0x0F) | xh_0) as i32;
+                let v1 = ((q >> 4) | xh_1) as i32;
+                sumi += v0 * y.qs[j] as i32 + v1 * y.qs[j + QK5_1 / 2] as i32;
+            }
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32() + x.m.to_f32() * y.s.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ8_0 {
+    const DTYPE: GgmlDType = GgmlDType::Q8_0;
+    const BLCK_SIZE: usize = QK8_0;
+    type VecDotType = BlockQ8_0;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            qs: [0; QK8_0],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK8_0)) {
+            let d = x.d.to_f32();
+            for (y, &q) in ys.iter_mut().zip(x.qs.iter()) {
+                *y = q as f32 * d
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK8_0).zip(ys.iter_mut()) {
+            let amax = xs.iter().fold(0f32, |amax, x| amax.max(x.abs()));
+            let d = amax / ((1 << 7) - 1) as f32;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            for (q, &x) in y.qs.iter_mut().zip(xs.iter()) {
+                *q = f32::round(x * id) as i8
+            }
+        }
+        Ok(())
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let sumi = dot_i8(&x.qs, &y.qs);
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ8_1 {
+    const DTYPE: GgmlDType = GgmlDType::Q8_1;
+    const BLCK_SIZE: usize = QK8_1;
+    type VecDotType = BlockQ8_1;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            s: f16::ZERO,
+            qs: [0; QK8_1],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK8_1)) {
+            let d = x.d.to_f32();
+            for (y, &q) in ys.iter_mut().zip(x.qs.iter()) {
+                *y = q as f32 * d
+            }
+        }
+        Ok(())
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK8_1).zip(ys.iter_mut()) {
+            let amax = xs.iter().fold(0f32, |amax, x| amax.max(x.abs()));
+            let d = amax / ((1 << 7) - 1) as f32;
+            let id = if d != 0. { 1. / d } else { 0. };
+            y.d = f16::from_f32(d);
+            let mut sum = 0i32;
+            for (q, &x) in y.qs.iter_mut().zip(xs.iter()) {
+                *q = f32::round(x * id) as i8;
+                sum += *q as i32;
+            }
+            y.s = f16::from_f32(sum as f32 * d);
+        }
+        Ok(())
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let sumi = dot_i8(&x.qs, &y.qs);
+            sumf += sumi as f32 * x.d.to_f32() * y.d.to_f32();
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ2K {
+    const DTYPE: GgmlDType = GgmlDType::Q2K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            scales: [0; QK_K / 16],
+            qs: [0; QK_K / 4],
+            d: f16::ZERO,
+            dmin: f16::ZERO,
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L354
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        let mut ys_index = 0;
+        for x in xs {
+            let d = x.d.to_f32();
+            let min = x.dmin.to_f32();
+            let q = &x.qs;
+
+            let mut is = 0;
+            for n in (0..QK_K).step_by(128) {
+                // Step by 32 over q.
+                let q = &q[n / 4..];
+                let mut shift = 0;
+                for _j in 0..4 {
+                    let sc = x.scales[is];
+                    is += 1;
+                    let dl = d * (sc & 0xF) as f32;
+                    let ml = min * (sc >> 4) as f32;
+                    for q in &q[..16] {
+                        let y = dl * ((q >> shift) & 3) as i8 as f32 - ml;
+                        ys[ys_index] = y;
+                        ys_index += 1;
+                    }
+
+                    let sc = x.scales[is];
+                    is += 1;
+                    let dl = d * (sc & 0xF) as f32;
+                    let ml = min * (sc >> 4) as f32;
+                    for q in &q[16..32] {
+                        let y = dl * ((q >> shift) & 3) as i8 as f32 - ml;
+                        ys[ys_index] = y;
+                        ys_index += 1;
+                    }
+
+                    shift += 2;
+                }
+            }
+        }
+        Ok(())
+    }
+    // Port of `quantize_row_q2_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        const Q4SCALE: f32 = 15.;
+        let mut ls = [0u8; QK_K];
+        let mut mins = [0f32; QK_K / 16];
+        let mut scales = [0f32; QK_K / 16];
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            let mut max_scale = 0f32;
+            let mut max_min = 0f32;
+            for (j, xs) in xs.chunks_exact(16).enumerate() {
+                let (scale, min) = make_qkx1_quants(3, 5, xs, &mut ls[16 * j..16 * (j + 1)]);
+                scales[j] = scale;
+                mins[j] = min;
+                max_scale = max_scale.max(scale);
+                max_min = max_min.max(min);
+            }
+            if max_scale > 0. {
+                let iscale = Q4SCALE / max_scale;
+                for (y_scale, &scale) in y.scales.iter_mut().zip(scales.iter()) {
+                    *y_scale = nearest_int(iscale * scale) as u8
+                }
+                y.d = f16::from_f32(max_scale / Q4SCALE);
+            } else {
+                y.scales.fill(0);
+                y.d = f16::ZERO;
+            }
+            if max_min > 0. {
+                let iscale = Q4SCALE / max_min;
+                for (y_scale, &min) in y.scales.iter_mut().zip(mins.iter()) {
+                    *y_scale |= (nearest_int(iscale * min) as u8) << 4
+                }
+                y.dmin = f16::from_f32(max_min / Q4SCALE);
+            } else {
+                y.dmin = f16::ZERO;
+            }
+            for (j, xs) in xs.chunks_exact(16).enumerate() {
+                let d = y.d.to_f32() * (y.scales[j] & 0xF) as f32;
+                if d == 0. {
+                    continue;
+                }
+                let dm = y.dmin.to_f32() * (y.scales[j] >> 4) as f32;
+                for (l, &x) in ls[16 * j..16 * (j + 1)].iter_mut().zip(xs.iter()) {
+                    *l = nearest_int((x + dm) / d).clamp(0, 3) as u8
+                }
+            }
+            for j in (0..QK_K).step_by(128) {
+                for l in 0..32 {
+                    y.qs[j / 4 + l] = ls[j + l]
+                        | (ls[j + l + 32] << 2)
+                        | (ls[j + l + 64] << 4)
+                        | (ls[j + l + 96] << 6);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Port of the generic `ggml_vec_dot_q2_K_q8_K` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            // The mins are applied to whole groups of 16 so only the activation sums are needed.
+            let summs: i32 = x
+                .scales
+                .iter()
+                .zip(y.bsums.iter())
+                .map(|(&sc, &bsum)| bsum as i32 * (sc >> 4) as i32)
+                .sum();
+            let mut isum = 0i32;
+            let mut is = 0;
+            let mut q8 = &y.qs[..];
+            for n in (0..QK_K).step_by(128) {
+                let q2 = &x.qs[n / 4..n / 4 + 32];
+                for shift in [0, 2, 4, 6] {
+                    for (q2, q8) in [(&q2[..16], &q8[..16]), (&q2[16..], &q8[16..32])] {
+                        let isuml: i32 = q2
+                            .iter()
+                            .zip(q8.iter())
+                            .map(|(&q2, &q8)| q8 as i32 * ((q2 >> shift) & 3) as i32)
+                            .sum();
+                        isum += (x.scales[is] & 0xF) as i32 * isuml;
+                        is += 1;
+                    }
+                    q8 = &q8[32..];
+                }
+            }
+            let dall = y.d * x.d.to_f32();
+            let dmin = y.d * x.dmin.to_f32();
+            sumf += dall * isum as f32 - dmin * summs as f32;
+        }
+        Ok(sumf)
+    }
+}
+
+fn get_scale_min_k4(j: usize, q: &[u8]) -> (u8, u8) {
+    if j < 4 {
+        let d = q[j] & 63;
+        let m = q[j + 4] & 63;
+        (d, m)
+    } else {
+        let d = (q[j + 4] & 0xF) | ((q[j - 4] >> 6) << 4);
+        let m = (q[j + 4] >> 4) | ((q[j] >> 6) << 4);
+        (d, m)
+    }
+}
+
+// Unpacks the 16 6-bit scales of a q3k block.
+fn q3k_scales(scales: &[u8; 12]) -> [i8; 16] {
+    const KMASK1: u32 = 0x03030303;
+    const KMASK2: u32 = 0x0f0f0f0f;
+    let mut aux = [0u32; 4];
+    for (i, aux) in aux.iter_mut().take(3).enumerate() {
+        let s = &scales[4 * i..4 * i + 4];
+        *aux = u32::from_le_bytes([s[0], s[1], s[2], s[3]]);
+    }
+    let tmp = aux[2];
+    aux[2] = ((aux[0] >> 4) & KMASK2) | (((tmp >> 4) & KMASK1) << 4);
+    aux[3] = ((aux[1] >> 4) & KMASK2) | (((tmp >> 6) & KMASK1) << 4);
+    aux[0] = (aux[0] & KMASK2) | ((tmp & KMASK1) << 4);
+    aux[1] = (aux[1] & KMASK2) | (((tmp >> 2) & KMASK1) << 4);
+    let mut scales = [0i8; 16];
+    for (i, aux) in aux.iter().enumerate() {
+        for (j, b) in aux.to_le_bytes().iter().enumerate() {
+            scales[4 * i + j] = *b as i8;
+        }
+    }
+    scales
+}
+
+impl GgmlType for BlockQ3K {
+    const DTYPE: GgmlDType = GgmlDType::Q3K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            hmask: [0; QK_K / 8],
+            qs: [0; QK_K / 4],
+            scales: [0; 12],
+            d: f16::ZERO,
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L533
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        let mut ys_index = 0;
+        for x in xs {
+            let d_all = x.d.to_f32();
+            let hm = &x.hmask;
+            let mut m = 1u8;
+
+            let scales = q3k_scales(&x.scales);
+
+            let mut is = 0;
+            for n in (0..QK_K).step_by(128) {
+                let q = &x.qs[n / 4..];
+                let mut shift = 0;
+                for _j in 0..4 {
+                    for offset in [0, 16] {
+                        let dl = d_all * (scales[is] as f32 - 32.);
+                        is += 1;
+                        for l in offset..offset + 16 {
+                            let q = ((q[l] >> shift) & 3) as i8;
+                            let h = if hm[l] & m != 0 { 0 } else { 4 };
+                            ys[ys_index] = dl * (q - h) as f32;
+                            ys_index += 1;
+                        }
+                    }
+                    shift += 2;
+                    m <<= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+    // Port of `quantize_row_q3_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        let mut ls = [0i8; QK_K];
+        let mut scales = [0f32; QK_K / 16];
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            for (j, xs) in xs.chunks_exact(16).enumerate() {
+                scales[j] = make_q3_quants(4, xs, &mut ls[16 * j..16 * (j + 1)]);
+            }
+            let max_scale = signed_amax(&scales);
+            y.scales.fill(0);
+            if max_scale != 0. {
+                let iscale = -32. / max_scale;
+                for (j, &scale) in scales.iter().enumerate() {
+                    let l = (nearest_int(iscale * scale).clamp(-32, 31) + 32) as u8;
+                    if j < 8 {
+                        y.scales[j] = l & 0xF;
+                    } else {
+                        y.scales[j - 8] |= (l & 0xF) << 4;
+                    }
+                    y.scales[j % 4 + 8] |= (l >> 4) << (2 * (j / 4));
+                }
+                y.d = f16::from_f32(1. / iscale);
+            } else {
+                y.d = f16::ZERO;
+            }
+            for (j, xs) in xs.chunks_exact(16).enumerate() {
+                let sc = if j < 8 {
+                    y.scales[j] & 0xF
+                } else {
+                    y.scales[j - 8] >> 4
+                };
+                let sc = (sc | (((y.scales[8 + j % 4] >> (2 * (j / 4))) & 3) << 4)) as i8 - 32;
+                let d = y.d.to_f32() * sc as f32;
+                if d == 0. {
+                    continue;
+                }
+                for (l, &x) in ls[16 * j..16 * (j + 1)].iter_mut().zip(xs.iter()) {
+                    *l = (nearest_int(x / d).clamp(-4, 3) + 4) as i8
+                }
+            }
+            // The high bit for the first 32 quants goes into bit 0 of hmask, the next 32 into
+            // bit 1, etc.
+            y.hmask.fill(0);
+            let mut m = 0;
+            let mut hm = 1u8;
+            for l in ls.iter_mut() {
+                if *l > 3 {
+                    y.hmask[m] |= hm;
+                    *l -= 4;
+                }
+                m += 1;
+                if m == QK_K / 8 {
+                    m = 0;
+                    hm <<= 1;
+                }
+            }
+            for j in (0..QK_K).step_by(128) {
+                for l in 0..32 {
+                    y.qs[j / 4 + l] = (ls[j + l]
+                        | (ls[j + l + 32] << 2)
+                        | (ls[j + l + 64] << 4)
+                        | (ls[j + l + 96] << 6)) as u8;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Port of the generic `ggml_vec_dot_q3_K_q8_K` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut aux8 = [0i8; QK_K];
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let mut m = 1u8;
+            let mut aux8_index = 0;
+            for n in (0..QK_K).step_by(128) {
+                let q = &x.qs[n / 4..n / 4 + 32];
+                for shift in [0, 2, 4, 6] {
+                    for (q, hm) in q.iter().zip(x.hmask.iter()) {
+                        let h = if hm & m != 0 { 0 } else { 4 };
+                        aux8[aux8_index] = ((q >> shift) & 3) as i8 - h;
+                        aux8_index += 1;
+                    }
+                    m <<= 1;
+                }
+            }
+            let scales = q3k_scales(&x.scales);
+            let isum: i32 = aux8
+                .chunks_exact(16)
+                .zip(y.qs.chunks_exact(16))
+                .zip(scales.iter())
+                .map(|((aux8, q8), &sc)| (sc as i32 - 32) * dot_i8(aux8, q8))
+                .sum();
+            sumf += x.d.to_f32() * y.d * isum as f32;
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ4K {
+    const DTYPE: GgmlDType = GgmlDType::Q4K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            dmin: f16::ZERO,
+            scales: [0; K_SCALE_SIZE],
+            qs: [0; QK_K / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L735
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        let mut ys_index = 0;
+        for x in xs.iter() {
+            let d = x.d.to_f32();
+            let min = x.dmin.to_f32();
+            let q = &x.qs;
+            let mut is = 0;
+            for j in (0..QK_K).step_by(64) {
+                let q = &q[j / 2..j / 2 + 32];
+                let (sc, m) = get_scale_min_k4(is, &x.scales);
+                let d1 = d * sc as f32;
+                let m1 = min * m as f32;
+                let (sc, m) = get_scale_min_k4(is + 1, &x.scales);
+                let d2 = d * sc as f32;
+                let m2 = min * m as f32;
+                for q in q {
+                    let y = d1 * (q & 0xF) as f32 - m1;
+                    ys[ys_index] = y;
+                    ys_index += 1;
+                }
+                for q in q {
+                    let y = d2 * (q >> 4) as f32 - m2;
+                    ys[ys_index] = y;
+                    ys_index += 1;
+                }
+                is += 2;
+            }
+        }
+        Ok(())
+    }
+    // Port of `quantize_row_q4_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        let mut ls = [0u8; QK_K];
+        let mut mins = [0f32; QK_K / 32];
+        let mut scales = [0f32; QK_K / 32];
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            let mut max_scale = 0f32;
+            let mut max_min = 0f32;
+            for (j, xs) in xs.chunks_exact(32).enumerate() {
+                let (scale, min) = make_qkx1_quants(15, 5, xs, &mut ls[32 * j..32 * (j + 1)]);
+                scales[j] = scale;
+                mins[j] = min;
+                max_scale = max_scale.max(scale);
+                max_min = max_min.max(min);
+            }
+            let inv_scale = if max_scale > 0. { 63. / max_scale } else { 0. };
+            let inv_min = if max_min > 0. { 63. / max_min } else { 0. };
+            y.scales.fill(0);
+            for j in 0..QK_K / 32 {
+                let ls = nearest_int(inv_scale * scales[j]).min(63) as u8;
+                let lm = nearest_int(inv_min * mins[j]).min(63) as u8;
+                if j < 4 {
+                    y.scales[j] = ls;
+                    y.scales[j + 4] = lm;
+                } else {
+                    y.scales[j + 4] = (ls & 0xF) | ((lm & 0xF) << 4);
+                    y.scales[j - 4] |= (ls >> 4) << 6;
+                    y.scales[j] |= (lm >> 4) << 6;
+                }
+            }
+            y.d = f16::from_f32(max_scale / 63.);
+            y.dmin = f16::from_f32(max_min / 63.);
+            for (j, xs) in xs.chunks_exact(32).enumerate() {
+                let (sc, m) = get_scale_min_k4(j, &y.scales);
+                let d = y.d.to_f32() * sc as f32;
+                if d == 0. {
+                    continue;
+                }
+                let dm = y.dmin.to_f32() * m as f32;
+                for (l, &x) in ls[32 * j..32 * (j + 1)].iter_mut().zip(xs.iter()) {
+                    *l = nearest_int((x + dm) / d).clamp(0, 15) as u8
+                }
+            }
+            for j in (0..QK_K).step_by(64) {
+                for l in 0..32 {
+                    y.qs[j / 2 + l] = ls[j + l] | (ls[j + l + 32] << 4);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Port of the generic `ggml_vec_dot_q4_K_q8_K` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut aux8 = [0i8; QK_K];
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            for j in (0..QK_K).step_by(64) {
+                let q = &x.qs[j / 2..j / 2 + 32];
+                for (l, q) in q.iter().enumerate() {
+                    aux8[j + l] = (q & 0xF) as i8;
+                    aux8[j + l + 32] = (q >> 4) as i8;
+                }
+            }
+            let mut sumi = 0i32;
+            let mut summs = 0i32;
+            for (j, (aux8, q8)) in aux8.chunks_exact(32).zip(y.qs.chunks_exact(32)).enumerate() {
+                let (sc, m) = get_scale_min_k4(j, &x.scales);
+                sumi += sc as i32 * dot_i8(aux8, q8);
+                summs += m as i32 * (y.bsums[2 * j] as i32 + y.bsums[2 * j + 1] as i32);
+            }
+            sumf += y.d * (x.d.to_f32() * sumi as f32 - x.dmin.to_f32() * summs as f32);
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ5K {
+    const DTYPE: GgmlDType = GgmlDType::Q5K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: f16::ZERO,
+            dmin: f16::ZERO,
+            scales: [0; K_SCALE_SIZE],
+            qh: [0; QK_K / 8],
+            qs: [0; QK_K / 2],
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L928
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        let mut ys_index = 0;
+        for x in xs.iter() {
+            let d = x.d.to_f32();
+            let min = x.dmin.to_f32();
+            let ql = &x.qs;
+            let qh = &x.qh;
+            let mut is = 0;
+            let mut u1 = 1;
+            let mut u2 = 2;
+            for j in (0..QK_K).step_by(64) {
+                let ql = &ql[j / 2..j / 2 + 32];
+                let (sc, m) = get_scale_min_k4(is, &x.scales);
+                let d1 = d * sc as f32;
+                let m1 = min * m as f32;
+                let (sc, m) = get_scale_min_k4(is + 1, &x.scales);
+                let d2 = d * sc as f32;
+                let m2 = min * m as f32;
+                for (ql, qh) in ql.iter().zip(qh) {
+                    let to_add = if qh & u1 != 0 { 16 } else { 0 };
+                    let y = d1 * ((ql & 0xF) + to_add) as f32 - m1;
+                    ys[ys_index] = y;
+                    ys_index += 1;
+                }
+                for (ql, qh) in ql.iter().zip(qh) {
+                    let to_add = if qh & u2 != 0 { 16 } else { 0 };
+                    let y = d2 * ((ql >> 4) + to_add) as f32 - m2;
+                    ys[ys_index] = y;
+                    ys_index += 1;
+                }
+                is += 2;
+                u1 <<= 2;
+                u2 <<= 2;
+            }
+        }
+        Ok(())
+    }
+    // Port of `quantize_row_q5_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        let mut ls = [0u8; QK_K];
+        let mut mins = [0f32; QK_K / 32];
+        let mut scales = [0f32; QK_K / 32];
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            let mut max_scale = 0f32;
+            let mut max_min = 0f32;
+            for (j, xs) in xs.chunks_exact(32).enumerate() {
+                let (scale, min) = make_qkx1_quants(31, 5, xs, &mut ls[32 * j..32 * (j + 1)]);
+                scales[j] = scale;
+                mins[j] = min;
+                max_scale = max_scale.max(scale);
+                max_min = max_min.max(min);
+            }
+            let inv_scale = if max_scale > 0. { 63. / max_scale } else { 0. };
+            let inv_min = if max_min > 0. { 63. / max_min } else { 0. };
+            y.scales.fill(0);
+            for j in 0..QK_K / 32 {
+                let ls = nearest_int(inv_scale * scales[j]).min(63) as u8;
+                let lm = nearest_int(inv_min * mins[j]).min(63) as u8;
+                if j < 4 {
+                    y.scales[j] = ls;
+                    y.scales[j + 4] = lm;
+                } else {
+                    y.scales[j + 4] = (ls & 0xF) | ((lm & 0xF) << 4);
+                    y.scales[j - 4] |= (ls >> 4) << 6;
+                    y.scales[j] |= (lm >> 4) << 6;
+                }
+            }
+            y.d = f16::from_f32(max_scale / 63.);
+            y.dmin = f16::from_f32(max_min / 63.);
+            for (j, xs) in xs.chunks_exact(32).enumerate() {
+                let (sc, m) = get_scale_min_k4(j, &y.scales);
+                let d = y.d.to_f32() * sc as f32;
+                if d == 0. {
+                    continue;
+                }
+                let dm = y.dmin.to_f32() * m as f32;
+                for (l, &x) in ls[32 * j..32 * (j + 1)].iter_mut().zip(xs.iter()) {
+                    *l = nearest_int((x + dm) / d).clamp(0, 31) as u8
+                }
+            }
+            y.qh.fill(0);
+            let mut m1 = 1u8;
+            let mut m2 = 2u8;
+            for n in (0..QK_K).step_by(64) {
+                for j in 0..32 {
+                    let mut l1 = ls[n + j];
+                    if l1 > 15 {
+                        l1 -= 16;
+                        y.qh[j] |= m1;
+                    }
+                    let mut l2 = ls[n + j + 32];
+                    if l2 > 15 {
+                        l2 -= 16;
+                        y.qh[j] |= m2;
+                    }
+                    y.qs[n / 2 + j] = l1 | (l2 << 4);
+                }
+                m1 <<= 2;
+                m2 <<= 2;
+            }
+        }
+        Ok(())
+    }
+
+    // Port of the generic `ggml_vec_dot_q5_K_q8_K` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut aux8 = [0i8; QK_K];
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let mut u1 = 1;
+            let mut u2 = 2;
+            for j in (0..QK_K).step_by(64) {
+                let ql = &x.qs[j / 2..j / 2 + 32];
+                for (l, (ql, qh)) in ql.iter().zip(x.qh.iter()).enumerate() {
+                    let h1 = if qh & u1 != 0 { 16 } else { 0 };
+                    let h2 = if qh & u2 != 0 { 16 } else { 0 };
+                    aux8[j + l] = ((ql & 0xF) + h1) as i8;
+                    aux8[j + l + 32] = ((ql >> 4) + h2) as i8;
+                }
+                u1 <<= 2;
+                u2 <<= 2;
+            }
+            let mut sumi = 0i32;
+            let mut summs = 0i32;
+            for (j, (aux8, q8)) in aux8.chunks_exact(32).zip(y.qs.chunks_exact(32)).enumerate() {
+                let (sc, m) = get_scale_min_k4(j, &x.scales);
+                sumi += sc as i32 * dot_i8(aux8, q8);
+                summs += m as i32 * (y.bsums[2 * j] as i32 + y.bsums[2 * j + 1] as i32);
+            }
+            sumf += y.d * (x.d.to_f32() * sumi as f32 - x.dmin.to_f32() * summs as f32);
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ6K {
+    const DTYPE: GgmlDType = GgmlDType::Q6K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            ql: [0; QK_K / 2],
+            qh: [0; QK_K / 4],
+            scales: [0; QK_K / 16],
+            d: f16::ZERO,
+        }
+    }
+
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c#L1067
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+            let d = x.d.to_f32();
+            let ql = &x.ql;
+            let qh = &x.qh;
+            let sc = &x.scales;
+            for n in (0..QK_K).step_by(128) {
+                let idx = n / 128;
+                let ys = &mut ys[n..];
+                let sc = &sc[8 * idx..];
+                let ql = &ql[64 * idx..];
+                let qh = &qh[32 * idx..];
+                for l in 0..32 {
+                    let is = l / 16;
+                    let q1 = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
+                    let q2 = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
+                    let q3 = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
+                    let q4 = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
+                    ys[l] = d * sc[is] as f32 * q1 as f32;
+                    ys[l + 32] = d * sc[is + 2] as f32 * q2 as f32;
+                    ys[l + 64] = d * sc[is + 4] as f32 * q3 as f32;
+                    ys[l + 96] = d * sc[is + 6] as f32 * q4 as f32;
+                }
+            }
+        }
+        Ok(())
+    }
+    // Port of `quantize_row_q6_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        let mut ls = [0i8; QK_K];
+        let mut scales = [0f32; QK_K / 16];
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            for (ib, xs) in xs.chunks_exact(16).enumerate() {
+                scales[ib] = make_qx_quants(32, xs, &mut ls[16 * ib..16 * (ib + 1)], 1);
+            }
+            let max_scale = signed_amax(&scales);
+            if max_scale == 0. {
+                *y = Self::zeros();
+                continue;
+            }
+            let iscale = -128. / max_scale;
+            y.d = f16::from_f32(1. / iscale);
+            for (y_scale, &scale) in y.scales.iter_mut().zip(scales.iter()) {
+                *y_scale = nearest_int(iscale * scale).min(127) as i8
+            }
+            for (j, xs) in xs.chunks_exact(16).enumerate() {
+                let d = y.d.to_f32() * y.scales[j] as f32;
+                if d == 0. {
+                    continue;
+                }
+                for (l, &x) in ls[16 * j..16 * (j + 1)].iter_mut().zip(xs.iter()) {
+                    *l = (nearest_int(x / d).clamp(-32, 31) + 32) as i8
+                }
+            }
+            for j in (0..QK_K).step_by(128) {
+                let ql = &mut y.ql[j / 2..j / 2 + 64];
+                let qh = &mut y.qh[j / 4..j / 4 + 32];
+                for l in 0..32 {
+                    let q1 = ls[j + l] as u8 & 0xF;
+                    let q2 = ls[j + l + 32] as u8 & 0xF;
+                    let q3 = ls[j + l + 64] as u8 & 0xF;
+                    let q4 = ls[j + l + 96] as u8 & 0xF;
+                    ql[l] = q1 | (q3 << 4);
+                    ql[l + 32] = q2 | (q4 << 4);
+                    qh[l] = (ls[j + l] as u8 >> 4)
+                        | ((ls[j + l + 32] as u8 >> 4) << 2)
+                        | ((ls[j + l + 64] as u8 >> 4) << 4)
+                        | ((ls[j + l + 96] as u8 >> 4) << 6);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Port of the generic `ggml_vec_dot_q6_K_q8_K` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut aux8 = [0i8; QK_K];
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            for n in (0..QK_K).step_by(128) {
+                let idx = n / 128;
+                let ql = &x.ql[64 * idx..];
+                let qh = &x.qh[32 * idx..];
+                let aux8 = &mut aux8[n..];
+                for l in 0..32 {
+                    aux8[l] = ((ql[l] & 0xF) | ((qh[l] & 3) << 4)) as i8 - 32;
+                    aux8[l + 32] = ((ql[l + 32] & 0xF) | (((qh[l] >> 2) & 3) << 4)) as i8 - 32;
+                    aux8[l + 64] = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i8 - 32;
+                    aux8[l + 96] = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i8 - 32;
+                }
+            }
+            // Each group of 16 elements has its own scale.
+            let isum: i32 = aux8
+                .chunks_exact(16)
+                .zip(y.qs.chunks_exact(16))
+                .zip(x.scales.iter())
+                .map(|((aux8, q8), &sc)| sc as i32 * dot_i8(aux8, q8))
+                .sum();
+            sumf += x.d.to_f32() * y.d * isum as f32;
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for BlockQ8K {
+    const DTYPE: GgmlDType = GgmlDType::Q8K;
+    const BLCK_SIZE: usize = QK_K;
+    type VecDotType = BlockQ8K;
+
+    fn zeros() -> Self {
+        Self {
+            d: 0.,
+            qs: [0; QK_K],
+            bsums: [0; QK_K / 16],
+        }
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, ys) in xs.iter().zip(ys.chunks_exact_mut(QK_K)) {
+            for (y, &q) in ys.iter_mut().zip(x.qs.iter()) {
+                *y = q as f32 * x.d
+            }
+        }
+        Ok(())
+    }
+
+    // Port of `quantize_row_q8_K_reference` from
+    // https://github.com/ggerganov/llama.cpp/blob/8183159cf3def112f6d1fe94815fce70e1bffa12/k_quants.c
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (xs, y) in xs.chunks_exact(QK_K).zip(ys.iter_mut()) {
+            let max = signed_amax(xs);
+            if max == 0. {
+                *y = Self::zeros();
+                continue;
+            }
+            let iscale = -128. / max;
+            for (q, &x) in y.qs.iter_mut().zip(xs.iter()) {
+                *q = nearest_int(iscale * x).min(127) as i8
+            }
+            for (bsum, qs) in y.bsums.iter_mut().zip(y.qs.chunks_exact(16)) {
+                *bsum = qs.iter().map(|&q| q as i16).sum()
+            }
+            y.d = 1. / iscale;
+        }
+        Ok(())
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        let mut sumf = 0f32;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            sumf += dot_i8(&x.qs, &y.qs) as f32 * x.d * y.d;
+        }
+        Ok(sumf)
+    }
+}
+
+impl GgmlType for f32 {
+    const DTYPE: GgmlDType = GgmlDType::F32;
+    const BLCK_SIZE: usize = 1;
+    type VecDotType = f32;
+
+    fn zeros() -> Self {
+        0f32
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        ys.copy_from_slice(xs);
+        Ok(())
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        ys.copy_from_slice(xs);
+        Ok(())
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        Ok(xs.iter().zip(ys.iter()).map(|(&x, &y)| x * y).sum())
+    }
+}
+
+impl GgmlType for f16 {
+    const DTYPE: GgmlDType = GgmlDType::F16;
+    const BLCK_SIZE: usize = 1;
+    type VecDotType = f16;
+
+    fn zeros() -> Self {
+        f16::ZERO
+    }
+
+    fn to_float(xs: &[Self], ys: &mut [f32]) -> Result<()> {
+        check_len(xs, ys)?;
+        for (x, y) in xs.iter().zip(ys.iter_mut()) {
+            *y = x.to_f32()
+        }
+        Ok(())
+    }
+
+    fn from_float(xs: &[f32], ys: &mut [Self]) -> Result<()> {
+        check_len(ys, xs)?;
+        for (x, y) in xs.iter().zip(ys.iter_mut()) {
+            *y = f16::from_f32(*x)
+        }
+        Ok(())
+    }
+
+    fn vec_dot(n: usize, xs: &[Self], ys: &[Self::VecDotType]) -> Result<f32> {
+        check_vec_dot_len(n, xs, ys)?;
+        Ok(xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(x, y)| x.to_f32() * y.to_f32())
+            .sum())
+    }
+}
@@ -0,0 +1,360 @@
+//! Quantized tensors, as used by the GGML file format.
+//!
+//! A [`QTensor`] keeps its data in the quantized block format, e.g. [`k_quants::BlockQ4K`], and
+//! only gets dequantized on demand. [`QMatMul`] multiplies f32 activations with such a quantized
+//! weight without materializing the dequantized weight.
+use crate::{CpuStorage, Device, Layout, Result, Shape, Tensor};
+use k_quants::*;
+
+pub mod k_quants;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GgmlDType {
+    F32,
+    F16,
+    Q4_0,
+    Q4_1,
+    Q5_0,
+    Q5_1,
+    Q8_0,
+    Q8_1,
+    Q2K,
+    Q3K,
+    Q4K,
+    Q5K,
+    Q6K,
+    Q8K,
+}
+
+impl GgmlDType {
+    pub(crate) fn from_u32(u: u32) -> Result<Self> {
+        let dtype = match u {
+            0 => Self::F32,
+            1 => Self::F16,
+            2 => Self::Q4_0,
+            3 => Self::Q4_1,
+            6 => Self::Q5_0,
+            7 => Self::Q5_1,
+            8 => Self::Q8_0,
+            9 => Self::Q8_1,
+            10 => Self::Q2K,
+            11 => Self::Q3K,
+            12 => Self::Q4K,
+            13 => Self::Q5K,
+            14 => Self::Q6K,
+            15 => Self::Q8K,
+            _ => crate::bail!("unknown dtype for tensor {u}"),
+        };
+        Ok(dtype)
+    }
+
+    pub(crate) fn to_u32(self) -> u32 {
+        match self {
+            Self::F32 => 0,
+            Self::F16 => 1,
+            Self::Q4_0 => 2,
+            Self::Q4_1 => 3,
+            Self::Q5_0 => 6,
+            Self::Q5_1 => 7,
+            Self::Q8_0 => 8,
+            Self::Q8_1 => 9,
+            Self::Q2K => 10,
+            Self::Q3K => 11,
+            Self::Q4K => 12,
+            Self::Q5K => 13,
+            Self::Q6K => 14,
+            Self::Q8K => 15,
+        }
+    }
+
+    /// The size of a block in bytes.
+    pub fn type_size(&self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::F16 => 2,
+            Self::Q4_0 => std::mem::size_of::<BlockQ4_0>(),
+            Self::Q4_1 => std::mem::size_of::<BlockQ4_1>(),
+            Self::Q5_0 => std::mem::size_of::<BlockQ5_0>(),
+            Self::Q5_1 => std::mem::size_of::<BlockQ5_1>(),
+            // https://github.com/ggerganov/llama.cpp/blob/468ea24fb4633a0d681f7ac84089566c1c6190cb/ggml.c#L932
+            Self::Q8_0 => std::mem::size_of::<BlockQ8_0>(),
+            Self::Q8_1 => std::mem::size_of::<BlockQ8_1>(),
+            Self::Q2K => std::mem::size_of::<BlockQ2K>(),
+            Self::Q3K => std::mem::size_of::<BlockQ3K>(),
+            Self::Q4K => std::mem::size_of::<BlockQ4K>(),
+            Self::Q5K => std::mem::size_of::<BlockQ5K>(),
+            Self::Q6K => std::mem::size_of::<BlockQ6K>(),
+            Self::Q8K => std::mem::size_of::<BlockQ8K>(),
+        }
+    }
+
+    /// The number of elements stored in a block.
+    pub fn blck_size(&self) -> usize {
+        match self {
+            Self::F32 => 1,
+            Self::F16 => 1,
+            Self::Q4_0 => QK4_0,
+            Self::Q4_1 => QK4_1,
+            Self::Q5_0 => QK5_0,
+            Self::Q5_1 => QK5_1,
+            Self::Q8_0 => QK8_0,
+            Self::Q8_1 => QK8_1,
+            Self::Q2K | Self::Q3K | Self::Q4K | Self::Q5K | Self::Q6K | Self::Q8K => QK_K,
+        }
+    }
+}
+
+/// The type-erased storage of a quantized tensor.
+pub trait QuantizedType: Send + Sync {
+    fn dtype(&self) -> GgmlDType;
+    fn matmul_t(&self, mkn: (usize, usize, usize), lhs: &[f32], dst: &mut [f32]) -> Result<()>;
+    fn to_float(&self, ys: &mut [f32]) -> Result<()>;
+    fn storage_size_in_bytes(&self) -> usize;
+    fn as_bytes(&self) -> &[u8];
+}
+
+impl<T: GgmlType> QuantizedType for Vec<T> {
+    fn dtype(&self) -> GgmlDType {
+        T::DTYPE
+    }
+
+    fn matmul_t(&self, mkn: (usize, usize, usize), lhs: &[f32], dst: &mut [f32]) -> Result<()> {
+        matmul(mkn, lhs, self.as_slice(), dst)
+    }
+
+    fn to_float(&self, ys: &mut [f32]) -> Result<()> {
+        T::to_float(self.as_slice(), ys)
+    }
+
+    fn storage_size_in_bytes(&self) -> usize {
+        self.len() * std::mem::size_of::<T>()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        let size_in_bytes = self.storage_size_in_bytes();
+        unsafe { std::slice::from_raw_parts(self.as_ptr() as *const u8, size_in_bytes) }
+    }
+}
+
+/// Computes `lhs @ rhs_t^T` where `lhs` has shape `(m, k)` and `rhs_t` contains `n` rows of `k`
+/// quantized elements. The rows of `lhs` are quantized once to `T::VecDotType` and each output
+/// element is a block-wise [`GgmlType::vec_dot`], the work is split over the available threads
+/// along `n`.
+pub(crate) fn matmul<T: GgmlType>(
+    (m, k, n): (usize, usize, usize),
+    lhs: &[f32],
+    rhs_t: &[T],
+    dst: &mut [f32],
+) -> Result<()> {
+    if k % T::BLCK_SIZE != 0 {
+        crate::bail!("{:?}: k {k} is not divisible by {}", T::DTYPE, T::BLCK_SIZE)
+    }
+    let k_in_blocks = k / T::BLCK_SIZE;
+    if lhs.len() != m * k || rhs_t.len() != n * k_in_blocks || dst.len() != m * n {
+        crate::bail!(
+            "unexpected sizes in quantized matmul, lhs {}, rhs {}, dst {}, mkn {:?}",
+            lhs.len(),
+            rhs_t.len() * T::BLCK_SIZE,
+            dst.len(),
+            (m, k, n)
+        )
+    }
+
+    let lhs_k_in_blocks = k / T::VecDotType::BLCK_SIZE;
+    let mut lhs_b = vec![T::VecDotType::zeros(); m * lhs_k_in_blocks];
+    for row_idx in 0..m {
+        let lhs = &lhs[row_idx * k..(row_idx + 1) * k];
+        let lhs_b = &mut lhs_b[row_idx * lhs_k_in_blocks..(row_idx + 1) * lhs_k_in_blocks];
+        T::VecDotType::from_float(lhs, lhs_b)?
+    }
+    let lhs_b = lhs_b.as_slice();
+
+    // Computes the output columns `n_start..n_start + n_len` into `dst` which has shape
+    // `(m, n_len)`.
+    let compute = |n_start: usize, n_len: usize, dst: &mut [f32]| -> Result<()> {
+        for col_idx in 0..n_len {
+            let rhs = &rhs_t[(n_start + col_idx) * k_in_blocks..][..k_in_blocks];
+            for row_idx in 0..m {
+                let lhs = &lhs_b[row_idx * lhs_k_in_blocks..(row_idx + 1) * lhs_k_in_blocks];
+                dst[row_idx * n_len + col_idx] = T::vec_dot(k, rhs, lhs)?;
+            }
+        }
+        Ok(())
+    };
+
+    // Avoid spawning threads for tiny matmuls.
+    let num_threads = crate::utils::get_num_threads().min(n * k / 4096).max(1);
+    if num_threads == 1 {
+        return compute(0, n, dst);
+    }
+    let chunk_len = n.div_ceil(num_threads);
+    let chunks = std::thread::scope(|s| {
+        let handles: Vec<_> = (0..n)
+            .step_by(chunk_len)
+            .map(|n_start| {
+                let n_len = usize::min(chunk_len, n - n_start);
+                s.spawn(move || -> Result<(usize, usize, Vec<f32>)> {
+                    let mut dst = vec![0f32; m * n_len];
+                    compute(n_start, n_len, &mut dst)?;
+                    Ok((n_start, n_len, dst))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("quantized matmul thread panicked"))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    for (n_start, n_len, chunk) in chunks {
+        for row_idx in 0..m {
+            dst[row_idx * n + n_start..row_idx * n + n_start + n_len]
+                .copy_from_slice(&chunk[row_idx * n_len..(row_idx + 1) * n_len])
+        }
+    }
+    Ok(())
+}
+
+/// A tensor which data is stored in one of the GGML quantized formats.
+pub struct QTensor {
+    data: Box<dyn QuantizedType>,
+    shape: Shape,
+}
+
+impl std::fmt::Debug for QTensor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "QTensor[{:?}; {:?}]", self.shape.dims(), self.dtype())
+    }
+}
+
+impl QTensor {
+    pub fn new<S: Into<Shape>, T: GgmlType + 'static>(data: Vec<T>, shape: S) -> Result<Self> {
+        let shape = shape.into();
+        let block_size = T::BLCK_SIZE;
+        let last_dim = match shape.dims().last() {
+            None => crate::bail!("quantized tensors cannot be scalars"),
+            Some(&d) => d,
+        };
+        if last_dim % block_size != 0 {
+            crate::bail!(
+                "{:?}: last dim of {shape:?} is not divisible by the block size {block_size}",
+                T::DTYPE
+            )
+        }
+        if data.len() * block_size != shape.elem_count() {
+            crate::bail!(
+                "{:?}: got {} blocks which is incompatible with shape {shape:?}",
+                T::DTYPE,
+                data.len()
+            )
+        }
+        Ok(Self {
+            data: Box::new(data),
+            shape,
+        })
+    }
+
+    /// Quantizes a f32 tensor using the `T` block format, the quantization happens along the last
+    /// dimension which size must be a multiple of the block size.
+    pub fn quantize<T: GgmlType + 'static>(src: &Tensor) -> Result<Self> {
+        let shape = src.shape();
+        match shape.dims().last() {
+            None => crate::bail!("quantized tensors cannot be scalars"),
+            Some(&d) if d % T::BLCK_SIZE != 0 => crate::bail!(
+                "{:?}: last dim of {shape:?} is not divisible by the block size {}",
+                T::DTYPE,
+                T::BLCK_SIZE
+            ),
+            Some(_) => {}
+        }
+        let src = src
+            .to_dtype(crate::DType::F32)?
+            .flatten_all()?
+            .to_vec1::<f32>()?;
+        let mut data = vec![T::zeros(); src.len() / T::BLCK_SIZE];
+        T::from_float(&src, &mut data)?;
+        Self::new(data, shape)
+    }
+
+    pub fn dtype(&self) -> GgmlDType {
+        self.data.dtype()
+    }
+
+    pub fn rank(&self) -> usize {
+        self.shape.rank()
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn storage_size_in_bytes(&self) -> usize {
+        self.data.storage_size_in_bytes()
+    }
+
+    /// The raw data as stored in the ggml/gguf files.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_bytes()
+    }
+
+    /// Converts the quantized data to a f32 tensor on the target device.
+    pub fn dequantize(&self, device: &Device) -> Result<Tensor> {
+        let mut f32_data = vec![0f32; self.shape.elem_count()];
+        self.data.to_float(&mut f32_data)?;
+        Tensor::from_vec(f32_data, &self.shape, device)
+    }
+
+    pub fn matmul_t(&self, mkn: (usize, usize, usize), lhs: &[f32], dst: &mut [f32]) -> Result<()> {
+        self.data.matmul_t(mkn, lhs, dst)
+    }
+}
+
+/// A matmul with a quantized right hand side, `xs.matmul(&w.t())` where `w` is the quantized
+/// tensor of shape `(n, k)` and `xs` has shape `(.., k)`.
+#[derive(Debug, Clone)]
+pub struct QMatMul(std::sync::Arc<QTensor>);
+
+impl QMatMul {
+    pub fn from_qtensor(qtensor: QTensor) -> Self {
+        Self(std::sync::Arc::new(qtensor))
+    }
+
+    pub fn qtensor(&self) -> &QTensor {
+        self.0.as_ref()
+    }
+
+    pub fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        xs.custom_op1_arc(std::sync::Arc::new(Box::new(self.clone())))
+    }
+}
+
+impl crate::CustomOp1 for QMatMul {
+    fn name(&self) -> &'static str {
+        "qmatmul"
+    }
+
+    fn cpu_fwd(&self, storage: &CpuStorage, layout: &Layout) -> Result<(CpuStorage, Shape)> {
+        let (n, k) = self.0.shape.dims2()?;
+        let (src_dims, k2) = match layout.dims().split_last() {
+            Some((&k2, src_dims)) => (src_dims, k2),
+            None => crate::bail!("qmatmul: input cannot be a scalar"),
+        };
+        if k != k2 {
+            crate::bail!(
+                "qmatmul: shape mismatch, input {:?}, weight {:?}",
+                layout.shape(),
+                self.0.shape
+            )
+        }
+        let (start, end) = match layout.contiguous_offsets() {
+            Some(offsets) => offsets,
+            None => crate::bail!("qmatmul: input must be contiguous"),
+        };
+        let lhs = &storage.as_slice::<f32>()?[start..end];
+        let m = src_dims.iter().product::<usize>();
+        let mut dst_shape = src_dims.to_vec();
+        dst_shape.push(n);
+        let mut dst = vec![0f32; m * n];
+        self.0.matmul_t((m, k, n), lhs, &mut dst)?;
+        Ok((CpuStorage::F32(dst), dst_shape.into()))
+    }
+}
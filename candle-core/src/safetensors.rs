@@ -10,6 +10,11 @@ impl From<DType> for st::Dtype {
         match value {
             DType::U8 => st::Dtype::U8,
             DType::U32 => st::Dtype::U32,
+            DType::I8 => st::Dtype::I8,
+            DType::I16 => st::Dtype::I16,
+            DType::I32 => st::Dtype::I32,
+            DType::I64 => st::Dtype::I64,
+            DType::Bool => st::Dtype::BOOL,
             DType::BF16 => st::Dtype::BF16,
             DType::F16 => st::Dtype::F16,
             DType::F32 => st::Dtype::F32,
@@ -24,6 +29,11 @@ impl TryFrom<st::Dtype> for DType {
         match value {
             st::Dtype::U8 => Ok(DType::U8),
             st::Dtype::U32 => Ok(DType::U32),
+            st::Dtype::I8 => Ok(DType::I8),
+            st::Dtype::I16 => Ok(DType::I16),
+            st::Dtype::I32 => Ok(DType::I32),
+            st::Dtype::I64 => Ok(DType::I64),
+            st::Dtype::BOOL => Ok(DType::Bool),
             st::Dtype::BF16 => Ok(DType::BF16),
             st::Dtype::F16 => Ok(DType::F16),
             st::Dtype::F32 => Ok(DType::F32),
@@ -189,6 +199,11 @@ impl Tensor {
         match dtype {
             DType::U8 => convert_slice::<u8>(data, shape, device),
             DType::U32 => convert_slice::<u32>(data, shape, device),
+            DType::I8 => convert_slice::<i8>(data, shape, device),
+            DType::I16 => convert_slice::<i16>(data, shape, device),
+            DType::I32 => convert_slice::<i32>(data, shape, device),
+            DType::I64 => convert_slice::<i64>(data, shape, device),
+            DType::Bool => convert_slice::<u8>(data, shape, device)?.to_dtype(DType::Bool),
             DType::BF16 => convert_slice::<half::bf16>(data, shape, device),
             DType::F16 => convert_slice::<half::f16>(data, shape, device),
             DType::F32 => convert_slice::<f32>(data, shape, device),
@@ -209,20 +224,11 @@ fn convert(view: &st::TensorView<'_>, device: &Device) -> Result<Tensor> {
         st::Dtype::F16 => convert_::<half::f16>(view, device),
         st::Dtype::F32 => convert_::<f32>(view, device),
         st::Dtype::F64 => convert_::<f64>(view, device),
-        st::Dtype::I32 => {
-            let conv = |x| {
-                u32::try_from(x)
-                    .map_err(|_| Error::Msg(format!("out of bounds value for u32: {x}")))
-            };
-            convert_with_cast_::<i32, u32, _>(view, device, conv)
-        }
-        st::Dtype::I64 => {
-            let conv = |x| {
-                u32::try_from(x)
-                    .map_err(|_| Error::Msg(format!("out of bounds value for u32: {x}")))
-            };
-            convert_with_cast_::<i64, u32, _>(view, device, conv)
-        }
+        st::Dtype::I8 => convert_::<i8>(view, device),
+        st::Dtype::I16 => convert_::<i16>(view, device),
+        st::Dtype::I32 => convert_::<i32>(view, device),
+        st::Dtype::I64 => convert_::<i64>(view, device),
+        st::Dtype::BOOL => convert_::<u8>(view, device)?.to_dtype(DType::Bool),
         dtype => Err(Error::UnsupportedSafeTensorDtype(dtype)),
     }
 }
@@ -233,6 +239,11 @@ fn convert_back(tensor: &Tensor) -> Result<Vec<u8>> {
     match tensor.dtype() {
         DType::U8 => Ok(convert_back_::<u8>(tensor.to_vec1()?)),
         DType::U32 => Ok(convert_back_::<u32>(tensor.to_vec1()?)),
+        DType::I8 => Ok(convert_back_::<i8>(tensor.to_vec1()?)),
+        DType::I16 => Ok(convert_back_::<i16>(tensor.to_vec1()?)),
+        DType::I32 => Ok(convert_back_::<i32>(tensor.to_vec1()?)),
+        DType::I64 => Ok(convert_back_::<i64>(tensor.to_vec1()?)),
+        DType::Bool => Ok(convert_back_::<u8>(tensor.to_vec1()?)),
         DType::F16 => Ok(convert_back_::<half::f16>(tensor.to_vec1()?)),
         DType::BF16 => Ok(convert_back_::<half::bf16>(tensor.to_vec1()?)),
         DType::F32 => Ok(convert_back_::<f32>(tensor.to_vec1()?)),
@@ -316,4 +327,27 @@ mod tests {
         assert_eq!(bytes, b"x\0\0\0\0\0\0\0{\"t\":{\"dtype\":\"F32\",\"shape\":[2,2],\"data_offsets\":[0,16]},\"u\":{\"dtype\":\"F32\",\"shape\":[1,2],\"data_offsets\":[16,24]}}      \0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
         std::fs::remove_file("multi.safetensors").unwrap();
     }
+
+    #[test]
+    fn load_signed_ints() {
+        let dev = &Device::Cpu;
+        let t = Tensor::new(&[-3i32, 0, i32::MAX], dev).unwrap();
+        let u = Tensor::new(&[[i64::MIN, -1], [1, 2]], dev).unwrap();
+        let b = Tensor::new(&[0f32, 2.], dev)
+            .unwrap()
+            .to_dtype(DType::Bool)
+            .unwrap();
+        let map: HashMap<_, _> = [("t", t), ("u", u), ("b", b)].into_iter().collect();
+        let bytes = st::serialize(&map, &None).unwrap();
+        let weights = load_buffer(&bytes, dev).unwrap();
+        let t = weights.get("t").unwrap();
+        assert_eq!(t.dtype(), DType::I32);
+        assert_eq!(t.to_vec1::<i32>().unwrap(), [-3, 0, i32::MAX]);
+        let u = weights.get("u").unwrap();
+        assert_eq!(u.dtype(), DType::I64);
+        assert_eq!(u.to_vec2::<i64>().unwrap(), [[i64::MIN, -1], [1, 2]]);
+        let b = weights.get("b").unwrap();
+        assert_eq!(b.dtype(), DType::Bool);
+        assert_eq!(b.to_vec1::<u8>().unwrap(), [0, 1]);
+    }
 }
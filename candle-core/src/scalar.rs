@@ -0,0 +1,27 @@
+//! Binary ops such as `maximum` or `pow` accept either a tensor or a scalar as their right-hand
+//! side.
+use crate::{Device, Result, Tensor, WithDType};
+
+pub enum TensorScalar {
+    Tensor(Tensor),
+    /// A scalar stored as a tensor with zero dimensions on the cpu, it gets converted to the
+    /// dtype and device of the other operand when used.
+    Scalar(Tensor),
+}
+
+pub trait TensorOrScalar {
+    fn to_tensor_scalar(self) -> Result<TensorScalar>;
+}
+
+impl TensorOrScalar for &Tensor {
+    fn to_tensor_scalar(self) -> Result<TensorScalar> {
+        Ok(TensorScalar::Tensor(self.clone()))
+    }
+}
+
+impl<T: WithDType> TensorOrScalar for T {
+    fn to_tensor_scalar(self) -> Result<TensorScalar> {
+        let scalar = Tensor::new(self, &Device::Cpu)?;
+        Ok(TensorScalar::Scalar(scalar))
+    }
+}
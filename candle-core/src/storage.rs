@@ -1,6 +1,6 @@
 use crate::backend::BackendStorage;
-use crate::op::{self, CmpOp, CustomOp1, CustomOp2, CustomOp3, ReduceOp};
-use crate::{CpuStorage, CudaStorage, DType, Device, Error, Layout, Result, Shape};
+use crate::op::{self, CmpOp, CustomOp1, CustomOp2, CustomOp3, ReduceOp, ScanOp};
+use crate::{CpuStorage, CudaStorage, DType, Device, Error, Layout, LazyStorage, Result, Shape};
 
 // We do not want to implement Clone on Storage as cloning may fail because of
 // out of memory. Instead try_clone should be used.
@@ -8,6 +8,7 @@ use crate::{CpuStorage, CudaStorage, DType, Device, Error, Layout, Result, Shape
 pub enum Storage {
     Cpu(CpuStorage),
     Cuda(CudaStorage),
+    Lazy(LazyStorage),
 }
 
 impl Storage {
@@ -18,6 +19,10 @@ impl Storage {
                 let storage = storage.try_clone(layout)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.try_clone(layout)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -25,6 +30,7 @@ impl Storage {
         match self {
             Self::Cpu(_) => Device::Cpu,
             Self::Cuda(storage) => Device::Cuda(storage.device().clone()),
+            Self::Lazy(_) => Device::Lazy,
         }
     }
 
@@ -32,6 +38,7 @@ impl Storage {
         match self {
             Self::Cpu(storage) => storage.dtype(),
             Self::Cuda(storage) => storage.dtype(),
+            Self::Lazy(storage) => storage.dtype(),
         }
     }
 
@@ -65,6 +72,10 @@ impl Storage {
                 let storage = storage.affine(layout, mul, add)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.affine(layout, mul, add)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -78,6 +89,10 @@ impl Storage {
                 let storage = storage.elu(layout, alpha)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.elu(layout, alpha)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -99,6 +114,10 @@ impl Storage {
                 let storage = lhs.cmp(op, rhs, lhs_layout, rhs_layout)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(lhs), Self::Lazy(rhs)) => {
+                let storage = lhs.cmp(op, rhs, lhs_layout, rhs_layout)?;
+                Ok(Self::Lazy(storage))
+            }
             (lhs, rhs) => {
                 // Should not happen because of the same device check above but we're defensive
                 // anyway.
@@ -122,6 +141,44 @@ impl Storage {
                 let storage = storage.reduce_op(op, layout, s)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.reduce_op(op, layout, s)?;
+                Ok(Self::Lazy(storage))
+            }
+        }
+    }
+
+    pub(crate) fn scan(&self, op: ScanOp, layout: &Layout, dim: usize) -> Result<Self> {
+        match self {
+            Storage::Cpu(storage) => {
+                let storage = storage.scan(op, layout, dim)?;
+                Ok(Self::Cpu(storage))
+            }
+            Self::Cuda(storage) => {
+                let storage = storage.scan(op, layout, dim)?;
+                Ok(Self::Cuda(storage))
+            }
+            Self::Lazy(storage) => {
+                let storage = storage.scan(op, layout, dim)?;
+                Ok(Self::Lazy(storage))
+            }
+        }
+    }
+
+    pub(crate) fn arg_sort(&self, layout: &Layout, dim: usize, asc: bool) -> Result<Self> {
+        match self {
+            Storage::Cpu(storage) => {
+                let storage = storage.arg_sort(layout, dim, asc)?;
+                Ok(Self::Cpu(storage))
+            }
+            Self::Cuda(storage) => {
+                let storage = storage.arg_sort(layout, dim, asc)?;
+                Ok(Self::Cuda(storage))
+            }
+            Self::Lazy(storage) => {
+                let storage = storage.arg_sort(layout, dim, asc)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -135,6 +192,10 @@ impl Storage {
                 let storage = storage.to_dtype(layout, dtype)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.to_dtype(layout, dtype)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -148,6 +209,12 @@ impl Storage {
                 let (storage, shape) = c.cuda_fwd(storage, l)?;
                 Ok((Self::Cuda(storage), shape))
             }
+            // Custom ops are not recorded in the lazy graph, their arguments get evaluated and the
+            // op runs straight away on the cpu.
+            Self::Lazy(storage) => {
+                let (storage, shape) = c.cpu_fwd(storage.realize()?.as_ref(), l)?;
+                Ok((Self::Lazy(LazyStorage::ready(storage)), shape))
+            }
         }
     }
 
@@ -168,6 +235,11 @@ impl Storage {
                 let (s, shape) = c.cuda_fwd(s1, l1, s2, l2)?;
                 Ok((Self::Cuda(s), shape))
             }
+            (Self::Lazy(s1), Self::Lazy(s2)) => {
+                let (s, shape) =
+                    c.cpu_fwd(s1.realize()?.as_ref(), l1, s2.realize()?.as_ref(), l2)?;
+                Ok((Self::Lazy(LazyStorage::ready(s)), shape))
+            }
             _ => unreachable!(),
         }
     }
@@ -192,11 +264,26 @@ impl Storage {
                 let (s, shape) = c.cuda_fwd(s1, l1, s2, l2, s3, l3)?;
                 Ok((Self::Cuda(s), shape))
             }
+            (Self::Lazy(s1), Self::Lazy(s2), Self::Lazy(s3)) => {
+                let (s1, s2, s3) = (s1.realize()?, s2.realize()?, s3.realize()?);
+                let (s, shape) = c.cpu_fwd(&s1, l1, &s2, l2, &s3, l3)?;
+                Ok((Self::Lazy(LazyStorage::ready(s)), shape))
+            }
             _ => unreachable!(),
         }
     }
 
+    fn check_unary<B: op::UnaryOpT>(&self) -> Result<()> {
+        let dtype = self.dtype();
+        if B::supports_dtype(dtype) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedDTypeForOp(dtype, B::NAME).bt())
+        }
+    }
+
     pub(crate) fn unary_impl<B: op::UnaryOpT>(&self, layout: &Layout) -> Result<Self> {
+        self.check_unary::<B>()?;
         match self {
             Storage::Cpu(storage) => {
                 let storage = storage.unary_impl::<B>(layout)?;
@@ -206,6 +293,76 @@ impl Storage {
                 let storage = storage.unary_impl::<B>(layout)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.unary_impl::<B>(layout)?;
+                Ok(Self::Lazy(storage))
+            }
+        }
+    }
+
+    pub(crate) fn unary_inplace<B: op::UnaryOpT>(&mut self, layout: &Layout) -> Result<()> {
+        self.check_unary::<B>()?;
+        match self {
+            Storage::Cpu(storage) => storage.unary_inplace::<B>(layout),
+            Self::Cuda(storage) => storage.unary_inplace::<B>(layout),
+            Self::Lazy(storage) => storage.unary_inplace::<B>(layout),
+        }
+    }
+
+    pub(crate) fn binary_inplace<B: op::BinaryOpT>(
+        &mut self,
+        rhs: &Self,
+        lhs_layout: &Layout,
+        rhs_layout: &Layout,
+    ) -> Result<()> {
+        self.same_device(rhs, B::NAME)?;
+        self.same_dtype(rhs, B::NAME)?;
+        match (self, rhs) {
+            (Storage::Cpu(lhs), Storage::Cpu(rhs)) => {
+                lhs.binary_inplace::<B>(rhs, lhs_layout, rhs_layout)
+            }
+            (Self::Cuda(lhs), Self::Cuda(rhs)) => {
+                lhs.binary_inplace::<B>(rhs, lhs_layout, rhs_layout)
+            }
+            (Self::Lazy(lhs), Self::Lazy(rhs)) => {
+                lhs.binary_inplace::<B>(rhs, lhs_layout, rhs_layout)
+            }
+            (lhs, rhs) => {
+                // Should not happen because of the same device check above but we're defensive
+                // anyway.
+                Err(Error::DeviceMismatchBinaryOp {
+                    lhs: lhs.device().location(),
+                    rhs: rhs.device().location(),
+                    op: B::NAME,
+                }
+                .bt())
+            }
+        }
+    }
+
+    pub(crate) fn index_put(
+        &mut self,
+        layout: &Layout,
+        indexes: &Self,
+        indexes_layout: &Layout,
+        source: &Self,
+        source_layout: &Layout,
+        dim: usize,
+    ) -> Result<()> {
+        self.same_device(indexes, "index-put")?;
+        self.same_device(source, "index-put")?;
+        self.same_dtype(source, "index-put")?;
+        match (self, indexes, source) {
+            (Self::Cpu(s), Self::Cpu(indexes), Self::Cpu(source)) => {
+                s.index_put(layout, indexes, indexes_layout, source, source_layout, dim)
+            }
+            (Self::Cuda(s), Self::Cuda(indexes), Self::Cuda(source)) => {
+                s.index_put(layout, indexes, indexes_layout, source, source_layout, dim)
+            }
+            (Self::Lazy(s), Self::Lazy(indexes), Self::Lazy(source)) => {
+                s.index_put(layout, indexes, indexes_layout, source, source_layout, dim)
+            }
+            _ => unreachable!(),
         }
     }
 
@@ -226,6 +383,10 @@ impl Storage {
                 let storage = lhs.binary_impl::<B>(rhs, lhs_layout, rhs_layout)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(lhs), Self::Lazy(rhs)) => {
+                let storage = lhs.binary_impl::<B>(rhs, lhs_layout, rhs_layout)?;
+                Ok(Self::Lazy(storage))
+            }
             (lhs, rhs) => {
                 // Should not happen because of the same device check above but we're defensive
                 // anyway.
@@ -257,6 +418,10 @@ impl Storage {
                 let s = inp.conv1d(l, kernel, kernel_l, params)?;
                 Ok(Self::Cuda(s))
             }
+            (Storage::Lazy(inp), Storage::Lazy(kernel)) => {
+                let s = inp.conv1d(l, kernel, kernel_l, params)?;
+                Ok(Self::Lazy(s))
+            }
             (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
@@ -284,6 +449,10 @@ impl Storage {
                 let s = inp.conv2d(l, kernel, kernel_l, params)?;
                 Ok(Self::Cuda(s))
             }
+            (Storage::Lazy(inp), Storage::Lazy(kernel)) => {
+                let s = inp.conv2d(l, kernel, kernel_l, params)?;
+                Ok(Self::Lazy(s))
+            }
             (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
@@ -293,39 +462,138 @@ impl Storage {
         }
     }
 
-    pub(crate) fn avg_pool2d(
+    pub(crate) fn conv3d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConv3D,
+    ) -> Result<Self> {
+        self.same_device(kernel, "conv3d")?;
+        self.same_dtype(kernel, "conv3d")?;
+        match (self, &kernel) {
+            (Storage::Cpu(inp), Storage::Cpu(kernel)) => {
+                let s = inp.conv3d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cpu(s))
+            }
+            (Storage::Cuda(inp), Storage::Cuda(kernel)) => {
+                let s = inp.conv3d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cuda(s))
+            }
+            (Storage::Lazy(inp), Storage::Lazy(kernel)) => {
+                let s = inp.conv3d(l, kernel, kernel_l, params)?;
+                Ok(Self::Lazy(s))
+            }
+            (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
+                lhs: lhs.device().location(),
+                rhs: rhs.device().location(),
+                op: "conv3d",
+            }
+            .bt()),
+        }
+    }
+
+    pub(crate) fn conv_transpose1d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose1D,
+    ) -> Result<Self> {
+        self.same_device(kernel, "conv_transpose1d")?;
+        self.same_dtype(kernel, "conv_transpose1d")?;
+        match (self, &kernel) {
+            (Storage::Cpu(inp), Storage::Cpu(kernel)) => {
+                let s = inp.conv_transpose1d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cpu(s))
+            }
+            (Storage::Cuda(inp), Storage::Cuda(kernel)) => {
+                let s = inp.conv_transpose1d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cuda(s))
+            }
+            (Storage::Lazy(inp), Storage::Lazy(kernel)) => {
+                let s = inp.conv_transpose1d(l, kernel, kernel_l, params)?;
+                Ok(Self::Lazy(s))
+            }
+            (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
+                lhs: lhs.device().location(),
+                rhs: rhs.device().location(),
+                op: "conv_transpose1d",
+            }
+            .bt()),
+        }
+    }
+
+    pub(crate) fn conv_transpose2d(
+        &self,
+        l: &Layout,
+        kernel: &Self,
+        kernel_l: &Layout,
+        params: &crate::conv::ParamsConvTranspose2D,
+    ) -> Result<Self> {
+        self.same_device(kernel, "conv_transpose2d")?;
+        self.same_dtype(kernel, "conv_transpose2d")?;
+        match (self, &kernel) {
+            (Storage::Cpu(inp), Storage::Cpu(kernel)) => {
+                let s = inp.conv_transpose2d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cpu(s))
+            }
+            (Storage::Cuda(inp), Storage::Cuda(kernel)) => {
+                let s = inp.conv_transpose2d(l, kernel, kernel_l, params)?;
+                Ok(Self::Cuda(s))
+            }
+            (Storage::Lazy(inp), Storage::Lazy(kernel)) => {
+                let s = inp.conv_transpose2d(l, kernel, kernel_l, params)?;
+                Ok(Self::Lazy(s))
+            }
+            (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
+                lhs: lhs.device().location(),
+                rhs: rhs.device().location(),
+                op: "conv_transpose2d",
+            }
+            .bt()),
+        }
+    }
+
+    pub(crate) fn avg_pool(
         &self,
         layout: &Layout,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
+        params: &crate::pool::ParamsPool,
     ) -> Result<Self> {
         match self {
             Storage::Cpu(storage) => {
-                let storage = storage.avg_pool2d(layout, kernel_size, stride)?;
+                let storage = storage.avg_pool(layout, params)?;
                 Ok(Self::Cpu(storage))
             }
             Self::Cuda(storage) => {
-                let storage = storage.avg_pool2d(layout, kernel_size, stride)?;
+                let storage = storage.avg_pool(layout, params)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.avg_pool(layout, params)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
-    pub(crate) fn max_pool2d(
+    pub(crate) fn max_pool(
         &self,
         layout: &Layout,
-        kernel_size: (usize, usize),
-        stride: (usize, usize),
+        params: &crate::pool::ParamsPool,
     ) -> Result<Self> {
         match self {
             Storage::Cpu(storage) => {
-                let storage = storage.max_pool2d(layout, kernel_size, stride)?;
+                let storage = storage.max_pool(layout, params)?;
                 Ok(Self::Cpu(storage))
             }
             Self::Cuda(storage) => {
-                let storage = storage.max_pool2d(layout, kernel_size, stride)?;
+                let storage = storage.max_pool(layout, params)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.max_pool(layout, params)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -339,6 +607,50 @@ impl Storage {
                 let storage = storage.upsample_nearest2d(layout, h, w)?;
                 Ok(Self::Cuda(storage))
             }
+            Self::Lazy(storage) => {
+                let storage = storage.upsample_nearest2d(layout, h, w)?;
+                Ok(Self::Lazy(storage))
+            }
+        }
+    }
+
+    pub(crate) fn interpolate2d(
+        &self,
+        layout: &Layout,
+        h: usize,
+        w: usize,
+        mode: crate::InterpolateMode,
+    ) -> Result<Self> {
+        match self {
+            Storage::Cpu(storage) => {
+                let storage = storage.interpolate2d(layout, h, w, mode)?;
+                Ok(Self::Cpu(storage))
+            }
+            Self::Cuda(storage) => {
+                let storage = storage.interpolate2d(layout, h, w, mode)?;
+                Ok(Self::Cuda(storage))
+            }
+            Self::Lazy(storage) => {
+                let storage = storage.interpolate2d(layout, h, w, mode)?;
+                Ok(Self::Lazy(storage))
+            }
+        }
+    }
+
+    pub(crate) fn fft(&self, layout: &Layout, kind: crate::fft::FftKind) -> Result<Self> {
+        match self {
+            Storage::Cpu(storage) => {
+                let storage = storage.fft(layout, kind)?;
+                Ok(Self::Cpu(storage))
+            }
+            Self::Cuda(storage) => {
+                let storage = storage.fft(layout, kind)?;
+                Ok(Self::Cuda(storage))
+            }
+            Self::Lazy(storage) => {
+                let storage = storage.fft(layout, kind)?;
+                Ok(Self::Lazy(storage))
+            }
         }
     }
 
@@ -362,6 +674,10 @@ impl Storage {
                 let storage = cond.where_cond(layout, t, layout_t, f, layout_f)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(cond), Self::Lazy(t), Self::Lazy(f)) => {
+                let storage = cond.where_cond(layout, t, layout_t, f, layout_f)?;
+                Ok(Self::Lazy(storage))
+            }
             (_, lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
@@ -388,6 +704,10 @@ impl Storage {
                 let storage = s.gather(l, indexes, indexes_l, d)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(s), Self::Lazy(indexes)) => {
+                let storage = s.gather(l, indexes, indexes_l, d)?;
+                Ok(Self::Lazy(storage))
+            }
             _ => unreachable!(),
         }
     }
@@ -412,6 +732,10 @@ impl Storage {
                 let storage = s.scatter_add(l, indexes, indexes_l, source, source_l, d)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(s), Self::Lazy(indexes), Self::Lazy(source)) => {
+                let storage = s.scatter_add(l, indexes, indexes_l, source, source_l, d)?;
+                Ok(Self::Lazy(storage))
+            }
             _ => unreachable!(),
         }
     }
@@ -436,6 +760,10 @@ impl Storage {
                 let storage = s.index_add(l, indexes, indexes_l, source, source_l, d)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(s), Self::Lazy(indexes), Self::Lazy(source)) => {
+                let storage = s.index_add(l, indexes, indexes_l, source, source_l, d)?;
+                Ok(Self::Lazy(storage))
+            }
             _ => unreachable!(),
         }
     }
@@ -457,6 +785,10 @@ impl Storage {
                 let storage = lhs.index_select(rhs, lhs_l, rhs_l, d)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(lhs), Self::Lazy(rhs)) => {
+                let storage = lhs.index_select(rhs, lhs_l, rhs_l, d)?;
+                Ok(Self::Lazy(storage))
+            }
             (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
@@ -484,6 +816,10 @@ impl Storage {
                 let storage = lhs.matmul(rhs, bmnk, lhs_layout, rhs_layout)?;
                 Ok(Self::Cuda(storage))
             }
+            (Self::Lazy(lhs), Self::Lazy(rhs)) => {
+                let storage = lhs.matmul(rhs, bmnk, lhs_layout, rhs_layout)?;
+                Ok(Self::Lazy(storage))
+            }
             (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
@@ -503,6 +839,26 @@ impl Storage {
         match (self, dst) {
             (Self::Cpu(src), Self::Cpu(dst)) => src.copy_strided_src(dst, dst_offset, src_l),
             (Self::Cuda(src), Self::Cuda(dst)) => Ok(src.copy_strided_src(dst, dst_offset, src_l)?),
+            (Self::Lazy(src), Self::Lazy(dst)) => src.copy_strided_src(dst, dst_offset, src_l),
+            (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
+                lhs: lhs.device().location(),
+                rhs: rhs.device().location(),
+                op: "copy",
+            }
+            .bt()),
+        }
+    }
+
+    pub(crate) fn copy_strided(
+        &self,
+        dst: &mut Self,
+        dst_l: &Layout,
+        src_l: &Layout,
+    ) -> Result<()> {
+        match (self, dst) {
+            (Self::Cpu(src), Self::Cpu(dst)) => src.copy_strided(dst, dst_l, src_l),
+            (Self::Cuda(src), Self::Cuda(dst)) => Ok(src.copy_strided(dst, dst_l, src_l)?),
+            (Self::Lazy(src), Self::Lazy(dst)) => src.copy_strided(dst, dst_l, src_l),
             (lhs, rhs) => Err(Error::DeviceMismatchBinaryOp {
                 lhs: lhs.device().location(),
                 rhs: rhs.device().location(),
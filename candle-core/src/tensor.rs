@@ -1,9 +1,11 @@
 use crate::backend::{BackendDevice, BackendStorage};
 use crate::op::{
-    BackpropOp, BinaryOp, CmpOp, CustomOp1, CustomOp2, CustomOp3, Op, ReduceOp, UnaryOp,
+    BackpropOp, BinaryOp, CmpOp, CustomOp1, CustomOp2, CustomOp3, Op, ReduceOp, ScanOp, UnaryOp,
 };
+use crate::scalar::{TensorOrScalar, TensorScalar};
 use crate::shape::{Dim, Dims};
 use crate::{storage::Storage, DType, Device, Error, Layout, Result, Shape};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 
 /// Unique identifier for tensors.
@@ -34,6 +36,10 @@ pub struct Tensor_ {
     // and Arc<Mutex<Storage>> for tensors where the data could be modified, e.g. variables but
     // that's tricky to encode in the current setup.
     storage: Arc<RwLock<Storage>>,
+    // The number of in place modifications of the storage, this is shared by all the tensors
+    // using the same storage. The computation graph records it for the tensors it captures so
+    // that backprop can detect the ones that have been modified since.
+    version: Arc<AtomicUsize>,
     layout: Layout,
     op: BackpropOp,
     is_variable: bool,
@@ -102,6 +108,30 @@ macro_rules! binary_op {
     };
 }
 
+// Binary ops where the right-hand side can also be a scalar, the scalar is broadcast to the shape
+// of the left-hand side.
+macro_rules! binary_op_scalar {
+    ($fn_name:ident, $op_name:ident) => {
+        pub fn $fn_name<T: TensorOrScalar>(&self, rhs: T) -> Result<Self> {
+            let rhs = match rhs.to_tensor_scalar()? {
+                TensorScalar::Tensor(rhs) => rhs,
+                TensorScalar::Scalar(rhs) => rhs
+                    .to_dtype(self.dtype())?
+                    .to_device(self.device())?
+                    .broadcast_as(self.shape())?,
+            };
+            let shape = self.same_shape_binary_op(&rhs, stringify!($fn_name))?;
+            let storage = self.storage().binary_impl::<crate::op::$op_name>(
+                &*rhs.storage(),
+                self.layout(),
+                rhs.layout(),
+            )?;
+            let op = BackpropOp::new2(self, &rhs, |t1, t2| Op::Binary(t1, t2, BinaryOp::$op_name));
+            Ok(from_storage(storage, shape.clone(), op, false))
+        }
+    };
+}
+
 macro_rules! broadcast_binary_op {
     ($fn_name:ident, $inner_fn_name:ident) => {
         pub fn $fn_name(&self, rhs: &Self) -> Result<Self> {
@@ -121,6 +151,27 @@ macro_rules! broadcast_binary_op {
     };
 }
 
+macro_rules! unary_inplace_op {
+    ($fn_name:ident, $op_name:ident) => {
+        pub fn $fn_name(&self) -> Result<()> {
+            self.check_inplace(stringify!($fn_name))?;
+            let (mut storage, layout) = self.storage_mut_and_layout();
+            storage.unary_inplace::<crate::op::$op_name>(layout)
+        }
+    };
+}
+
+macro_rules! binary_inplace_op {
+    ($fn_name:ident, $op_name:ident) => {
+        pub fn $fn_name(&self, rhs: &Self) -> Result<()> {
+            let rhs = self.inplace_src(rhs, stringify!($fn_name))?;
+            let (mut storage, layout) = self.storage_mut_and_layout();
+            let (rhs_storage, rhs_layout) = rhs.storage_and_layout();
+            storage.binary_inplace::<crate::op::$op_name>(&rhs_storage, layout, rhs_layout)
+        }
+    };
+}
+
 /// Creates a fresh tensor structure based on a storage and a shape, this uses contiguous strides.
 fn from_storage<S: Into<Shape>>(
     storage: Storage,
@@ -133,6 +184,7 @@ fn from_storage<S: Into<Shape>>(
     let tensor_ = Tensor_ {
         id: TensorId::new(),
         storage: Arc::new(RwLock::new(storage)),
+        version: Arc::default(),
         layout: Layout::contiguous(shape),
         op,
         is_variable,
@@ -478,8 +530,6 @@ impl Tensor {
         self.is_variable || self.op.is_some()
     }
 
-    // TODO: Also make an inplace version or a pre-allocated? This could be tricky
-    // if this can create cycles in the compute graph.
     binary_op!(add, Add);
     binary_op!(mul, Mul);
     binary_op!(sub, Sub);
@@ -488,6 +538,16 @@ impl Tensor {
     broadcast_binary_op!(broadcast_mul, mul);
     broadcast_binary_op!(broadcast_sub, sub);
     broadcast_binary_op!(broadcast_div, div);
+    binary_op_scalar!(maximum, Maximum);
+    binary_op_scalar!(minimum, Minimum);
+    binary_op_scalar!(pow, Pow);
+    binary_op_scalar!(atan2, Atan2);
+    binary_op_scalar!(rem, Rem);
+    broadcast_binary_op!(broadcast_maximum, maximum);
+    broadcast_binary_op!(broadcast_minimum, minimum);
+    broadcast_binary_op!(broadcast_pow, pow);
+    broadcast_binary_op!(broadcast_atan2, atan2);
+    broadcast_binary_op!(broadcast_rem, rem);
 
     unary_op!(recip, Recip);
     unary_op!(neg, Neg);
@@ -500,6 +560,94 @@ impl Tensor {
     unary_op!(sqrt, Sqrt);
     unary_op!(gelu, Gelu);
     unary_op!(relu, Relu);
+    unary_op!(gelu_erf, GeluErf);
+    unary_op!(tanh, Tanh);
+    unary_op!(erf, Erf);
+    unary_op!(floor, Floor);
+    unary_op!(ceil, Ceil);
+    unary_op!(round, Round);
+    unary_op!(sigmoid, Sigmoid);
+    unary_op!(softplus, Softplus);
+    unary_op!(log1p, Log1p);
+
+    // In place versions of the ops above, these write the result in the storage of `self` and
+    // so are only available on tensors that are not tracked by the computation graph. For binary
+    // ops, `rhs` is broadcasted to the shape of `self`. Modifying a tensor that an existing graph
+    // has captured, e.g. `x` in `w.mul(&x)` with `w` a variable, makes the backward pass of that
+    // graph return an error. Tensors created with `zeros`/`ones` broadcast a single value and
+    // have to be made contiguous before being modified.
+    binary_inplace_op!(add_inplace, Add);
+    binary_inplace_op!(mul_inplace, Mul);
+    binary_inplace_op!(sub_inplace, Sub);
+    binary_inplace_op!(div_inplace, Div);
+
+    unary_inplace_op!(recip_inplace, Recip);
+    unary_inplace_op!(neg_inplace, Neg);
+    unary_inplace_op!(exp_inplace, Exp);
+    unary_inplace_op!(log_inplace, Log);
+    unary_inplace_op!(sin_inplace, Sin);
+    unary_inplace_op!(cos_inplace, Cos);
+    unary_inplace_op!(abs_inplace, Abs);
+    unary_inplace_op!(sqr_inplace, Sqr);
+    unary_inplace_op!(sqrt_inplace, Sqrt);
+    unary_inplace_op!(gelu_inplace, Gelu);
+    unary_inplace_op!(relu_inplace, Relu);
+    unary_inplace_op!(gelu_erf_inplace, GeluErf);
+    unary_inplace_op!(tanh_inplace, Tanh);
+    unary_inplace_op!(erf_inplace, Erf);
+    unary_inplace_op!(floor_inplace, Floor);
+    unary_inplace_op!(ceil_inplace, Ceil);
+    unary_inplace_op!(round_inplace, Round);
+    unary_inplace_op!(sigmoid_inplace, Sigmoid);
+    unary_inplace_op!(softplus_inplace, Softplus);
+    unary_inplace_op!(log1p_inplace, Log1p);
+
+    // Checks that the storage of `self` can be modified in place.
+    fn check_inplace(&self, op: &'static str) -> Result<()> {
+        if self.track_op() {
+            let msg = "the tensor is tracked by the computation graph";
+            Err(Error::InplaceNotSupported { op, msg }.bt())?
+        }
+        let layout = self.layout();
+        let broadcasted = layout
+            .dims()
+            .iter()
+            .zip(layout.stride().iter())
+            .any(|(&dim, &stride)| dim > 1 && stride == 0);
+        if broadcasted {
+            let msg = "the tensor has some broadcasted dimensions, use contiguous() first";
+            Err(Error::InplaceNotSupported { op, msg }.bt())?
+        }
+        Ok(())
+    }
+
+    // Returns the tensor to use as a source when modifying `self` in place. The source is
+    // broadcasted to the shape of `self` and copied if it shares its storage with `self`.
+    fn inplace_src(&self, src: &Self, op: &'static str) -> Result<Self> {
+        self.check_inplace(op)?;
+        if src.track_op() {
+            let msg = "the source is tracked by the computation graph";
+            Err(Error::InplaceNotSupported { op, msg }.bt())?
+        }
+        let src = if src.shape() == self.shape() {
+            src.clone()
+        } else {
+            src.broadcast_as(self.shape())?
+        };
+        if self.same_storage(&src) {
+            src.copy()
+        } else {
+            Ok(src)
+        }
+    }
+
+    // Copies the content of `src` in the storage of `self`.
+    pub(crate) fn assign(&self, src: &Self, op: &'static str) -> Result<()> {
+        let src = self.inplace_src(src, op)?;
+        let (mut storage, layout) = self.storage_mut_and_layout();
+        let (src_storage, src_layout) = src.storage_and_layout();
+        src_storage.copy_strided(&mut storage, layout, src_layout)
+    }
 
     /// Retrieves the single scalar value hold in the tensor. If the tensor contains multiple
     /// dimensions, an error is returned instead.
@@ -519,6 +667,7 @@ impl Tensor {
         match &*self.storage() {
             Storage::Cpu(cpu_storage) => from_cpu_storage(cpu_storage),
             Storage::Cuda(storage) => from_cpu_storage(&storage.to_cpu_storage()?),
+            Storage::Lazy(storage) => from_cpu_storage(storage.realize()?.as_ref()),
         }
     }
 
@@ -527,6 +676,20 @@ impl Tensor {
         self.to_scalar::<S>()
     }
 
+    /// Clamps the values of the tensor between `min` and `max`, both bounds can either be
+    /// scalars or tensors with the same shape as `self`.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[-2f32, 0.5, 3.], &Device::Cpu)?;
+    /// let a = a.clamp(0f32, 1f32)?;
+    /// assert_eq!(a.to_vec1::<f32>()?, &[0., 0.5, 1.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn clamp<T1: TensorOrScalar, T2: TensorOrScalar>(&self, min: T1, max: T2) -> Result<Self> {
+        self.maximum(min)?.minimum(max)
+    }
+
     /// This operation multiplies the input tensor by `mul` then adds `add` and return the result.
     /// The input values `mul` and `add` are casted to the appropriate type so some rounding might
     /// be performed.
@@ -613,6 +776,7 @@ impl Tensor {
             let tensor_ = Tensor_ {
                 id: TensorId::new(),
                 storage: self.storage.clone(),
+                version: self.version.clone(),
                 layout,
                 op,
                 is_variable: false,
@@ -659,24 +823,53 @@ impl Tensor {
         }
     }
 
-    fn sum_impl<D: Dims>(&self, sum_dims: D, keepdim: bool) -> Result<Self> {
-        let sum_dims = sum_dims.to_indexes(self.shape(), "sum")?;
-        let storage = self
-            .storage()
-            .reduce_op(ReduceOp::Sum, self.layout(), &sum_dims)?;
+    fn reduce_dims_impl<D: Dims>(
+        &self,
+        reduce_dims: D,
+        keepdim: bool,
+        op: ReduceOp,
+    ) -> Result<Self> {
+        let reduce_dims = reduce_dims.to_indexes(self.shape(), op.name())?;
+        let storage = self.storage().reduce_op(op, self.layout(), &reduce_dims)?;
         let mut dims = self.dims().to_vec();
-        for &sum_dim in sum_dims.iter() {
-            dims[sum_dim] = 1
+        for &reduce_dim in reduce_dims.iter() {
+            dims[reduce_dim] = 1
         }
-        let op = BackpropOp::new1(self, |a| Op::Reduce(a, ReduceOp::Sum, dims.to_vec()));
-        let sum = from_storage(storage, dims, op, false);
+        let op = BackpropOp::new1(self, |a| Op::Reduce(a, op, dims.to_vec()));
+        let res = from_storage(storage, dims, op, false);
         if keepdim {
-            Ok(sum)
+            Ok(res)
         } else {
-            sum.squeeze_dims(&sum_dims)
+            res.squeeze_dims(&reduce_dims)
         }
     }
 
+    // The number of elements that get reduced together when reducing over `dims`.
+    fn reduced_elem_count(&self, dims: &[usize]) -> usize {
+        dims.iter().map(|&d| self.dims()[d]).product()
+    }
+
+    // The dtype used to compute averages: integers are rejected as the division would truncate
+    // and half precision values are accumulated in f32 so that large reductions keep their
+    // precision.
+    fn averaging_dtype(&self, op: &'static str) -> Result<DType> {
+        match self.dtype() {
+            DType::F16 | DType::BF16 => Ok(DType::F32),
+            dtype @ (DType::F32 | DType::F64) => Ok(dtype),
+            dtype => Err(Error::UnsupportedDTypeForOp(dtype, op).bt()),
+        }
+    }
+
+    // Applies `f` to the non-zero values of `self` and returns zero elsewhere. The zeros are
+    // replaced before calling `f` so that they get a zero gradient rather than a NaN one, e.g. for
+    // `sqrt` or `log`.
+    fn map_non_zero<F: Fn(&Self) -> Result<Self>>(&self, f: F) -> Result<Self> {
+        let zeros = self.zeros_like()?;
+        let non_zero = self.ne(&zeros)?;
+        let safe = non_zero.where_cond(self, &self.ones_like()?)?;
+        non_zero.where_cond(&f(&safe)?, &zeros)
+    }
+
     /// Returns the sum of all elements in the input tensor. The sum is performed over all the
     /// input dimensions.
     ///
@@ -695,14 +888,232 @@ impl Tensor {
     /// # Ok::<(), candle_core::Error>(())
     /// ```
     pub fn sum_keepdim<D: Dims>(&self, sum_dims: D) -> Result<Self> {
-        self.sum_impl(sum_dims, true)
+        self.reduce_dims_impl(sum_dims, true, ReduceOp::Sum)
     }
 
     /// Returns the sum of all elements in the input tensor. The sum is performed over all the
     /// input dimensions and compared to `sum_keepdim` these dimensions are squeezed rather than
     /// kept.
     pub fn sum<D: Dims>(&self, sum_dims: D) -> Result<Self> {
-        self.sum_impl(sum_dims, false)
+        self.reduce_dims_impl(sum_dims, false, ReduceOp::Sum)
+    }
+
+    /// Returns the product of all elements in the input tensor over the `prod_dims` dimensions.
+    /// These dimensions are kept with a size of 1.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[1f32, 2.], [3., 4.]], &Device::Cpu)?;
+    /// let p = a.prod_keepdim(1)?;
+    /// assert_eq!(p.to_vec2::<f32>()?, &[[2.], [12.]]);
+    /// let p = a.prod_keepdim((0, 1))?;
+    /// assert_eq!(p.to_vec2::<f32>()?, &[[24.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn prod_keepdim<D: Dims>(&self, prod_dims: D) -> Result<Self> {
+        self.reduce_dims_impl(prod_dims, true, ReduceOp::Prod)
+    }
+
+    /// Similar to `prod_keepdim` but the reduced dimensions are squeezed.
+    pub fn prod<D: Dims>(&self, prod_dims: D) -> Result<Self> {
+        self.reduce_dims_impl(prod_dims, false, ReduceOp::Prod)
+    }
+
+    /// Returns the mean of the elements over the `mean_dims` dimensions, these dimensions are
+    /// kept with a size of 1. Only float dtypes are supported, half precision values are
+    /// accumulated in f32.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[0f32, 1.], [2., 3.]], &Device::Cpu)?;
+    /// let m = a.mean_keepdim(0)?;
+    /// assert_eq!(m.to_vec2::<f32>()?, &[[1., 2.]]);
+    /// let m = a.mean_keepdim((0, 1))?;
+    /// assert_eq!(m.to_vec2::<f32>()?, &[[1.5]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn mean_keepdim<D: Dims>(&self, mean_dims: D) -> Result<Self> {
+        let mean_dims = mean_dims.to_indexes(self.shape(), "mean")?;
+        let n = self.reduced_elem_count(&mean_dims);
+        let dtype = self.averaging_dtype("mean")?;
+        self.to_dtype(dtype)?
+            .sum_keepdim(mean_dims)?
+            .affine(1f64 / n as f64, 0.)?
+            .to_dtype(self.dtype())
+    }
+
+    /// Similar to `mean_keepdim` but the reduced dimensions are squeezed.
+    pub fn mean<D: Dims>(&self, mean_dims: D) -> Result<Self> {
+        let mean_dims = mean_dims.to_indexes(self.shape(), "mean")?;
+        self.mean_keepdim(mean_dims.as_slice())?
+            .squeeze_dims(&mean_dims)
+    }
+
+    /// Returns the variance of the elements over the `var_dims` dimensions, these dimensions are
+    /// kept with a size of 1. When `unbiased` is true, the sum of squared deviations is divided
+    /// by `n - 1` rather than `n`.
+    ///
+    /// The mean is subtracted before squaring so that large offsets do not cancel out the
+    /// deviations.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[1f32, 3.], [5., 9.]], &Device::Cpu)?;
+    /// let v = a.var_keepdim(1, false)?;
+    /// assert_eq!(v.to_vec2::<f32>()?, &[[1.], [4.]]);
+    /// let v = a.var_keepdim(1, true)?;
+    /// assert_eq!(v.to_vec2::<f32>()?, &[[2.], [8.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn var_keepdim<D: Dims>(&self, var_dims: D, unbiased: bool) -> Result<Self> {
+        let var_dims = var_dims.to_indexes(self.shape(), "var")?;
+        let n = self.reduced_elem_count(&var_dims);
+        let n = if unbiased { n.saturating_sub(1) } else { n };
+        let dtype = self.averaging_dtype("var")?;
+        let xs = self.to_dtype(dtype)?;
+        let mean = xs.mean_keepdim(var_dims.as_slice())?;
+        let squares = xs.broadcast_sub(&mean)?.sqr()?;
+        squares
+            .sum_keepdim(var_dims)?
+            .affine(1f64 / n as f64, 0.)?
+            .to_dtype(self.dtype())
+    }
+
+    /// Similar to `var_keepdim` but the reduced dimensions are squeezed.
+    pub fn var<D: Dims>(&self, var_dims: D, unbiased: bool) -> Result<Self> {
+        let var_dims = var_dims.to_indexes(self.shape(), "var")?;
+        self.var_keepdim(var_dims.as_slice(), unbiased)?
+            .squeeze_dims(&var_dims)
+    }
+
+    /// Returns the standard deviation of the elements over the `std_dims` dimensions, see
+    /// `var_keepdim` for the meaning of `unbiased`.
+    pub fn std_keepdim<D: Dims>(&self, std_dims: D, unbiased: bool) -> Result<Self> {
+        self.var_keepdim(std_dims, unbiased)?.sqrt()
+    }
+
+    /// Similar to `std_keepdim` but the reduced dimensions are squeezed.
+    pub fn std<D: Dims>(&self, std_dims: D, unbiased: bool) -> Result<Self> {
+        self.var(std_dims, unbiased)?.sqrt()
+    }
+
+    /// Returns `log(sum(exp(x)))` over the `dims` dimensions, these dimensions are kept with a
+    /// size of 1.
+    ///
+    /// The maximum value is subtracted before exponentiating so that large inputs do not
+    /// overflow.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[1000f32, 1000.], [0., f32::NEG_INFINITY]], &Device::Cpu)?;
+    /// let l = a.logsumexp_keepdim(1)?;
+    /// let l: Vec<f32> = l.flatten_all()?.to_vec1()?;
+    /// assert!((l[0] - 1000.6931).abs() < 1e-3);
+    /// assert_eq!(l[1], 0.);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn logsumexp_keepdim<D: Dims>(&self, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "logsumexp")?;
+        let mut max = self.detach()?;
+        for &dim in dims.iter() {
+            max = max.max_keepdim(dim)?;
+        }
+        // Rows where the maximum is infinite are not shifted, this avoids computing `inf - inf`.
+        let finite = max
+            .abs()?
+            .lt(&max.ones_like()?.affine(f64::INFINITY, 0.)?)?;
+        let max = finite.where_cond(&max, &max.zeros_like()?)?;
+        let sum = self.broadcast_sub(&max)?.exp()?.sum_keepdim(dims)?;
+        sum.log()?.broadcast_add(&max)
+    }
+
+    /// Similar to `logsumexp_keepdim` but the reduced dimensions are squeezed.
+    pub fn logsumexp<D: Dims>(&self, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "logsumexp")?;
+        self.logsumexp_keepdim(dims.as_slice())?.squeeze_dims(&dims)
+    }
+
+    /// Returns the p-norm `sum(|x|^p)^(1/p)` over the `dims` dimensions, these dimensions are
+    /// kept with a size of 1. `p` has to be strictly positive, `f64::INFINITY` returns the
+    /// maximum absolute value.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[3f32, -4.], [-1., 1.]], &Device::Cpu)?;
+    /// let n = a.norm_keepdim(2., 1)?;
+    /// assert_eq!(n.to_vec2::<f32>()?, &[[5.], [2f32.sqrt()]]);
+    /// let n = a.norm_keepdim(1., 1)?;
+    /// assert_eq!(n.to_vec2::<f32>()?, &[[7.], [2.]]);
+    /// let n = a.norm_keepdim(f64::INFINITY, (0, 1))?;
+    /// assert_eq!(n.to_vec2::<f32>()?, &[[4.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn norm_keepdim<D: Dims>(&self, p: f64, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "norm")?;
+        if p.is_nan() || p <= 0. {
+            crate::bail!("norm: p has to be strictly positive, got {p}")
+        }
+        let abs = self.abs()?;
+        if p == f64::INFINITY {
+            let mut max = abs;
+            for &dim in dims.iter() {
+                max = max.max_keepdim(dim)?;
+            }
+            Ok(max)
+        } else if p == 1. {
+            abs.sum_keepdim(dims)
+        } else if p == 2. {
+            // As in PyTorch, the gradient is zero rather than NaN where the norm is zero.
+            self.sqr()?.sum_keepdim(dims)?.map_non_zero(|s| s.sqrt())
+        } else {
+            let pow = abs.map_non_zero(|a| a.log()?.affine(p, 0.)?.exp())?;
+            pow.sum_keepdim(dims)?
+                .map_non_zero(|s| s.log()?.affine(1. / p, 0.)?.exp())
+        }
+    }
+
+    /// Similar to `norm_keepdim` but the reduced dimensions are squeezed.
+    pub fn norm<D: Dims>(&self, p: f64, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "norm")?;
+        self.norm_keepdim(p, dims.as_slice())?.squeeze_dims(&dims)
+    }
+
+    /// Returns a boolean tensor that is true where all the elements over the `dims` dimensions
+    /// are non-zero, these dimensions are kept with a size of 1.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::new(&[[1u32, 2], [0, 3]], &Device::Cpu)?;
+    /// let all = a.all_keepdim(1)?;
+    /// assert_eq!(all.to_vec2::<u8>()?, &[[1], [0]]);
+    /// let any = a.any_keepdim(0)?;
+    /// assert_eq!(any.to_vec2::<u8>()?, &[[1, 1]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn all_keepdim<D: Dims>(&self, dims: D) -> Result<Self> {
+        let non_zero = self.ne(&self.zeros_like()?)?.to_dtype(DType::U8)?;
+        let all_non_zero = non_zero.prod_keepdim(dims)?;
+        all_non_zero.ne(&all_non_zero.zeros_like()?)
+    }
+
+    /// Similar to `all_keepdim` but the reduced dimensions are squeezed.
+    pub fn all<D: Dims>(&self, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "all")?;
+        self.all_keepdim(dims.as_slice())?.squeeze_dims(&dims)
+    }
+
+    /// Returns a boolean tensor that is true where any of the elements over the `dims`
+    /// dimensions is non-zero, these dimensions are kept with a size of 1.
+    pub fn any_keepdim<D: Dims>(&self, dims: D) -> Result<Self> {
+        let zero = self.eq(&self.zeros_like()?)?.to_dtype(DType::U8)?;
+        let all_zero = zero.prod_keepdim(dims)?;
+        all_zero.eq(&all_zero.zeros_like()?)
+    }
+
+    /// Similar to `any_keepdim` but the reduced dimensions are squeezed.
+    pub fn any<D: Dims>(&self, dims: D) -> Result<Self> {
+        let dims = dims.to_indexes(self.shape(), "any")?;
+        self.any_keepdim(dims.as_slice())?.squeeze_dims(&dims)
     }
 
     pub fn max_keepdim<D: Dim>(&self, dim: D) -> Result<Self> {
@@ -737,6 +1148,134 @@ impl Tensor {
         self.reduce_impl(dim, false, ReduceOp::ArgMin)
     }
 
+    fn scan_impl<D: Dim>(&self, dim: D, op: ScanOp) -> Result<Self> {
+        let dim = dim.to_index(self.shape(), op.name())?;
+        let storage = self.storage().scan(op, self.layout(), dim)?;
+        let op = match op {
+            ScanOp::ArgMax => BackpropOp::none(),
+            ScanOp::Sum | ScanOp::Prod | ScanOp::LogSumExp => {
+                BackpropOp::new1(self, |arg| Op::Scan(arg, op, dim))
+            }
+        };
+        Ok(from_storage(storage, self.shape(), op, false))
+    }
+
+    /// The cumulative sum along dimension `dim`, the `i`-th element along this dimension is the
+    /// sum of the elements `0..=i` of the input.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[1f32, 2., 3.], [4., 5., 6.]], &Device::Cpu)?;
+    /// assert_eq!(t.cumsum(1)?.to_vec2::<f32>()?, &[[1., 3., 6.], [4., 9., 15.]]);
+    /// assert_eq!(t.cumsum(0)?.to_vec2::<f32>()?, &[[1., 2., 3.], [5., 7., 9.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn cumsum<D: Dim>(&self, dim: D) -> Result<Self> {
+        self.scan_impl(dim, ScanOp::Sum)
+    }
+
+    /// The cumulative product along dimension `dim`.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[1f32, 2., 3., 4.], &Device::Cpu)?;
+    /// assert_eq!(t.cumprod(0)?.to_vec1::<f32>()?, &[1., 2., 6., 24.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn cumprod<D: Dim>(&self, dim: D) -> Result<Self> {
+        self.scan_impl(dim, ScanOp::Prod)
+    }
+
+    /// The cumulative maximum along dimension `dim`, returns the values and the `u32` indexes
+    /// where these values are found in the input. The gradients flow through the values.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[1f32, 3., 2., 5., 4.], &Device::Cpu)?;
+    /// let (values, indexes) = t.cummax(0)?;
+    /// assert_eq!(values.to_vec1::<f32>()?, &[1., 3., 3., 5., 5.]);
+    /// assert_eq!(indexes.to_vec1::<u32>()?, &[0, 1, 1, 3, 3]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn cummax<D: Dim>(&self, dim: D) -> Result<(Self, Self)> {
+        let dim = dim.to_index(self.shape(), "cummax")?;
+        let indexes = self.scan_impl(dim, ScanOp::ArgMax)?;
+        let values = self.contiguous()?.gather(&indexes, dim)?;
+        Ok((values, indexes))
+    }
+
+    /// The logarithm of the cumulative sum of the exponentials along dimension `dim`, this is
+    /// computed in a numerically stable way.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[0f32, 0., 1000.], &Device::Cpu)?;
+    /// let t = t.logcumsumexp(0)?.to_vec1::<f32>()?;
+    /// assert_eq!(t, &[0., 2f32.ln(), 1000.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn logcumsumexp<D: Dim>(&self, dim: D) -> Result<Self> {
+        self.scan_impl(dim, ScanOp::LogSumExp)
+    }
+
+    /// Returns the indexes that sort the tensor along dimension `dim`, in ascending order if
+    /// `asc` is true and in descending order otherwise. The indexes use the `u32` dtype and the
+    /// sort is stable. NaN values are considered larger than any other value.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[3f32, 1., 2.], [0., 5., 4.]], &Device::Cpu)?;
+    /// assert_eq!(t.argsort(1, true)?.to_vec2::<u32>()?, &[[1, 2, 0], [0, 2, 1]]);
+    /// assert_eq!(t.argsort(0, false)?.to_vec2::<u32>()?, &[[0, 1, 1], [1, 0, 0]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn argsort<D: Dim>(&self, dim: D, asc: bool) -> Result<Self> {
+        let dim = dim.to_index(self.shape(), "argsort")?;
+        let storage = self.storage().arg_sort(self.layout(), dim, asc)?;
+        Ok(from_storage(
+            storage,
+            self.shape(),
+            BackpropOp::none(),
+            false,
+        ))
+    }
+
+    /// Sorts the tensor along dimension `dim`, returns the sorted values and the indexes that
+    /// sort the tensor as with `argsort`. The gradients flow through the values.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[3f32, 1., 2.], &Device::Cpu)?;
+    /// let (values, indexes) = t.sort(0, false)?;
+    /// assert_eq!(values.to_vec1::<f32>()?, &[3., 2., 1.]);
+    /// assert_eq!(indexes.to_vec1::<u32>()?, &[0, 2, 1]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn sort<D: Dim>(&self, dim: D, asc: bool) -> Result<(Self, Self)> {
+        let dim = dim.to_index(self.shape(), "sort")?;
+        let indexes = self.argsort(dim, asc)?;
+        let values = self.contiguous()?.gather(&indexes, dim)?;
+        Ok((values, indexes))
+    }
+
+    /// Returns the `k` largest values along dimension `dim` in descending order, together with
+    /// their indexes. The gradients flow through the values.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[3f32, 1., 2.], [0., 5., 4.]], &Device::Cpu)?;
+    /// let (values, indexes) = t.topk(2, 1)?;
+    /// assert_eq!(values.to_vec2::<f32>()?, &[[3., 2.], [5., 4.]]);
+    /// assert_eq!(indexes.to_vec2::<u32>()?, &[[0, 2], [1, 2]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn topk<D: Dim>(&self, k: usize, dim: D) -> Result<(Self, Self)> {
+        let dim = dim.to_index(self.shape(), "topk")?;
+        let indexes = self.argsort(dim, false)?.narrow(dim, 0, k)?.contiguous()?;
+        let values = self.contiguous()?.gather(&indexes, dim)?;
+        Ok((values, indexes))
+    }
+
     pub fn cmp(&self, rhs: &Self, op: CmpOp) -> Result<Self> {
         let shape = self.same_shape_binary_op(rhs, "cmp")?;
         let storage = self
@@ -762,57 +1301,386 @@ impl Tensor {
         self.cmp(rhs, CmpOp::Gt)
     }
 
-    pub fn ge(&self, rhs: &Self) -> Result<Self> {
-        self.cmp(rhs, CmpOp::Ge)
+    pub fn ge(&self, rhs: &Self) -> Result<Self> {
+        self.cmp(rhs, CmpOp::Ge)
+    }
+
+    pub fn le(&self, rhs: &Self) -> Result<Self> {
+        self.cmp(rhs, CmpOp::Le)
+    }
+
+    /// Applies a 1D convolution over the input tensor.
+    ///
+    /// The input has shape `(b_size, c_in, l_in)` and the kernel `(c_out, c_in / groups, k_size)`.
+    /// The input is padded with `padding` zeros on both sides, `dilation` is the spacing between
+    /// the kernel elements and `groups` splits the input and output channels in groups that are
+    /// convolved independently, e.g. `groups = c_in` results in a depthwise convolution.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[[1f32, 2., 3., 4.], [5., 6., 7., 8.]]], &Device::Cpu)?;
+    /// let k = Tensor::new(&[[[1f32, 1.]], [[1., -1.]]], &Device::Cpu)?;
+    /// let res = t.conv1d(&k, 0, 1, 2, 2)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[4., 6.], [-2., -2.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn conv1d(
+        &self,
+        kernel: &Self,
+        padding: usize,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (c_out, c_in_k, k_size) = kernel.dims3()?;
+        let (b_size, c_in, l_in) = self.dims3()?;
+        let err = |msg| {
+            Err(Error::Conv1dInvalidArgs {
+                inp_shape: self.shape().clone(),
+                k_shape: kernel.shape().clone(),
+                padding,
+                stride,
+                msg,
+            }
+            .bt())
+        };
+        if stride == 0 || dilation == 0 || groups == 0 {
+            err("stride, dilation and groups have to be strictly positive")?
+        }
+        if c_in != c_in_k * groups {
+            err("the number of in-channels on the input doesn't match the kernel size")?
+        }
+        if c_out % groups != 0 {
+            err("the number of out-channels is not divisible by the number of groups")?
+        }
+        if l_in + 2 * padding < dilation * (k_size - 1) + 1 {
+            err("the padded input is smaller than the dilated kernel")?
+        }
+        let params = crate::conv::ParamsConv1D {
+            b_size,
+            l_in,
+            c_out,
+            c_in,
+            k_size,
+            padding,
+            stride,
+            dilation,
+            groups,
+        };
+        let storage =
+            self.storage()
+                .conv1d(self.layout(), &kernel.storage(), kernel.layout(), &params)?;
+        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::Conv1D {
+            arg,
+            kernel,
+            padding,
+            stride,
+            dilation,
+            groups,
+        });
+        let out_dims = params.out_dims();
+        Ok(from_storage(storage, out_dims, op, false))
+    }
+
+    /// Applies a 2D convolution over the input tensor.
+    ///
+    /// The input has shape `(b_size, c_in, h, w)` and the kernel `(c_out, c_in / groups, k_h,
+    /// k_w)`, `padding`, `stride`, `dilation` and `groups` behave as in [`Tensor::conv1d`] and
+    /// apply to both spatial dimensions.
+    pub fn conv2d(
+        &self,
+        kernel: &Self,
+        padding: usize,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (b_size, c_in, i_h, i_w) = self.dims4()?;
+        let (c_out, c_in_k, k_h, k_w) = kernel.dims4()?;
+        if stride == 0 || dilation == 0 || groups == 0 {
+            crate::bail!("conv2d: stride ({stride}), dilation ({dilation}) and groups ({groups}) have to be strictly positive")
+        }
+        if c_in != c_in_k * groups {
+            crate::bail!(
+                "in_channel mismatch between input ({c_in}) and kernel ({c_in_k}) with {groups} groups"
+            )
+        }
+        if c_out % groups != 0 {
+            crate::bail!(
+                "out_channel ({c_out}) is not divisible by the number of groups ({groups})"
+            )
+        }
+        if i_h + 2 * padding < dilation * (k_h - 1) + 1
+            || i_w + 2 * padding < dilation * (k_w - 1) + 1
+        {
+            crate::bail!("conv2d: the padded input ({i_h}, {i_w}) is smaller than the dilated kernel ({k_h}, {k_w})")
+        }
+        let params = crate::conv::ParamsConv2D {
+            b_size,
+            i_h,
+            i_w,
+            k_h,
+            k_w,
+            c_out,
+            c_in,
+            padding,
+            stride,
+            dilation,
+            groups,
+        };
+        let storage =
+            self.storage()
+                .conv2d(self.layout(), &kernel.storage(), kernel.layout(), &params)?;
+        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::Conv2D {
+            arg,
+            kernel,
+            padding,
+            stride,
+            dilation,
+            groups,
+        });
+        let out_dims = params.out_dims();
+        Ok(from_storage(storage, out_dims, op, false))
+    }
+
+    /// Applies a 3D convolution over the input tensor.
+    ///
+    /// The input has shape `(b_size, c_in, d, h, w)` and the kernel `(c_out, c_in / groups, k_d,
+    /// k_h, k_w)`, `padding`, `stride`, `dilation` and `groups` behave as in [`Tensor::conv1d`]
+    /// and apply to the three spatial dimensions.
+    pub fn conv3d(
+        &self,
+        kernel: &Self,
+        padding: usize,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (b_size, c_in, i_d, i_h, i_w) = self.dims5()?;
+        let (c_out, c_in_k, k_d, k_h, k_w) = kernel.dims5()?;
+        if stride == 0 || dilation == 0 || groups == 0 {
+            crate::bail!("conv3d: stride ({stride}), dilation ({dilation}) and groups ({groups}) have to be strictly positive")
+        }
+        if c_in != c_in_k * groups {
+            crate::bail!(
+                "in_channel mismatch between input ({c_in}) and kernel ({c_in_k}) with {groups} groups"
+            )
+        }
+        if c_out % groups != 0 {
+            crate::bail!(
+                "out_channel ({c_out}) is not divisible by the number of groups ({groups})"
+            )
+        }
+        let too_small = |i: usize, k: usize| i + 2 * padding < dilation * (k - 1) + 1;
+        if too_small(i_d, k_d) || too_small(i_h, k_h) || too_small(i_w, k_w) {
+            crate::bail!("conv3d: the padded input ({i_d}, {i_h}, {i_w}) is smaller than the dilated kernel ({k_d}, {k_h}, {k_w})")
+        }
+        let params = crate::conv::ParamsConv3D {
+            b_size,
+            i_d,
+            i_h,
+            i_w,
+            k_d,
+            k_h,
+            k_w,
+            c_out,
+            c_in,
+            padding,
+            stride,
+            dilation,
+            groups,
+        };
+        let storage =
+            self.storage()
+                .conv3d(self.layout(), &kernel.storage(), kernel.layout(), &params)?;
+        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::Conv3D {
+            arg,
+            kernel,
+            padding,
+            stride,
+            dilation,
+            groups,
+        });
+        let out_dims = params.out_dims();
+        Ok(from_storage(storage, out_dims, op, false))
+    }
+
+    // Pads the spatial dimensions of the input, starting from dimension 2, for a convolution with
+    // the given kernel sizes. The convolution kernels only support the same symmetric padding on
+    // all dimensions so the rest is added explicitly, this symmetric padding is returned.
+    fn pad_for_conv(
+        &self,
+        k_sizes: &[usize],
+        padding: crate::ConvPadding,
+        stride: usize,
+        dilation: usize,
+    ) -> Result<(Self, usize)> {
+        let pads = k_sizes
+            .iter()
+            .map(|&k_size| padding.start_end(k_size, stride, dilation))
+            .collect::<Result<Vec<_>>>()?;
+        let symmetric = if pads.iter().all(|&p| p == pads[0]) {
+            usize::min(pads[0].0, pads[0].1)
+        } else {
+            0
+        };
+        let mut xs = self.clone();
+        for (i, &(start, end)) in pads.iter().enumerate() {
+            if start > symmetric || end > symmetric {
+                xs = xs.pad_with_zeros(i + 2, start - symmetric, end - symmetric)?
+            }
+        }
+        Ok((xs, symmetric))
+    }
+
+    /// Same as [`Tensor::conv1d`] but the padding can be asymmetric or computed so that the
+    /// output has the same length as the input, see [`crate::ConvPadding`].
+    ///
+    /// ```rust
+    /// use candle_core::{ConvPadding, Tensor, Device};
+    /// let t = Tensor::new(&[[[1f32, 2., 3., 4.]]], &Device::Cpu)?;
+    /// let k = Tensor::new(&[[[1f32, 1.]]], &Device::Cpu)?;
+    /// let res = t.conv1d_padded(&k, ConvPadding::Same, 1, 1, 1)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[3., 5., 7., 4.]]]);
+    /// let padding = ConvPadding::Asymmetric { start: 2, end: 0 };
+    /// let res = t.conv1d_padded(&k, padding, 1, 1, 1)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[0., 1., 3., 5., 7.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn conv1d_padded(
+        &self,
+        kernel: &Self,
+        padding: crate::ConvPadding,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (_, _, k_size) = kernel.dims3()?;
+        let (xs, padding) = self.pad_for_conv(&[k_size], padding, stride, dilation)?;
+        xs.conv1d(kernel, padding, stride, dilation, groups)
+    }
+
+    /// Same as [`Tensor::conv2d`] but the padding can be asymmetric or computed so that the
+    /// output has the same size as the input, see [`crate::ConvPadding`].
+    pub fn conv2d_padded(
+        &self,
+        kernel: &Self,
+        padding: crate::ConvPadding,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (_, _, k_h, k_w) = kernel.dims4()?;
+        let (xs, padding) = self.pad_for_conv(&[k_h, k_w], padding, stride, dilation)?;
+        xs.conv2d(kernel, padding, stride, dilation, groups)
     }
 
-    pub fn le(&self, rhs: &Self) -> Result<Self> {
-        self.cmp(rhs, CmpOp::Le)
+    /// Same as [`Tensor::conv3d`] but the padding can be asymmetric or computed so that the
+    /// output has the same size as the input, see [`crate::ConvPadding`].
+    pub fn conv3d_padded(
+        &self,
+        kernel: &Self,
+        padding: crate::ConvPadding,
+        stride: usize,
+        dilation: usize,
+        groups: usize,
+    ) -> Result<Self> {
+        let (_, _, k_d, k_h, k_w) = kernel.dims5()?;
+        let (xs, padding) = self.pad_for_conv(&[k_d, k_h, k_w], padding, stride, dilation)?;
+        xs.conv3d(kernel, padding, stride, dilation, groups)
     }
 
-    /// Applies a 1D convolution over the input tensor.
-    pub fn conv1d(&self, kernel: &Self, padding: usize, stride: usize) -> Result<Self> {
-        let (c_out, c_in_k, k_size) = kernel.dims3()?;
+    /// Applies a 1D transposed convolution over the input tensor, this is the gradient of
+    /// [`Tensor::conv1d`] with respect to its input.
+    ///
+    /// The input has shape `(b_size, c_in, l_in)` and the kernel `(c_in, c_out, k_size)`, the
+    /// output length is `(l_in - 1) * stride - 2 * padding + dilation * (k_size - 1) +
+    /// output_padding + 1`.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[[1f32, 2., 3.]]], &Device::Cpu)?;
+    /// let k = Tensor::new(&[[[1f32, 10.]]], &Device::Cpu)?;
+    /// let res = t.conv_transpose1d(&k, 0, 0, 2, 1)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[1., 10., 2., 20., 3., 30.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn conv_transpose1d(
+        &self,
+        kernel: &Self,
+        padding: usize,
+        output_padding: usize,
+        stride: usize,
+        dilation: usize,
+    ) -> Result<Self> {
         let (b_size, c_in, l_in) = self.dims3()?;
+        let (c_in_k, c_out, k_size) = kernel.dims3()?;
         if c_in != c_in_k {
-            Err(Error::Conv1dInvalidArgs {
-                inp_shape: self.shape().clone(),
-                k_shape: kernel.shape().clone(),
-                padding,
-                stride,
-                msg: "the number of in-channels on the input doesn't match the kernel size",
-            }
-            .bt())?
+            crate::bail!("in_channel mismatch between input ({c_in}) and kernel ({c_in_k})")
         }
-        let params = crate::conv::ParamsConv1D {
+        if stride == 0 || dilation == 0 {
+            crate::bail!("conv_transpose1d: stride ({stride}) and dilation ({dilation}) have to be strictly positive")
+        }
+        if output_padding >= stride && output_padding >= dilation {
+            crate::bail!("conv_transpose1d: output_padding ({output_padding}) has to be smaller than the stride ({stride}) or the dilation ({dilation})")
+        }
+        let params = crate::conv::ParamsConvTranspose1D {
             b_size,
             l_in,
             c_out,
             c_in,
             k_size,
             padding,
+            output_padding,
             stride,
+            dilation,
         };
-        let storage =
-            self.storage()
-                .conv1d(self.layout(), &kernel.storage(), kernel.layout(), &params)?;
-        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::Conv1D {
+        if (l_in - 1) * stride + dilation * (k_size - 1) + output_padding < 2 * padding {
+            crate::bail!("conv_transpose1d: the padding ({padding}) is too large for the input {:?} and kernel {:?}", self.shape(), kernel.shape())
+        }
+        let storage = self.storage().conv_transpose1d(
+            self.layout(),
+            &kernel.storage(),
+            kernel.layout(),
+            &params,
+        )?;
+        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::ConvTranspose1D {
             arg,
             kernel,
             padding,
+            output_padding,
             stride,
+            dilation,
         });
         let out_dims = params.out_dims();
         Ok(from_storage(storage, out_dims, op, false))
     }
 
-    pub fn conv2d(&self, kernel: &Self, padding: usize, stride: usize) -> Result<Self> {
+    /// Applies a 2D transposed convolution over the input tensor, this is the gradient of
+    /// [`Tensor::conv2d`] with respect to its input.
+    ///
+    /// The input has shape `(b_size, c_in, h, w)` and the kernel `(c_in, c_out, k_h, k_w)`, the
+    /// parameters behave as in [`Tensor::conv_transpose1d`] and apply to both spatial dimensions.
+    pub fn conv_transpose2d(
+        &self,
+        kernel: &Self,
+        padding: usize,
+        output_padding: usize,
+        stride: usize,
+        dilation: usize,
+    ) -> Result<Self> {
         let (b_size, c_in, i_h, i_w) = self.dims4()?;
-        let (c_out, c_in_k, k_h, k_w) = kernel.dims4()?;
+        let (c_in_k, c_out, k_h, k_w) = kernel.dims4()?;
         if c_in != c_in_k {
             crate::bail!("in_channel mismatch between input ({c_in}) and kernel ({c_in_k})")
         }
-        let params = crate::conv::ParamsConv2D {
+        if stride == 0 || dilation == 0 {
+            crate::bail!("conv_transpose2d: stride ({stride}) and dilation ({dilation}) have to be strictly positive")
+        }
+        if output_padding >= stride && output_padding >= dilation {
+            crate::bail!("conv_transpose2d: output_padding ({output_padding}) has to be smaller than the stride ({stride}) or the dilation ({dilation})")
+        }
+        let params = crate::conv::ParamsConvTranspose2D {
             b_size,
             i_h,
             i_w,
@@ -821,16 +1689,28 @@ impl Tensor {
             c_out,
             c_in,
             padding,
+            output_padding,
             stride,
+            dilation,
         };
-        let storage =
-            self.storage()
-                .conv2d(self.layout(), &kernel.storage(), kernel.layout(), &params)?;
-        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::Conv2D {
+        let min_size =
+            |i: usize, k: usize| (i - 1) * stride + dilation * (k - 1) + output_padding + 1;
+        if min_size(i_h, k_h) < 2 * padding || min_size(i_w, k_w) < 2 * padding {
+            crate::bail!("conv_transpose2d: the padding ({padding}) is too large for the input {:?} and kernel {:?}", self.shape(), kernel.shape())
+        }
+        let storage = self.storage().conv_transpose2d(
+            self.layout(),
+            &kernel.storage(),
+            kernel.layout(),
+            &params,
+        )?;
+        let op = BackpropOp::new2(self, kernel, |arg, kernel| Op::ConvTranspose2D {
             arg,
             kernel,
             padding,
+            output_padding,
             stride,
+            dilation,
         });
         let out_dims = params.out_dims();
         Ok(from_storage(storage, out_dims, op, false))
@@ -845,36 +1725,218 @@ impl Tensor {
         Ok(from_storage(storage, (n, c, target_h, target_w), op, false))
     }
 
-    pub fn avg_pool2d(&self, kernel_size: (usize, usize), stride: (usize, usize)) -> Result<Self> {
+    /// Resizes the spatial dimensions of an input of shape `(b_size, c, h, w)` to `(target_h,
+    /// target_w)` using the given interpolation mode, the results match PyTorch's
+    /// `torch.nn.functional.interpolate`.
+    ///
+    /// ```rust
+    /// use candle_core::{Device, InterpolateMode, Tensor};
+    /// let t = Tensor::new(&[0f32, 1., 2., 3.], &Device::Cpu)?.reshape((1, 1, 2, 2))?;
+    /// let mode = InterpolateMode::Bilinear { align_corners: true };
+    /// let res = t.interpolate2d(3, 3, mode)?.flatten_all()?;
+    /// assert_eq!(res.to_vec1::<f32>()?, &[0., 0.5, 1., 1., 1.5, 2., 2., 2.5, 3.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn interpolate2d(
+        &self,
+        target_h: usize,
+        target_w: usize,
+        mode: crate::InterpolateMode,
+    ) -> Result<Self> {
+        if mode == crate::InterpolateMode::Nearest {
+            return self.upsample_nearest2d(target_h, target_w);
+        }
         let (n, c, h, w) = self.dims4()?;
-        // https://pytorch.org/docs/stable/generated/torch.nn.AvgPool2d.html#torch.nn.AvgPool2d
-        let h_out = (h - kernel_size.0) / stride.0 + 1;
-        let w_out = (w - kernel_size.1) / stride.1 + 1;
-        let op = BackpropOp::new1(self, |arg| Op::AvgPool2D {
-            arg,
-            kernel_size,
-            stride,
-        });
+        if h == 0 || w == 0 {
+            crate::bail!("interpolate2d: empty input spatial dims ({h}, {w})")
+        }
+        if target_h == 0 || target_w == 0 {
+            crate::bail!("interpolate2d: invalid target size ({target_h}, {target_w})")
+        }
         let storage = self
             .storage()
-            .avg_pool2d(self.layout(), kernel_size, stride)?;
-        Ok(from_storage(storage, (n, c, h_out, w_out), op, false))
+            .interpolate2d(self.layout(), target_h, target_w, mode)?;
+        let op = BackpropOp::new1(self, |arg| Op::Interpolate2D { arg, mode });
+        Ok(from_storage(storage, (n, c, target_h, target_w), op, false))
+    }
+
+    // Applies the fft op along its fixed dimension, see [`crate::fft::FftKind`].
+    pub(crate) fn fft_op(&self, kind: crate::fft::FftKind) -> Result<Self> {
+        let arg = self.contiguous()?;
+        let (_, dims) = kind.dims(arg.dims(), "fft")?;
+        let storage = arg.storage().fft(arg.layout(), kind)?;
+        let op = BackpropOp::new1(&arg, |arg| Op::Fft { arg, kind });
+        Ok(from_storage(storage, dims, op, false))
+    }
+
+    /// Scales the spatial dimensions of an input of shape `(b_size, c, h, w)` by arbitrary
+    /// factors, see [`Tensor::interpolate2d`]. The target sizes are rounded down and the
+    /// interpolation is based on the actual ratio between the input and output sizes, as with
+    /// `recompute_scale_factor=True` in PyTorch.
+    pub fn interpolate2d_with_scale(
+        &self,
+        scale_h: f64,
+        scale_w: f64,
+        mode: crate::InterpolateMode,
+    ) -> Result<Self> {
+        let (_, _, h, w) = self.dims4()?;
+        if !(scale_h > 0. && scale_w > 0.) {
+            crate::bail!("interpolate2d: invalid scale factors ({scale_h}, {scale_w})")
+        }
+        let target_h = (h as f64 * scale_h).floor() as usize;
+        let target_w = (w as f64 * scale_w).floor() as usize;
+        self.interpolate2d(target_h, target_w, mode)
+    }
+
+    /// Applies a 1D average pooling over the last dimension of an input of shape `(b_size, c,
+    /// l)`, see [`Tensor::avg_pool_nd`] for padding and ceil mode.
+    pub fn avg_pool1d(&self, kernel_size: usize, stride: usize) -> Result<Self> {
+        self.avg_pool_nd(&[kernel_size], &[stride], &[0], false)
+    }
+
+    /// Applies a 1D max pooling over the last dimension of an input of shape `(b_size, c, l)`,
+    /// see [`Tensor::max_pool_nd`] for padding and ceil mode.
+    pub fn max_pool1d(&self, kernel_size: usize, stride: usize) -> Result<Self> {
+        self.max_pool_nd(&[kernel_size], &[stride], &[0], false)
+    }
+
+    /// Applies a 2D average pooling over an input of shape `(b_size, c, h, w)`.
+    pub fn avg_pool2d(&self, kernel_size: (usize, usize), stride: (usize, usize)) -> Result<Self> {
+        let (k_h, k_w) = kernel_size;
+        let (s_h, s_w) = stride;
+        self.avg_pool_nd(&[k_h, k_w], &[s_h, s_w], &[0, 0], false)
     }
 
+    /// Applies a 2D max pooling over an input of shape `(b_size, c, h, w)`.
     pub fn max_pool2d(&self, kernel_size: (usize, usize), stride: (usize, usize)) -> Result<Self> {
-        let (n, c, h, w) = self.dims4()?;
-        // https://pytorch.org/docs/stable/generated/torch.nn.MaxPool2d.html#torch.nn.MaxPool2d
-        let h_out = (h - kernel_size.0) / stride.0 + 1;
-        let w_out = (w - kernel_size.1) / stride.1 + 1;
-        let op = BackpropOp::new1(self, |arg| Op::MaxPool2D {
+        let (k_h, k_w) = kernel_size;
+        let (s_h, s_w) = stride;
+        self.max_pool_nd(&[k_h, k_w], &[s_h, s_w], &[0, 0], false)
+    }
+
+    /// Applies a 3D average pooling over an input of shape `(b_size, c, d, h, w)`.
+    pub fn avg_pool3d(
+        &self,
+        kernel_size: (usize, usize, usize),
+        stride: (usize, usize, usize),
+    ) -> Result<Self> {
+        let (k_d, k_h, k_w) = kernel_size;
+        let (s_d, s_h, s_w) = stride;
+        self.avg_pool_nd(&[k_d, k_h, k_w], &[s_d, s_h, s_w], &[0, 0, 0], false)
+    }
+
+    /// Applies a 3D max pooling over an input of shape `(b_size, c, d, h, w)`.
+    pub fn max_pool3d(
+        &self,
+        kernel_size: (usize, usize, usize),
+        stride: (usize, usize, usize),
+    ) -> Result<Self> {
+        let (k_d, k_h, k_w) = kernel_size;
+        let (s_d, s_h, s_w) = stride;
+        self.max_pool_nd(&[k_d, k_h, k_w], &[s_d, s_h, s_w], &[0, 0, 0], false)
+    }
+
+    /// Applies an average pooling over the spatial dimensions of the input, i.e. all the
+    /// dimensions after the batch and channel ones. The number of spatial dimensions is the
+    /// length of `kernel_size`, `stride` and `padding`.
+    ///
+    /// The padding counts as zeros in the averages and can be at most half of the kernel size.
+    /// With `ceil_mode`, the output sizes are rounded up so that the last window can go past the
+    /// end of the input, these windows are averaged over the positions that they cover.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[[1f32, 2., 3., 4., 5.]]], &Device::Cpu)?;
+    /// let res = t.avg_pool_nd(&[2], &[2], &[0], false)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[1.5, 3.5]]]);
+    /// let res = t.avg_pool_nd(&[2], &[2], &[1], false)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[0.5, 2.5, 4.5]]]);
+    /// let res = t.avg_pool_nd(&[2], &[2], &[0], true)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[1.5, 3.5, 5.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn avg_pool_nd(
+        &self,
+        kernel_size: &[usize],
+        stride: &[usize],
+        padding: &[usize],
+        ceil_mode: bool,
+    ) -> Result<Self> {
+        let params =
+            crate::pool::ParamsPool::new(kernel_size, stride, padding, ceil_mode, "avg-pool")?;
+        let out_dims = params.out_dims(self.dims(), "avg-pool")?;
+        let storage = self.storage().avg_pool(self.layout(), &params)?;
+        let op = BackpropOp::new1(self, |arg| Op::AvgPool {
             arg,
-            kernel_size,
-            stride,
+            params: params.clone(),
         });
-        let storage = self
-            .storage()
-            .max_pool2d(self.layout(), kernel_size, stride)?;
-        Ok(from_storage(storage, (n, c, h_out, w_out), op, false))
+        Ok(from_storage(storage, out_dims, op, false))
+    }
+
+    /// Applies a max pooling over the spatial dimensions of the input, the arguments are the
+    /// same as for [`Tensor::avg_pool_nd`]. Padded positions are never selected.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[[-1f32, -2., -3., -4., -5.]]], &Device::Cpu)?;
+    /// let res = t.max_pool_nd(&[2], &[2], &[1], true)?;
+    /// assert_eq!(res.to_vec3::<f32>()?, &[[[-1., -2., -4.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn max_pool_nd(
+        &self,
+        kernel_size: &[usize],
+        stride: &[usize],
+        padding: &[usize],
+        ceil_mode: bool,
+    ) -> Result<Self> {
+        let params =
+            crate::pool::ParamsPool::new(kernel_size, stride, padding, ceil_mode, "max-pool")?;
+        let out_dims = params.out_dims(self.dims(), "max-pool")?;
+        let storage = self.storage().max_pool(self.layout(), &params)?;
+        let op = BackpropOp::new1(self, |arg| Op::MaxPool {
+            arg,
+            params: params.clone(),
+        });
+        Ok(from_storage(storage, out_dims, op, false))
+    }
+
+    /// Applies a 2D adaptive average pooling over an input of shape `(b_size, c, h, w)`, the
+    /// output has shape `(b_size, c, out_h, out_w)`.
+    ///
+    /// As in PyTorch, the window for output index `i` along a dimension of size `in_size` covers
+    /// the indexes from `floor(i * in_size / out_size)` up to `ceil((i + 1) * in_size /
+    /// out_size)` excluded, so windows may overlap and have different sizes.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::arange(1f32, 7., &Device::Cpu)?.reshape((1, 1, 2, 3))?;
+    /// let res = t.adaptive_avg_pool2d((1, 2))?;
+    /// assert_eq!(res.flatten_all()?.to_vec1::<f32>()?, &[3., 4.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn adaptive_avg_pool2d(&self, output_size: (usize, usize)) -> Result<Self> {
+        let (_, _, h, w) = self.dims4()?;
+        let (out_h, out_w) = output_size;
+        if out_h == 0 || out_w == 0 {
+            crate::bail!("adaptive-avg-pool2d: invalid output size {output_size:?}")
+        }
+        if h % out_h == 0 && w % out_w == 0 {
+            // All the windows have the same size and do not overlap.
+            let kernel_size = (h / out_h, w / out_w);
+            return self.avg_pool2d(kernel_size, kernel_size);
+        }
+        let pool = |t: &Self, dim: usize, in_size: usize, out_size: usize| {
+            let mut windows = Vec::with_capacity(out_size);
+            for i in 0..out_size {
+                let start = i * in_size / out_size;
+                let end = ((i + 1) * in_size).div_ceil(out_size);
+                windows.push(t.narrow(dim, start, end - start)?.mean_keepdim(dim)?)
+            }
+            Self::cat(&windows, dim)
+        };
+        let t = pool(self, 2, h, out_h)?;
+        pool(&t, 3, w, out_w)
     }
 
     /// Returns the matrix-multiplication of the input tensor with the other provided tensor.
@@ -884,46 +1946,90 @@ impl Tensor {
     /// * `self` - A tensor with dimensions `b1, b2, ..., bi, m, k`.
     /// * `rhs` - A tensor with dimensions `b1, b2, ..., bi, k, n`.
     ///
-    /// The resulting tensor has dimensions `b1, b2, ..., bi, m, n`.
+    /// The resulting tensor has dimensions `b1, b2, ..., bi, m, n`. The batch dimensions are
+    /// broadcasted using the same rules as the broadcasting binary ops, so a `(b, h, t, d)` tensor
+    /// can be multiplied by a `(d, e)` one. The broadcasted operands are passed to the backend with
+    /// a stride of 0 on the broadcasted dimensions and are not copied.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let a = Tensor::arange(0f32, 12., &Device::Cpu)?.reshape((2, 2, 3))?;
+    /// let w = Tensor::new(&[[1f32, 0.], [0., 1.], [1., 1.]], &Device::Cpu)?;
+    /// let c = a.matmul(&w)?;
+    /// assert_eq!(c.to_vec3::<f32>()?, &[[[2., 3.], [8., 9.]], [[14., 15.], [20., 21.]]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
     pub fn matmul(&self, rhs: &Self) -> Result<Self> {
         let a_dims = self.shape().dims();
         let b_dims = rhs.shape().dims();
 
-        let dim = a_dims.len();
-
-        if dim < 2 || b_dims.len() != dim {
-            Err(Error::ShapeMismatchBinaryOp {
+        let shape_mismatch = || {
+            Error::ShapeMismatchBinaryOp {
                 lhs: self.shape().clone(),
                 rhs: rhs.shape().clone(),
                 op: "matmul",
             }
-            .bt())?
+            .bt()
+        };
+        if a_dims.len() < 2 || b_dims.len() < 2 {
+            Err(shape_mismatch())?
+        }
+        let (a_batch, a_mk) = a_dims.split_at(a_dims.len() - 2);
+        let (b_batch, b_kn) = b_dims.split_at(b_dims.len() - 2);
+        let (m, k) = (a_mk[0], a_mk[1]);
+        let (k2, n) = (b_kn[0], b_kn[1]);
+        if k != k2 {
+            Err(shape_mismatch())?
         }
 
-        let m = a_dims[dim - 2];
-        let k = a_dims[dim - 1];
-        let k2 = b_dims[dim - 2];
-        let n = b_dims[dim - 1];
-
-        let c_shape = Shape::from(&a_dims[..dim - 2]).extend(&[m, n]);
-        let batching: usize = a_dims[..dim - 2].iter().product();
-        let batching_b: usize = b_dims[..dim - 2].iter().product();
-        if k != k2 || batching != batching_b {
-            Err(Error::ShapeMismatchBinaryOp {
-                lhs: self.shape().clone(),
-                rhs: rhs.shape().clone(),
-                op: "matmul",
-            }
-            .bt())?
+        let batch_rank = usize::max(a_batch.len(), b_batch.len());
+        let mut batch_dims = Vec::with_capacity(batch_rank + 2);
+        for idx in 0..batch_rank {
+            let get = |dims: &[usize]| {
+                let offset = batch_rank - dims.len();
+                if idx < offset {
+                    1
+                } else {
+                    dims[idx - offset]
+                }
+            };
+            let (a, b) = (get(a_batch), get(b_batch));
+            let dim = if a == b || b == 1 {
+                a
+            } else if a == 1 {
+                b
+            } else {
+                Err(shape_mismatch())?
+            };
+            batch_dims.push(dim)
         }
+        let batching: usize = batch_dims.iter().product();
+        // Broadcast the batch dims of both operands, the backends require the matrices of a batch
+        // to be evenly spaced which is the case for contiguous and fully broadcasted operands.
+        let broadcast = |t: &Self, rows: usize, cols: usize| -> Result<Self> {
+            let mut dims = batch_dims.clone();
+            dims.extend([rows, cols]);
+            let t = if t.dims() == dims {
+                t.clone()
+            } else {
+                t.broadcast_as(dims)?
+            };
+            match t.layout().matmul_batch_stride() {
+                Some(_) => Ok(t),
+                None => t.contiguous(),
+            }
+        };
+        let lhs = broadcast(self, m, k)?;
+        let rhs = broadcast(rhs, k, n)?;
+        let c_shape = Shape::from(batch_dims).extend(&[m, n]);
 
-        let storage = self.storage().matmul(
+        let storage = lhs.storage().matmul(
             &rhs.storage(),
             (batching, m, n, k),
-            self.layout(),
+            lhs.layout(),
             rhs.layout(),
         )?;
-        let op = BackpropOp::new2(self, rhs, Op::Matmul);
+        let op = BackpropOp::new2(&lhs, &rhs, Op::Matmul);
         Ok(from_storage(storage, c_shape, op, false))
     }
 
@@ -1067,6 +2173,66 @@ impl Tensor {
         Ok(from_storage(storage, self.shape(), op, false))
     }
 
+    /// Writes the slices of `source` along dimension `dim` in place to `self`, the slice `i` of
+    /// `source` is written at the position `indexes[i]` of `self`. This is the in place
+    /// counterpart of `index_add` where the values are replaced rather than accumulated.
+    /// Negative or out of range indexes result in an error and leave `self` unchanged.
+    pub fn index_put<D: Dim>(&self, indexes: &Self, source: &Self, dim: D) -> Result<()> {
+        let dim = dim.to_index(self.shape(), "index-put")?;
+        let source_dims = source.dims();
+        let self_dims = self.dims();
+        let mismatch = source_dims.len() != self_dims.len()
+            || self_dims
+                .iter()
+                .zip(source_dims.iter())
+                .enumerate()
+                .any(|(i, (&d1, &d2))| i != dim && d1 != d2);
+        if mismatch {
+            Err(Error::ShapeMismatchBinaryOp {
+                op: "index-put (self, source)",
+                lhs: self.shape().clone(),
+                rhs: source.shape().clone(),
+            })?
+        }
+        let indexes_len = indexes.dims1()?;
+        if source_dims[dim] != indexes_len {
+            Err(Error::ShapeMismatchBinaryOp {
+                op: "index-put (ids, source))",
+                lhs: indexes.shape().clone(),
+                rhs: source.shape().clone(),
+            })?
+        }
+        self.check_inplace("index-put")?;
+        if source.track_op() {
+            let msg = "the source is tracked by the computation graph";
+            Err(Error::InplaceNotSupported {
+                op: "index-put",
+                msg,
+            }
+            .bt())?
+        }
+        // The storage of self is locked for writing below so the arguments cannot share it.
+        let copy_if_shared = |t: &Self| {
+            if self.same_storage(t) {
+                t.copy()
+            } else {
+                Ok(t.clone())
+            }
+        };
+        let (indexes, source) = (copy_if_shared(indexes)?, copy_if_shared(source)?);
+        let (indexes_storage, indexes_layout) = indexes.storage_and_layout();
+        let (source_storage, source_layout) = source.storage_and_layout();
+        let (mut storage, layout) = self.storage_mut_and_layout();
+        storage.index_put(
+            layout,
+            &indexes_storage,
+            indexes_layout,
+            &source_storage,
+            source_layout,
+            dim,
+        )
+    }
+
     pub fn gather<D: Dim>(&self, indexes: &Self, dim: D) -> Result<Self> {
         let dim = dim.to_index(self.shape(), "gather")?;
         let self_dims = self.dims();
@@ -1155,6 +2321,7 @@ impl Tensor {
         match &*self.storage() {
             Storage::Cpu(storage) => from_cpu_storage(storage),
             Storage::Cuda(storage) => from_cpu_storage(&storage.to_cpu_storage()?),
+            Storage::Lazy(storage) => from_cpu_storage(storage.realize()?.as_ref()),
         }
     }
 
@@ -1185,6 +2352,7 @@ impl Tensor {
         match &*self.storage() {
             Storage::Cpu(storage) => from_cpu_storage(storage),
             Storage::Cuda(storage) => from_cpu_storage(&storage.to_cpu_storage()?),
+            Storage::Lazy(storage) => from_cpu_storage(storage.realize()?.as_ref()),
         }
     }
 
@@ -1225,6 +2393,7 @@ impl Tensor {
         match &*self.storage() {
             Storage::Cpu(storage) => from_cpu_storage(storage),
             Storage::Cuda(storage) => from_cpu_storage(&storage.to_cpu_storage()?),
+            Storage::Lazy(storage) => from_cpu_storage(storage.realize()?.as_ref()),
         }
     }
 
@@ -1416,6 +2585,7 @@ impl Tensor {
         let tensor_ = Tensor_ {
             id: TensorId::new(),
             storage: self.storage.clone(),
+            version: self.version.clone(),
             layout: self.layout.transpose(dim1, dim2)?,
             op,
             is_variable: false,
@@ -1425,6 +2595,153 @@ impl Tensor {
         Ok(Tensor(Arc::new(tensor_)))
     }
 
+    /// Returns a tensor with the same data as the input where the dimensions have been permuted,
+    /// dimension `i` of the result is dimension `dims[i]` of the input. This does not copy the
+    /// data.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::arange(0u32, 24, &Device::Cpu)?.reshape((2, 3, 4))?;
+    /// let t = t.permute((2, 0, 1))?;
+    /// assert_eq!(t.dims(), &[4, 2, 3]);
+    /// assert_eq!(t.stride(), &[1, 12, 4]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn permute<D: Dims>(&self, dims: D) -> Result<Tensor> {
+        let dims = dims.to_indexes(self.shape(), "permute")?;
+        if dims.len() != self.rank() {
+            Err(Error::UnexpectedNumberOfDims {
+                expected: self.rank(),
+                got: dims.len(),
+                shape: self.shape().clone(),
+            }
+            .bt())?
+        }
+        let layout = self.layout.permute(&dims);
+        let op = BackpropOp::new1(self, |t| Op::Permute(t, dims.clone()));
+        let tensor_ = Tensor_ {
+            id: TensorId::new(),
+            storage: self.storage.clone(),
+            version: self.version.clone(),
+            layout,
+            op,
+            is_variable: false,
+            dtype: self.dtype,
+            device: self.device.clone(),
+        };
+        Ok(Tensor(Arc::new(tensor_)))
+    }
+
+    /// Returns a tensor with the elements of the input reversed along the given dimensions.
+    /// Strides cannot be negative so this copies the data.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[[0f32, 1., 2.], [3., 4., 5.]], &Device::Cpu)?;
+    /// assert_eq!(t.flip(1)?.to_vec2::<f32>()?, &[[2., 1., 0.], [5., 4., 3.]]);
+    /// assert_eq!(t.flip((0, 1))?.to_vec2::<f32>()?, &[[5., 4., 3.], [2., 1., 0.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn flip<D: Dims>(&self, dims: D) -> Result<Tensor> {
+        let dims = dims.to_indexes(self.shape(), "flip")?;
+        let mut t = self.contiguous()?;
+        for dim in dims {
+            let size = self.dims()[dim];
+            if size > 1 {
+                let ids: Vec<u32> = (0..size as u32).rev().collect();
+                let ids = Tensor::new(ids.as_slice(), self.device())?;
+                t = t.index_select(&ids, dim)?
+            }
+        }
+        Ok(t)
+    }
+
+    /// Rolls the elements of the input along dimension `dim`, the element at position `i` ends
+    /// up at position `i + shift` modulo the size of the dimension. `shift` can be negative.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::new(&[0f32, 1., 2., 3., 4.], &Device::Cpu)?;
+    /// assert_eq!(t.roll(2, 0)?.to_vec1::<f32>()?, &[3., 4., 0., 1., 2.]);
+    /// assert_eq!(t.roll(-1, 0)?.to_vec1::<f32>()?, &[1., 2., 3., 4., 0.]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn roll<D: Dim>(&self, shift: i64, dim: D) -> Result<Tensor> {
+        let dim = dim.to_index(self.shape(), "roll")?;
+        let size = self.dims()[dim];
+        if size == 0 {
+            return Ok(self.clone());
+        }
+        let shift = shift.rem_euclid(size as i64) as usize;
+        if shift == 0 {
+            return Ok(self.clone());
+        }
+        let head = self.narrow(dim, size - shift, shift)?;
+        let tail = self.narrow(dim, 0, size - shift)?;
+        Tensor::cat(&[&head, &tail], dim)
+    }
+
+    /// Returns a view on the input with the given shape, strides and offset. The strides and
+    /// offset are given in number of elements and index the input as if it was contiguous,
+    /// elements can be read multiple times by using overlapping strides. The input is made
+    /// contiguous first so this only copies the data when the input is not contiguous.
+    ///
+    /// ```rust
+    /// use candle_core::{Tensor, Device};
+    /// let t = Tensor::arange(0f32, 5., &Device::Cpu)?;
+    /// // Sliding windows of size 3.
+    /// let t = t.as_strided((3, 3), vec![1, 1], 0)?;
+    /// assert_eq!(t.to_vec2::<f32>()?, &[[0., 1., 2.], [1., 2., 3.], [2., 3., 4.]]);
+    /// # Ok::<(), candle_core::Error>(())
+    /// ```
+    pub fn as_strided<S: Into<Shape>>(
+        &self,
+        shape: S,
+        stride: Vec<usize>,
+        offset: usize,
+    ) -> Result<Tensor> {
+        let shape = shape.into();
+        if stride.len() != shape.rank() {
+            Err(Error::UnexpectedNumberOfDims {
+                expected: shape.rank(),
+                got: stride.len(),
+                shape: shape.clone(),
+            }
+            .bt())?
+        }
+        if shape.elem_count() > 0 {
+            let max_index = shape
+                .dims()
+                .iter()
+                .zip(stride.iter())
+                .map(|(&d, &s)| (d - 1) * s)
+                .sum::<usize>()
+                + offset;
+            if max_index >= self.elem_count() {
+                Err(Error::InvalidIndex {
+                    op: "as-strided",
+                    index: max_index,
+                    size: self.elem_count(),
+                }
+                .bt())?
+            }
+        }
+        let arg = self.contiguous()?;
+        let layout = Layout::new(shape, stride, arg.layout.start_offset() + offset);
+        let op = BackpropOp::new1(&arg, Op::AsStrided);
+        let tensor_ = Tensor_ {
+            id: TensorId::new(),
+            storage: arg.storage.clone(),
+            version: arg.version.clone(),
+            layout,
+            op,
+            is_variable: false,
+            dtype: arg.dtype,
+            device: arg.device.clone(),
+        };
+        Ok(Tensor(Arc::new(tensor_)))
+    }
+
     /// Returns true if the data is stored in a C contiguous (aka row major) way.
     pub fn is_contiguous(&self) -> bool {
         self.layout.is_contiguous()
@@ -1442,6 +2759,7 @@ impl Tensor {
         let tensor_ = Tensor_ {
             id: TensorId::new(),
             storage: Arc::new(RwLock::new(self.storage().try_clone(self.layout())?)),
+            version: Arc::default(),
             layout: self.layout.clone(),
             op,
             is_variable: false,
@@ -1457,6 +2775,7 @@ impl Tensor {
         let tensor_ = Tensor_ {
             id: TensorId::new(),
             storage: self.storage.clone(),
+            version: self.version.clone(),
             layout: self.layout.clone(),
             op: BackpropOp::none(),
             is_variable: false,
@@ -1483,11 +2802,23 @@ impl Tensor {
                     Storage::Cuda(cuda.storage_from_cpu_storage(&cpu_storage)?)
                 }
                 (Storage::Cpu(storage), Device::Cpu) => Storage::Cpu(storage.clone()),
+                (Storage::Cpu(storage), Device::Lazy) => {
+                    Storage::Lazy(crate::LazyStorage::ready(storage.clone()))
+                }
+                (Storage::Cuda(storage), Device::Lazy) => {
+                    Storage::Lazy(crate::LazyStorage::ready(storage.to_cpu_storage()?))
+                }
+                (Storage::Lazy(storage), Device::Cpu) => Storage::Cpu(storage.to_cpu_storage()?),
+                (Storage::Lazy(storage), Device::Cuda(cuda)) => {
+                    Storage::Cuda(cuda.storage_from_cpu_storage(storage.realize()?.as_ref())?)
+                }
+                (Storage::Lazy(storage), Device::Lazy) => Storage::Lazy(storage.clone()),
             };
             let op = BackpropOp::new1(self, Op::ToDevice);
             let tensor_ = Tensor_ {
                 id: TensorId::new(),
                 storage: Arc::new(RwLock::new(storage)),
+                version: Arc::default(),
                 layout: self.layout.clone(),
                 op,
                 is_variable: false,
@@ -1518,6 +2849,7 @@ impl Tensor {
         let tensor_ = Tensor_ {
             id: TensorId::new(),
             storage: self.storage.clone(),
+            version: self.version.clone(),
             layout: self.layout.broadcast_as(shape)?,
             op: BackpropOp::new1(self, Op::Broadcast),
             is_variable: false,
@@ -1610,6 +2942,7 @@ impl Tensor {
             let tensor_ = Tensor_ {
                 id: TensorId::new(),
                 storage: self.storage.clone(),
+                version: self.version.clone(),
                 layout: Layout::contiguous_with_offset(shape, self.layout.start_offset()),
                 op,
                 is_variable: false,
@@ -1855,9 +3188,24 @@ impl Tensor {
         &self,
     ) -> (std::sync::RwLockWriteGuard<'_, Storage>, &Layout) {
         let storage = self.storage.write().unwrap();
+        self.version.fetch_add(1, Ordering::SeqCst);
         (storage, &self.layout)
     }
 
+    // The current version of the storage, see `Tensor_::version`.
+    pub(crate) fn version(&self) -> (Arc<AtomicUsize>, usize) {
+        (self.version.clone(), self.version.load(Ordering::SeqCst))
+    }
+
+    // Checks that none of the tensors captured by the op that produced `self` has been modified
+    // in place since then.
+    pub(crate) fn check_saved_versions(&self) -> Result<()> {
+        if self.op.versions_changed() {
+            crate::bail!("backward: a tensor used by the computation graph was modified in place")
+        }
+        Ok(())
+    }
+
     /// The storage used by this tensor, together with the layout to use to access it safely.
     pub fn storage_and_layout(&self) -> (std::sync::RwLockReadGuard<'_, Storage>, &Layout) {
         let storage = self.storage.read().unwrap();
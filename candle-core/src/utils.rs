@@ -11,6 +11,14 @@ pub fn get_num_threads() -> usize {
     }
 }
 
+// Float convolutions on the cpu use im2col followed by a matmul unless this environment variable
+// is set, in which case the direct (and slower) kernels are used. The variable is only read on
+// the first call.
+pub fn use_im2col_conv() -> bool {
+    static USE_IM2COL: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *USE_IM2COL.get_or_init(|| std::env::var("CANDLE_DISABLE_IM2COL").is_err())
+}
+
 pub fn has_accelerate() -> bool {
     cfg!(feature = "accelerate")
 }
@@ -124,10 +124,6 @@ impl Var {
             Err(Error::CannotSetVar { msg }.bt())?
         }
         let (mut dst, layout) = self.storage_mut_and_layout();
-        if !layout.is_contiguous() {
-            let msg = "cannot set a non-contiguous variable";
-            Err(Error::CannotSetVar { msg }.bt())?
-        }
         let (src, src_l) = src.storage_and_layout();
         if layout.shape() != src_l.shape() {
             Err(Error::ShapeMismatchBinaryOp {
@@ -137,7 +133,7 @@ impl Var {
             }
             .bt())?
         }
-        src.copy_strided_src(&mut dst, layout.start_offset(), src_l)?;
+        src.copy_strided(&mut dst, layout, src_l)?;
         Ok(())
     }
 }
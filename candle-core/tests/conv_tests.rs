@@ -1,6 +1,6 @@
 mod test_utils;
 use anyhow::Result;
-use candle_core::{Device, Tensor};
+use candle_core::{ConvPadding, DType, Device, Tensor};
 
 /* This test is based on the following script.
 import torch
@@ -33,23 +33,41 @@ fn conv1d() -> Result<()> {
         dev,
     )?
     .reshape((2, 4, 3))?;
-    let res = t.conv1d(&w, 0, 1)?;
+    let res = t.conv1d(&w, 0, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 2, 3]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
         [2.6357, -1.3336, 4.1393, -1.1784, 3.5675, 0.5069]
     );
-    let res = t.conv1d(&w, /*padding*/ 1, 1)?;
+    let res = t.conv1d(&w, /*padding*/ 1, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 2, 5]);
-    /* Note that the default for padding is different from PyTorch at the moment: instead of
-       padding with zeros, the edge value from the input tensor is used, i.e. this is similiar to:
-    t = torch.nn.functional.pad(t, (1, 1), mode='replicate')
-    res = torch.nn.functional.conv1d(t, w, padding=0)
-    */
+    // res = torch.nn.functional.conv1d(t, w, padding=1)
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
-        [2.5209, 2.6357, -1.3336, 4.1393, 0.4951, 3.6855, -1.1784, 3.5675, 0.5069, 4.9562]
+        [2.4509, 2.6357, -1.3336, 4.1393, 0.5657, 1.8091, -1.1784, 3.5675, 0.5069, 3.3352]
     );
+    // res = torch.nn.functional.conv1d(t, w, padding=2, dilation=2)
+    let res = t.conv1d(&w, 2, 1, 2, 1)?;
+    assert_eq!(res.dims(), [1, 2, 5]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [1.8688, -1.2388, 1.7601, -0.165, 2.4064, -0.0899, -4.3771, 6.5222, 0.9286, 3.3736]
+    );
+    // res = torch.nn.functional.conv1d(t, w[:, :2], padding=1, groups=2)
+    let w = w.narrow(1, 0, 2)?;
+    let res = t.conv1d(&w, 1, 1, 1, 2)?;
+    assert_eq!(res.dims(), [1, 2, 5]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [2.9615, -1.8213, -1.833, 0.0412, 1.203, 4.4183, -1.4755, 5.4457, -5.121, 2.9271]
+    );
+    let res = t.conv1d(&w, 1, 2, 1, 2)?;
+    assert_eq!(res.dims(), [1, 2, 3]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [2.9615, -1.833, 1.203, 4.4183, 5.4457, 2.9271]
+    );
+    assert!(t.conv1d(&w, 1, 1, 1, 1).is_err());
     Ok(())
 }
 
@@ -58,17 +76,17 @@ fn conv1d_small() -> Result<()> {
     let dev = &Device::Cpu;
     let t = Tensor::new(&[0.4056f32, -0.8689, -0.0773, -1.5630], dev)?.reshape((1, 1, 4))?;
     let w = Tensor::new(&[1f32, 0., 0.], dev)?.reshape((1, 1, 3))?;
-    let res = t.conv1d(&w, 0, 1)?;
+    let res = t.conv1d(&w, 0, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 1, 2]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
         [0.4056, -0.8689]
     );
-    let res = t.conv1d(&w, /*padding*/ 1, 1)?;
+    let res = t.conv1d(&w, /*padding*/ 1, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 1, 4]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
-        [0.4056, 0.4056, -0.8689, -0.0773],
+        [0.0, 0.4056, -0.8689, -0.0773],
     );
     Ok(())
 }
@@ -117,7 +135,7 @@ fn conv2d() -> Result<()> {
     )?;
     let t = t.reshape((1, 4, 5, 5))?;
     let w = w.reshape((2, 4, 3, 3))?;
-    let res = t.conv2d(&w, 0, 1)?;
+    let res = t.conv2d(&w, 0, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 2, 3, 3]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
@@ -153,7 +171,7 @@ fn conv2d_small() -> Result<()> {
     let w = Tensor::new(&[-0.9259f32, 1.3017], dev)?;
     let t = t.reshape((1, 2, 3, 3))?;
     let w = w.reshape((1, 2, 1, 1))?;
-    let res = t.conv2d(&w, 0, 1)?;
+    let res = t.conv2d(&w, 0, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 1, 3, 3]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
@@ -174,7 +192,7 @@ fn conv2d_smaller() -> Result<()> {
     let w = Tensor::new(&[1f32, 1., 1., 1., 1., 1., 1., 1., 1.], dev)?;
     let t = t.reshape((1, 1, 3, 3))?;
     let w = w.reshape((1, 1, 3, 3))?;
-    let res = t.conv2d(&w, 0, 1)?;
+    let res = t.conv2d(&w, 0, 1, 1, 1)?;
     assert_eq!(res.dims(), [1, 1, 1, 1]);
     assert_eq!(
         test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
@@ -182,3 +200,306 @@ fn conv2d_smaller() -> Result<()> {
     );
     Ok(())
 }
+
+/* This test is based on the following script.
+import torch
+torch.manual_seed(4242)
+
+t = torch.randn((1, 2, 3, 3))
+w = torch.randn((2, 1, 2, 2))
+res = torch.nn.functional.conv2d(t, w, padding=1, dilation=2, groups=2)
+print(res.flatten())
+res = torch.nn.functional.conv2d(t, w, padding=1, stride=2, groups=2)
+print(res.flatten())
+*/
+#[test]
+fn conv2d_dilation_groups() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::new(
+        &[
+            0.4056f32, -0.8689, 0.6843, 0.2395, 1.2279, -0.9287, -1.7030, 0.1370, 0.1866, 0.4145,
+            -0.6266, 0.3529, 2.2013, -0.6836, 0.2477, 1.3127, -0.6957, 0.3278,
+        ],
+        dev,
+    )?
+    .reshape((1, 2, 3, 3))?;
+    let w = Tensor::new(
+        &[
+            -0.9325f32, 0.6451, -0.8537, 0.2378, 0.8764, -0.1832, 0.2987, -0.6488,
+        ],
+        dev,
+    )?
+    .reshape((2, 1, 2, 2))?;
+    let res = t.conv2d(&w, 1, 1, 2, 2)?;
+    assert_eq!(res.dims(), [1, 2, 3, 3]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            0.292, -0.4253, -1.0483, -0.5279, 1.5614, 0.6933, 0.7921, -0.8224, -1.145, 0.4435,
+            0.4968, -0.2042, 0.5662, 0.478, -0.757, 0.1252, 1.8838, -0.5991
+        ]
+    );
+    let res = t.conv2d(&w, 1, 2, 1, 2)?;
+    assert_eq!(res.dims(), [1, 2, 2, 2]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [0.0965, 0.9045, -0.2505, -1.8167, -0.2689, -0.4161, -1.255, -1.065]
+    );
+    Ok(())
+}
+
+/* This test is based on the following script.
+import torch
+torch.manual_seed(4242)
+
+t = torch.randn((1, 4, 5))
+w = torch.randn((4, 2, 3))
+res = torch.nn.functional.conv_transpose1d(t, w)
+print(res.flatten())
+res = torch.nn.functional.conv_transpose1d(t, w, padding=1, output_padding=1, stride=2)
+print(res.flatten())
+res = torch.nn.functional.conv_transpose1d(t, w, padding=2, dilation=2)
+print(res.flatten())
+*/
+#[test]
+fn conv_transpose1d() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::new(
+        &[
+            0.4056f32, -0.8689, -0.0773, -1.5630, 1.2279, -0.9287, -1.7030, 0.1370, 0.1866, 0.4145,
+            1.8025, -0.1536, 2.2013, -0.6836, 0.2477, 1.3127, -0.6957, 0.3278, -1.0124, 0.5599,
+        ],
+        dev,
+    )?
+    .reshape((1, 4, 5))?;
+    let w = Tensor::new(
+        &[
+            -0.8404f32, -0.3490, 0.0130, 1.3123, 0.1763, -1.9249, 1.4270, 0.9421, 0.8670, -0.7181,
+            -1.1111, 0.8869, -1.2429, 1.8357, 1.6052, -1.3844, 0.3951, -1.2036, 0.6686, 1.6261,
+            -0.6451, -0.0840, -1.4247, 0.5512,
+        ],
+        dev,
+    )?
+    .reshape((4, 2, 3))?;
+    let res = t.conv_transpose1d(&w, 0, 0, 1, 1)?;
+    assert_eq!(res.dims(), [1, 2, 7]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            -3.0288, 2.4528, -3.7241, 5.197, 0.886, 1.0244, 0.4118, -1.4065, 0.2991, -3.6557,
+            -0.9533, -0.5853, 2.495, -1.9855
+        ]
+    );
+    let res = t.conv_transpose1d(&w, 1, 1, 2, 1)?;
+    assert_eq!(res.dims(), [1, 2, 10]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            4.4269, -0.7276, -2.7144, -3.5419, 4.73, 5.1924, -2.1799, -0.6767, 1.3271, 0.4118,
+            -0.0547, -2.6966, 2.6695, -3.3113, 0.2369, -3.3522, 0.6894, 4.3626, -0.9439, -1.9855
+        ]
+    );
+    let res = t.conv_transpose1d(&w, 2, 0, 1, 2)?;
+    assert_eq!(res.dims(), [1, 2, 5]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [2.1706, -0.9618, 5.6027, -3.4654, 4.767, -3.3295, 1.5158, -1.8897, 0.6529, -3.1424]
+    );
+    // The output padding has to be smaller than the stride or the dilation.
+    assert!(t.conv_transpose1d(&w, 0, 1, 1, 1).is_err());
+    Ok(())
+}
+
+/* This test is based on the following script.
+import torch
+torch.manual_seed(4242)
+
+t = torch.randn((1, 2, 3, 3))
+w = torch.randn((2, 1, 2, 2))
+res = torch.nn.functional.conv_transpose2d(t, w)
+print(res.flatten())
+res = torch.nn.functional.conv_transpose2d(t, w, padding=1, output_padding=1, stride=2)
+print(res.flatten())
+res = torch.nn.functional.conv_transpose2d(t, w, dilation=2)
+print(res.flatten())
+*/
+#[test]
+fn conv_transpose2d() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::new(
+        &[
+            0.4056f32, -0.8689, 0.6843, 0.2395, 1.2279, -0.9287, -1.7030, 0.1370, 0.1866, 0.4145,
+            -0.6266, 0.3529, 2.2013, -0.6836, 0.2477, 1.3127, -0.6957, 0.3278,
+        ],
+        dev,
+    )?
+    .reshape((1, 2, 3, 3))?;
+    let w = Tensor::new(
+        &[
+            -0.9325f32, 0.6451, -0.8537, 0.2378, 0.8764, -0.1832, 0.2987, -0.6488,
+        ],
+        dev,
+    )?
+    .reshape((2, 1, 2, 2))?;
+    let res = t.conv_transpose2d(&w, 0, 0, 1, 1)?;
+    assert_eq!(res.dims(), [1, 1, 4, 4]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            -0.015, 0.4468, -0.7746, 0.3768, 1.4834, -1.6108, 1.7216, -0.7107, 3.1916, -4.7003,
+            1.9314, -0.3212, 1.846, -1.5814, 0.4226, -0.1683
+        ]
+    );
+    let res = t.conv_transpose2d(&w, 1, 1, 2, 1)?;
+    assert_eq!(res.dims(), [1, 1, 5, 5]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            -0.1725, 0.5546, 0.1999, -0.4788, -0.0662, -0.2488, -1.7441, 0.9174, 1.0831, -0.6445,
+            -1.3713, -1.2524, 0.7355, 0.8668, -0.3816, -1.3391, -0.7375, 0.2158, 0.1133, 0.0603,
+            -1.2567, -0.3248, 0.4839, -0.0614, -0.1683
+        ]
+    );
+    let res = t.conv_transpose2d(&w, 0, 0, 1, 2)?;
+    assert_eq!(res.dims(), [1, 1, 5, 5]);
+    assert_eq!(
+        test_utils::to_vec1_round(&res.flatten_all()?, 4)?,
+        [
+            -0.015, 0.2611, -0.1431, -0.4457, 0.3768, 1.7059, -1.7441, 0.8343, 0.9174, -0.6445,
+            2.516, -0.1828, -1.8771, 0.4157, -0.0059, 0.4531, -1.2524, -0.5044, 0.7355, -0.3816,
+            1.846, -0.3248, -1.318, 0.4839, -0.1683
+        ]
+    );
+    Ok(())
+}
+
+// Float convolutions go through im2col + gemm on the cpu whereas integer ones use the direct
+// kernels, the values used here are small integers so both results have to match exactly.
+#[test]
+fn conv_im2col() -> Result<()> {
+    let dev = &Device::Cpu;
+    let small_ints = |n: usize, m: f64| -> Result<Tensor> {
+        let t = Tensor::arange(0i64, n as i64, dev)?.rem(m)?;
+        Ok(t.affine(1., -(m / 2.).floor())?)
+    };
+    let t = small_ints(2 * 4 * 19 * 13, 7.)?;
+    for (padding, stride, dilation, groups) in [(0, 1, 1, 1), (2, 2, 3, 2), (5, 3, 1, 4)] {
+        let t = t.reshape((2, 4, 247))?;
+        let w = small_ints(8 * 4 / groups * 3, 5.)?.reshape((8, 4 / groups, 3))?;
+        let expected = t.conv1d(&w, padding, stride, dilation, groups)?;
+        let (t, w) = (t.to_dtype(DType::F32)?, w.to_dtype(DType::F32)?);
+        let res = t.conv1d(&w, padding, stride, dilation, groups)?;
+        assert_eq!(
+            res.to_dtype(DType::I64)?.to_vec3::<i64>()?,
+            expected.to_vec3::<i64>()?
+        );
+    }
+    for (padding, stride, dilation, groups) in [(0, 1, 1, 1), (1, 2, 2, 2), (3, 1, 1, 4)] {
+        let t = t.reshape((2, 4, 19, 13))?;
+        let w = small_ints(8 * 4 / groups * 6, 5.)?.reshape((8, 4 / groups, 3, 2))?;
+        let expected = t.conv2d(&w, padding, stride, dilation, groups)?;
+        let (t, w) = (t.to_dtype(DType::F64)?, w.to_dtype(DType::F64)?);
+        let res = t.conv2d(&w, padding, stride, dilation, groups)?;
+        let res = res.to_dtype(DType::I64)?.flatten_all()?;
+        assert_eq!(
+            res.to_vec1::<i64>()?,
+            expected.flatten_all()?.to_vec1::<i64>()?
+        );
+    }
+    let t = small_ints(2 * 4 * 5 * 7 * 6, 7.)?.reshape((2, 4, 5, 7, 6))?;
+    for (padding, stride, dilation, groups) in [(0, 1, 1, 1), (1, 2, 2, 2), (2, 1, 1, 4)] {
+        let w = small_ints(8 * 4 / groups * 12, 5.)?.reshape((8, 4 / groups, 2, 3, 2))?;
+        let expected = t.conv3d(&w, padding, stride, dilation, groups)?;
+        let (t, w) = (t.to_dtype(DType::F32)?, w.to_dtype(DType::F32)?);
+        let res = t.conv3d(&w, padding, stride, dilation, groups)?;
+        let res = res.to_dtype(DType::I64)?.flatten_all()?;
+        assert_eq!(
+            res.to_vec1::<i64>()?,
+            expected.flatten_all()?.to_vec1::<i64>()?
+        );
+    }
+    Ok(())
+}
+
+/* The expected values have been computed with the following PyTorch snippet.
+x = ((torch.arange(96) * 7 % 11 - 5) / 4).reshape(1, 2, 3, 4, 4)
+w = ((torch.arange(32) * 5 % 7 - 3) / 2).reshape(2, 2, 2, 2, 2)
+torch.nn.functional.conv3d(x, w)
+w = ((torch.arange(48) * 5 % 7 - 3) / 2).reshape(4, 1, 2, 3, 2)
+torch.nn.functional.conv3d(x, w, padding=1, stride=2, groups=2)
+*/
+#[test]
+fn conv3d() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::arange(0f32, 96., dev)?
+        .affine(7., 0.)?
+        .rem(11.)?
+        .affine(0.25, -1.25)?
+        .reshape((1, 2, 3, 4, 4))?;
+    let w = |n: usize| {
+        Tensor::arange(0f32, n as f32, dev)?
+            .affine(5., 0.)?
+            .rem(7.)?
+            .affine(0.5, -1.5)
+    };
+    let res = t.conv3d(&w(32)?.reshape((2, 2, 2, 2, 2))?, 0, 1, 1, 1)?;
+    assert_eq!(res.dims(), [1, 2, 2, 3, 3]);
+    assert_eq!(
+        res.flatten_all()?.to_vec1::<f32>()?,
+        [
+            7.375, -1.75, -1.25, -7.125, 3.0, 0.75, 4.5, 2.25, -4.125, 3.0, 0.75, -7.0, 2.25,
+            -4.125, 7.375, -1.25, 3.375, -7.125, -5.75, 0.75, 3.125, 3.75, 0.625, -3.875, -3.25,
+            0.5, 5.625, 0.625, -3.875, 4.0, 0.5, 5.625, -5.75, 3.125, -5.5, 3.75
+        ]
+    );
+    let res = t.conv3d(&w(48)?.reshape((4, 1, 2, 3, 2))?, 1, 2, 1, 2)?;
+    assert_eq!(res.dims(), [1, 4, 2, 2, 3]);
+    assert_eq!(
+        res.flatten_all()?.to_vec1::<f32>()?,
+        [
+            0.375, 2.75, 1.25, 0.375, 2.0, -1.5, 3.0, -3.625, -0.75, -2.0, -2.875, -1.625, 1.875,
+            -0.25, -0.625, 1.625, -1.125, 0.875, -1.125, -0.125, 2.75, -1.25, -0.25, 4.25, 1.625,
+            -0.625, 0.625, 2.375, -0.375, 0.875, -0.25, -1.75, 3.0, -2.5, 0.625, 3.75, -0.75, 0.0,
+            -1.875, -1.875, -1.125, 0.375, 0.125, -1.5, -1.25, 3.0, 1.0, -1.375
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn conv_padded() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::arange(0f32, 60., dev)?
+        .affine(7., 0.)?
+        .rem(11.)?
+        .reshape((1, 2, 5, 6))?;
+    let w = Tensor::arange(0f32, 24., dev)?
+        .affine(5., 0.)?
+        .rem(7.)?
+        .affine(0.5, -1.5)?
+        .reshape((2, 2, 2, 3))?;
+    // With a dilation of 2, same padding adds (1, 1) rows and (2, 2) columns.
+    let res = t.conv2d_padded(&w, ConvPadding::Same, 1, 2, 1)?;
+    assert_eq!(res.dims(), [1, 2, 5, 6]);
+    let expected = t
+        .pad_with_zeros(2, 1, 1)?
+        .pad_with_zeros(3, 2, 2)?
+        .conv2d(&w, 0, 1, 2, 1)?;
+    assert_eq!(
+        res.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+    // The odd total padding of a kernel of size 2 goes at the end.
+    let t = t.flatten(2, 3)?;
+    let w = w.narrow(3, 0, 2)?.flatten(2, 3)?;
+    let res = t.conv1d_padded(&w, ConvPadding::Same, 1, 1, 1)?;
+    let expected = t.pad_with_zeros(2, 1, 2)?.conv1d(&w, 0, 1, 1, 1)?;
+    assert_eq!(res.dims(), [1, 2, 30]);
+    assert_eq!(res.to_vec3::<f32>()?, expected.to_vec3::<f32>()?);
+    let padding = ConvPadding::Asymmetric { start: 3, end: 1 };
+    let res = t.conv1d_padded(&w, padding, 2, 1, 1)?;
+    let expected = t.pad_with_zeros(2, 2, 0)?.conv1d(&w, 1, 2, 1, 1)?;
+    assert_eq!(res.to_vec3::<f32>()?, expected.to_vec3::<f32>()?);
+    assert!(t.conv1d_padded(&w, ConvPadding::Same, 2, 1, 1).is_err());
+    Ok(())
+}
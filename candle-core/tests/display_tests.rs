@@ -82,3 +82,14 @@ Tensor[[2, 1, 1, 100, 100], f32]"#;
     assert_eq!(&t, expected);
     Ok(())
 }
+
+#[test]
+fn display_ints_and_bools() -> Result<()> {
+    let t = Tensor::new(&[-12i64, 3, 456], &Cpu)?;
+    let s = format!("{t}");
+    assert_eq!(&s, "[-12,   3, 456]\nTensor[[3], i64]");
+    let t = t.ge(&t.zeros_like()?)?;
+    let s = format!("{t}");
+    assert_eq!(&s, "[false,  true,  true]\nTensor[[3], bool]");
+    Ok(())
+}
@@ -0,0 +1,197 @@
+mod test_utils;
+use candle_core::{DType, Device, IndexOp, Result, Tensor, D};
+use test_utils::{to_vec2_round, to_vec3_round};
+
+// A naive O(n^2) dft of a complex signal given as (re, im) pairs.
+fn naive_dft(x: &[(f64, f64)], inverse: bool) -> Vec<(f64, f64)> {
+    let n = x.len();
+    let sign = if inverse { 1. } else { -1. };
+    (0..n)
+        .map(|k| {
+            let (mut re, mut im) = (0., 0.);
+            for (j, &(x_re, x_im)) in x.iter().enumerate() {
+                let theta = sign * 2. * std::f64::consts::PI * ((j * k) % n) as f64 / n as f64;
+                re += x_re * theta.cos() - x_im * theta.sin();
+                im += x_re * theta.sin() + x_im * theta.cos();
+            }
+            (re, im)
+        })
+        .collect()
+}
+
+fn assert_close(lhs: &Tensor, rhs: &Tensor, tol: f64) -> Result<()> {
+    let diff = (lhs - rhs)?
+        .abs()?
+        .flatten_all()?
+        .max(0)?
+        .to_scalar::<f64>()?;
+    assert!(diff < tol, "max diff {diff} for {lhs} vs {rhs}");
+    Ok(())
+}
+
+fn signal(len: usize, phase: f64, dev: &Device) -> Result<Tensor> {
+    Tensor::arange(0f64, len as f64, dev)?
+        .affine(0.37, phase)?
+        .sin()
+}
+
+fn rfft(dev: &Device) -> Result<()> {
+    let t = Tensor::new(&[[1f32, 2., 3., 4.], [0., 1., 0., -1.]], dev)?;
+    let f = t.rfft(1)?;
+    assert_eq!(f.dims(), [2, 3, 2]);
+    assert_eq!(
+        to_vec3_round(f.clone(), 4)?,
+        [
+            [[10., 0.], [-2., 2.], [-2., 0.]],
+            [[0., 0.], [0., -2.], [0., 0.]]
+        ]
+    );
+    let t = t.t()?.rfft(0)?;
+    assert_eq!(t.dims(), [3, 2, 2]);
+    assert_eq!(to_vec3_round(t.transpose(0, 1)?, 4)?, to_vec3_round(f, 4)?);
+
+    // Lengths with various prime factors, including large primes.
+    for n in [1, 2, 5, 7, 12, 49, 143, 400] {
+        let t = signal(3 * n, 0.1, dev)?.reshape((3, n))?;
+        let f = t.rfft(D::Minus1)?;
+        assert_eq!(f.dims(), [3, n / 2 + 1, 2]);
+        let values: Vec<(f64, f64)> = t.i(2)?.to_vec1::<f64>()?.iter().map(|&v| (v, 0.)).collect();
+        let expected: Vec<f64> = naive_dft(&values, false)[..n / 2 + 1]
+            .iter()
+            .flat_map(|&(re, im)| [re, im])
+            .collect();
+        let expected = Tensor::from_vec(expected, (n / 2 + 1, 2), dev)?;
+        assert_close(&f.i(2)?, &expected, 1e-9)?;
+        let t2 = f.irfft(1, n)?;
+        assert_close(&t, &t2, 1e-12)?;
+    }
+    Ok(())
+}
+
+fn fft(dev: &Device) -> Result<()> {
+    let t = Tensor::new(&[[1f32, 0.], [0., 1.], [-1., 0.], [0., -1.]], dev)?;
+    let f = t.fft(0)?;
+    assert_eq!(
+        to_vec2_round(&f, 4)?,
+        [[0., 0.], [4., 0.], [0., 0.], [0., 0.]]
+    );
+    assert_eq!(to_vec2_round(&f.ifft(0)?, 4)?, to_vec2_round(&t, 4)?);
+
+    // Transform along a dimension that is not the last one.
+    for n in [3, 8, 10, 13] {
+        let t = signal(n * 4 * 2, 0.5, dev)?.reshape((n, 4, 2))?;
+        let f = t.fft(0)?;
+        assert_eq!(f.dims(), [n, 4, 2]);
+        let values: Vec<(f64, f64)> = t
+            .i((.., 1))?
+            .to_vec2::<f64>()?
+            .iter()
+            .map(|v| (v[0], v[1]))
+            .collect();
+        for (inverse, f) in [(false, &f), (true, &t.ifft(0)?)] {
+            let scale = if inverse { n as f64 } else { 1. };
+            let expected: Vec<f64> = naive_dft(&values, inverse)
+                .iter()
+                .flat_map(|&(re, im)| [re / scale, im / scale])
+                .collect();
+            let expected = Tensor::from_vec(expected, (n, 2), dev)?;
+            assert_close(&f.i((.., 1))?, &expected, 1e-9)?;
+        }
+        assert_close(&f.ifft(0)?, &t, 1e-12)?;
+    }
+    Ok(())
+}
+
+fn fft_batched(dev: &Device) -> Result<()> {
+    // Large enough for the rows to be split between threads.
+    let t = signal(64 * 400, 0.2, dev)?.reshape((4, 16, 400))?;
+    let f = t.rfft(2)?;
+    for (i, j) in [(0, 0), (1, 7), (3, 15)] {
+        assert_close(&f.i((i, j))?, &t.i((i, j))?.rfft(0)?, 1e-12)?;
+    }
+    assert_close(&f.irfft(2, 400)?, &t, 1e-12)?;
+    let f = f.to_dtype(DType::F32)?.fft(1)?;
+    assert_eq!(f.dtype(), DType::F32);
+    assert_close(&f.ifft(1)?.to_dtype(DType::F64)?.irfft(2, 400)?, &t, 1e-4)?;
+    Ok(())
+}
+
+fn hann_window(n: usize, dev: &Device) -> Result<Tensor> {
+    let w: Vec<f64> = (0..n)
+        .map(|i| 0.5 - 0.5 * (2. * std::f64::consts::PI * i as f64 / n as f64).cos())
+        .collect();
+    Tensor::from_vec(w, n, dev)
+}
+
+fn stft(dev: &Device) -> Result<()> {
+    let t = Tensor::arange(0f64, 10., dev)?;
+    let s = t.stft(4, 2, None, false)?;
+    assert_eq!(s.dims(), [3, 4, 2]);
+    let frames = Tensor::stack(
+        &[
+            t.narrow(0, 0, 4)?,
+            t.narrow(0, 2, 4)?,
+            t.narrow(0, 4, 4)?,
+            t.narrow(0, 6, 4)?,
+        ],
+        1,
+    )?;
+    assert_close(&s, &frames.rfft(0)?, 1e-12)?;
+
+    // With center, the first frame covers [2, 1, 0, 1] once reflect-padded.
+    let s = t.stft(4, 2, None, true)?;
+    assert_eq!(s.dims(), [3, 6, 2]);
+    let first = Tensor::new(&[2f64, 1., 0., 1.], dev)?.rfft(0)?;
+    assert_close(&s.i((.., 0))?, &first, 1e-12)?;
+
+    // Roundtrip through istft with a hann window, as used for whisper.
+    let n_fft = 16;
+    let t = signal(2 * 100, 0.3, dev)?.reshape((2, 100))?;
+    let window = hann_window(n_fft, dev)?;
+    let s = t.stft(n_fft, 4, Some(&window), true)?;
+    assert_eq!(s.dims(), [2, 9, 26, 2]);
+    let t2 = s.istft(n_fft, 4, Some(&window), true, Some(100))?;
+    assert_close(&t2, &t, 1e-10)?;
+    let t2 = s.istft(n_fft, 4, Some(&window), true, None)?;
+    assert_close(&t2, &t, 1e-10)?;
+    // The frames only cover 108 samples once the padding is removed, the rest is zero-padded.
+    let t2 = s.istft(n_fft, 4, Some(&window), true, Some(112))?;
+    assert_eq!(t2.dims(), [2, 112]);
+    assert_close(&t2.narrow(1, 0, 100)?, &t, 1e-10)?;
+    assert_eq!(
+        t2.narrow(1, 108, 4)?.abs()?.sum_all()?.to_scalar::<f64>()?,
+        0.
+    );
+
+    // Without center, the rectangular window needs no normalization on the edges.
+    let s = t.stft(n_fft, 6, None, false)?;
+    assert_eq!(s.dims(), [2, 9, 15, 2]);
+    let t2 = s.istft(n_fft, 6, None, false, None)?;
+    assert_close(&t2, &t.narrow(1, 0, 100)?, 1e-10)?;
+    // The hann window is zero on the first sample.
+    let s = t.stft(n_fft, 4, Some(&window), false)?;
+    assert!(s.istft(n_fft, 4, Some(&window), false, None).is_err());
+    Ok(())
+}
+
+#[test]
+fn fft_errors() -> Result<()> {
+    let dev = &Device::Cpu;
+    let t = Tensor::new(&[[1u32, 2], [3, 4]], dev)?;
+    assert!(t.rfft(1).is_err());
+    let t = Tensor::new(&[[1f32, 2., 3.], [3., 4., 5.]], dev)?;
+    // The (re, im) dimension is missing.
+    assert!(t.fft(0).is_err());
+    assert!(t.rfft(1)?.fft(2).is_err());
+    assert!(t.rfft(1)?.irfft(1, 4).is_err());
+    assert!(t.stft(4, 1, None, false).is_err());
+    assert!(t
+        .stft(2, 1, Some(&Tensor::ones(3, DType::F32, dev)?), false)
+        .is_err());
+    Ok(())
+}
+
+test_device!(rfft, rfft_cpu, rfft_gpu);
+test_device!(fft, fft_cpu, fft_gpu);
+test_device!(fft_batched, fft_batched_cpu, fft_batched_gpu);
+test_device!(stft, stft_cpu, stft_gpu);
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use candle_core::{Device, Shape, Tensor, Var};
+use candle_core::{Device, InterpolateMode, Shape, Tensor, Var};
 mod test_utils;
 
 fn simple_grad(device: &Device) -> Result<()> {
@@ -61,6 +61,44 @@ fn matmul_grad(device: &Device) -> Result<()> {
             [[15., 15.], [17., 17.], [19., 19.]]
         ]
     );
+
+    // The gradient of a weight broadcasted over the batch is summed over the batch.
+    let w = Var::from_slice(&data[..6], (3, 2), device)?;
+    let c = x.matmul(&w)?;
+    let grads = c.backward()?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(grad_w.dims(), [3, 2]);
+    assert_eq!(
+        grad_w.to_vec2::<f32>()?,
+        [[18., 18.], [22., 22.], [26., 26.]]
+    );
+    Ok(())
+}
+
+fn einsum_grad(device: &Device) -> Result<()> {
+    let data: Vec<_> = (0..12).map(|i| i as f32).collect();
+    let x = Var::from_slice(&data, (2, 2, 3), device)?;
+    let y = Var::from_slice(&data, (2, 3, 2), device)?;
+    let c = Tensor::einsum("bij,bjk->bik", &[&x, &y])?;
+    let grads = c.backward()?;
+    let grad_x = grads.get(&x).context("no grad for x")?;
+    let grad_y = grads.get(&y).context("no grad for y")?;
+    let c = x.matmul(&y)?;
+    let grads = c.backward()?;
+    let matmul_grad_x = grads.get(&x).context("no grad for x")?;
+    let matmul_grad_y = grads.get(&y).context("no grad for y")?;
+    assert_eq!(grad_x.to_vec3::<f32>()?, matmul_grad_x.to_vec3::<f32>()?);
+    assert_eq!(grad_y.to_vec3::<f32>()?, matmul_grad_y.to_vec3::<f32>()?);
+
+    // The gradient of the trace is the identity.
+    let x = Var::from_slice(&data[..9], (3, 3), device)?;
+    let trace = Tensor::einsum("ii", &[&x])?;
+    let grads = trace.backward()?;
+    let grad_x = grads.get(&x).context("no grad for x")?;
+    assert_eq!(
+        grad_x.to_vec2::<f32>()?,
+        [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]
+    );
     Ok(())
 }
 
@@ -168,8 +206,471 @@ fn unary_grad(device: &Device) -> Result<()> {
     Ok(())
 }
 
+fn view_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[[1f32, 2., 3.], [4., 5., 6.]], device)?;
+    let x = x.as_tensor();
+    let w = Tensor::new(&[[1f32, 10.], [100., 1000.], [0., 1.]], device)?;
+    let y = x.unsqueeze(2)?.permute((1, 2, 0))?.squeeze(1)?;
+    assert_eq!(y.dims(), &[3, 2]);
+    let grads = (y * &w)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[1., 100., 0.], [10., 1000., 1.]]);
+
+    let y = x.flip(1)?.roll(1, 0)?;
+    let grads = (y * w.t()?)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[1., 1000., 10.], [0., 100., 1.]]);
+
+    // Overlapping windows, the gradients are accumulated.
+    let y = x.t()?.as_strided((2, 3), vec![1, 1], 1)?;
+    assert_eq!(y.to_vec2::<f32>()?, [[4., 2., 5.], [2., 5., 3.]]);
+    let grads = y.sqr()?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[0., 8., 6.], [8., 20., 0.]]);
+    Ok(())
+}
+
+fn sort_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[[3f32, 1., 2.], [0., 5., 4.]], device)?;
+    let x = x.as_tensor();
+    let (values, _) = x.topk(2, 1)?;
+    let w = Tensor::new(&[[1f32, 10.], [100., 1000.]], device)?;
+    let grads = (values * w)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[1., 0., 10.], [0., 100., 1000.]]);
+    Ok(())
+}
+
+fn cumulative_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[[1f32, 2., 3.], [4., 5., 6.]], device)?;
+    let x = x.as_tensor();
+    let w = Tensor::new(&[[1f32, 10., 100.], [2., 20., 200.]], device)?;
+    let grads = (x.cumsum(1)? * &w)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(
+        grad_x.to_vec2::<f32>()?,
+        [[111., 110., 100.], [222., 220., 200.]]
+    );
+
+    // y = [x0, x0.x1, x0.x1.x2] so dy/dx0 = 1 + x1 + x1.x2, dy/dx1 = x0 + x0.x2, dy/dx2 = x0.x1
+    let grads = x.cumprod(1)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[9., 4., 2.], [36., 28., 20.]]);
+
+    // The gradient is also defined when some of the inputs are zero.
+    let z = Var::new(&[[1f32, 0., 3.], [0., 0., 2.]], device)?;
+    let z = z.as_tensor();
+    let grads = (z.cumprod(1)? * &w)?.sum_all()?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[1., 310., 0.], [2., 0., 0.]]);
+    let grads = z.t()?.cumprod(0)?.sum_all()?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[1., 4., 0.], [1., 0., 0.]]);
+
+    let (values, _) = x.t()?.cummax(0)?;
+    let grads = values.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[1., 1., 1.], [1., 1., 1.]]);
+
+    // The gradient of logcumsumexp summed over the dimension is the sum of the softmax over
+    // each prefix.
+    let x = Var::new(&[0f32, 1f32.ln(), 2f32.ln()], device)?;
+    let x = x.as_tensor();
+    let grads = x.logcumsumexp(0)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let expected = [1. + 1. / 2. + 1. / 4., 1. / 2. + 1. / 4., 2. / 4.];
+    for (g, e) in grad_x.to_vec1::<f32>()?.iter().zip(expected.iter()) {
+        assert!((g - e).abs() < 1e-5, "{g} {e}")
+    }
+
+    // Inputs spanning a wide range must not overflow.
+    let x = Var::new(&[[0f32, 0., 1000.], [0., 100., -100.]], device)?;
+    let x = x.as_tensor();
+    let grads = x.logcumsumexp(1)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(
+        test_utils::to_vec2_round(grad_x, 3)?,
+        [[1.5, 0.5, 1.], [1., 2., 0.]]
+    );
+    // Same with gradients of both signs, exp(-100) is below the f32 resolution.
+    let w = Tensor::new(&[[1f32, -2., 3.], [-1., 0., 2.]], device)?;
+    let grads = (x.logcumsumexp(1)? * &w)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(
+        test_utils::to_vec2_round(grad_x, 3)?,
+        [[0., -1., 3.], [-1., 2., 0.]]
+    );
+    Ok(())
+}
+
+fn reduction_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[[1f32, 2., 4.], [3., 5., 7.]], device)?;
+    let x = x.as_tensor();
+    let grads = x.prod(1)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[8., 4., 2.], [35., 21., 15.]]);
+
+    // The gradient with zero inputs is the product of the other elements.
+    let z = Var::new(&[[1f32, 0., 4.], [3., 0., 0.]], device)?;
+    let z = z.as_tensor();
+    let grads = z.prod(1)?.sum_all()?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[0., 4., 0.], [0., 0., 0.]]);
+    let grads = z.t()?.prod_keepdim((0, 1))?.sum_all()?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[0.; 3]; 2]);
+    let z = Var::new(&[[1f32, 2.], [0., 5.]], device)?;
+    let z = z.as_tensor();
+    let grads = z.prod((0, 1))?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[0., 0.], [10., 0.]]);
+    let grads = z.prod(0)?.sum_all()?.backward()?;
+    let grad_z = grads.get(z).context("no grad for z")?;
+    assert_eq!(grad_z.to_vec2::<f32>()?, [[0., 5.], [1., 2.]]);
+
+    let grads = x.mean((0, 1))?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec2::<f32>()?, [[1. / 6.; 3]; 2]);
+
+    // d(var)/dx_i = 2 (x_i - mean) / n
+    let grads = x.var(1, false)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let expected = [[-8. / 9., -2. / 9., 10. / 9.], [-4. / 3., 0., 4. / 3.]];
+    for (g, e) in grad_x.to_vec2::<f32>()?.iter().zip(expected.iter()) {
+        for (g, e) in g.iter().zip(e.iter()) {
+            assert!((g - e).abs() < 1e-5, "{g} {e}")
+        }
+    }
+
+    // The gradient of logsumexp is the softmax of the inputs.
+    let x = Var::new(&[0f32, 2f32.ln(), 5f32.ln()], device)?;
+    let x = x.as_tensor();
+    let grads = x.logsumexp(0)?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    for (g, e) in grad_x.to_vec1::<f32>()?.iter().zip([0.125, 0.25, 0.625]) {
+        assert!((g - e).abs() < 1e-5, "{g} {e}")
+    }
+
+    // The norm gradient is x |x|^(p-2) / norm^(p-1) and zero where x or the norm is zero.
+    let x = Var::new(&[[3f32, -4.], [0., 0.], [2., 0.]], device)?;
+    let x = x.as_tensor();
+    let grads = x.norm(2., 1)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(
+        test_utils::to_vec2_round(grad_x, 4)?,
+        [[0.6, -0.8], [0., 0.], [1., 0.]]
+    );
+    let grads = x.norm(3., 1)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    // 91^(2/3) = 20.2313
+    assert_eq!(
+        test_utils::to_vec2_round(grad_x, 4)?,
+        [[0.4449, -0.7908], [0., 0.], [1., 0.]]
+    );
+    Ok(())
+}
+
+fn binary_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[3f32, -1., 4.], device)?;
+    let x = x.as_tensor();
+    let y = Var::new(&[2f32, 2., 4.], device)?;
+    let y = y.as_tensor();
+    let grads = x.maximum(y)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let grad_y = grads.get(y).context("no grad for y")?;
+    assert_eq!(grad_x.to_vec1::<f32>()?, [1., 0., 0.5]);
+    assert_eq!(grad_y.to_vec1::<f32>()?, [0., 1., 0.5]);
+
+    let grads = x.clamp(0f32, 3.5f32)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(grad_x.to_vec1::<f32>()?, [1., 0., 0.]);
+
+    let x = Var::new(&[1f32, 2., 3.], device)?;
+    let x = x.as_tensor();
+    let y = Var::new(&[2f32, 3., 0.5], device)?;
+    let y = y.as_tensor();
+    let grads = x.pow(y)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let grad_y = grads.get(y).context("no grad for y")?;
+    assert_eq!(test_utils::to_vec1_round(grad_x, 4)?, [2., 12., 0.2887]);
+    assert_eq!(test_utils::to_vec1_round(grad_y, 4)?, [0., 5.5452, 1.9029]);
+
+    let grads = x.atan2(y)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let grad_y = grads.get(y).context("no grad for y")?;
+    assert_eq!(test_utils::to_vec1_round(grad_x, 4)?, [0.4, 0.2308, 0.0541]);
+    assert_eq!(
+        test_utils::to_vec1_round(grad_y, 4)?,
+        [-0.2, -0.1538, -0.3243]
+    );
+
+    let x = Var::new(&[7f32, -7.], device)?;
+    let x = x.as_tensor();
+    let y = Var::new(&[3f32, 3.], device)?;
+    let y = y.as_tensor();
+    let grads = x.rem(y)?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    let grad_y = grads.get(y).context("no grad for y")?;
+    assert_eq!(grad_x.to_vec1::<f32>()?, [1., 1.]);
+    assert_eq!(grad_y.to_vec1::<f32>()?, [-2., 3.]);
+    Ok(())
+}
+
+fn activation_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[-1.5f32, -0.25, 0.5, 2.5], device)?;
+    let x = x.as_tensor();
+    let grad = |y: Tensor| -> Result<Vec<f32>> {
+        let grads = y.sum_all()?.backward()?;
+        let grad_x = grads.get(x).context("no grad for x")?;
+        Ok(test_utils::to_vec1_round(grad_x, 4)?)
+    };
+    assert_eq!(grad(x.gelu()?)?, [-0.1277, 0.3046, 0.8674, 1.038]);
+    assert_eq!(grad(x.gelu_erf()?)?, [-0.1275, 0.3046, 0.8675, 1.0376]);
+    assert_eq!(grad(x.tanh()?)?, [0.1807, 0.94, 0.7864, 0.0266]);
+    assert_eq!(grad(x.erf()?)?, [0.1189, 1.06, 0.8788, 0.0022]);
+    assert_eq!(grad(x.sigmoid()?)?, [0.1491, 0.2461, 0.235, 0.0701]);
+    // The derivative of softplus is the sigmoid.
+    assert_eq!(grad(x.softplus()?)?, [0.1824, 0.4378, 0.6225, 0.9241]);
+    // Rounding ops do not propagate any gradient.
+    assert_eq!(grad(((x.round()? + x.floor()?)? + x)?)?, [1., 1., 1., 1.]);
+
+    let x = Var::new(&[-0.25f32, 0.5, 2.5], device)?;
+    let x = x.as_tensor();
+    let grads = x.log1p()?.sum_all()?.backward()?;
+    let grad_x = grads.get(x).context("no grad for x")?;
+    assert_eq!(
+        test_utils::to_vec1_round(grad_x, 4)?,
+        [1.3333, 0.6667, 0.2857]
+    );
+    Ok(())
+}
+
+fn conv_transpose_grad(device: &Device) -> Result<()> {
+    let x = Var::new(&[[[0f32, 1., 2.], [3., 4., 5.]]], device)?;
+    let w = Tensor::arange(-3f32, 5., device)?.reshape((2, 2, 2))?;
+    let w = Var::from_tensor(&w)?;
+    // Weighting the output makes the gradients depend on the position of each output element.
+    let c = Tensor::arange(0f32, 10., device)?;
+    let c = (&c - (&c / 5.)?.floor()? * 5.)?.reshape((1, 2, 5))?;
+    let y = x.conv_transpose1d(&w, 1, 1, 2, 1)?;
+    let grads = y.mul(&c)?.sum_all()?.backward()?;
+    let grad_x = grads.get(&x).context("no grad for x")?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(
+        grad_x.to_vec3::<f32>()?,
+        [[[0., -8., -20.], [0., 16., 36.]]]
+    );
+    assert_eq!(
+        grad_w.to_vec3::<f32>()?,
+        [[[7., 10.], [7., 10.]], [[19., 28.], [19., 28.]]]
+    );
+
+    let y = x.conv_transpose1d(&w, 0, 0, 1, 2)?;
+    let grads = y.mul(&c)?.sum_all()?.backward()?;
+    let grad_x = grads.get(&x).context("no grad for x")?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(
+        grad_x.to_vec3::<f32>()?,
+        [[[-4., -10., -16.], [12., 22., 32.]]]
+    );
+    assert_eq!(
+        grad_w.to_vec3::<f32>()?,
+        [[[5., 11.], [5., 11.]], [[14., 38.], [14., 38.]]]
+    );
+
+    let x = Var::from_tensor(&Tensor::arange(0f32, 8., device)?.reshape((1, 2, 2, 2))?)?;
+    let w = Var::from_tensor(&Tensor::arange(-2f32, 2., device)?.reshape((2, 1, 2, 1))?)?;
+    let c = Tensor::arange(0f32, 20., device)?;
+    let c = (&c - (&c / 5.)?.floor()? * 5.)?.reshape((1, 1, 5, 4))?;
+    let y = x.conv_transpose2d(&w, 0, 1, 2, 1)?;
+    let grads = y.mul(&c)?.sum_all()?.backward()?;
+    let grad_x = grads.get(&x).context("no grad for x")?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(
+        grad_x.flatten_all()?.to_vec1::<f32>()?,
+        [-4., -5., -8., -4., 4., 1., 2., 4.]
+    );
+    assert_eq!(grad_w.flatten_all()?.to_vec1::<f32>()?, [8., 17., 28., 61.]);
+    Ok(())
+}
+
+// Deterministic inputs without ties, so that max-pooling has a well defined gradient.
+fn smooth_values<S: Into<Shape>>(shape: S, phase: f64, device: &Device) -> Result<Tensor> {
+    let shape = shape.into();
+    let t = Tensor::arange(0f64, shape.elem_count() as f64, device)?;
+    Ok(t.affine(1.7, phase)?.sin()?.reshape(shape)?)
+}
+
+// Compares the gradient of `f(x).sum()` computed by backprop against central finite differences.
+fn check_grad<F>(f: F, x: &Tensor, name: &str) -> Result<()>
+where
+    F: Fn(&Tensor) -> candle_core::Result<Tensor>,
+{
+    let x = Var::from_tensor(x)?;
+    let grads = f(&x)?.sum_all()?.backward()?;
+    let grad = grads.get(&x).context("no grad")?.flatten_all()?;
+    let grad = grad.to_vec1::<f64>()?;
+    let eps = 1e-6;
+    let values = x.flatten_all()?.to_vec1::<f64>()?;
+    for i in 0..values.len() {
+        let eval = |delta: f64| -> Result<f64> {
+            let mut values = values.clone();
+            values[i] += delta;
+            let x = Tensor::from_vec(values, x.shape(), x.device())?;
+            Ok(f(&x)?.sum_all()?.to_scalar::<f64>()?)
+        };
+        let numerical = (eval(eps)? - eval(-eps)?) / (2. * eps);
+        if (numerical - grad[i]).abs() > 1e-5 {
+            anyhow::bail!(
+                "{name}: gradient mismatch at {i}, {} vs {numerical}",
+                grad[i]
+            )
+        }
+    }
+    Ok(())
+}
+
+fn fft_grad(device: &Device) -> Result<()> {
+    // Odd and even lengths as the nyquist frequency only exists for the latter.
+    for n in [6, 7] {
+        let x = smooth_values((2, n), 0.3, device)?;
+        let c = smooth_values((2, n / 2 + 1, 2), 0.7, device)?;
+        check_grad(|x| x.rfft(1)?.mul(&c), &x, "rfft")?;
+        let c = smooth_values((2, n), 0.7, device)?;
+        let f = x.rfft(1)?;
+        check_grad(|f| f.irfft(1, n)?.mul(&c), &f, "irfft")?;
+        let x = smooth_values((n, 3, 2), 0.3, device)?;
+        let c = smooth_values((n, 3, 2), 0.7, device)?;
+        check_grad(|x| x.fft(0)?.mul(&c), &x, "fft")?;
+        check_grad(|x| x.ifft(0)?.mul(&c), &x, "ifft")?;
+    }
+    let x = smooth_values((2, 20), 0.3, device)?;
+    let w = smooth_values(8, 1.1, device)?;
+    let c = smooth_values((2, 5, 6, 2), 0.7, device)?;
+    check_grad(|x| x.stft(8, 4, Some(&w), true)?.mul(&c), &x, "stft")?;
+    Ok(())
+}
+
+fn conv_pool_grad(device: &Device) -> Result<()> {
+    let x = smooth_values((2, 4, 9), 0.3, device)?;
+    let w = smooth_values((4, 2, 3), 1.1, device)?;
+    let c = smooth_values((2, 4, 3), 0.7, device)?;
+    let f = |x: &Tensor| x.conv1d(&w, 1, 3, 2, 2)?.mul(&c);
+    check_grad(f, &x, "conv1d-input")?;
+    let f = |w: &Tensor| x.conv1d(w, 1, 3, 2, 2)?.mul(&c);
+    check_grad(f, &w, "conv1d-kernel")?;
+
+    // The input height leaves an element unused because of the stride but the width does not.
+    let x = smooth_values((2, 4, 6, 6), 0.3, device)?;
+    let w = smooth_values((4, 2, 3, 2), 1.1, device)?;
+    let c = smooth_values((2, 4, 3, 4), 0.7, device)?;
+    let f = |x: &Tensor| x.conv2d(&w, 1, 2, 1, 2)?.mul(&c);
+    check_grad(f, &x, "conv2d-input")?;
+    let f = |w: &Tensor| x.conv2d(w, 1, 2, 1, 2)?.mul(&c);
+    check_grad(f, &w, "conv2d-kernel")?;
+    let c = smooth_values((2, 4, 2, 4), 0.7, device)?;
+    let f = |x: &Tensor| x.conv2d(&w, 0, 1, 2, 2)?.mul(&c);
+    check_grad(f, &x, "conv2d-dilation")?;
+
+    let x = smooth_values((1, 4, 5, 6, 6), 0.3, device)?;
+    let w = smooth_values((2, 2, 2, 3, 1), 1.1, device)?;
+    let c = smooth_values((1, 2, 3, 3, 4), 0.7, device)?;
+    let f = |x: &Tensor| x.conv3d(&w, 1, 2, 1, 2)?.mul(&c);
+    check_grad(f, &x, "conv3d-input")?;
+    let f = |w: &Tensor| x.conv3d(w, 1, 2, 1, 2)?.mul(&c);
+    check_grad(f, &w, "conv3d-kernel")?;
+    let c = smooth_values((1, 2, 3, 2, 6), 0.7, device)?;
+    let f = |x: &Tensor| x.conv3d(&w, 0, 1, 2, 2)?.mul(&c);
+    check_grad(f, &x, "conv3d-dilation")?;
+
+    let x = smooth_values((2, 3, 7, 6), 0.3, device)?;
+    let c = smooth_values((2, 3, 3, 4), 0.7, device)?;
+    let f = |x: &Tensor| x.avg_pool2d((2, 3), (2, 1))?.mul(&c);
+    check_grad(f, &x, "avg-pool2d")?;
+    let c = smooth_values((2, 3, 3, 3), 0.7, device)?;
+    let f = |x: &Tensor| x.max_pool2d((3, 2), (2, 2))?.mul(&c);
+    check_grad(f, &x, "max-pool2d")?;
+
+    // Padding and ceil mode, the last window of each dimension only partially covers the input.
+    let x = smooth_values((2, 3, 11), 0.3, device)?;
+    let c = smooth_values((2, 3, 6), 0.7, device)?;
+    let f = |x: &Tensor| x.avg_pool_nd(&[3], &[2], &[1], true)?.mul(&c);
+    check_grad(f, &x, "avg-pool1d")?;
+    let f = |x: &Tensor| x.max_pool_nd(&[3], &[2], &[1], true)?.mul(&c);
+    check_grad(f, &x, "max-pool1d")?;
+    let x = smooth_values((1, 2, 4, 5, 3), 0.3, device)?;
+    let c = smooth_values((1, 2, 3, 3, 2), 0.7, device)?;
+    let f = |x: &Tensor| {
+        x.avg_pool_nd(&[2, 3, 2], &[2, 2, 1], &[1, 1, 0], true)?
+            .mul(&c)
+    };
+    check_grad(f, &x, "avg-pool3d")?;
+    let f = |x: &Tensor| {
+        x.max_pool_nd(&[2, 3, 2], &[2, 2, 1], &[1, 1, 0], true)?
+            .mul(&c)
+    };
+    check_grad(f, &x, "max-pool3d")?;
+    let x = smooth_values((2, 3, 7, 6), 0.3, device)?;
+    let c = smooth_values((2, 3, 3, 4), 0.7, device)?;
+    let f = |x: &Tensor| x.adaptive_avg_pool2d((3, 4))?.mul(&c);
+    check_grad(f, &x, "adaptive-avg-pool2d")?;
+
+    let x = smooth_values((2, 3, 3, 4), 0.3, device)?;
+    let c = smooth_values((2, 3, 7, 9), 0.7, device)?;
+    let f = |x: &Tensor| x.upsample_nearest2d(7, 9)?.mul(&c);
+    check_grad(f, &x, "upsample-nearest2d")?;
+    for mode in [
+        InterpolateMode::Bilinear {
+            align_corners: false,
+        },
+        InterpolateMode::Bicubic {
+            align_corners: true,
+        },
+    ] {
+        let f = |x: &Tensor| x.interpolate2d(7, 9, mode)?.mul(&c);
+        check_grad(f, &x, "interpolate2d")?;
+        let c = smooth_values((2, 3, 2, 3), 0.7, device)?;
+        let f = |x: &Tensor| x.interpolate2d(2, 3, mode)?.mul(&c);
+        check_grad(f, &x, "interpolate2d-downsample")?;
+    }
+    Ok(())
+}
+
+fn inplace_grad(device: &Device) -> Result<()> {
+    let w = Var::new(&[1f32, 2., 3.], device)?;
+    let x = Tensor::new(&[4f32, 5., 6.], device)?;
+    let y = w.mul(&x)?.sum_all()?;
+    let grads = y.backward()?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(grad_w.to_vec1::<f32>()?, [4., 5., 6.]);
+    // Modifying a tensor captured by the graph, even through a view, invalidates the graph.
+    x.narrow(0, 1, 1)?
+        .mul_inplace(&Tensor::new(&[2f32], device)?)?;
+    assert!(y.backward().is_err());
+    let y = w.mul(&x)?.sum_all()?;
+    let grads = y.backward()?;
+    let grad_w = grads.get(&w).context("no grad for w")?;
+    assert_eq!(grad_w.to_vec1::<f32>()?, [4., 10., 6.]);
+    Ok(())
+}
+
 test_device!(simple_grad, simple_grad_cpu, simple_grad_gpu);
 test_device!(sum_grad, sum_grad_cpu, sum_grad_gpu);
 test_device!(matmul_grad, matmul_grad_cpu, matmul_grad_gpu);
+test_device!(einsum_grad, einsum_grad_cpu, einsum_grad_gpu);
 test_device!(grad_descent, grad_descent_cpu, grad_descent_gpu);
 test_device!(unary_grad, unary_grad_cpu, unary_grad_gpu);
+test_device!(view_grad, view_grad_cpu, view_grad_gpu);
+test_device!(sort_grad, sort_grad_cpu, sort_grad_gpu);
+test_device!(cumulative_grad, cumulative_grad_cpu, cumulative_grad_gpu);
+test_device!(reduction_grad, reduction_grad_cpu, reduction_grad_gpu);
+test_device!(binary_grad, binary_grad_cpu, binary_grad_gpu);
+test_device!(activation_grad, activation_grad_cpu, activation_grad_gpu);
+test_device!(
+    conv_transpose_grad,
+    conv_transpose_grad_cpu,
+    conv_transpose_grad_gpu
+);
+test_device!(conv_pool_grad, conv_pool_grad_cpu, conv_pool_grad_gpu);
+test_device!(fft_grad, fft_grad_cpu, fft_grad_gpu);
+test_device!(inplace_grad, inplace_grad_cpu, inplace_grad_gpu);
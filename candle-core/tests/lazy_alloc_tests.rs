@@ -0,0 +1,166 @@
+// The allocations are counted with a global allocator, this is done in its own test binary with
+// a single test so that other tests cannot interfere with the counts.
+use candle_core::{DType, Device, Result, Tensor, D};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Allocations of at least this size are counted as tensor buffers, the smaller ones are mostly
+// shapes, strides and graph nodes.
+const BUFFER_SIZE: usize = 1024;
+
+struct Counting;
+
+static BUFFERS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= BUFFER_SIZE {
+            BUFFERS.fetch_add(1, Ordering::Relaxed);
+        }
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const HIDDEN: usize = 128;
+const INTERMEDIATE: usize = 344;
+const N_HEAD: usize = 4;
+
+struct Block {
+    attn_norm: Tensor,
+    wq: Tensor,
+    wk: Tensor,
+    wv: Tensor,
+    wo: Tensor,
+    mlp_norm: Tensor,
+    w_gate: Tensor,
+    w_up: Tensor,
+    w_down: Tensor,
+}
+
+impl Block {
+    fn new() -> Result<Self> {
+        let w = |i: usize, o: usize| {
+            (Tensor::arange(0f32, (i * o) as f32, &Device::Cpu)? * 0.37)?
+                .sin()?
+                .affine(0.05, 0.)?
+                .reshape((o, i))
+        };
+        Ok(Self {
+            attn_norm: Tensor::ones(HIDDEN, DType::F32, &Device::Cpu)?,
+            wq: w(HIDDEN, HIDDEN)?,
+            wk: w(HIDDEN, HIDDEN)?.flip(0)?.contiguous()?,
+            wv: w(HIDDEN, HIDDEN)?.affine(-1., 0.)?,
+            wo: w(HIDDEN, HIDDEN)?.flip(1)?.contiguous()?,
+            mlp_norm: Tensor::ones(HIDDEN, DType::F32, &Device::Cpu)?,
+            w_gate: w(HIDDEN, INTERMEDIATE)?,
+            w_up: w(HIDDEN, INTERMEDIATE)?.flip(0)?.contiguous()?,
+            w_down: w(INTERMEDIATE, HIDDEN)?,
+        })
+    }
+
+    fn to_device(&self, device: &Device) -> Result<Self> {
+        Ok(Self {
+            attn_norm: self.attn_norm.to_device(device)?,
+            wq: self.wq.to_device(device)?,
+            wk: self.wk.to_device(device)?,
+            wv: self.wv.to_device(device)?,
+            wo: self.wo.to_device(device)?,
+            mlp_norm: self.mlp_norm.to_device(device)?,
+            w_gate: self.w_gate.to_device(device)?,
+            w_up: self.w_up.to_device(device)?,
+            w_down: self.w_down.to_device(device)?,
+        })
+    }
+
+    // A llama decoder layer: rms-norm, causal self-attention, rms-norm and a silu gated mlp.
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let (seq_len, hidden) = xs.dims2()?;
+        let head_dim = hidden / N_HEAD;
+        let heads = |t: Tensor| t.reshape((seq_len, N_HEAD, head_dim))?.transpose(0, 1);
+        let h = rms_norm(xs, &self.attn_norm)?;
+        let q = heads(h.matmul(&self.wq.t()?)?)?;
+        let k = heads(h.matmul(&self.wk.t()?)?)?;
+        let v = heads(h.matmul(&self.wv.t()?)?)?;
+        let att = (q.matmul(&k.t()?)? / (head_dim as f64).sqrt())?;
+        let mask: Vec<f32> = (0..seq_len * seq_len)
+            .map(|i| {
+                if i % seq_len > i / seq_len {
+                    f32::NEG_INFINITY
+                } else {
+                    0.
+                }
+            })
+            .collect();
+        let mask = Tensor::from_vec(mask, (seq_len, seq_len), xs.device())?;
+        let att = att.broadcast_add(&mask)?;
+        let att = att.broadcast_sub(&att.max_keepdim(D::Minus1)?)?.exp()?;
+        let att = att.broadcast_div(&att.sum_keepdim(D::Minus1)?)?;
+        let ys = att
+            .matmul(&v)?
+            .transpose(0, 1)?
+            .reshape((seq_len, hidden))?;
+        let xs = (xs + ys.matmul(&self.wo.t()?)?)?;
+        let h = rms_norm(&xs, &self.mlp_norm)?;
+        let gate = h.matmul(&self.w_gate.t()?)?;
+        let silu = (&gate / (gate.neg()?.exp()? + 1.)?)?;
+        let ys = (silu * h.matmul(&self.w_up.t()?)?)?.matmul(&self.w_down.t()?)?;
+        xs + ys
+    }
+}
+
+fn rms_norm(xs: &Tensor, weight: &Tensor) -> Result<Tensor> {
+    let norm = (xs.sqr()?.mean_keepdim(D::Minus1)? + 1e-5)?.sqrt()?;
+    xs.broadcast_div(&norm)?.broadcast_mul(weight)
+}
+
+// Runs a few layers and returns the result together with the number of tensor buffers and bytes
+// allocated while doing so.
+fn run(block: &Block, device: &Device) -> Result<(Vec<f32>, usize, usize)> {
+    let block = block.to_device(device)?;
+    let xs = (Tensor::arange(0f32, (16 * HIDDEN) as f32, &Device::Cpu)? * 0.01)?
+        .cos()?
+        .reshape((16, HIDDEN))?
+        .to_device(device)?;
+    let (buffers, bytes) = (
+        BUFFERS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    let mut ys = xs;
+    for _ in 0..4 {
+        ys = block.forward(&ys)?;
+    }
+    let ys = ys.flatten_all()?.to_vec1::<f32>()?;
+    let buffers = BUFFERS.load(Ordering::Relaxed) - buffers;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+    Ok((ys, buffers, bytes))
+}
+
+#[test]
+fn lazy_llama_allocations() -> Result<()> {
+    let block = Block::new()?;
+    let (eager, eager_buffers, eager_bytes) = run(&block, &Device::Cpu)?;
+    let (lazy, lazy_buffers, lazy_bytes) = run(&block, &Device::Lazy)?;
+    for (e, l) in eager.iter().zip(lazy.iter()) {
+        assert!((e - l).abs() < 1e-3 * e.abs().max(1.), "{e} {l}");
+    }
+    // The fused elementwise chains do not materialize their intermediary values and the other
+    // buffers are reused once their last consumer has run.
+    assert!(
+        3 * lazy_buffers < 2 * eager_buffers,
+        "buffers lazy {lazy_buffers} eager {eager_buffers}"
+    );
+    assert!(
+        2 * lazy_bytes < eager_bytes,
+        "bytes lazy {lazy_bytes} eager {eager_bytes}"
+    );
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use candle_core::{DType, Device, IndexOp, Result, Storage, Tensor, Var, D};
+
+fn lazy_and_cpu(data: &[f32], shape: (usize, usize)) -> Result<(Tensor, Tensor)> {
+    let cpu = Tensor::from_slice(data, shape, &Device::Cpu)?;
+    let lazy = cpu.to_device(&Device::Lazy)?;
+    Ok((cpu, lazy))
+}
+
+fn assert_same(lazy: &Tensor, cpu: &Tensor) -> Result<()> {
+    assert!(lazy.device().is_lazy());
+    assert_eq!(lazy.dims(), cpu.dims());
+    let lazy = lazy.flatten_all()?.to_dtype(DType::F32)?.to_vec1::<f32>()?;
+    let cpu = cpu.flatten_all()?.to_dtype(DType::F32)?.to_vec1::<f32>()?;
+    for (l, c) in lazy.iter().zip(cpu.iter()) {
+        assert!((l - c).abs() < 1e-5, "{lazy:?} {cpu:?}");
+    }
+    Ok(())
+}
+
+fn is_ready(t: &Tensor) -> bool {
+    match &*t.storage_and_layout().0 {
+        Storage::Lazy(s) => s.is_ready(),
+        _ => true,
+    }
+}
+
+#[test]
+fn elementwise_chain() -> Result<()> {
+    let data: Vec<f32> = (0..24).map(|v| v as f32 / 7. - 1.).collect();
+    let (cpu, lazy) = lazy_and_cpu(&data, (4, 6))?;
+    let f = |t: &Tensor| -> Result<Tensor> {
+        let u = t.exp()?.sqr()?.affine(0.5, -0.25)?;
+        let v = (&u + t.neg()?)?.mul(t)?;
+        (v.abs()?.sqrt()? / 3.)?.gelu()
+    };
+    let res = f(&lazy)?;
+    assert!(!is_ready(&res));
+    assert_same(&res, &f(&cpu)?)?;
+    Ok(())
+}
+
+#[test]
+fn broadcast_and_strided() -> Result<()> {
+    let data: Vec<f32> = (0..12).map(|v| v as f32).collect();
+    let (cpu, lazy) = lazy_and_cpu(&data, (3, 4))?;
+    let f = |t: &Tensor| -> Result<Tensor> {
+        let row = t.narrow(0, 1, 1)?;
+        let v = t.broadcast_sub(&row)?.t()?;
+        let v = (v.relu()? + 1.)?;
+        Tensor::cat(&[&v, &v.sqr()?], 1)
+    };
+    assert_same(&f(&lazy)?, &f(&cpu)?)
+}
+
+#[test]
+fn matmul_softmax() -> Result<()> {
+    let data: Vec<f32> = (0..16).map(|v| (v as f32 * 0.37).sin()).collect();
+    let (cpu, lazy) = lazy_and_cpu(&data, (4, 4))?;
+    let f = |t: &Tensor| -> Result<Tensor> {
+        let w = t.matmul(&t.t()?)?;
+        let max = w.max_keepdim(D::Minus1)?;
+        let e = w.broadcast_sub(&max)?.exp()?;
+        let sm = e.broadcast_div(&e.sum_keepdim(D::Minus1)?)?;
+        let mask = sm.ge(&sm.ones_like()?.affine(0.25, 0.)?)?;
+        mask.where_cond(&sm, &sm.zeros_like()?)?.argmax(1)
+    };
+    assert_same(&f(&lazy)?, &f(&cpu)?)
+}
+
+#[test]
+fn shared_intermediate() -> Result<()> {
+    let data: Vec<f32> = (0..8).map(|v| v as f32).collect();
+    let (cpu, lazy) = lazy_and_cpu(&data, (2, 4))?;
+    let shared = lazy.exp()?.affine(2., 1.)?;
+    let a = shared.log()?;
+    let b = (&shared + &lazy)?;
+    assert_same(&a, &cpu.exp()?.affine(2., 1.)?.log()?)?;
+    // The intermediate value is still referenced, so it has been cached.
+    assert!(is_ready(&shared));
+    assert!(!is_ready(&b));
+    assert_same(&b, &(cpu.exp()?.affine(2., 1.)? + &cpu)?)?;
+    assert!(is_ready(&b));
+    // Realizing a computed value a second time is a no-op.
+    assert_same(&b, &(cpu.exp()?.affine(2., 1.)? + &cpu)?)?;
+    Ok(())
+}
+
+#[test]
+fn device_round_trip() -> Result<()> {
+    let t = Tensor::arange(0u32, 6, &Device::Lazy)?.reshape((2, 3))?;
+    let t = (t.to_dtype(DType::F32)? * 2.)?;
+    let cpu = t.to_device(&Device::Cpu)?;
+    assert!(cpu.device().is_cpu());
+    assert_eq!(cpu.to_vec2::<f32>()?, [[0., 2., 4.], [6., 8., 10.]]);
+    let z = Tensor::ones((2, 2), DType::F64, &Device::Lazy)?;
+    assert_eq!(z.sum_all()?.to_vec0::<f64>()?, 4.);
+    Ok(())
+}
+
+#[test]
+fn var_set() -> Result<()> {
+    let var = Var::zeros((2, 3), DType::F32, &Device::Lazy)?;
+    let src = (Tensor::ones((2, 3), DType::F32, &Device::Lazy)? * 3.)?;
+    var.set(&src)?;
+    let res = (var.as_tensor() + 1.)?;
+    assert_eq!(res.to_vec2::<f32>()?, [[4., 4., 4.], [4., 4., 4.]]);
+    Ok(())
+}
+
+#[test]
+fn inplace_updates() -> Result<()> {
+    let data: Vec<f32> = (0..12).map(|v| v as f32 / 4.).collect();
+    let (cpu, lazy) = lazy_and_cpu(&data, (3, 4))?;
+    // Results computed before an update keep seeing the previous values.
+    let before = lazy.exp()?;
+    let rhs = Tensor::new(&[1f32, 2., 3., 4.], &Device::Lazy)?;
+    lazy.mul_inplace(&rhs)?;
+    lazy.i((.., 1..3))?.neg_inplace()?;
+    lazy.slice_assign((1.., ..2), &lazy.i((..2, 2..))?)?;
+    assert!(!is_ready(&lazy));
+
+    let rhs = rhs.to_device(&Device::Cpu)?;
+    let expected = cpu.exp()?;
+    cpu.mul_inplace(&rhs)?;
+    cpu.i((.., 1..3))?.neg_inplace()?;
+    cpu.slice_assign((1.., ..2), &cpu.i((..2, 2..))?)?;
+    assert_same(&before, &expected)?;
+    assert_same(&lazy, &cpu)?;
+    Ok(())
+}
@@ -0,0 +1,217 @@
+mod test_utils;
+use candle_core::{DType, Device, Result, Tensor};
+use test_utils::{to_vec1_round, to_vec2_round, to_vec3_round};
+
+fn eye(n: usize, dev: &Device) -> Result<Tensor> {
+    let data: Vec<f32> = (0..n * n)
+        .map(|i| if i / n == i % n { 1. } else { 0. })
+        .collect();
+    Tensor::from_vec(data, (n, n), dev)
+}
+
+fn solve(dev: &Device) -> Result<()> {
+    let a = Tensor::new(&[[0f32, 2., 1.], [1., 1., 1.], [2., 1., -1.]], dev)?;
+    let b = Tensor::new(&[[7f32, 1.], [6., 0.], [1., 2.]], dev)?;
+    let x = a.solve(&b)?;
+    assert_eq!(to_vec2_round(&x, 4)?, [[1.0, 0.0], [2.0, 1.0], [3.0, -1.0]]);
+    // Batched systems, the second matrix requires pivoting.
+    let a = Tensor::new(&[[[2f64, 1.], [1., 3.]], [[0., 1.], [1., 0.]]], dev)?;
+    let b = Tensor::new(&[[[3f64], [5.]], [[4.], [5.]]], dev)?;
+    let x = a.solve(&b)?;
+    assert_eq!(x.dims(), [2, 2, 1]);
+    assert_eq!(x.dtype(), DType::F64);
+    let x = x.flatten_all()?.to_vec1::<f64>()?;
+    let x: Vec<f64> = x.iter().map(|v| (v * 1e6).round() / 1e6).collect();
+    assert_eq!(x, [0.8, 1.4, 5., 4.]);
+
+    let singular = Tensor::new(&[[1f32, 2.], [2., 4.]], dev)?;
+    let b = Tensor::new(&[[1f32], [1.]], dev)?;
+    assert!(singular.solve(&b).is_err());
+    let b = Tensor::new(&[[1f32], [1.], [1.]], dev)?;
+    assert!(singular.solve(&b).is_err());
+    Ok(())
+}
+
+fn inv(dev: &Device) -> Result<()> {
+    let a = Tensor::new(&[[4f32, 7.], [2., 6.]], dev)?;
+    let inv = a.inv()?;
+    assert_eq!(to_vec2_round(&inv, 4)?, [[0.6, -0.7], [-0.2, 0.4]]);
+    let a = Tensor::new(
+        &[
+            [1f32, 2., 0., 1.],
+            [3., -1., 2., 0.],
+            [0., 1., 4., 2.],
+            [2., 0., 1., 5.],
+        ],
+        dev,
+    )?;
+    let prod = a.matmul(&a.inv()?)?;
+    assert_eq!(to_vec2_round(&prod, 4)?, to_vec2_round(&eye(4, dev)?, 4)?);
+    assert!(Tensor::new(&[[1f32, 2.], [2., 4.]], dev)?.inv().is_err());
+    Ok(())
+}
+
+fn det(dev: &Device) -> Result<()> {
+    let a = Tensor::new(&[[6f32, 1., 1.], [4., -2., 5.], [2., 8., 7.]], dev)?;
+    let det = a.det()?;
+    assert_eq!(det.dims(), [] as [usize; 0]);
+    assert_eq!((det.to_scalar::<f32>()? * 1e3).round() / 1e3, -306.);
+    let a = Tensor::new(
+        &[
+            [[0f32, 1.], [1., 0.]],
+            [[1., 2.], [2., 4.]],
+            [[2., 0.], [0., 3.]],
+        ],
+        dev,
+    )?;
+    assert_eq!(to_vec1_round(&a.det()?, 4)?, [-1., 0., 6.]);
+    Ok(())
+}
+
+fn cholesky(dev: &Device) -> Result<()> {
+    let a = Tensor::new(
+        &[[4f32, 12., -16.], [12., 37., -43.], [-16., -43., 98.]],
+        dev,
+    )?;
+    let l = a.cholesky()?;
+    assert_eq!(
+        to_vec2_round(&l, 4)?,
+        [[2., 0., 0.], [6., 1., 0.], [-8., 5., 3.]]
+    );
+    // Only the lower triangle is used.
+    let a = Tensor::new(&[[4f32, 100.], [2., 2.]], dev)?;
+    assert_eq!(to_vec2_round(&a.cholesky()?, 4)?, [[2., 0.], [1., 1.]]);
+    let not_pd = Tensor::new(&[[1f32, 2.], [2., 1.]], dev)?;
+    assert!(not_pd.cholesky().is_err());
+    Ok(())
+}
+
+fn qr(dev: &Device) -> Result<()> {
+    let a = Tensor::new(
+        &[[12f32, -51., 4.], [6., 167., -68.], [-4., 24., -41.]],
+        dev,
+    )?;
+    let (q, r) = a.qr()?;
+    assert_eq!(
+        to_vec2_round(&r.abs()?, 3)?,
+        [[14., 21., 14.], [0., 175., 70.], [0., 0., 35.]]
+    );
+    assert_eq!(to_vec2_round(&q.matmul(&r)?, 3)?, to_vec2_round(&a, 3)?);
+
+    // Tall and wide matrices in a batch.
+    let a = Tensor::arange(0f32, 24., dev)?.reshape((2, 4, 3))?.sin()?;
+    let (q, r) = a.qr()?;
+    assert_eq!(q.dims(), [2, 4, 3]);
+    assert_eq!(r.dims(), [2, 3, 3]);
+    assert_eq!(
+        to_vec3_round(q.matmul(&r)?, 4)?,
+        to_vec3_round(a.clone(), 4)?
+    );
+    let qtq = q.t()?.matmul(&q)?;
+    assert_eq!(
+        to_vec3_round(qtq, 4)?,
+        to_vec3_round(eye(3, dev)?.broadcast_as((2, 3, 3))?, 4)?
+    );
+    for r in r.to_vec3::<f32>()? {
+        assert_eq!([r[1][0], r[2][0], r[2][1]], [0., 0., 0.]);
+    }
+
+    let a = a.t()?;
+    let (q, r) = a.qr()?;
+    assert_eq!(q.dims(), [2, 3, 3]);
+    assert_eq!(r.dims(), [2, 3, 4]);
+    assert_eq!(to_vec3_round(q.matmul(&r)?, 4)?, to_vec3_round(a, 4)?);
+    Ok(())
+}
+
+fn svd(dev: &Device) -> Result<()> {
+    let a = Tensor::new(&[[3f32, 2., 2.], [2., 3., -2.]], dev)?;
+    let (u, s, vt) = a.svd()?;
+    assert_eq!(u.dims(), [2, 2]);
+    assert_eq!(vt.dims(), [2, 3]);
+    assert_eq!(to_vec1_round(&s, 4)?, [5., 3.]);
+    let us = u.broadcast_mul(&s.unsqueeze(0)?)?;
+    assert_eq!(to_vec2_round(&us.matmul(&vt)?, 4)?, to_vec2_round(&a, 4)?);
+    let vvt = vt.matmul(&vt.t()?)?;
+    assert_eq!(to_vec2_round(&vvt, 4)?, to_vec2_round(&eye(2, dev)?, 4)?);
+
+    // A batch of tall matrices, the second one has rank 1.
+    let a = Tensor::new(
+        &[
+            [[1f32, 2., 3.], [4., 5., 6.], [7., 8., 10.], [1., 0., 1.]],
+            [[1., 2., 3.], [2., 4., 6.], [0., 0., 0.], [-1., -2., -3.]],
+        ],
+        dev,
+    )?;
+    let (u, s, vt) = a.svd()?;
+    assert_eq!(u.dims(), [2, 4, 3]);
+    assert_eq!(s.dims(), [2, 3]);
+    assert_eq!(vt.dims(), [2, 3, 3]);
+    let s_ = s.to_vec2::<f32>()?;
+    assert!(s_[0][0] >= s_[0][1] && s_[0][1] >= s_[0][2]);
+    assert_eq!(to_vec1_round(&s.get(1)?, 3)?, [9.165, 0., 0.]);
+    let us = u.broadcast_mul(&s.unsqueeze(1)?)?;
+    assert_eq!(to_vec3_round(us.matmul(&vt)?, 4)?, to_vec3_round(a, 4)?);
+    let utu = u.t()?.matmul(&u)?;
+    assert_eq!(
+        to_vec3_round(utu, 4)?,
+        to_vec3_round(eye(3, dev)?.broadcast_as((2, 3, 3))?, 4)?
+    );
+    Ok(())
+}
+
+fn eigh(dev: &Device) -> Result<()> {
+    let a = Tensor::new(&[[2f32, 1.], [1., 2.]], dev)?;
+    let (w, v) = a.eigh()?;
+    assert_eq!(to_vec1_round(&w, 4)?, [1., 3.]);
+    let c = (f32::sqrt(0.5) * 1e4).round() / 1e4;
+    assert_eq!(to_vec2_round(&v.abs()?, 4)?, [[c, c], [c, c]]);
+
+    let a = Tensor::new(
+        &[
+            [[4f32, 1., -2.], [1., 2., 0.], [-2., 0., 3.]],
+            [[1., 0., 0.], [0., -3., 0.], [0., 0., 2.]],
+        ],
+        dev,
+    )?;
+    let (w, v) = a.eigh()?;
+    assert_eq!(w.dims(), [2, 3]);
+    assert_eq!(to_vec1_round(&w.get(1)?, 4)?, [-3., 1., 2.]);
+    // a v = v diag(w)
+    let av = a.matmul(&v)?;
+    let vw = v.broadcast_mul(&w.unsqueeze(1)?)?;
+    assert_eq!(to_vec3_round(av, 4)?, to_vec3_round(vw, 4)?);
+    let vtv = v.t()?.matmul(&v)?;
+    assert_eq!(
+        to_vec3_round(vtv, 4)?,
+        to_vec3_round(eye(3, dev)?.broadcast_as((2, 3, 3))?, 4)?
+    );
+    Ok(())
+}
+
+#[test]
+fn linalg_errors() -> Result<()> {
+    let dev = &Device::Cpu;
+    assert!(Tensor::new(&[[1u32, 2], [3, 4]], dev)?.det().is_err());
+    assert!(Tensor::new(&[1f32, 2.], dev)?.svd().is_err());
+    assert!(Tensor::zeros((2, 3), DType::F32, dev)?.inv().is_err());
+    let a = Tensor::ones((2, 2), DType::F32, dev)?;
+    assert!(a.solve(&Tensor::ones((2, 2), DType::F64, dev)?).is_err());
+    // The native jacobi iterations never converge on nan values.
+    if cfg!(not(any(feature = "mkl", feature = "accelerate"))) {
+        let a = Tensor::new(&[[1f64, f64::NAN], [f64::NAN, 3.]], dev)?;
+        let err = a.svd().unwrap_err().to_string();
+        assert!(err.contains("did not converge"), "{err}");
+        let err = a.eigh().unwrap_err().to_string();
+        assert!(err.contains("did not converge"), "{err}");
+    }
+    Ok(())
+}
+
+test_device!(solve, solve_cpu, solve_gpu);
+test_device!(inv, inv_cpu, inv_gpu);
+test_device!(det, det_cpu, det_gpu);
+test_device!(cholesky, cholesky_cpu, cholesky_gpu);
+test_device!(qr, qr_cpu, qr_gpu);
+test_device!(svd, svd_cpu, svd_gpu);
+test_device!(eigh, eigh_cpu, eigh_gpu);
@@ -59,3 +59,293 @@ fn avg_pool2d_pytorch() -> anyhow::Result<()> {
     assert_eq!(test_utils::to_vec3_round(pool, 4)?, [[[0.085]], [[0.0078]]]);
     Ok(())
 }
+
+fn pytorch_input() -> candle_core::Result<Tensor> {
+    Tensor::new(
+        &[
+            0.4056f32, -0.8689, -0.0773, -1.5630, -2.8012, -1.5059, 0.3972, 1.0852, 0.4997, 3.0616,
+            1.6541, 0.0964, -0.8338, -1.6523, -0.8323, -0.1699, 0.0823, 0.3526, 0.6843, 0.2395,
+            1.2279, -0.9287, -1.7030, 0.1370, 0.6047, 0.3770, -0.6266, 0.3529, 2.2013, -0.6836,
+            0.2477, 1.3127,
+        ],
+        &Device::Cpu,
+    )
+}
+
+/* The expected values match the following PyTorch calls where t is the input of the
+avg_pool2d_pytorch test reshaped as (1, 2, 16).
+torch.nn.functional.avg_pool1d(t, 3, 2, 1)
+torch.nn.functional.avg_pool1d(t, 3, 2, 1, ceil_mode=True)
+torch.nn.functional.max_pool1d(t, 4, 3, 0, ceil_mode=True)
+*/
+#[test]
+fn pool1d() -> anyhow::Result<()> {
+    let t = pytorch_input()?.reshape((1, 2, 16))?;
+    let pool = t.avg_pool_nd(&[3], &[2], &[1], false)?;
+    assert_eq!(pool.dims(), [1, 2, 8]);
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            -0.1544, -0.8364, -1.9567, -0.0078, 1.5488, 1.604, -0.7966, -0.8848, 0.145, 0.4255,
+            0.1796, -0.8316, 0.3729, 0.0344, 0.6235, 0.2923
+        ]
+    );
+    let pool = t.avg_pool_nd(&[3], &[2], &[1], true)?;
+    assert_eq!(pool.dims(), [1, 2, 9]);
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            -0.1544, -0.8364, -1.9567, -0.0078, 1.5488, 1.604, -0.7966, -0.8848, -0.085, 0.145,
+            0.4255, 0.1796, -0.8316, 0.3729, 0.0344, 0.6235, 0.2923, 0.6564
+        ]
+    );
+    let pool = t.max_pool_nd(&[4], &[3], &[0], true)?;
+    assert_eq!(
+        test_utils::to_vec3_round(pool, 4)?,
+        [[
+            [0.4056, 0.3972, 3.0616, 3.0616, -0.1699],
+            [0.6843, 1.2279, 0.6047, 2.2013, 2.2013]
+        ]]
+    );
+    let pool = t.max_pool1d(3, 2)?;
+    assert_eq!(pool.dims(), [1, 2, 7]);
+    let pool = t.avg_pool1d(4, 4)?;
+    assert_eq!(
+        test_utils::to_vec3_round(pool, 4)?,
+        [[
+            [-0.5259, -0.7062, 1.3279, -0.8721],
+            [0.3397, -0.3167, 0.177, 0.7695]
+        ]]
+    );
+    Ok(())
+}
+
+/* torch.nn.functional.avg_pool2d(t, (3, 2), 2, 1, ceil_mode=True)
+torch.nn.functional.max_pool2d(t, (3, 2), 2, 1, ceil_mode=True)
+torch.nn.functional.avg_pool2d(t, (2, 3), (3, 2), (1, 0))
+*/
+#[test]
+fn pool2d_padding_ceil_mode() -> anyhow::Result<()> {
+    let t = pytorch_input()?.reshape((1, 2, 4, 4))?;
+    let pool = t.avg_pool_nd(&[3, 2], &[2, 2], &[1, 1], true)?;
+    assert_eq!(pool.dims(), [1, 2, 3, 3]);
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            -0.3993, -0.3425, -0.0796, -0.5226, 0.1871, 0.1686, -0.2085, -0.6212, -0.0425, 0.2184,
+            -0.2658, 0.0628, 0.6723, -0.5529, 0.3004, 0.5503, -0.109, 0.3282
+        ]
+    );
+    let pool = t.max_pool_nd(&[3, 2], &[2, 2], &[1, 1], true)?;
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            0.4056, 0.3972, 1.0852, 0.4997, 3.0616, 1.0852, -0.8338, -0.8323, -0.1699, 1.2279,
+            0.6843, 0.2395, 2.2013, 0.377, 1.3127, 2.2013, 0.2477, 1.3127
+        ]
+    );
+    let pool = t.avg_pool_nd(&[2, 3], &[3, 2], &[1, 0], false)?;
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [-0.0901, 0.3162, 0.1865, 0.3534]
+    );
+    assert!(t.avg_pool_nd(&[2, 2], &[2, 2], &[2, 0], false).is_err());
+    Ok(())
+}
+
+/* t is reshaped as (1, 1, 2, 4, 4).
+torch.nn.functional.avg_pool3d(t, 2, (1, 2, 2))
+torch.nn.functional.max_pool3d(t, 2, (1, 2, 2))
+torch.nn.functional.avg_pool3d(t, (2, 3, 2), (2, 2, 3), 1, ceil_mode=True)
+torch.nn.functional.max_pool3d(t, (2, 3, 2), (2, 2, 3), 1, ceil_mode=True)
+*/
+#[test]
+fn pool3d() -> anyhow::Result<()> {
+    let t = pytorch_input()?.reshape((1, 1, 2, 4, 4))?;
+    let pool = t.avg_pool3d((2, 2, 2), (1, 2, 2))?;
+    assert_eq!(pool.dims(), [1, 1, 1, 2, 2]);
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [-0.5045, -0.1, 0.4468, 0.2544]
+    );
+    let pool = t.max_pool3d((2, 2, 2), (1, 2, 2))?;
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [1.2279, 1.0852, 3.0616, 1.6541]
+    );
+    let pool = t.avg_pool_nd(&[2, 3, 2], &[2, 2, 3], &[1, 1, 1], true)?;
+    assert_eq!(pool.dims(), [1, 1, 2, 3, 2]);
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            -0.1996, -0.0132, -0.2613, 0.1859, -0.1042, -0.1253, 0.1092, -0.0535, 0.3362, -0.0233,
+            0.2752, 0.1951
+        ]
+    );
+    let pool = t.max_pool_nd(&[2, 3, 2], &[2, 2, 3], &[1, 1, 1], true)?;
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        [
+            0.4056, 1.0852, 0.4997, 1.6541, -0.8338, -0.1699, 1.2279, 0.6843, 2.2013, 1.3127,
+            2.2013, 1.3127
+        ]
+    );
+    Ok(())
+}
+
+// torch.nn.functional.adaptive_avg_pool2d(t, (3, 2))
+#[test]
+fn adaptive_avg_pool2d() -> anyhow::Result<()> {
+    let t = pytorch_input()?.reshape((1, 2, 4, 4))?;
+    let pool = t.adaptive_avg_pool2d((3, 2))?.squeeze(0)?;
+    assert_eq!(
+        test_utils::to_vec3_round(pool, 4)?,
+        [
+            [[-1.1926, -0.0395], [-0.1864, 0.8082], [0.2688, 0.1871]],
+            [[0.1835, -0.1605], [0.3202, -0.4599], [0.6248, 0.3217]]
+        ]
+    );
+    let pool = t.adaptive_avg_pool2d((2, 2))?;
+    let expected = t.avg_pool2d((2, 2), (2, 2))?;
+    assert_eq!(
+        pool.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+    let pool = t.adaptive_avg_pool2d((1, 1))?;
+    let expected = t.mean_keepdim((2, 3))?;
+    assert_eq!(
+        test_utils::to_vec1_round(&pool.flatten_all()?, 4)?,
+        test_utils::to_vec1_round(&expected.flatten_all()?, 4)?
+    );
+    Ok(())
+}
+
+#[test]
+fn upsample_nearest2d() -> anyhow::Result<()> {
+    let t = Tensor::arange(0f32, 6., &Device::Cpu)?.reshape((1, 1, 2, 3))?;
+    let upsampled = t.upsample_nearest2d(4, 6)?.squeeze(0)?.squeeze(0)?;
+    assert_eq!(
+        upsampled.to_vec2::<f32>()?,
+        [
+            [0f32, 0., 1., 1., 2., 2.],
+            [0., 0., 1., 1., 2., 2.],
+            [3., 3., 4., 4., 5., 5.],
+            [3., 3., 4., 4., 5., 5.]
+        ]
+    );
+    let upsampled = t.upsample_nearest2d(3, 2)?.squeeze(0)?.squeeze(0)?;
+    assert_eq!(
+        upsampled.to_vec2::<f32>()?,
+        [[0f32, 1.], [0., 1.], [3., 4.]]
+    );
+    Ok(())
+}
+
+/* The expected values match the following PyTorch calls.
+t = torch.tensor([0.4056, -0.8689, -0.0773, -1.5630, -2.8012, -1.5059, 0.3972, 1.0852, 0.4997,
+    3.0616, 1.6541, 0.0964]).reshape(1, 1, 3, 4)
+interpolate(t, (5, 3), mode="bilinear", align_corners=False)
+interpolate(t, (5, 3), mode="bilinear", align_corners=True)
+interpolate(t, (5, 6), mode="bicubic", align_corners=False)
+interpolate(t, (5, 6), mode="bicubic", align_corners=True)
+interpolate(t, (2, 3), mode="bicubic", align_corners=False)
+*/
+#[test]
+fn interpolate2d() -> anyhow::Result<()> {
+    use candle_core::InterpolateMode::{Bicubic, Bilinear, Nearest};
+    let t = pytorch_input()?.narrow(0, 0, 12)?.reshape((1, 1, 3, 4))?;
+    let interpolate = |h: usize, w: usize, mode| -> anyhow::Result<Vec<f32>> {
+        let res = t.interpolate2d(h, w, mode)?;
+        assert_eq!(res.dims(), [1, 1, h, w]);
+        Ok(test_utils::to_vec1_round(&res.flatten_all()?, 4)?)
+    };
+    assert_eq!(
+        interpolate(
+            5,
+            3,
+            Bilinear {
+                align_corners: false
+            }
+        )?,
+        [
+            0.1932, -0.4731, -1.3154, -0.9182, -0.5056, -0.401, -2.5853, -0.5544, 0.9705, -0.4781,
+            1.193, 0.6018, 0.9267, 2.3579, 0.356
+        ]
+    );
+    assert_eq!(
+        interpolate(
+            5,
+            3,
+            Bilinear {
+                align_corners: true
+            }
+        )?,
+        [
+            0.4056, -0.4731, -1.563, -1.1978, -0.5137, -0.2389, -2.8012, -0.5544, 1.0852, -1.1507,
+            0.9018, 0.5908, 0.4997, 2.3579, 0.0964
+        ]
+    );
+    assert_eq!(
+        interpolate(
+            5,
+            6,
+            Bicubic {
+                align_corners: false
+            }
+        )?,
+        [
+            0.8455, -0.1114, -0.8121, -0.1005, -0.9058, -1.9643, -1.0443, -1.3976, -1.2589,
+            -0.1581, -0.087, -0.506, -2.9136, -2.332, -1.1323, 0.0868, 0.9196, 1.1449, -1.2077,
+            0.1198, 1.4424, 1.2919, 0.9316, 0.6247, 0.5836, 2.3201, 3.5162, 2.2229, 0.7264,
+            -0.1525
+        ]
+    );
+    assert_eq!(
+        interpolate(
+            5,
+            6,
+            Bicubic {
+                align_corners: true
+            }
+        )?,
+        [
+            0.4056, -0.4599, -0.8163, -0.1046, -0.5683, -1.563, -1.5073, -1.7526, -1.3297, -0.2342,
+            0.1481, -0.1462, -2.8012, -2.214, -1.0631, 0.0273, 0.8697, 1.0852, -1.4514, -0.1617,
+            0.9939, 1.0824, 1.0185, 0.8391, 0.4997, 2.2196, 3.0972, 2.1128, 0.8977, 0.0964
+        ]
+    );
+    assert_eq!(
+        interpolate(
+            2,
+            3,
+            Bicubic {
+                align_corners: false
+            }
+        )?,
+        [-0.5629, -0.5773, -0.7769, -0.0107, 2.0081, 0.5424]
+    );
+    assert_eq!(
+        interpolate(6, 8, Nearest)?,
+        test_utils::to_vec1_round(&t.upsample_nearest2d(6, 8)?.flatten_all()?, 4)?
+    );
+
+    let res = t.interpolate2d_with_scale(
+        1.5,
+        0.5,
+        Bilinear {
+            align_corners: false,
+        },
+    )?;
+    assert_eq!(res.dims(), [1, 1, 4, 2]);
+
+    let empty = Tensor::zeros((1, 1, 0, 3), candle_core::DType::F32, &Device::Cpu)?;
+    let err = empty.interpolate2d(
+        2,
+        3,
+        Bicubic {
+            align_corners: false,
+        },
+    );
+    assert!(err.unwrap_err().to_string().contains("empty input"));
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use candle_core::{pickle, DType, Result};
+use std::io::Write;
+
+// The pickled content of `torch.save({"epoch": 3, "model_state_dict": sd}, ...)` where `sd` is a
+// state dict with a (2, 3) weight, a transposed view of the same storage and a (3,) bias stored
+// at offset 1 of a storage of 4 elements.
+const DATA_PKL: &[u8] = b"\x80\x02}q\x00(X\x05\x00\x00\x00epochq\x01K\x03X\x10\x00\x00\x00model_state_dictq\x02ccollections\nOrderedDict\nq\x03)Rq\x04(X\r\x00\x00\x00linear.weightq\x05ctorch._utils\n_rebuild_tensor_v2\nq\x06((X\x07\x00\x00\x00storageq\x07ctorch\nFloatStorage\nq\x08X\x01\x00\x00\x000q\tX\x03\x00\x00\x00cpuq\nK\x06tq\x0bQK\x00K\x02K\x03\x86q\x0cK\x03K\x01\x86q\r\x89h\x03)Rq\x0etq\x0fRq\x10X\x0f\x00\x00\x00linear.weight_tq\x11h\x06((h\x07h\x08h\th\nK\x06tq\x12QK\x00K\x03K\x02\x86q\x13K\x01K\x03\x86q\x14\x89h\x03)Rq\x15tq\x16Rq\x17X\x0b\x00\x00\x00linear.biasq\x18h\x06((h\x07h\x08X\x01\x00\x00\x001q\x19h\nK\x04tq\x1aQK\x01K\x03\x85q\x1bK\x01\x85q\x1c\x89h\x03)Rq\x1dtq\x1eRq\x1fuu.";
+
+fn write_pth(path: &std::path::Path) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("archive/data.pkl", options)?;
+    zip.write_all(DATA_PKL)?;
+    zip.start_file("archive/data/0", options)?;
+    for v in 0..6 {
+        zip.write_all(&(v as f32).to_le_bytes())?
+    }
+    zip.start_file("archive/data/1", options)?;
+    for v in [-1f32, 0.5, 1.5, 2.5] {
+        zip.write_all(&v.to_le_bytes())?
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+#[test]
+fn read_pth() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("candle-read-pth-{}.pth", std::process::id()));
+    write_pth(&path)?;
+    let tensor_infos = pickle::read_pth_tensor_info(&path)?;
+    let names = tensor_infos
+        .iter()
+        .map(|ti| ti.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "model_state_dict.linear.weight",
+            "model_state_dict.linear.weight_t",
+            "model_state_dict.linear.bias"
+        ]
+    );
+    assert_eq!(tensor_infos[0].dtype, DType::F32);
+    assert_eq!(tensor_infos[1].layout.stride(), [1, 3]);
+    assert_eq!(tensor_infos[2].path, "archive/data/1");
+
+    let tensors = pickle::read_all(&path)?;
+    assert_eq!(
+        tensors[0].1.to_vec2::<f32>()?,
+        &[[0., 1., 2.], [3., 4., 5.]]
+    );
+    assert_eq!(
+        tensors[1].1.to_vec2::<f32>()?,
+        &[[0., 3.], [1., 4.], [2., 5.]]
+    );
+    assert_eq!(tensors[2].1.to_vec1::<f32>()?, &[0.5, 1.5, 2.5]);
+
+    let pth = pickle::PthTensors::new(&path)?;
+    let bias = pth.get("model_state_dict.linear.bias")?.unwrap();
+    assert_eq!(bias.dims(), [3]);
+    assert!(pth.get("epoch")?.is_none());
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
@@ -0,0 +1,410 @@
+use candle_core::{ggml, quantized, Device, Result, Tensor};
+use quantized::{k_quants, GgmlDType, QMatMul};
+
+mod test_utils;
+use test_utils::{to_vec1_round, to_vec2_round};
+
+// Builds an in-memory ggjt v3 file containing a single tensor with the given raw data.
+fn ggjt_file(name: &str, dtype: u32, dims: &[u32], raw_data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for v in [0x67676a74u32, 3, 0, 0, 0, 0, 0, 0, 0, dims.len() as u32] {
+        bytes.extend_from_slice(&v.to_le_bytes())
+    }
+    bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&dtype.to_le_bytes());
+    // The dims are stored with the fastest changing one first.
+    for d in dims.iter().rev() {
+        bytes.extend_from_slice(&d.to_le_bytes())
+    }
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.resize(bytes.len().div_ceil(32) * 32, 0);
+    bytes.extend_from_slice(raw_data);
+    bytes
+}
+
+fn read_single_tensor(bytes: Vec<u8>) -> Result<quantized::QTensor> {
+    let content = ggml::Content::from_bytes(bytes)?;
+    assert_eq!(content.tensor_infos.len(), 1);
+    let name = content.tensor_infos.keys().next().unwrap();
+    content.tensor(name)
+}
+
+#[test]
+fn quantized_matmul_f32() -> Result<()> {
+    let cpu = &Device::Cpu;
+    let (m, k, n) = (3, 4, 2);
+    let rhs = (0..k * n).map(|v| v as f32).collect::<Vec<_>>();
+    let raw_data = rhs.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+    let qtensor = read_single_tensor(ggjt_file("w", 0, &[n as u32, k as u32], &raw_data))?;
+    assert_eq!(qtensor.dtype(), GgmlDType::F32);
+    assert_eq!(qtensor.shape().dims(), [n, k]);
+
+    let lhs = Tensor::arange(0f32, (m * k) as f32, cpu)?.reshape((m, k))?;
+    let mm = QMatMul::from_qtensor(qtensor).forward(&lhs)?;
+    assert_eq!(mm.dims(), [m, n]);
+    assert_eq!(
+        to_vec2_round(&mm, 3)?,
+        &[[14.0, 38.0], [38.0, 126.0], [62.0, 214.0]]
+    );
+    Ok(())
+}
+
+#[test]
+fn ggml_mmaped_file() -> Result<()> {
+    let raw_data = (0..6)
+        .flat_map(|v| (v as f32).to_le_bytes())
+        .collect::<Vec<_>>();
+    let bytes = ggjt_file("w", 0, &[2, 3], &raw_data);
+    let path = std::env::temp_dir().join(format!("candle-mmaped-{}.ggml", std::process::id()));
+    std::fs::write(&path, bytes)?;
+    let content = unsafe { ggml::Content::from_mmaped_file(&path)? };
+    let tensor_info = &content.tensor_infos["w"];
+    assert_eq!(tensor_info.ggml_dtype, GgmlDType::F32);
+    assert_eq!(tensor_info.shape.dims(), [2, 3]);
+    assert_eq!(tensor_info.size_in_bytes(), 24);
+    let w = content.tensor("w")?.dequantize(&Device::Cpu)?;
+    assert_eq!(w.to_vec2::<f32>()?, &[[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+    assert!(content.tensor("b").is_err());
+    // Truncated files are rejected when parsing the header.
+    let bytes = ggjt_file("w", 0, &[2, 3], &raw_data[..20]);
+    assert!(ggml::Content::from_bytes(bytes).is_err());
+    drop(content);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn quantized_q4k_dequantize() -> Result<()> {
+    // A single block with a scale of 1 and a min of 0 for all the sub-blocks, so that the
+    // dequantized values are the raw nibbles.
+    let mut raw_data = vec![];
+    raw_data.extend_from_slice(&half::f16::ONE.to_le_bytes());
+    raw_data.extend_from_slice(&half::f16::ZERO.to_le_bytes());
+    raw_data.extend_from_slice(&[1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1]);
+    for i in 0..k_quants::QK_K / 2 {
+        let i = (i % 16) as u8;
+        raw_data.push(i | ((15 - i) << 4))
+    }
+    let qtensor = read_single_tensor(ggjt_file("w", 12, &[1, 256], &raw_data))?;
+    assert_eq!(qtensor.dtype(), GgmlDType::Q4K);
+    assert_eq!(qtensor.storage_size_in_bytes(), 144);
+    let values = qtensor
+        .dequantize(&Device::Cpu)?
+        .flatten_all()?
+        .to_vec1::<f32>()?;
+    // Each group of 64 values starts with the low nibbles followed by the high ones.
+    assert_eq!(values[..4], [0., 1., 2., 3.]);
+    assert_eq!(values[30..34], [14., 15., 15., 14.]);
+    assert_eq!(values[64..66], [0., 1.]);
+    assert_eq!(values[254..], [1., 0.]);
+    Ok(())
+}
+
+#[test]
+fn quantized_matmul_k_quants() -> Result<()> {
+    let cpu = &Device::Cpu;
+    let (m, k, n) = (3, 512, 40);
+    // Random blocks with small scales, the f16 values are set explicitly so that they are
+    // finite.
+    let mut seed = 299792458u32;
+    let mut next_u8 = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 24) as u8
+    };
+    for (dtype, block_size) in [(10u32, 84), (11, 110), (12, 144), (13, 176), (14, 210)] {
+        let mut raw_data = (0..n * k / 256 * block_size)
+            .map(|_| next_u8())
+            .collect::<Vec<_>>();
+        for block in raw_data.chunks_exact_mut(block_size) {
+            let d = half::f16::from_f32(0.01).to_le_bytes();
+            let dmin = half::f16::from_f32(0.002).to_le_bytes();
+            match dtype {
+                // The scales are stored at the end for q2k, q3k and q6k.
+                10 => block[80..].copy_from_slice(&[d[0], d[1], dmin[0], dmin[1]]),
+                11 | 14 => block[block_size - 2..].copy_from_slice(&d),
+                _ => block[..4].copy_from_slice(&[d[0], d[1], dmin[0], dmin[1]]),
+            }
+        }
+        let qtensor = read_single_tensor(ggjt_file("w", dtype, &[n as u32, k as u32], &raw_data))?;
+        let rhs = qtensor.dequantize(cpu)?;
+        let lhs = Tensor::arange(0f32, (m * k) as f32, cpu)?.reshape((m, k))?;
+        let lhs = (lhs / (m * k) as f64)?;
+        let mm = QMatMul::from_qtensor(qtensor).forward(&lhs)?;
+        // The activations are quantized to q8k before the dot products so the exact result
+        // uses the same quantized lhs.
+        let lhs_q = quantized::QTensor::quantize::<k_quants::BlockQ8K>(&lhs)?.dequantize(cpu)?;
+        let diff = relative_diff(&mm, &lhs_q.matmul(&rhs.t()?)?)?;
+        assert!(diff < 1e-5, "{dtype} {diff}");
+        let diff = relative_diff(&mm, &lhs.matmul(&rhs.t()?)?)?;
+        assert!(diff < 1e-2, "{dtype} {diff}");
+    }
+    Ok(())
+}
+
+// Only relative differences are checked as the accumulation order differs.
+fn relative_diff(lhs: &Tensor, rhs: &Tensor) -> Result<f32> {
+    let diff = ((lhs - rhs)?.abs()?.sum_all()? / rhs.abs()?.sum_all()?)?;
+    diff.to_vec0::<f32>()
+}
+
+// Quantizes `(i - 32) / 10` for `i` in `0..64` and returns the dequantized values, the reference
+// values have been computed with the ggml reference implementations.
+fn quantize_round_trip<T: k_quants::GgmlType + 'static>() -> Result<Vec<f32>> {
+    let cpu = &Device::Cpu;
+    let src = (0..64).map(|i| (i as f32 - 32.) / 10.).collect::<Vec<_>>();
+    let src = Tensor::new(src.as_slice(), cpu)?;
+    let qtensor = quantized::QTensor::quantize::<T>(&src)?;
+    assert_eq!(qtensor.dtype(), T::DTYPE);
+    to_vec1_round(&qtensor.dequantize(cpu)?, 4)
+}
+
+#[test]
+fn quantize_q4_0() -> Result<()> {
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ4_0>()?,
+        &[
+            -3.1992, -3.1992, -2.7993, -2.7993, -2.7993, -2.7993, -2.3994, -2.3994, -2.3994,
+            -2.3994, -1.9995, -1.9995, -1.9995, -1.9995, -1.5996, -1.5996, -1.5996, -1.5996,
+            -1.1997, -1.1997, -1.1997, -1.1997, -0.7998, -0.7998, -0.7998, -0.7998, -0.3999,
+            -0.3999, -0.3999, -0.3999, 0.0, 0.0, 0.0, 0.0, 0.3875, 0.3875, 0.3875, 0.3875, 0.7749,
+            0.7749, 0.7749, 0.7749, 1.1624, 1.1624, 1.1624, 1.1624, 1.5498, 1.5498, 1.5498, 1.5498,
+            1.9373, 1.9373, 1.9373, 1.9373, 2.3247, 2.3247, 2.3247, 2.3247, 2.7122, 2.7122, 2.7122,
+            2.7122, 3.0996, 3.0996
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn quantize_q4_1() -> Result<()> {
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ4_1>()?,
+        &[
+            -3.1992, -3.1992, -2.9926, -2.9926, -2.7859, -2.7859, -2.5792, -2.5792, -2.3726,
+            -2.3726, -2.1659, -2.1659, -1.9592, -1.9592, -1.7526, -1.7526, -1.5459, -1.5459,
+            -1.3392, -1.3392, -1.1326, -1.1326, -0.9259, -0.9259, -0.7192, -0.7192, -0.5126,
+            -0.5126, -0.3059, -0.3059, -0.0992, -0.0992, 0.0, 0.0, 0.2067, 0.2067, 0.4133, 0.4133,
+            0.62, 0.62, 0.8267, 0.8267, 1.0333, 1.0333, 1.24, 1.24, 1.4467, 1.4467, 1.6533, 1.6533,
+            1.86, 1.86, 2.0667, 2.0667, 2.2733, 2.2733, 2.48, 2.48, 2.6866, 2.6866, 2.8933, 2.8933,
+            3.1, 3.1
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn quantize_q5_0() -> Result<()> {
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ5_0>()?,
+        &[
+            -3.1992, -2.9993, -2.9993, -2.7993, -2.7993, -2.5994, -2.5994, -2.3994, -2.3994,
+            -2.1995, -2.1995, -1.9995, -1.9995, -1.7996, -1.7996, -1.5996, -1.5996, -1.3997,
+            -1.3997, -1.1997, -1.1997, -0.9998, -0.9998, -0.7998, -0.7998, -0.5999, -0.5999,
+            -0.3999, -0.3999, -0.2, -0.2, 0.0, 0.0, 0.1937, 0.1937, 0.3875, 0.3875, 0.5812, 0.5812,
+            0.7749, 0.7749, 0.9686, 0.9686, 1.1624, 1.1624, 1.3561, 1.3561, 1.5498, 1.5498, 1.7435,
+            1.7435, 1.9373, 1.9373, 2.131, 2.131, 2.3247, 2.3247, 2.5184, 2.5184, 2.7122, 2.7122,
+            2.9059, 2.9059, 3.0996
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn quantize_q5_1() -> Result<()> {
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ5_1>()?,
+        &[
+            -3.1992, -3.0992, -2.9993, -2.8993, -2.7993, -2.6993, -2.5994, -2.4994, -2.3994,
+            -2.2994, -2.1995, -2.0995, -1.9995, -1.8995, -1.7996, -1.6996, -1.5996, -1.4996,
+            -1.3997, -1.2997, -1.1997, -1.0997, -0.9998, -0.8998, -0.7998, -0.6998, -0.5999,
+            -0.4999, -0.3999, -0.2999, -0.2, -0.1, 0.0, 0.1, 0.2, 0.2999, 0.3999, 0.4999, 0.5999,
+            0.6998, 0.7998, 0.8998, 0.9998, 1.0997, 1.1997, 1.2997, 1.3997, 1.4996, 1.5996, 1.6996,
+            1.7996, 1.8995, 1.9995, 2.0995, 2.1995, 2.2994, 2.3994, 2.4994, 2.5994, 2.6993, 2.7993,
+            2.8993, 2.9993, 3.0992
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn quantize_q8_0() -> Result<()> {
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ8_0>()?,
+        &[
+            -3.1994, -3.0986, -2.9979, -2.8971, -2.7963, -2.6956, -2.5948, -2.494, -2.3933,
+            -2.2925, -2.1917, -2.091, -1.9902, -1.8894, -1.7887, -1.6879, -1.6123, -1.5115,
+            -1.4108, -1.31, -1.2092, -1.1085, -1.0077, -0.9069, -0.8062, -0.7054, -0.6046, -0.5038,
+            -0.4031, -0.3023, -0.2015, -0.1008, 0.0, 0.0977, 0.1953, 0.293, 0.3906, 0.4883, 0.6104,
+            0.708, 0.8057, 0.9033, 1.001, 1.0986, 1.1963, 1.2939, 1.3916, 1.4893, 1.6113, 1.709,
+            1.8066, 1.9043, 2.002, 2.0996, 2.1973, 2.2949, 2.3926, 2.4902, 2.6123, 2.71, 2.8076,
+            2.9053, 3.0029, 3.1006
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn quantize_q8_1() -> Result<()> {
+    // q8_1 uses the same scale and values as q8_0, it only adds the sum of each block.
+    assert_eq!(
+        quantize_round_trip::<k_quants::BlockQ8_1>()?,
+        quantize_round_trip::<k_quants::BlockQ8_0>()?
+    );
+    Ok(())
+}
+
+#[test]
+fn vec_dot_q8_1() -> Result<()> {
+    use k_quants::GgmlType;
+    let n = 4 * k_quants::QK8_1;
+    let xs = (0..n).map(|i| (i as f32 * 0.37).sin()).collect::<Vec<_>>();
+    let ys = (0..n).map(|i| (i as f32 - 64.) / 16.).collect::<Vec<_>>();
+    let mut xs_q = vec![k_quants::BlockQ8_1::zeros(); n / k_quants::QK8_1];
+    let mut ys_q = vec![k_quants::BlockQ8_1::zeros(); n / k_quants::QK8_1];
+    k_quants::BlockQ8_1::from_float(&xs, &mut xs_q)?;
+    k_quants::BlockQ8_1::from_float(&ys, &mut ys_q)?;
+    let dot = k_quants::BlockQ8_1::vec_dot(n, &xs_q, &ys_q)?;
+
+    // The errors are relative to the sum of the absolute values of the products as the dot
+    // product itself can be close to zero.
+    let scale: f32 = xs.iter().zip(ys.iter()).map(|(x, y)| (x * y).abs()).sum();
+    // The f32 dot product of the dequantized values only differs by the rounding errors.
+    let (mut xs_dq, mut ys_dq) = (vec![0f32; n], vec![0f32; n]);
+    k_quants::BlockQ8_1::to_float(&xs_q, &mut xs_dq)?;
+    k_quants::BlockQ8_1::to_float(&ys_q, &mut ys_dq)?;
+    let expected: f32 = xs_dq.iter().zip(ys_dq.iter()).map(|(x, y)| x * y).sum();
+    assert!((dot - expected).abs() < 1e-5 * scale, "{dot} {expected}");
+    let expected: f32 = xs.iter().zip(ys.iter()).map(|(x, y)| x * y).sum();
+    assert!((dot - expected).abs() < 1e-2 * scale, "{dot} {expected}");
+    assert!(k_quants::BlockQ8_1::vec_dot(n / 2, &xs_q, &ys_q).is_err());
+    Ok(())
+}
+
+#[test]
+fn quantized_matmul_legacy() -> Result<()> {
+    let cpu = &Device::Cpu;
+    let (m, k, n) = (3, 64, 4);
+    let lhs = Tensor::arange(0f32, (m * k) as f32, cpu)?.reshape((m, k))?;
+    let rhs = ((Tensor::arange(0f32, (n * k) as f32, cpu)? - 100.)? / 20.)?.reshape((n, k))?;
+    fn check<T: k_quants::GgmlType + 'static>(lhs: &Tensor, rhs: &Tensor) -> Result<()>
+    where
+        T::VecDotType: 'static,
+    {
+        let qtensor = quantized::QTensor::quantize::<T>(rhs)?;
+        let rhs = qtensor.dequantize(&Device::Cpu)?;
+        let mm = QMatMul::from_qtensor(qtensor).forward(lhs)?;
+        let lhs_q = quantized::QTensor::quantize::<T::VecDotType>(lhs)?.dequantize(&Device::Cpu)?;
+        // q8_1 stores the sum of each block as a f16 so the result is not exact.
+        let diff = relative_diff(&mm, &lhs_q.matmul(&rhs.t()?)?)?;
+        assert!(diff < 1e-3, "{:?} {diff}", T::DTYPE);
+        let diff = relative_diff(&mm, &lhs.matmul(&rhs.t()?)?)?;
+        assert!(diff < 1e-2, "{:?} {diff}", T::DTYPE);
+        Ok(())
+    }
+    check::<k_quants::BlockQ4_0>(&lhs, &rhs)?;
+    check::<k_quants::BlockQ4_1>(&lhs, &rhs)?;
+    check::<k_quants::BlockQ5_0>(&lhs, &rhs)?;
+    check::<k_quants::BlockQ5_1>(&lhs, &rhs)?;
+    check::<k_quants::BlockQ8_0>(&lhs, &rhs)?;
+    check::<k_quants::BlockQ8_1>(&lhs, &rhs)?;
+    Ok(())
+}
+
+#[test]
+fn gguf_round_trip() -> Result<()> {
+    use candle_core::gguf::{self, Value};
+    let cpu = &Device::Cpu;
+    let t_f32 = Tensor::arange(0f32, 6f32, cpu)?.reshape((2, 3))?;
+    let t_f32 = quantized::QTensor::quantize::<f32>(&t_f32)?;
+    let t_q4 = ((Tensor::arange(0f32, 128f32, cpu)? - 64.)? / 16.)?.reshape((2, 64))?;
+    let t_q4 = quantized::QTensor::quantize::<k_quants::BlockQ4_0>(&t_q4)?;
+
+    let name = Value::String("tiny".to_string());
+    let n_layer = Value::U32(2);
+    let eps = Value::F32(1e-5);
+    let use_bias = Value::Bool(false);
+    let offsets = Value::Array(vec![Value::I64(-1), Value::I64(3)]);
+    let metadata = [
+        ("general.name", &name),
+        ("tiny.n_layer", &n_layer),
+        ("tiny.eps", &eps),
+        ("tiny.use_bias", &use_bias),
+        ("tiny.offsets", &offsets),
+    ];
+    let mut buffer = std::io::Cursor::new(vec![]);
+    gguf::write(&mut buffer, &metadata, &[("w1", &t_f32), ("w2", &t_q4)])?;
+
+    let mut reader = std::io::Cursor::new(buffer.into_inner());
+    let content = gguf::Content::read(&mut reader)?;
+    assert_eq!(content.magic, gguf::VersionedMagic::GgufV2);
+    assert_eq!(content.metadata.len(), 5);
+    assert_eq!(content.metadata["general.name"].to_string()?, "tiny");
+    assert_eq!(content.metadata["tiny.n_layer"].to_u32()?, 2);
+    assert_eq!(content.metadata["tiny.eps"].to_f32()?, 1e-5);
+    assert!(!content.metadata["tiny.use_bias"].to_bool()?);
+    assert_eq!(content.metadata["tiny.offsets"], offsets);
+    assert_eq!(content.tensor_data_offset % gguf::DEFAULT_ALIGNMENT, 0);
+    assert_eq!(content.tensor_infos["w1"].offset, 0);
+    assert_eq!(content.tensor_infos["w2"].offset, 32);
+
+    let w1 = content.tensor(&mut reader, "w1")?;
+    assert_eq!(w1.dtype(), GgmlDType::F32);
+    assert_eq!(
+        w1.dequantize(cpu)?.to_vec2::<f32>()?,
+        &[[0., 1., 2.], [3., 4., 5.]]
+    );
+    let w2 = content.tensor(&mut reader, "w2")?;
+    assert_eq!(w2.dtype(), GgmlDType::Q4_0);
+    assert_eq!(w2.shape().dims(), [2, 64]);
+    assert_eq!(w2.as_bytes(), t_q4.as_bytes());
+    assert!(content.tensor(&mut reader, "w3").is_err());
+    Ok(())
+}
+
+// Quantizes a smooth signal and returns the root mean square error after dequantization.
+fn quantization_rmse<T: k_quants::GgmlType + 'static>() -> Result<f32> {
+    let cpu = &Device::Cpu;
+    let src = (0..4 * k_quants::QK_K)
+        .map(|i| (i as f32 / 7.).sin() * (1. + i as f32 / 512.))
+        .collect::<Vec<_>>();
+    let src = Tensor::from_vec(src, (4, k_quants::QK_K), cpu)?;
+    let qtensor = quantized::QTensor::quantize::<T>(&src)?;
+    assert_eq!(qtensor.dtype(), T::DTYPE);
+    let dst = qtensor.dequantize(cpu)?;
+    let rmse = ((dst - &src)?.sqr()?.sum_all()? / src.elem_count() as f64)?.sqrt()?;
+    rmse.to_vec0::<f32>()
+}
+
+#[test]
+fn quantize_k_quants() -> Result<()> {
+    // The error should decrease with the number of bits used per weight.
+    assert!(quantization_rmse::<k_quants::BlockQ2K>()? < 0.25);
+    assert!(quantization_rmse::<k_quants::BlockQ3K>()? < 0.15);
+    assert!(quantization_rmse::<k_quants::BlockQ4K>()? < 0.08);
+    assert!(quantization_rmse::<k_quants::BlockQ5K>()? < 0.04);
+    assert!(quantization_rmse::<k_quants::BlockQ6K>()? < 0.02);
+    assert!(quantization_rmse::<k_quants::BlockQ8_0>()? < 0.005);
+    Ok(())
+}
+
+#[test]
+fn quantize_k_quants_zeros() -> Result<()> {
+    fn check<T: k_quants::GgmlType + 'static>() -> Result<()> {
+        let cpu = &Device::Cpu;
+        let src = Tensor::zeros((2, k_quants::QK_K), candle_core::DType::F32, cpu)?;
+        let qtensor = quantized::QTensor::quantize::<T>(&src)?;
+        let dst = qtensor
+            .dequantize(cpu)?
+            .abs()?
+            .sum_all()?
+            .to_vec0::<f32>()?;
+        assert_eq!(dst, 0., "{:?}", T::DTYPE);
+        Ok(())
+    }
+    check::<k_quants::BlockQ2K>()?;
+    check::<k_quants::BlockQ3K>()?;
+    check::<k_quants::BlockQ4K>()?;
+    check::<k_quants::BlockQ5K>()?;
+    check::<k_quants::BlockQ6K>()?;
+    Ok(())
+}
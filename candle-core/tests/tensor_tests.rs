@@ -52,6 +52,104 @@ fn binary_op(device: &Device) -> Result<()> {
     Ok(())
 }
 
+fn binary_op_ext(device: &Device) -> Result<()> {
+    let a = Tensor::new(&[[3f32, -1., 4.], [-2., 5., 0.]], device)?;
+    let b = Tensor::new(&[[2f32, 2., -3.], [3., 4., 1.]], device)?;
+    assert_eq!(
+        a.maximum(&b)?.to_vec2::<f32>()?,
+        &[[3., 2., 4.], [3., 5., 1.]]
+    );
+    assert_eq!(
+        a.minimum(&b)?.to_vec2::<f32>()?,
+        &[[2., -1., -3.], [-2., 4., 0.]]
+    );
+    assert_eq!(
+        a.pow(&b)?.to_vec2::<f32>()?,
+        &[[9., 1., 0.015625], [-8., 625., 0.]]
+    );
+    assert_eq!(a.rem(&b)?.to_vec2::<f32>()?, &[[1., 1., -2.], [1., 1., 0.]]);
+    assert_eq!(
+        test_utils::to_vec2_round(&a.atan2(&b)?, 4)?,
+        &[[0.9828, -0.4636, 2.2143], [-0.588, 0.8961, 0.0]]
+    );
+
+    // Scalar right-hand sides get converted to the dtype of the tensor.
+    assert_eq!(
+        a.maximum(0f32)?.to_vec2::<f32>()?,
+        &[[3., 0., 4.], [0., 5., 0.]]
+    );
+    assert_eq!(
+        a.pow(2f64)?.to_vec2::<f32>()?,
+        &[[9., 1., 16.], [4., 25., 0.]]
+    );
+    let c = Tensor::new(&[0f32, 1., 2.], device)?;
+    assert_eq!(
+        a.broadcast_maximum(&c)?.to_vec2::<f32>()?,
+        &[[3., 1., 4.], [0., 5., 2.]]
+    );
+
+    let nan = Tensor::new(&[f32::NAN, 1.], device)?;
+    let max = nan.maximum(0f32)?.to_vec1::<f32>()?;
+    assert!(max[0].is_nan());
+    assert_eq!(max[1], 1.);
+
+    let t = Tensor::new(&[2u32, 3, 10], device)?;
+    let e = Tensor::new(&[3u32, 2, 0], device)?;
+    assert_eq!(t.pow(&e)?.to_vec1::<u32>()?, &[8, 9, 1]);
+    assert_eq!(t.minimum(3u32)?.to_vec1::<u32>()?, &[2, 3, 3]);
+    if device.is_cpu() {
+        let t = Tensor::new(&[2i64, -1, 1, -1], device)?;
+        let e = Tensor::new(&[-1i64, -3, -5, -2], device)?;
+        assert_eq!(t.pow(&e)?.to_vec1::<i64>()?, &[0, -1, 1, 1]);
+        let t = Tensor::new(&[-7i64, 7], device)?;
+        assert_eq!(
+            t.rem(&Tensor::new(&[3i64, -3], device)?)?
+                .to_vec1::<i64>()?,
+            &[2, -2]
+        );
+    }
+    Ok(())
+}
+
+fn unary_op_ext(device: &Device) -> Result<()> {
+    let t = Tensor::new(&[-1.5f32, -0.25, 0.5, 2.5], device)?;
+    let round = |t: Tensor| test_utils::to_vec1_round(&t, 4);
+    assert_eq!(round(t.tanh()?)?, [-0.9051, -0.2449, 0.4621, 0.9866]);
+    assert_eq!(round(t.erf()?)?, [-0.9661, -0.2763, 0.5205, 0.9996]);
+    assert_eq!(round(t.sigmoid()?)?, [0.1824, 0.4378, 0.6225, 0.9241]);
+    assert_eq!(round(t.softplus()?)?, [0.2014, 0.5759, 0.9741, 2.5789]);
+    assert_eq!(round(t.i(1..)?.log1p()?)?, [-0.2877, 0.4055, 1.2528]);
+    assert_eq!(round(t.gelu_erf()?)?, [-0.1002, -0.1003, 0.3457, 2.4845]);
+    assert_eq!(round(t.gelu()?)?, [-0.1004, -0.1003, 0.3457, 2.4849]);
+
+    // Large inputs do not overflow.
+    let t = Tensor::new(&[-100f32, 100.], device)?;
+    assert_eq!(round(t.softplus()?)?, [0., 100.]);
+    assert_eq!(t.sigmoid()?.to_vec1::<f32>()?, [0., 1.]);
+
+    let t = Tensor::new(&[-1.5f32, -0.5, 0.5, 1.5, 2.5, 2.7], device)?;
+    assert_eq!(t.floor()?.to_vec1::<f32>()?, [-2., -1., 0., 1., 2., 2.]);
+    assert_eq!(t.ceil()?.to_vec1::<f32>()?, [-1., -0., 1., 2., 3., 3.]);
+    // Halfway values are rounded to the nearest even integer.
+    assert_eq!(t.round()?.to_vec1::<f32>()?, [-2., -0., 0., 2., 2., 3.]);
+    let t = t.to_dtype(DType::BF16)?;
+    assert_eq!(
+        t.round()?.to_dtype(DType::F32)?.to_vec1::<f32>()?,
+        [-2., -0., 0., 2., 2., 3.]
+    );
+    let t = Tensor::new(&[0f32, 2.], device)?.to_dtype(DType::F16)?;
+    assert_eq!(
+        t.sigmoid()?
+            .tanh()?
+            .to_dtype(DType::F32)?
+            .to_vec1::<f32>()?,
+        [0.4621582, 0.70703125]
+    );
+    let t = Tensor::new(&[3u32, 7], device)?;
+    assert_eq!(t.floor()?.to_vec1::<u32>()?, [3, 7]);
+    Ok(())
+}
+
 fn transpose(device: &Device) -> Result<()> {
     let data = &[[3f32, 1., 4., 1., 5.], [2., 1., 7., 8., 2.]];
     let tensor = Tensor::new(data, device)?.t()?;
@@ -550,6 +648,7 @@ fn cmp(device: &Device) -> Result<()> {
     assert_eq!(t1.lt(&t2)?.to_vec2::<u8>()?, &[[1, 0], [1, 0], [0, 1]]);
     assert_eq!(t1.gt(&t2)?.to_vec2::<u8>()?, &[[0, 1], [0, 0], [0, 0]]);
     assert_eq!(t1.ge(&t2)?.to_vec2::<u8>()?, &[[0, 1], [0, 1], [1, 0]]);
+    assert_eq!(t1.ge(&t2)?.dtype(), DType::Bool);
     Ok(())
 }
 
@@ -666,6 +765,249 @@ fn scatter_add(device: &Device) -> Result<()> {
     Ok(())
 }
 
+fn permute_flip_roll(device: &Device) -> Result<()> {
+    let t = Tensor::arange(0u32, 24, device)?.reshape((2, 3, 4))?;
+    let p = t.permute((1, 2, 0))?;
+    assert_eq!(p.dims(), &[3, 4, 2]);
+    assert_eq!(p.i((2, 1))?.to_vec1::<u32>()?, &[9, 21]);
+    assert_eq!(p.permute((2, 0, 1))?.to_vec3::<u32>()?, t.to_vec3::<u32>()?);
+    assert!(t.permute((1, 0)).is_err());
+    assert!(t.permute((1, 0, 1)).is_err());
+
+    let t = Tensor::arange(0u32, 6, device)?.reshape((2, 3))?;
+    assert_eq!(t.flip(1)?.to_vec2::<u32>()?, &[[2, 1, 0], [5, 4, 3]]);
+    assert_eq!(t.t()?.flip(0)?.to_vec2::<u32>()?, &[[2, 5], [1, 4], [0, 3]]);
+    assert_eq!(t.roll(1, 1)?.to_vec2::<u32>()?, &[[2, 0, 1], [5, 3, 4]]);
+    assert_eq!(t.roll(-4, 1)?.to_vec2::<u32>()?, &[[1, 2, 0], [4, 5, 3]]);
+    assert_eq!(t.roll(3, 0)?.to_vec2::<u32>()?, &[[3, 4, 5], [0, 1, 2]]);
+
+    let s = t.as_strided((2, 2), vec![2, 1], 1)?;
+    assert_eq!(s.to_vec2::<u32>()?, &[[1, 2], [3, 4]]);
+    let s = t.t()?.as_strided((3,), vec![2], 0)?;
+    assert_eq!(s.to_vec1::<u32>()?, &[0, 1, 2]);
+    assert!(t.as_strided((2, 2), vec![3, 1], 2).is_err());
+    Ok(())
+}
+
+fn sort(device: &Device) -> Result<()> {
+    let t = Tensor::new(&[[3f32, 1., 4., 1.], [5., 9., 2., 6.]], device)?;
+    assert_eq!(
+        t.argsort(1, true)?.to_vec2::<u32>()?,
+        &[[1, 3, 0, 2], [2, 0, 3, 1]]
+    );
+    assert_eq!(
+        t.argsort(1, false)?.to_vec2::<u32>()?,
+        &[[2, 0, 1, 3], [1, 3, 0, 2]]
+    );
+    let (values, indexes) = t.sort(0, false)?;
+    assert_eq!(
+        values.to_vec2::<f32>()?,
+        &[[5., 9., 4., 6.], [3., 1., 2., 1.]]
+    );
+    assert_eq!(indexes.to_vec2::<u32>()?, &[[1, 1, 0, 1], [0, 0, 1, 0]]);
+
+    // Strided inputs and integer dtypes.
+    let t = t.to_dtype(DType::I64)?.t()?;
+    let (values, indexes) = t.sort(1, true)?;
+    assert_eq!(values.to_vec2::<i64>()?, &[[3, 5], [1, 9], [2, 4], [1, 6]]);
+    assert_eq!(indexes.to_vec2::<u32>()?, &[[0, 1], [0, 1], [1, 0], [0, 1]]);
+    let (values, indexes) = t.topk(3, 0)?;
+    assert_eq!(values.to_vec2::<i64>()?, &[[4, 9], [3, 6], [1, 5]]);
+    assert_eq!(indexes.to_vec2::<u32>()?, &[[2, 1], [0, 3], [1, 0]]);
+
+    let t = Tensor::new(&[2f32, f32::NAN, -1.], device)?;
+    assert_eq!(t.argsort(0, true)?.to_vec1::<u32>()?, &[2, 0, 1]);
+    Ok(())
+}
+
+fn reductions(device: &Device) -> Result<()> {
+    let data = &[[[3f32, 1., 4.], [1., 5., 9.]], [[2., 1., 7.], [8., 2., 8.]]];
+    let t = Tensor::new(data, device)?;
+    assert_eq!(
+        test_utils::to_vec2_round(&t.mean(2)?, 4)?,
+        &[[2.6667, 5.0], [3.3333, 6.0]]
+    );
+    assert_eq!(
+        test_utils::to_vec1_round(&t.mean((1, 2))?, 4)?,
+        &[3.8333, 4.6667]
+    );
+    assert_eq!(t.mean_keepdim((0, 2))?.dims(), &[1, 2, 1]);
+    assert_eq!(
+        test_utils::to_vec1_round(&t.i(0)?.var(1, false)?, 4)?,
+        &[1.5556, 10.6667]
+    );
+    assert_eq!(
+        test_utils::to_vec1_round(&t.i(0)?.var(1, true)?, 4)?,
+        &[2.3333, 16.0]
+    );
+    assert_eq!(t.i((0, 1))?.std(0, true)?.to_scalar::<f32>()?, 4.);
+    assert_eq!(t.prod(2)?.to_vec2::<f32>()?, &[[12., 45.], [14., 128.]]);
+    assert_eq!(t.prod((0, 1))?.to_vec1::<f32>()?, &[48., 10., 2016.]);
+    assert_eq!(
+        t.to_dtype(DType::U32)?.prod_keepdim(2)?.to_vec3::<u32>()?,
+        &[[[12], [45]], [[14], [128]]]
+    );
+
+    // The variance is computed after removing the mean so a large offset does not wipe out the
+    // deviations.
+    let t = Tensor::new(&[1e4f32 + 1., 1e4 + 2., 1e4 + 3.], device)?;
+    assert_eq!(t.var(0, true)?.to_scalar::<f32>()?, 1.);
+
+    let t = Tensor::new(
+        &[[100f32, 100. + 3f32.ln()], [0., f32::NEG_INFINITY]],
+        device,
+    )?;
+    assert_eq!(
+        test_utils::to_vec1_round(&t.logsumexp(1)?, 4)?,
+        &[101.3863, 0.0]
+    );
+    let t = Tensor::new(&[[f32::NEG_INFINITY, f32::NEG_INFINITY]], device)?;
+    assert_eq!(t.logsumexp(1)?.to_vec1::<f32>()?, &[f32::NEG_INFINITY]);
+
+    let t = Tensor::new(&[[1f32, -2.], [0., 0.]], device)?;
+    assert_eq!(
+        test_utils::to_vec1_round(&t.norm(3., 1)?, 4)?,
+        &[2.0801, 0.0]
+    );
+    assert_eq!(t.norm(f64::INFINITY, 1)?.to_vec1::<f32>()?, &[2., 0.]);
+    assert_eq!(t.norm(1., (0, 1))?.to_scalar::<f32>()?, 3.);
+    assert!(t.norm(0., 1).is_err());
+
+    let t = Tensor::new(&[[1u32, 0], [0, 0]], device)?;
+    assert_eq!(t.all(1)?.to_vec1::<u8>()?, &[0, 0]);
+    assert_eq!(t.any(1)?.to_vec1::<u8>()?, &[1, 0]);
+    assert_eq!(t.any_keepdim((0, 1))?.to_vec2::<u8>()?, &[[1]]);
+    assert_eq!(t.ones_like()?.all((0, 1))?.to_scalar::<u8>()?, 1);
+    assert_eq!(t.all(1)?.dtype(), DType::Bool);
+
+    // Averages of half precision values are accumulated in f32, sums are not.
+    let t = Tensor::ones(4096, DType::F16, device)?;
+    assert_eq!(t.mean(0)?.to_scalar::<half::f16>()?.to_f32(), 1.);
+    assert_eq!(t.sum_all()?.to_scalar::<half::f16>()?.to_f32(), 2048.);
+    let t = Tensor::ones(4096, DType::BF16, device)?;
+    assert_eq!(t.mean_keepdim(0)?.to_vec1::<half::bf16>()?[0].to_f32(), 1.);
+    assert_eq!(t.var(0, false)?.to_scalar::<half::bf16>()?.to_f32(), 0.);
+
+    // The division would truncate on integers.
+    let t = Tensor::new(&[1i64, 2], device)?;
+    assert!(t.mean(0).is_err());
+    assert!(t.var(0, true).is_err());
+    assert!(t.std(0, true).is_err());
+    Ok(())
+}
+
+fn cumulative_ops(device: &Device) -> Result<()> {
+    let t = Tensor::new(&[[1f32, 2., 3.], [4., -5., 6.]], device)?;
+    assert_eq!(
+        t.cumsum(1)?.to_vec2::<f32>()?,
+        &[[1., 3., 6.], [4., -1., 5.]]
+    );
+    assert_eq!(
+        t.cumprod(0)?.to_vec2::<f32>()?,
+        &[[1., 2., 3.], [4., -10., 18.]]
+    );
+    // Strided input.
+    assert_eq!(
+        t.t()?.cumsum(1)?.to_vec2::<f32>()?,
+        &[[1., 5.], [2., -3.], [3., 9.]]
+    );
+    let (values, indexes) = t.t()?.cummax(0)?;
+    assert_eq!(values.to_vec2::<f32>()?, &[[1., 4.], [2., 4.], [3., 6.]]);
+    assert_eq!(indexes.to_vec2::<u32>()?, &[[0, 0], [1, 0], [2, 2]]);
+    let t = Tensor::new(&[3i64, 1, 4, 1, 5], device)?;
+    assert_eq!(t.cumsum(0)?.to_vec1::<i64>()?, &[3, 4, 8, 9, 14]);
+    assert!(t.logcumsumexp(0).is_err());
+
+    let t = Tensor::new(&[[0f32, 1., -2., 100.]], device)?;
+    let expected = t.exp()?.cumsum(1)?.log()?.to_vec2::<f32>()?;
+    let lcse = t.logcumsumexp(1)?.to_vec2::<f32>()?;
+    for (l, e) in lcse[0].iter().zip(expected[0].iter()).take(3) {
+        assert!((l - e).abs() < 1e-5, "{lcse:?} {expected:?}")
+    }
+    assert_eq!(lcse[0][3], 100.);
+    Ok(())
+}
+
+fn slice_assign(device: &Device) -> Result<()> {
+    // Tensors created with zeros broadcast a single value and cannot be written to directly.
+    let t = Tensor::zeros((3, 4), DType::F32, device)?;
+    assert_eq!(t.stride(), [0, 0]);
+    assert!(t.slice_assign((1.., 1..3), &t.i((..2, ..2))?).is_err());
+    let t = t.contiguous()?;
+    let src = Tensor::arange(0f32, 4f32, device)?.reshape((2, 2))?;
+    t.slice_assign((1.., 1..3), &src)?;
+    assert_eq!(
+        t.to_vec2::<f32>()?,
+        &[[0., 0., 0., 0.], [0., 0., 1., 0.], [0., 2., 3., 0.]]
+    );
+    // The source is broadcasted and can be strided, the write is visible through other views.
+    let view = t.i(2)?;
+    t.slice_assign((.., 3), &Tensor::new(&[5f32], device)?)?;
+    t.slice_assign((0, ..2), &src.t()?.i(1)?)?;
+    assert_eq!(view.to_vec1::<f32>()?, &[0., 2., 3., 5.]);
+    assert_eq!(
+        t.to_vec2::<f32>()?,
+        &[[1., 3., 0., 5.], [0., 0., 1., 5.], [0., 2., 3., 5.]]
+    );
+    // Writing to a transposed destination, with a source sharing the same storage.
+    let t = Tensor::arange(0f32, 6f32, device)?.reshape((2, 3))?;
+    t.t()?
+        .slice_assign((..2, ..), &t.i((.., 1..))?.t()?.affine(1., 0.)?)?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 2., 2.], [4., 5., 5.]]);
+    t.slice_assign(.., &t.i((.., ..1))?)?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 1., 1.], [4., 4., 4.]]);
+    assert!(t.slice_assign(.., &src).is_err());
+    assert!(t.broadcast_as((2, 2, 3))?.slice_assign(.., &t).is_err());
+
+    let t = Tensor::zeros((4, 2), DType::F32, device)?.contiguous()?;
+    let ids = Tensor::new(&[2u32, 0], device)?;
+    t.index_put(&ids, &src, 0)?;
+    assert_eq!(
+        t.to_vec2::<f32>()?,
+        &[[2., 3.], [0., 0.], [0., 1.], [0., 0.]]
+    );
+    // Along a dimension that is not the outermost one, through a transposed view.
+    let ids = Tensor::new(&[1i64], device)?;
+    t.t()?
+        .index_put(&ids, &Tensor::new(&[[7f32], [8.]], device)?, 1)?;
+    assert_eq!(
+        t.to_vec2::<f32>()?,
+        &[[2., 3.], [7., 8.], [0., 1.], [0., 0.]]
+    );
+    // Invalid indexes are rejected before anything gets written.
+    let ids = Tensor::new(&[0u32, 4], device)?;
+    assert!(t.index_put(&ids, &src, 0).is_err());
+    let ids = Tensor::new(&[0i64, -1], device)?;
+    assert!(t.index_put(&ids, &src, 0).is_err());
+    assert_eq!(
+        t.to_vec2::<f32>()?,
+        &[[2., 3.], [7., 8.], [0., 1.], [0., 0.]]
+    );
+    Ok(())
+}
+
+fn inplace_ops(device: &Device) -> Result<()> {
+    let t = Tensor::new(&[[1f32, 2., 3.], [4., 5., 6.]], device)?;
+    let rhs = Tensor::new(&[1f32, 0.5, 2.], device)?;
+    t.mul_inplace(&rhs)?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 1., 6.], [4., 2.5, 12.]]);
+    t.i(1)?.sub_inplace(&t.i(0)?)?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 1., 6.], [3., 1.5, 6.]]);
+    t.i((.., 2..))?.neg_inplace()?;
+    t.i((.., ..2))?.sqr_inplace()?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 1., -6.], [9., 2.25, -6.]]);
+    t.add_inplace(&t)?;
+    t.div_inplace(&Tensor::new(&[2f32], device)?)?;
+    assert_eq!(t.to_vec2::<f32>()?, &[[1., 1., -6.], [9., 2.25, -6.]]);
+
+    let t = Tensor::new(&[1u32, 2, 3], device)?;
+    assert!(t.add_inplace(&rhs).is_err());
+    let var = candle_core::Var::new(&[1f32, 2., 3.], device)?;
+    assert!(var.exp_inplace().is_err());
+    assert!(rhs.add_inplace(&var).is_err());
+    Ok(())
+}
+
 fn gather(device: &Device) -> Result<()> {
     let ids = Tensor::new(&[[0u32], [2u32], [1u32], [0u32]], device)?;
     let t = Tensor::arange(0f32, 12f32, device)?.reshape((4, 3))?;
@@ -747,6 +1089,51 @@ fn matmul(device: &Device) -> Result<()> {
     Ok(())
 }
 
+fn broadcast_matmul(device: &Device) -> Result<()> {
+    let lhs = Tensor::arange(0f32, 72., device)?.reshape((3, 2, 3, 4))?;
+    let rhs = Tensor::arange(0f32, 20., device)?.reshape((4, 5))?;
+    let out = lhs.matmul(&rhs)?;
+    assert_eq!(out.dims(), [3, 2, 3, 5]);
+    let expected = lhs.matmul(&rhs.broadcast_as((3, 2, 4, 5))?.contiguous()?)?;
+    assert_eq!(
+        out.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+    // A shared lhs and a transposed rhs.
+    let out = rhs.t()?.matmul(&lhs.t()?)?;
+    assert_eq!(out.dims(), [3, 2, 5, 3]);
+    let expected = expected.t()?;
+    assert_eq!(
+        out.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+
+    // Size 1 batch dims on both sides, the matrices are not evenly spaced in this case.
+    let lhs = Tensor::arange(0f32, 12., device)?.reshape((2, 1, 2, 3))?;
+    let rhs = Tensor::arange(0f32, 18., device)?.reshape((3, 3, 2))?;
+    let out = lhs.matmul(&rhs)?;
+    assert_eq!(out.dims(), [2, 3, 2, 2]);
+    for i in 0..2 {
+        for j in 0..3 {
+            let expected = lhs.get(i)?.get(0)?.matmul(&rhs.get(j)?)?;
+            assert_eq!(
+                out.get(i)?.get(j)?.to_vec2::<f32>()?,
+                expected.to_vec2::<f32>()?
+            );
+        }
+    }
+
+    let lhs = Tensor::zeros((2, 3, 4), DType::F32, device)?;
+    assert!(lhs
+        .matmul(&Tensor::zeros((3, 4, 5), DType::F32, device)?)
+        .is_err());
+    assert!(lhs
+        .matmul(&Tensor::zeros((2, 3, 5), DType::F32, device)?)
+        .is_err());
+    assert!(lhs.matmul(&Tensor::zeros(4, DType::F32, device)?).is_err());
+    Ok(())
+}
+
 fn broadcasting(device: &Device) -> Result<()> {
     let t1 = Tensor::arange(0f32, 24f32, device)?.reshape((4, 2, 3))?;
     let t2 = Tensor::new(&[100f32, 200f32], device)?;
@@ -848,6 +1235,76 @@ fn broadcasting(device: &Device) -> Result<()> {
     Ok(())
 }
 
+fn einsum(device: &Device) -> Result<()> {
+    let a = Tensor::arange(0f32, 6., device)?.reshape((2, 3))?;
+    let b = Tensor::arange(0f32, 12., device)?.reshape((3, 4))?;
+    let c = Tensor::einsum("ij,jk->ik", &[&a, &b])?;
+    assert_eq!(c.to_vec2::<f32>()?, a.matmul(&b)?.to_vec2::<f32>()?);
+    let c = Tensor::einsum("ij,jk", &[&a, &b])?;
+    assert_eq!(c.to_vec2::<f32>()?, a.matmul(&b)?.to_vec2::<f32>()?);
+    let t = Tensor::einsum("ij->ji", &[&a])?;
+    assert_eq!(t.to_vec2::<f32>()?, [[0., 3.], [1., 4.], [2., 5.]]);
+    let s = Tensor::einsum("ij->", &[&a])?;
+    assert_eq!(s.to_scalar::<f32>()?, 15.);
+    let s = Tensor::einsum("ij->j", &[&a])?;
+    assert_eq!(s.to_vec1::<f32>()?, [3., 5., 7.]);
+    let v = Tensor::new(&[1f32, 2.], device)?;
+    let w = Tensor::new(&[1f32, 10., 100.], device)?;
+    let outer = Tensor::einsum("i,j->ij", &[&v, &w])?;
+    assert_eq!(outer.to_vec2::<f32>()?, [[1., 10., 100.], [2., 20., 200.]]);
+    let dot = Tensor::einsum("i,i->", &[&w, &w])?;
+    assert_eq!(dot.to_scalar::<f32>()?, 10101.);
+    let mv = Tensor::einsum("ij,j->i", &[&a, &w])?;
+    assert_eq!(mv.to_vec1::<f32>()?, [210., 543.]);
+
+    // Diagonal and trace.
+    let sq = Tensor::arange(0f32, 9., device)?.reshape((3, 3))?;
+    let diag = Tensor::einsum("ii->i", &[&sq])?;
+    assert_eq!(diag.to_vec1::<f32>()?, [0., 4., 8.]);
+    let trace = Tensor::einsum("ii", &[&sq])?;
+    assert_eq!(trace.to_scalar::<f32>()?, 12.);
+
+    // Attention scores and batched matmul with broadcasted batch dims.
+    let q = Tensor::arange(0f32, 48., device)?.reshape((2, 2, 3, 4))?;
+    let k = Tensor::arange(0f32, 80., device)?.reshape((2, 2, 5, 4))?;
+    let scores = Tensor::einsum("bhqd,bhkd->bhqk", &[&q, &k])?;
+    let expected = q.matmul(&k.t()?.contiguous()?)?;
+    assert_eq!(scores.dims(), [2, 2, 3, 5]);
+    assert_eq!(
+        scores.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+    let x = Tensor::arange(0f32, 24., device)?.reshape((2, 3, 4))?;
+    let y = Tensor::einsum("...ij,jk->...ik", &[&x, &b.t()?])?;
+    assert_eq!(y.dims(), [2, 3, 3]);
+    let expected = x.reshape((6, 4))?.matmul(&b.t()?.contiguous()?)?;
+    assert_eq!(
+        y.flatten_all()?.to_vec1::<f32>()?,
+        expected.flatten_all()?.to_vec1::<f32>()?
+    );
+    // Size 1 dimensions broadcast.
+    let z = Tensor::einsum("bij,bjk->bik", &[&x, &b.t()?.unsqueeze(0)?])?;
+    assert_eq!(z.dims(), [2, 3, 3]);
+    assert_eq!(z.to_vec3::<f32>()?, y.to_vec3::<f32>()?);
+
+    // Bilinear layer and a chain of three matmuls.
+    let x1 = Tensor::new(&[[1f32, 2.], [0., 1.]], device)?;
+    let w = Tensor::arange(0f32, 12., device)?.reshape((3, 2, 2))?;
+    let x2 = Tensor::new(&[[1f32, 1.], [2., 0.]], device)?;
+    let y = Tensor::einsum("bi,oij,bj->bo", &[&x1, &w, &x2])?;
+    assert_eq!(y.to_vec2::<f32>()?, [[11., 35., 59.], [4., 12., 20.]]);
+    let c = Tensor::einsum("ij,jk,kl->il", &[&a, &b, &b.t()?])?;
+    let expected = a.matmul(&b)?.matmul(&b.t()?)?;
+    assert_eq!(c.to_vec2::<f32>()?, expected.to_vec2::<f32>()?);
+
+    assert!(Tensor::einsum("ij,jk->ik", &[&a]).is_err());
+    assert!(Tensor::einsum("ij,jk->ik", &[&a, &a]).is_err());
+    assert!(Tensor::einsum("ijk->i", &[&a]).is_err());
+    assert!(Tensor::einsum("i1->i", &[&a]).is_err());
+    assert!(Tensor::einsum("ij->iz", &[&a]).is_err());
+    Ok(())
+}
+
 test_device!(zeros, zeros_cpu, zeros_gpu);
 test_device!(add_mul, add_mul_cpu, add_mul_gpu);
 test_device!(tensor_2d, tensor_2d_cpu, tensor_2d_gpu);
@@ -861,14 +1318,28 @@ test_device!(argmax, argmax_cpu, argmax_gpu);
 test_device!(argmin, argmin_cpu, argmin_gpu);
 test_device!(transpose, transpose_cpu, transpose_gpu);
 test_device!(binary_op, binary_op_cpu, binary_op_gpu);
+test_device!(binary_op_ext, binary_op_ext_cpu, binary_op_ext_gpu);
+test_device!(unary_op_ext, unary_op_ext_cpu, unary_op_ext_gpu);
 test_device!(embeddings, embeddings_cpu, embeddings_gpu);
 test_device!(cmp, cmp_cpu, cmp_gpu);
 test_device!(matmul, matmul_cpu, matmul_gpu);
+test_device!(broadcast_matmul, broadcast_matmul_cpu, broadcast_matmul_gpu);
 test_device!(broadcasting, broadcasting_cpu, broadcasting_gpu);
 test_device!(index_select, index_select_cpu, index_select_gpu);
 test_device!(index_add, index_add_cpu, index_add_gpu);
 test_device!(gather, gather_cpu, gather_gpu);
 test_device!(scatter_add, scatter_add_cpu, scatter_add_gpu);
+test_device!(
+    permute_flip_roll,
+    permute_flip_roll_cpu,
+    permute_flip_roll_gpu
+);
+test_device!(sort, sort_cpu, sort_gpu);
+test_device!(reductions, reductions_cpu, reductions_gpu);
+test_device!(cumulative_ops, cumulative_ops_cpu, cumulative_ops_gpu);
+test_device!(slice_assign, slice_assign_cpu, slice_assign_gpu);
+test_device!(inplace_ops, inplace_ops_cpu, inplace_ops_gpu);
+test_device!(einsum, einsum_cpu, einsum_gpu);
 
 // There was originally a bug on the CPU implementation for randn
 // https://github.com/huggingface/candle/issues/381
@@ -880,3 +1351,85 @@ fn randn_hasneg() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn signed_ints() -> Result<()> {
+    let t = Tensor::new(&[-3i64, 0, 2, -7], &Device::Cpu)?;
+    assert_eq!(t.dtype(), DType::I64);
+    assert_eq!(t.abs()?.to_vec1::<i64>()?, [3, 0, 2, 7]);
+    assert_eq!(t.neg()?.to_vec1::<i64>()?, [3, 0, -2, 7]);
+    assert_eq!(t.relu()?.to_vec1::<i64>()?, [0, 0, 2, 0]);
+    assert_eq!((&t * &t)?.to_vec1::<i64>()?, [9, 0, 4, 49]);
+    assert_eq!(t.sum_all()?.to_scalar::<i64>()?, -8);
+    assert_eq!(t.max(0)?.to_scalar::<i64>()?, 2);
+    assert_eq!(
+        t.to_dtype(DType::F32)?.to_vec1::<f32>()?,
+        [-3., 0., 2., -7.]
+    );
+    assert_eq!(t.to_dtype(DType::I8)?.to_vec1::<i8>()?, [-3, 0, 2, -7]);
+    assert_eq!(t.to_dtype(DType::I16)?.to_vec1::<i16>()?, [-3, 0, 2, -7]);
+    // The MIN values wrap around rather than overflowing.
+    let t = Tensor::new(&[i8::MIN, -1, 5], &Device::Cpu)?;
+    assert_eq!(t.abs()?.to_vec1::<i8>()?, [i8::MIN, 1, 5]);
+    assert_eq!(t.neg()?.to_vec1::<i8>()?, [i8::MIN, 1, -5]);
+    let t = Tensor::new(&[i64::MIN, 3], &Device::Cpu)?;
+    assert_eq!(t.abs()?.to_vec1::<i64>()?, [i64::MIN, 3]);
+    assert_eq!(t.neg()?.to_vec1::<i64>()?, [i64::MIN, -3]);
+    // Ops without an integer version return an error rather than panicking.
+    assert!(t.tanh().is_err());
+    assert!(t.erf().is_err());
+    assert!(t.gelu_erf().is_err());
+    let u = Tensor::new(&[1u32, 2], &Device::Cpu)?;
+    assert!(u.neg().is_err());
+    assert!(u.sigmoid().is_err());
+    assert_eq!(u.round()?.to_vec1::<u32>()?, [1, 2]);
+    let u = u.to_dtype(DType::U8)?;
+    assert!(u.exp_inplace().is_err());
+    let t = Tensor::new(&[-1.7f32, 2.5, 300.], &Device::Cpu)?;
+    assert_eq!(t.to_dtype(DType::I32)?.to_vec1::<i32>()?, [-1, 2, 300]);
+    assert_eq!(t.to_dtype(DType::I8)?.to_vec1::<i8>()?, [-1, 2, 127]);
+
+    let ids = Tensor::new(&[2i64, 0], &Device::Cpu)?;
+    let t = Tensor::arange(0f32, 6f32, &Device::Cpu)?.reshape((3, 2))?;
+    assert_eq!(
+        t.index_select(&ids, 0)?.to_vec2::<f32>()?,
+        [[4., 5.], [0., 1.]]
+    );
+    // Negative ids are rejected with an explicit error.
+    let ids = Tensor::new(&[-1i32], &Device::Cpu)?;
+    let err = t.index_select(&ids, 0).unwrap_err().to_string();
+    assert!(err.contains("negative index -1"), "{err}");
+    let ids = Tensor::new(&[[0i64, -2], [1, 0], [0, 1]], &Device::Cpu)?;
+    let err = t.gather(&ids, 1).unwrap_err().to_string();
+    assert!(err.contains("negative index -2"), "{err}");
+    let err = t.scatter_add(&ids, &t, 1).unwrap_err();
+    assert!(err.to_string().contains("negative index -2"), "{err}");
+    let ids = Tensor::new(&[1i8, -3], &Device::Cpu)?;
+    let err = t.index_add(&ids, &t.narrow(0, 0, 2)?, 0).unwrap_err();
+    assert!(err.to_string().contains("negative index -3"), "{err}");
+    let err = t.index_add(&ids, &t, 1).unwrap_err();
+    assert!(err.to_string().contains("negative index -3"), "{err}");
+    Ok(())
+}
+
+#[test]
+fn bool_dtype() -> Result<()> {
+    let t = Tensor::new(&[0f32, 1.5, -2., 0.], &Device::Cpu)?;
+    let b = t.to_dtype(DType::Bool)?;
+    assert_eq!(b.dtype(), DType::Bool);
+    assert_eq!(b.to_vec1::<u8>()?, [0, 1, 1, 0]);
+    assert_eq!(b.to_dtype(DType::I64)?.to_vec1::<i64>()?, [0, 1, 1, 0]);
+    assert_eq!(b.max(0)?.to_vec0::<u8>()?, 1);
+
+    let mask = t.gt(&t.zeros_like()?)?;
+    assert_eq!(mask.dtype(), DType::Bool);
+    let on_true = Tensor::new(&[1i64, 2, 3, 4], &Device::Cpu)?;
+    let on_false = on_true.neg()?;
+    let w = mask.where_cond(&on_true, &on_false)?;
+    assert_eq!(w.to_vec1::<i64>()?, [-1, 2, -3, -4]);
+
+    assert!(b.sum_all().is_err());
+    assert!(b.affine(2., 1.).is_err());
+    assert!((&b + &b).is_err());
+    Ok(())
+}
@@ -8,6 +8,8 @@ pub const DTYPE: DType = DType::F32;
 #[serde(rename_all = "lowercase")]
 enum HiddenAct {
     Gelu,
+    #[serde(alias = "gelu_new")]
+    GeluApproximate,
     Relu,
 }
 
@@ -25,10 +27,10 @@ impl HiddenActLayer {
     fn forward(&self, xs: &Tensor) -> candle::Result<Tensor> {
         let _enter = self.span.enter();
         match self.act {
-            // TODO: The all-MiniLM-L6-v2 model uses "gelu" whereas this is "gelu_new", this explains some
-            // small numerical difference.
+            // The "gelu" activation uses erf whereas "gelu_new" uses the tanh approximation.
             // https://github.com/huggingface/transformers/blob/cd4584e3c809bb9e1392ccd3fe38b40daba5519a/src/transformers/activations.py#L213
-            HiddenAct::Gelu => xs.gelu(),
+            HiddenAct::Gelu => xs.gelu_erf(),
+            HiddenAct::GeluApproximate => xs.gelu(),
             HiddenAct::Relu => xs.relu(),
         }
     }
@@ -49,11 +51,7 @@ impl Linear {
 
     pub fn forward(&self, x: &Tensor) -> candle::Result<Tensor> {
         let _enter = self.span.enter();
-        let w = match x.dims() {
-            &[bsize, _, _] => self.weight.broadcast_left(bsize)?.t()?,
-            _ => self.weight.t()?,
-        };
-        let x = x.matmul(&w)?;
+        let x = x.matmul(&self.weight.t()?)?;
         match &self.bias {
             None => Ok(x),
             Some(bias) => x.broadcast_add(bias),
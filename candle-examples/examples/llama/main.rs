@@ -18,7 +18,7 @@ extern crate intel_mkl_src;
 use anyhow::{Error as E, Result};
 use clap::Parser;
 
-use candle::{DType, Tensor};
+use candle::{DType, Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::generation::LogitsProcessor;
 use hf_hub::api::sync::Api;
@@ -86,6 +86,10 @@ struct Args {
     #[arg(long)]
     cpu: bool,
 
+    /// Run on CPU and evaluate the computation graph lazily.
+    #[arg(long)]
+    lazy: bool,
+
     /// Use npy instead of safetensors
     #[arg(long)]
     npy: Option<String>,
@@ -94,6 +98,10 @@ struct Args {
     #[arg(long)]
     temperature: Option<f64>,
 
+    /// Nucleus sampling probability cutoff.
+    #[arg(long)]
+    top_p: Option<f64>,
+
     /// The seed to use when generating random samples.
     #[arg(long, default_value_t = 299792458)]
     seed: u64,
@@ -143,7 +151,11 @@ fn main() -> Result<()> {
         None
     };
 
-    let device = candle_examples::device(args.cpu)?;
+    let device = if args.lazy {
+        Device::Lazy
+    } else {
+        candle_examples::device(args.cpu)?
+    };
     let config = if args.v1 {
         Config::config_7b_v1(args.use_flash_attn)
     } else {
@@ -201,7 +213,8 @@ fn main() -> Result<()> {
         .to_vec();
 
     println!("starting the inference loop");
-    let mut logits_processor = LogitsProcessor::new(args.seed, args.temperature);
+    let mut logits_processor =
+        LogitsProcessor::new_with_top_p(args.seed, args.temperature, args.top_p);
     let mut new_tokens = vec![];
     let start_gen = std::time::Instant::now();
     let mut index_pos = 0;
@@ -62,12 +62,69 @@ impl Linear {
     }
 }
 
+// The keys and values for a single block, these are stored in preallocated buffers that are
+// updated in place and grown when full. Only the last MAX_SEQ_LEN positions are kept.
+#[derive(Clone)]
+struct KvCache {
+    k: Tensor,
+    v: Tensor,
+    seq_len: usize,
+}
+
+impl KvCache {
+    fn new(k: &Tensor, v: &Tensor) -> Result<Self> {
+        let mut cache = Self {
+            k: k.zeros_like()?.contiguous()?,
+            v: v.zeros_like()?.contiguous()?,
+            seq_len: 0,
+        };
+        cache.append(k, v)?;
+        Ok(cache)
+    }
+
+    fn append(&mut self, k: &Tensor, v: &Tensor) -> Result<(Tensor, Tensor)> {
+        let k_len = k.dim(2)?;
+        let (k, v) = if k_len > MAX_SEQ_LEN {
+            let start = k_len - MAX_SEQ_LEN;
+            (
+                k.narrow(2, start, MAX_SEQ_LEN)?,
+                v.narrow(2, start, MAX_SEQ_LEN)?,
+            )
+        } else {
+            (k.clone(), v.clone())
+        };
+        // The oldest positions are dropped when the cache would exceed MAX_SEQ_LEN.
+        let kept = usize::min(self.seq_len, MAX_SEQ_LEN - k.dim(2)?);
+        let seq_len = kept + k.dim(2)?;
+        let prev_k = self.k.narrow(2, self.seq_len - kept, kept)?;
+        let prev_v = self.v.narrow(2, self.seq_len - kept, kept)?;
+        let capacity = self.k.dim(2)?;
+        let grow = seq_len > capacity;
+        if grow {
+            let capacity = usize::min(usize::max(seq_len, 2 * capacity), MAX_SEQ_LEN);
+            let (b_sz, n_head, _, head_dim) = self.k.dims4()?;
+            let shape = (b_sz, n_head, capacity, head_dim);
+            self.k = Tensor::zeros(shape, self.k.dtype(), self.k.device())?.contiguous()?;
+            self.v = Tensor::zeros(shape, self.v.dtype(), self.v.device())?.contiguous()?;
+        }
+        if kept > 0 && (grow || kept < self.seq_len) {
+            self.k.slice_assign((.., .., ..kept), &prev_k)?;
+            self.v.slice_assign((.., .., ..kept), &prev_v)?;
+        }
+        self.k.slice_assign((.., .., kept..seq_len), &k)?;
+        self.v.slice_assign((.., .., kept..seq_len), &v)?;
+        self.seq_len = seq_len;
+        let k = self.k.narrow(2, 0, seq_len)?;
+        let v = self.v.narrow(2, 0, seq_len)?;
+        Ok((k, v))
+    }
+}
+
 #[derive(Clone)]
 pub struct Cache {
     masks: Arc<Mutex<HashMap<usize, Tensor>>>,
     pub use_kv_cache: bool,
-    #[allow(clippy::type_complexity)]
-    kvs: Arc<Mutex<Vec<Option<(Tensor, Tensor)>>>>,
+    kvs: Arc<Mutex<Vec<Option<KvCache>>>>,
     cos: Tensor,
     sin: Tensor,
     device: Device,
@@ -150,7 +207,7 @@ impl RmsNorm {
         // This is a no-op if x's dtype is already f32.
         let x = x.to_dtype(DType::F32)?;
         let (b_sz, seq_len, hidden_size) = x.dims3()?;
-        let norm_x = (x.sqr()?.sum_keepdim(2)? / hidden_size as f64)?;
+        let norm_x = x.sqr()?.mean_keepdim(2)?;
         let norm_x = norm_x.broadcast_as((b_sz, seq_len, hidden_size))?;
         let x_normed = (x / (norm_x + self.eps)?.sqrt()?)?;
         let size = self.scale.dims1()?;
@@ -231,23 +288,10 @@ impl CausalSelfAttention {
 
         if self.cache.use_kv_cache {
             let mut cache = self.cache.kvs.lock().unwrap();
-            if let Some((cache_k, cache_v)) = &cache[block_idx] {
-                k = Tensor::cat(&[cache_k, &k], 2)?.contiguous()?;
-                v = Tensor::cat(&[cache_v, &v], 2)?.contiguous()?;
-                let k_seq_len = k.dims()[1];
-                if k_seq_len > MAX_SEQ_LEN {
-                    k = k
-                        .narrow(D::Minus1, k_seq_len - MAX_SEQ_LEN, MAX_SEQ_LEN)?
-                        .contiguous()?
-                }
-                let v_seq_len = v.dims()[1];
-                if v_seq_len > 2 * MAX_SEQ_LEN {
-                    v = v
-                        .narrow(D::Minus1, v_seq_len - MAX_SEQ_LEN, MAX_SEQ_LEN)?
-                        .contiguous()?
-                }
+            match &mut cache[block_idx] {
+                Some(kv_cache) => (k, v) = kv_cache.append(&k, &v)?,
+                None => cache[block_idx] = Some(KvCache::new(&k, &v)?),
             }
-            cache[block_idx] = Some((k.clone(), v.clone()))
         }
 
         let k = self.repeat_kv(k)?;
@@ -5,7 +5,7 @@ extern crate intel_mkl_src;
 use clap::{Parser, ValueEnum};
 
 use candle::{DType, Result, Tensor, D};
-use candle_nn::{loss, ops, Linear, VarBuilder, VarMap};
+use candle_nn::{loss, ops, Conv2d, Linear, VarBuilder, VarMap};
 
 const IMAGE_DIM: usize = 784;
 const LABELS: usize = 10;
@@ -55,11 +55,53 @@ impl Model for Mlp {
     }
 }
 
+struct ConvNet {
+    conv1: Conv2d,
+    conv2: Conv2d,
+    fc1: Linear,
+    fc2: Linear,
+}
+
+impl Model for ConvNet {
+    fn new(vs: VarBuilder) -> Result<Self> {
+        let conv1 = candle_nn::conv2d(1, 16, 5, Default::default(), vs.pp("c1"))?;
+        let conv2 = candle_nn::conv2d(16, 32, 5, Default::default(), vs.pp("c2"))?;
+        let fc1 = candle_nn::linear(32 * 4 * 4, 100, vs.pp("fc1"))?;
+        let fc2 = candle_nn::linear(100, LABELS, vs.pp("fc2"))?;
+        Ok(Self {
+            conv1,
+            conv2,
+            fc1,
+            fc2,
+        })
+    }
+
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let (b_sz, _img_dim) = xs.dims2()?;
+        let xs = xs.reshape((b_sz, 1, 28, 28))?;
+        // (b, 1, 28, 28) -> (b, 16, 12, 12) -> (b, 32, 4, 4)
+        let xs = self
+            .conv1
+            .forward(&xs)?
+            .max_pool2d((2, 2), (2, 2))?
+            .relu()?;
+        let xs = self
+            .conv2
+            .forward(&xs)?
+            .max_pool2d((2, 2), (2, 2))?
+            .relu()?;
+        let xs = xs.flatten_from(1)?;
+        let xs = self.fc1.forward(&xs)?.relu()?;
+        self.fc2.forward(&xs)
+    }
+}
+
 struct TrainingArgs {
     learning_rate: f64,
     load: Option<String>,
     save: Option<String>,
     epochs: usize,
+    batch_size: usize,
 }
 
 fn training_loop<M: Model>(
@@ -111,10 +153,75 @@ fn training_loop<M: Model>(
     Ok(())
 }
 
+// The convolutional model is too expensive to run on the whole training set at each step so it
+// is trained with shuffled mini-batches and evaluated on the test set after each epoch.
+fn training_loop_cnn(
+    m: candle_datasets::vision::Dataset,
+    args: &TrainingArgs,
+) -> anyhow::Result<()> {
+    use rand::seq::SliceRandom;
+
+    let dev = candle::Device::cuda_if_available(0)?;
+
+    let train_labels = m.train_labels;
+    let train_images = m.train_images.to_device(&dev)?;
+    let train_labels = train_labels.to_dtype(DType::U32)?.to_device(&dev)?;
+
+    let mut varmap = VarMap::new();
+    let vs = VarBuilder::from_varmap(&varmap, DType::F32, &dev);
+    let model = ConvNet::new(vs.clone())?;
+
+    if let Some(load) = &args.load {
+        println!("loading weights from {load}");
+        varmap.load(load)?
+    }
+
+    let sgd = candle_nn::SGD::new(varmap.all_vars(), args.learning_rate);
+    let test_images = m.test_images.to_device(&dev)?;
+    let test_labels = m.test_labels.to_dtype(DType::U32)?.to_device(&dev)?;
+    let n_batches = train_images.dim(0)? / args.batch_size;
+    let mut batch_idxs = (0..n_batches).collect::<Vec<usize>>();
+    for epoch in 1..args.epochs {
+        let mut sum_loss = 0f32;
+        batch_idxs.shuffle(&mut rand::thread_rng());
+        for batch_idx in batch_idxs.iter() {
+            let start = batch_idx * args.batch_size;
+            let train_images = train_images.narrow(0, start, args.batch_size)?;
+            let train_labels = train_labels.narrow(0, start, args.batch_size)?;
+            let logits = model.forward(&train_images)?;
+            let log_sm = ops::log_softmax(&logits, D::Minus1)?;
+            let loss = loss::nll(&log_sm, &train_labels)?;
+            sgd.backward_step(&loss)?;
+            sum_loss += loss.to_scalar::<f32>()?;
+        }
+        let avg_loss = sum_loss / n_batches as f32;
+
+        let test_logits = model.forward(&test_images)?;
+        let sum_ok = test_logits
+            .argmax(D::Minus1)?
+            .eq(&test_labels)?
+            .to_dtype(DType::F32)?
+            .sum_all()?
+            .to_scalar::<f32>()?;
+        let test_accuracy = sum_ok / test_labels.dims1()? as f32;
+        println!(
+            "{epoch:4} train loss {:8.5} test acc: {:5.2}%",
+            avg_loss,
+            100. * test_accuracy
+        );
+    }
+    if let Some(save) = &args.save {
+        println!("saving trained weights in {save}");
+        varmap.save(save)?
+    }
+    Ok(())
+}
+
 #[derive(ValueEnum, Clone)]
 enum WhichModel {
     Linear,
     Mlp,
+    Cnn,
 }
 
 #[derive(Parser)]
@@ -125,8 +232,13 @@ struct Args {
     #[arg(long)]
     learning_rate: Option<f64>,
 
-    #[arg(long, default_value_t = 200)]
-    epochs: usize,
+    /// The number of epochs, defaults to 200 for the linear and mlp models and 10 for the cnn.
+    #[arg(long)]
+    epochs: Option<usize>,
+
+    /// The mini-batch size, only used by the cnn model.
+    #[arg(long, default_value_t = 64)]
+    batch_size: usize,
 
     /// The file where to save the trained weights, in safetensors format.
     #[arg(long)]
@@ -149,9 +261,15 @@ pub fn main() -> anyhow::Result<()> {
     let default_learning_rate = match args.model {
         WhichModel::Linear => 1.,
         WhichModel::Mlp => 0.05,
+        WhichModel::Cnn => 0.05,
+    };
+    let default_epochs = match args.model {
+        WhichModel::Linear | WhichModel::Mlp => 200,
+        WhichModel::Cnn => 10,
     };
     let training_args = TrainingArgs {
-        epochs: args.epochs,
+        epochs: args.epochs.unwrap_or(default_epochs),
+        batch_size: args.batch_size,
         learning_rate: args.learning_rate.unwrap_or(default_learning_rate),
         load: args.load,
         save: args.save,
@@ -159,5 +277,6 @@ pub fn main() -> anyhow::Result<()> {
     match args.model {
         WhichModel::Linear => training_loop::<LinearModel>(m, &training_args),
         WhichModel::Mlp => training_loop::<Mlp>(m, &training_args),
+        WhichModel::Cnn => training_loop_cnn(m, &training_args),
     }
 }
@@ -1,6 +1,7 @@
 use crate::nn::{conv1d, conv1d_weight_norm, Conv1d, Conv1dConfig, VarBuilder};
 use anyhow::Result;
-use candle::{DType, IndexOp, Tensor};
+use candle::{DType, IndexOp, Tensor, D};
+use candle_nn::{ConvTranspose1d, ConvTranspose1dConfig};
 
 // Encodec Model
 // https://github.com/huggingface/transformers/blob/main/src/transformers/models/encodec/modeling_encodec.py
@@ -223,9 +224,10 @@ impl EncodecLSTM {
 
 #[derive(Debug)]
 struct EncodecConvTranspose1d {
-    weight_g: Tensor,
-    weight_v: Tensor,
-    bias: Tensor,
+    causal: bool,
+    trim_right_ratio: f64,
+    padding_total: usize,
+    conv: ConvTranspose1d,
 }
 
 impl EncodecConvTranspose1d {
@@ -233,23 +235,44 @@ impl EncodecConvTranspose1d {
         in_c: usize,
         out_c: usize,
         k: usize,
-        _stride: usize,
+        stride: usize,
         vb: VarBuilder,
-        _cfg: &Config,
+        cfg: &Config,
     ) -> Result<Self> {
         let vb = &vb.pp("conv");
+        // Weight norm for inference, the norm is computed over all the dims except the first one.
         let weight_g = vb.get((in_c, 1, 1), "weight_g")?;
         let weight_v = vb.get((in_c, out_c, k), "weight_v")?;
+        let norm_v = weight_v.sqr()?.sum_keepdim((1, 2))?.sqrt()?;
+        let weight = weight_v.broadcast_mul(&weight_g)?.broadcast_div(&norm_v)?;
         let bias = vb.get(out_c, "bias")?;
+        let config = ConvTranspose1dConfig {
+            stride,
+            ..Default::default()
+        };
+        let conv = ConvTranspose1d::new(weight, Some(bias), config);
         Ok(Self {
-            weight_g,
-            weight_v,
-            bias,
+            causal: cfg.use_causal_conv,
+            trim_right_ratio: cfg.trim_right_ratio,
+            padding_total: k.saturating_sub(stride),
+            conv,
         })
     }
 
-    fn forward(&self, _xs: &Tensor) -> Result<Tensor> {
-        todo!()
+    fn forward(&self, xs: &Tensor) -> Result<Tensor> {
+        let xs = self.conv.forward(xs)?;
+        // Remove the padding that the transposed convolution added on both sides, for causal
+        // convolutions trim_right_ratio decides how much of it is removed on the right.
+        let padding_right = if self.causal {
+            ((self.padding_total as f64 * self.trim_right_ratio).ceil() as usize)
+                .min(self.padding_total)
+        } else {
+            self.padding_total / 2
+        };
+        let padding_left = self.padding_total - padding_right;
+        let len = xs.dim(D::Minus1)? - self.padding_total;
+        let xs = xs.narrow(D::Minus1, padding_left, len)?;
+        Ok(xs)
     }
 }
 
@@ -265,24 +288,20 @@ impl EncodecConv1d {
         out_c: usize,
         kernel_size: usize,
         stride: usize,
+        dilation: usize,
         vb: VarBuilder,
         cfg: &Config,
     ) -> Result<Self> {
+        let conv_cfg = Conv1dConfig {
+            stride,
+            dilation,
+            ..Default::default()
+        };
         let conv = match cfg.norm_type {
-            NormType::WeightNorm => conv1d_weight_norm(
-                in_c,
-                out_c,
-                kernel_size,
-                Conv1dConfig { padding: 0, stride },
-                vb.pp("conv"),
-            )?,
-            NormType::None => conv1d(
-                in_c,
-                out_c,
-                kernel_size,
-                Conv1dConfig { padding: 0, stride },
-                vb.pp("conv"),
-            )?,
+            NormType::WeightNorm => {
+                conv1d_weight_norm(in_c, out_c, kernel_size, conv_cfg, vb.pp("conv"))?
+            }
+            NormType::None => conv1d(in_c, out_c, kernel_size, conv_cfg, vb.pp("conv"))?,
         };
         Ok(Self {
             causal: cfg.use_causal_conv,
@@ -312,14 +331,20 @@ impl EncodecResnetBlock {
         if dilations.len() != 2 {
             anyhow::bail!("expected dilations of size 2")
         }
-        // TODO: Apply dilations!
         layer.inc();
-        let block_conv1 =
-            EncodecConv1d::load(dim, h, cfg.residual_kernel_size, 1, layer.next(), cfg)?;
+        let block_conv1 = EncodecConv1d::load(
+            dim,
+            h,
+            cfg.residual_kernel_size,
+            1,
+            dilations[0],
+            layer.next(),
+            cfg,
+        )?;
         layer.inc();
-        let block_conv2 = EncodecConv1d::load(h, dim, 1, 1, layer.next(), cfg)?;
+        let block_conv2 = EncodecConv1d::load(h, dim, 1, 1, dilations[1], layer.next(), cfg)?;
         let shortcut = if cfg.use_conv_shortcut {
-            let conv = EncodecConv1d::load(dim, dim, 1, 1, vb.pp("shortcut"), cfg)?;
+            let conv = EncodecConv1d::load(dim, dim, 1, 1, 1, vb.pp("shortcut"), cfg)?;
             Some(conv)
         } else {
             None
@@ -382,6 +407,7 @@ impl EncodecEncoder {
             cfg.num_filters,
             cfg.kernel_size,
             1,
+            1,
             layer.next(),
             cfg,
         )?;
@@ -405,6 +431,7 @@ impl EncodecEncoder {
                 current_scale * 2,
                 ratio * 2,
                 ratio,
+                1,
                 layer.next(),
                 cfg,
             )?;
@@ -418,6 +445,7 @@ impl EncodecEncoder {
             cfg.hidden_size,
             cfg.last_kernel_size,
             1,
+            1,
             layer.next(),
             cfg,
         )?;
@@ -451,6 +479,7 @@ impl EncodecDecoder {
             cfg.num_filters * scaling,
             cfg.last_kernel_size,
             1,
+            1,
             layer.next(),
             cfg,
         )?;
@@ -486,6 +515,7 @@ impl EncodecDecoder {
             cfg.audio_channels,
             cfg.last_kernel_size,
             1,
+            1,
             layer.next(),
             cfg,
         )?;
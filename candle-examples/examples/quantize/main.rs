@@ -0,0 +1,108 @@
+//! Converts safetensors checkpoints to a gguf file with quantized weights.
+//!
+//! ```bash
+//! cargo run --example quantize --release -- model.safetensors --out-file model-q4k.gguf
+//! ```
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use candle::quantized::{k_quants, QTensor};
+use candle::{Device, Tensor};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Quantization {
+    #[value(name = "q4_0")]
+    Q4_0,
+    #[value(name = "q4_1")]
+    Q4_1,
+    #[value(name = "q5_0")]
+    Q5_0,
+    #[value(name = "q5_1")]
+    Q5_1,
+    #[value(name = "q8_0")]
+    Q8_0,
+    Q2k,
+    Q3k,
+    Q4k,
+    Q5k,
+    Q6k,
+    F32,
+}
+
+impl Quantization {
+    fn block_size(&self) -> usize {
+        match self {
+            Self::Q4_0 | Self::Q4_1 | Self::Q5_0 | Self::Q5_1 | Self::Q8_0 => 32,
+            Self::Q2k | Self::Q3k | Self::Q4k | Self::Q5k | Self::Q6k => k_quants::QK_K,
+            Self::F32 => 1,
+        }
+    }
+
+    fn quantize(&self, tensor: &Tensor) -> candle::Result<QTensor> {
+        match self {
+            Self::Q4_0 => QTensor::quantize::<k_quants::BlockQ4_0>(tensor),
+            Self::Q4_1 => QTensor::quantize::<k_quants::BlockQ4_1>(tensor),
+            Self::Q5_0 => QTensor::quantize::<k_quants::BlockQ5_0>(tensor),
+            Self::Q5_1 => QTensor::quantize::<k_quants::BlockQ5_1>(tensor),
+            Self::Q8_0 => QTensor::quantize::<k_quants::BlockQ8_0>(tensor),
+            Self::Q2k => QTensor::quantize::<k_quants::BlockQ2K>(tensor),
+            Self::Q3k => QTensor::quantize::<k_quants::BlockQ3K>(tensor),
+            Self::Q4k => QTensor::quantize::<k_quants::BlockQ4K>(tensor),
+            Self::Q5k => QTensor::quantize::<k_quants::BlockQ5K>(tensor),
+            Self::Q6k => QTensor::quantize::<k_quants::BlockQ6K>(tensor),
+            Self::F32 => QTensor::quantize::<f32>(tensor),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// The safetensors files to read the weights from.
+    #[arg(required = true)]
+    in_files: Vec<String>,
+
+    /// The gguf file to write.
+    #[arg(long)]
+    out_file: String,
+
+    /// The quantization to use for the weight matrices, the other tensors are kept in f32.
+    #[arg(long, value_enum, default_value_t = Quantization::Q4k)]
+    quantization: Quantization,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut tensors = vec![];
+    for in_file in args.in_files.iter() {
+        let file_tensors = candle::safetensors::load(in_file, &Device::Cpu)?;
+        tensors.extend(file_tensors)
+    }
+    tensors.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let block_size = args.quantization.block_size();
+    let mut qtensors = Vec::with_capacity(tensors.len());
+    for (name, tensor) in tensors.iter() {
+        // Only the matrices get quantized, the biases, norms, etc are kept as f32.
+        let should_quantize = tensor.rank() == 2 && tensor.dim(1)? % block_size == 0;
+        let qtensor = if should_quantize {
+            args.quantization.quantize(tensor)?
+        } else {
+            QTensor::quantize::<f32>(tensor)?
+        };
+        println!("{name} {:?} {:?}", qtensor.shape(), qtensor.dtype());
+        qtensors.push((name.as_str(), qtensor))
+    }
+    let qtensors = qtensors
+        .iter()
+        .map(|(name, qtensor)| (*name, qtensor))
+        .collect::<Vec<_>>();
+
+    let mut out_file = std::fs::File::create(&args.out_file)?;
+    candle::gguf::write(&mut out_file, &[], &qtensors)?;
+    println!("wrote {} tensors to {}", qtensors.len(), args.out_file);
+    Ok(())
+}
@@ -79,12 +79,7 @@ impl DDIMScheduler {
             }
             BetaSchedule::SquaredcosCapV2 => betas_for_alpha_bar(config.train_timesteps, 0.999)?,
         };
-        let betas = betas.to_vec1::<f64>()?;
-        let mut alphas_cumprod = Vec::with_capacity(betas.len());
-        for &beta in betas.iter() {
-            let alpha = 1.0 - beta;
-            alphas_cumprod.push(alpha * *alphas_cumprod.last().unwrap_or(&1f64))
-        }
+        let alphas_cumprod = betas.affine(-1., 1.)?.cumprod(0)?.to_vec1::<f64>()?;
         Ok(Self {
             alphas_cumprod,
             timesteps,
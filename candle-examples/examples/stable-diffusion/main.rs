@@ -245,6 +245,7 @@ fn run(args: Args) -> Result<()> {
             if args.intermediary_images {
                 let image = vae.decode(&(&latents / 0.18215)?)?;
                 let image = ((image / 2.)? + 0.5)?.to_device(&Device::Cpu)?;
+                let image = image.clamp(0f32, 1f32)?;
                 let image = (image * 255.)?.to_dtype(DType::U8)?;
                 let image_filename =
                     output_filename(&final_image, idx + 1, num_samples, Some(timestep_index + 1));
@@ -258,8 +259,8 @@ fn run(args: Args) -> Result<()> {
             num_samples
         );
         let image = vae.decode(&(&latents / 0.18215)?)?;
-        // TODO: Add the clamping between 0 and 1.
         let image = ((image / 2.)? + 0.5)?.to_device(&Device::Cpu)?;
+        let image = image.clamp(0f32, 1f32)?;
         let image = (image * 255.)?.to_dtype(DType::U8)?;
         let image_filename = output_filename(&final_image, idx + 1, num_samples, None);
         crate::utils::save_image(&image, image_filename)?
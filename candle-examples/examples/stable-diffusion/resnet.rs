@@ -61,8 +61,8 @@ impl ResnetBlock2D {
     ) -> Result<Self> {
         let out_channels = config.out_channels.unwrap_or(in_channels);
         let conv_cfg = nn::Conv2dConfig {
-            stride: 1,
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
+            ..Default::default()
         };
         let norm1 = nn::group_norm(config.groups, in_channels, config.eps, vs.pp("norm1"))?;
         let conv1 = nn::conv2d(in_channels, out_channels, 3, conv_cfg, vs.pp("conv1"))?;
@@ -74,8 +74,8 @@ impl ResnetBlock2D {
             .unwrap_or(in_channels != out_channels);
         let conv_shortcut = if use_in_shortcut {
             let conv_cfg = nn::Conv2dConfig {
-                stride: 1,
-                padding: 0,
+                padding: nn::ConvPadding::Symmetric(0),
+                ..Default::default()
             };
             Some(nn::conv2d(
                 in_channels,
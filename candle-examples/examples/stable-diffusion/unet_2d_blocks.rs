@@ -23,7 +23,11 @@ impl Downsample2D {
         padding: usize,
     ) -> Result<Self> {
         let conv = if use_conv {
-            let config = nn::Conv2dConfig { stride: 2, padding };
+            let config = nn::Conv2dConfig {
+                padding: nn::ConvPadding::Symmetric(padding),
+                stride: 2,
+                ..Default::default()
+            };
             let conv = nn::conv2d(in_channels, out_channels, 3, config, vs.pp("conv"))?;
             Some(conv)
         } else {
@@ -60,7 +64,7 @@ struct Upsample2D {
 impl Upsample2D {
     fn new(vs: nn::VarBuilder, in_channels: usize, out_channels: usize) -> Result<Self> {
         let config = nn::Conv2dConfig {
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
             ..Default::default()
         };
         let conv = nn::conv2d(in_channels, out_channels, 3, config, vs.pp("conv"))?;
@@ -50,8 +50,8 @@ impl Encoder {
         config: EncoderConfig,
     ) -> Result<Self> {
         let conv_cfg = nn::Conv2dConfig {
-            stride: 1,
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
+            ..Default::default()
         };
         let conv_in = nn::conv2d(
             in_channels,
@@ -108,7 +108,7 @@ impl Encoder {
             out_channels
         };
         let conv_cfg = nn::Conv2dConfig {
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
             ..Default::default()
         };
         let conv_out = nn::conv2d(
@@ -181,8 +181,8 @@ impl Decoder {
         let n_block_out_channels = config.block_out_channels.len();
         let last_block_out_channels = *config.block_out_channels.last().unwrap();
         let conv_cfg = nn::Conv2dConfig {
-            stride: 1,
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
+            ..Default::default()
         };
         let conv_in = nn::conv2d(
             in_channels,
@@ -234,7 +234,7 @@ impl Decoder {
             vs.pp("conv_norm_out"),
         )?;
         let conv_cfg = nn::Conv2dConfig {
-            padding: 1,
+            padding: nn::ConvPadding::Symmetric(1),
             ..Default::default()
         };
         let conv_out = nn::conv2d(
@@ -1,5 +1,7 @@
 use candle::{Device, Result, Tensor};
-use candle_nn::{ops::softmax, Conv1d, Conv1dConfig, Embedding, LayerNorm, VarBuilder};
+use candle_nn::{
+    ops::softmax, Conv1d, Conv1dConfig, ConvPadding, Embedding, LayerNorm, VarBuilder,
+};
 use serde::Deserialize;
 
 // The names in comments correspond to the original implementation:
@@ -244,12 +246,14 @@ impl AudioEncoder {
         let n_head = cfg.encoder_attention_heads;
         let n_ctx = cfg.max_source_positions;
         let cfg1 = Conv1dConfig {
-            padding: 1,
+            padding: ConvPadding::Symmetric(1),
             stride: 1,
+            ..Default::default()
         };
         let cfg2 = Conv1dConfig {
-            padding: 1,
+            padding: ConvPadding::Symmetric(1),
             stride: 2,
+            ..Default::default()
         };
         let conv1 = conv1d(cfg.num_mel_bins, n_state, 3, cfg1, vb.pp("conv1"))?;
         let conv2 = conv1d(n_state, n_state, 3, cfg2, vb.pp("conv2"))?;
@@ -344,11 +348,7 @@ impl TextDecoder {
             x = block.forward(&x, Some(xa), Some(&self.mask))?;
         }
         let x = self.ln.forward(&x)?;
-        let w = self
-            .token_embedding
-            .embeddings()
-            .broadcast_left(x_dims[0])?;
-        let logits = x.matmul(&w.t()?)?;
+        let logits = x.matmul(&self.token_embedding.embeddings().t()?)?;
         Ok(logits)
     }
 }
@@ -1,11 +1,34 @@
 #include "binary_op_macros.cuh"
 #include<stdint.h>
 
+// The remainder has the same sign as the divisor, as for the python `%` operator.
+template<typename T>
+__device__ __forceinline__ T remg(T x, T y) {
+    T r = fmodg(x, y);
+    return (r != T(0) && ((r < T(0)) != (y < T(0)))) ? r + y : r;
+}
+
+template<typename T>
+__device__ __forceinline__ T upowg(T x, T y) {
+    T acc = 1;
+    while (y > 0) {
+        if (y & 1) acc *= x;
+        x *= x;
+        y >>= 1;
+    }
+    return acc;
+}
+
 #if __CUDA_ARCH__ >= 800
 BINARY_OP(__nv_bfloat16, badd_bf16, x + y)
 BINARY_OP(__nv_bfloat16, bdiv_bf16, x / y)
 BINARY_OP(__nv_bfloat16, bmul_bf16, x * y)
 BINARY_OP(__nv_bfloat16, bsub_bf16, x - y)
+BINARY_OP(__nv_bfloat16, bmaximum_bf16, (isnang(x) || x > y) ? x : y)
+BINARY_OP(__nv_bfloat16, bminimum_bf16, (isnang(x) || x < y) ? x : y)
+BINARY_OP(__nv_bfloat16, bpow_bf16, powg(x, y))
+BINARY_OP(__nv_bfloat16, batan2_bf16, atan2g(x, y))
+BINARY_OP(__nv_bfloat16, brem_bf16, remg(x, y))
 BINARY_OP_OUT(__nv_bfloat16, uint8_t, eq_bf16, x == y)
 BINARY_OP_OUT(__nv_bfloat16, uint8_t, ne_bf16, x != y)
 BINARY_OP_OUT(__nv_bfloat16, uint8_t, lt_bf16, x < y)
@@ -19,6 +42,11 @@ BINARY_OP(__half, badd_f16, x + y)
 BINARY_OP(__half, bdiv_f16, x / y)
 BINARY_OP(__half, bmul_f16, x * y)
 BINARY_OP(__half, bsub_f16, x - y)
+BINARY_OP(__half, bmaximum_f16, (isnang(x) || x > y) ? x : y)
+BINARY_OP(__half, bminimum_f16, (isnang(x) || x < y) ? x : y)
+BINARY_OP(__half, bpow_f16, powg(x, y))
+BINARY_OP(__half, batan2_f16, atan2g(x, y))
+BINARY_OP(__half, brem_f16, remg(x, y))
 BINARY_OP_OUT(__half, uint8_t, eq_f16, x == y)
 BINARY_OP_OUT(__half, uint8_t, ne_f16, x != y)
 BINARY_OP_OUT(__half, uint8_t, lt_f16, x < y)
@@ -43,6 +71,26 @@ BINARY_OP(float, bsub_f32, x - y)
 BINARY_OP(double, bsub_f64, x - y);
 BINARY_OP(uint8_t, bsub_u8, x - y);
 BINARY_OP(uint32_t, bsub_u32, x - y);
+BINARY_OP(float, bmaximum_f32, (isnang(x) || x > y) ? x : y)
+BINARY_OP(float, bminimum_f32, (isnang(x) || x < y) ? x : y)
+BINARY_OP(float, bpow_f32, powg(x, y))
+BINARY_OP(float, batan2_f32, atan2g(x, y))
+BINARY_OP(float, brem_f32, remg(x, y))
+BINARY_OP(double, bmaximum_f64, (isnang(x) || x > y) ? x : y)
+BINARY_OP(double, bminimum_f64, (isnang(x) || x < y) ? x : y)
+BINARY_OP(double, bpow_f64, powg(x, y))
+BINARY_OP(double, batan2_f64, atan2g(x, y))
+BINARY_OP(double, brem_f64, remg(x, y))
+BINARY_OP(uint8_t, bmaximum_u8, maxg(x, y));
+BINARY_OP(uint32_t, bmaximum_u32, maxg(x, y));
+BINARY_OP(uint8_t, bminimum_u8, ming(x, y));
+BINARY_OP(uint32_t, bminimum_u32, ming(x, y));
+BINARY_OP(uint8_t, bpow_u8, upowg(x, y));
+BINARY_OP(uint32_t, bpow_u32, upowg(x, y));
+BINARY_OP(uint8_t, batan2_u8, atan2f(x, y));
+BINARY_OP(uint32_t, batan2_u32, atan2f(x, y));
+BINARY_OP(uint8_t, brem_u8, x % y);
+BINARY_OP(uint32_t, brem_u32, x % y);
 
 BINARY_OP_OUT(float, uint8_t, eq_f32, x == y)
 BINARY_OP_OUT(double, uint8_t, eq_f64, x == y)
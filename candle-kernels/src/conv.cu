@@ -5,39 +5,48 @@ template <typename T, typename A>
 __device__ void conv1d(
     const size_t src_numel,
     const size_t l_out,
-    const size_t stride, 
+    const size_t stride,
+    const size_t padding,
+    const size_t dilation,
+    const size_t groups,
     const size_t *info,
     const T *src,
     const T *kernel,
     T *dst
 ) {
   // src: (b_size, c_in, l_in)
-  // k: (c_out, c_in, k_size)
+  // k: (c_out, c_in / groups, k_size)
   const size_t *src_dims = info;
   const size_t *src_s = info + 3;
   const size_t *k_dims = info + 6;
   const size_t *k_s = info + 9;
   const size_t dst_i = blockIdx.x * blockDim.x + threadIdx.x;
   const size_t k_size = k_dims[2];
-  const size_t k_over_2 = k_size / 2;
   const size_t c_out = k_dims[0];
-  const size_t c_in = src_dims[1];
+  const size_t c_in_per_group = k_dims[1];
+  const size_t c_out_per_group = c_out / groups;
+  const size_t b_size = src_dims[0];
   const size_t l_in = src_dims[2];
 
   // TODO
   const size_t b_idx = dst_i / (l_out * c_out);
+  if (b_idx >= b_size) {
+    return;
+  }
   const size_t dst_c_idx = (dst_i / l_out) % c_out;
   const size_t dst_l = dst_i % l_out;
+  const size_t src_c_start = (dst_c_idx / c_out_per_group) * c_in_per_group;
 
   const size_t src_idx0 = b_idx * src_s[0];
   A d = 0;
   for (size_t offset = 0; offset < k_size; ++offset) {
-    const size_t src_l_plus = stride * dst_l + offset;
-    if (k_over_2 <= src_l_plus && src_l_plus < l_in + k_over_2) {
-      const size_t src_l = src_l_plus - k_over_2;
-      for (size_t src_c_idx = 0; src_c_idx < c_in; ++src_c_idx) {
+    const size_t src_l_plus = stride * dst_l + dilation * offset;
+    if (padding <= src_l_plus && src_l_plus < l_in + padding) {
+      const size_t src_l = src_l_plus - padding;
+      for (size_t k_c_idx = 0; k_c_idx < c_in_per_group; ++k_c_idx) {
+        const size_t src_c_idx = src_c_start + k_c_idx;
         const size_t src_idx = src_idx0 + src_c_idx * src_s[1] + src_l * src_s[2];
-        const size_t k_idx = dst_c_idx * k_s[0] + src_c_idx * k_s[1] + offset * k_s[2];
+        const size_t k_idx = dst_c_idx * k_s[0] + k_c_idx * k_s[1] + offset * k_s[2];
         d += static_cast<A>(src[src_idx]) * static_cast<A>(kernel[k_idx]);
       }
     }
@@ -49,14 +58,17 @@ __device__ void conv1d(
 #define CONV1D_OP(TYPENAME, TYPEACC, FN_NAME) \
 extern "C" __global__ void FN_NAME(  \
     const size_t src_numel, \
-    const size_t num_dims, \
+    const size_t l_out, \
     const size_t stride, \
+    const size_t padding, \
+    const size_t dilation, \
+    const size_t groups, \
     const size_t *info, \
     const TYPENAME *src, \
     const TYPENAME *kernel, \
     TYPENAME *dst \
 ) {  \
-  conv1d<TYPENAME, TYPEACC>(src_numel, num_dims, stride, info, src, kernel, dst); \
+  conv1d<TYPENAME, TYPEACC>(src_numel, l_out, stride, padding, dilation, groups, info, src, kernel, dst); \
 } \
 
 #if __CUDA_ARCH__ >= 800
@@ -141,6 +141,20 @@ __device__ __forceinline__ float absg(float a) { return fabsf(a); }
 __device__ __forceinline__ double absg(double a) { return fabs(a); }
 __device__ __forceinline__ float copysigng(float a, float b) { return copysignf(a, b); }
 __device__ __forceinline__ double copysigng(double a, double b) { return copysign(a, b); }
+__device__ __forceinline__ float atan2g(float a, float b) { return atan2f(a, b); }
+__device__ __forceinline__ double atan2g(double a, double b) { return atan2(a, b); }
+__device__ __forceinline__ float fmodg(float a, float b) { return fmodf(a, b); }
+__device__ __forceinline__ double fmodg(double a, double b) { return fmod(a, b); }
+__device__ __forceinline__ float erfg(float a) { return erff(a); }
+__device__ __forceinline__ double erfg(double a) { return erf(a); }
+__device__ __forceinline__ float floorg(float a) { return floorf(a); }
+__device__ __forceinline__ double floorg(double a) { return floor(a); }
+__device__ __forceinline__ float ceilg(float a) { return ceilf(a); }
+__device__ __forceinline__ double ceilg(double a) { return ceil(a); }
+__device__ __forceinline__ float roundg(float a) { return rintf(a); }
+__device__ __forceinline__ double roundg(double a) { return rint(a); }
+__device__ __forceinline__ float log1pg(float a) { return log1pf(a); }
+__device__ __forceinline__ double log1pg(double a) { return log1p(a); }
 
 __device__ __forceinline__ uint32_t ming(uint32_t a, uint32_t b) { return min(a, b); }
 __device__ __forceinline__ uint32_t maxg(uint32_t a, uint32_t b) { return max(a, b); }
@@ -160,6 +174,13 @@ __device__ __forceinline__ __half logg(__half a) { return hlog(a); }
 __device__ __forceinline__ __half expg(__half a) { return hexp(a); }
 __device__ __forceinline__ __half absg(__half a) { return __habs(a); }
 __device__ __forceinline__ __half copysigng(__half a, __half b) { return __float2half(copysignf(__half2float(a), __half2float(b))); }
+__device__ __forceinline__ __half atan2g(__half a, __half b) { return __float2half(atan2f(__half2float(a), __half2float(b))); }
+__device__ __forceinline__ __half fmodg(__half a, __half b) { return __float2half(fmodf(__half2float(a), __half2float(b))); }
+__device__ __forceinline__ __half erfg(__half a) { return __float2half(erff(__half2float(a))); }
+__device__ __forceinline__ __half floorg(__half a) { return __float2half(floorf(__half2float(a))); }
+__device__ __forceinline__ __half ceilg(__half a) { return __float2half(ceilf(__half2float(a))); }
+__device__ __forceinline__ __half roundg(__half a) { return __float2half(rintf(__half2float(a))); }
+__device__ __forceinline__ __half log1pg(__half a) { return __float2half(log1pf(__half2float(a))); }
 #endif
 
 #if __CUDA_ARCH__ >= 800
@@ -176,4 +197,11 @@ __device__ __forceinline__ __nv_bfloat16 logg(__nv_bfloat16 a) { return hlog(a);
 __device__ __forceinline__ __nv_bfloat16 expg(__nv_bfloat16 a) { return hexp(a); }
 __device__ __forceinline__ __nv_bfloat16 absg(__nv_bfloat16 a) { return __habs(a); }
 __device__ __forceinline__ __nv_bfloat16 copysigng(__nv_bfloat16 a, __nv_bfloat16 b) { return __float2bfloat16(copysignf(__bfloat162float(a), __bfloat162float(b))); }
+__device__ __forceinline__ __nv_bfloat16 atan2g(__nv_bfloat16 a, __nv_bfloat16 b) { return __float2bfloat16(atan2f(__bfloat162float(a), __bfloat162float(b))); }
+__device__ __forceinline__ __nv_bfloat16 fmodg(__nv_bfloat16 a, __nv_bfloat16 b) { return __float2bfloat16(fmodf(__bfloat162float(a), __bfloat162float(b))); }
+__device__ __forceinline__ __nv_bfloat16 erfg(__nv_bfloat16 a) { return __float2bfloat16(erff(__bfloat162float(a))); }
+__device__ __forceinline__ __nv_bfloat16 floorg(__nv_bfloat16 a) { return __float2bfloat16(floorf(__bfloat162float(a))); }
+__device__ __forceinline__ __nv_bfloat16 ceilg(__nv_bfloat16 a) { return __float2bfloat16(ceilf(__bfloat162float(a))); }
+__device__ __forceinline__ __nv_bfloat16 roundg(__nv_bfloat16 a) { return __float2bfloat16(rintf(__bfloat162float(a))); }
+__device__ __forceinline__ __nv_bfloat16 log1pg(__nv_bfloat16 a) { return __float2bfloat16(log1pf(__bfloat162float(a))); }
 #endif
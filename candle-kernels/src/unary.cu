@@ -36,6 +36,22 @@ __device__ __forceinline__ T gelu_fwd(T x) {
     return static_cast<T>(0.5) * x * (static_cast<T>(1.0) + tanhg(static_cast<T>(M_2_SQRTPI * M_SQRT1_2) * alpha));
 }
 
+template<typename T>
+__device__ __forceinline__ T gelu_erf_fwd(T x) {
+    return static_cast<T>(0.5) * x * (static_cast<T>(1.0) + erfg(x * static_cast<T>(M_SQRT1_2)));
+}
+
+template<typename T>
+__device__ __forceinline__ T sigmoid_fwd(T x) {
+    return recipg(static_cast<T>(1.0) + expg(-x));
+}
+
+template<typename T>
+__device__ __forceinline__ T softplus_fwd(T x) {
+    T zero = 0.;
+    return maxg(x, zero) + log1pg(expg(-absg(x)));
+}
+
 template<typename T>
 __device__ __forceinline__ T elu_fwd(T x, T alpha) {
   if (x > static_cast<T>(0)) {
@@ -91,6 +107,15 @@ UNARY_OP(__nv_bfloat16, usqrt_bf16, sqrtg(x))
 UNARY_OP(__nv_bfloat16, ugelu_bf16, gelu_fwd(x))
 UNARY_OP(__nv_bfloat16, urelu_bf16, relu_fwd(x))
 UNARY_OP1(__nv_bfloat16, uelu_bf16, elu_fwd(x, param))
+UNARY_OP(__nv_bfloat16, ugelu_erf_bf16, gelu_erf_fwd(x))
+UNARY_OP(__nv_bfloat16, utanh_bf16, tanhg(x))
+UNARY_OP(__nv_bfloat16, uerf_bf16, erfg(x))
+UNARY_OP(__nv_bfloat16, ufloor_bf16, floorg(x))
+UNARY_OP(__nv_bfloat16, uceil_bf16, ceilg(x))
+UNARY_OP(__nv_bfloat16, uround_bf16, roundg(x))
+UNARY_OP(__nv_bfloat16, usigmoid_bf16, sigmoid_fwd(x))
+UNARY_OP(__nv_bfloat16, usoftplus_bf16, softplus_fwd(x))
+UNARY_OP(__nv_bfloat16, ulog1p_bf16, log1pg(x))
 #endif
 
 #if __CUDA_ARCH__ >= 530
@@ -107,6 +132,15 @@ UNARY_OP(__half, usqrt_f16, sqrtg(x))
 UNARY_OP(__half, ugelu_f16, gelu_fwd(x))
 UNARY_OP(__half, urelu_f16, relu_fwd(x))
 UNARY_OP1(__half, uelu_f16, elu_fwd(x, param))
+UNARY_OP(__half, ugelu_erf_f16, gelu_erf_fwd(x))
+UNARY_OP(__half, utanh_f16, tanhg(x))
+UNARY_OP(__half, uerf_f16, erfg(x))
+UNARY_OP(__half, ufloor_f16, floorg(x))
+UNARY_OP(__half, uceil_f16, ceilg(x))
+UNARY_OP(__half, uround_f16, roundg(x))
+UNARY_OP(__half, usigmoid_f16, sigmoid_fwd(x))
+UNARY_OP(__half, usoftplus_f16, softplus_fwd(x))
+UNARY_OP(__half, ulog1p_f16, log1pg(x))
 #endif
 
 UNARY_OP(uint8_t, ucopy_u8, x)
@@ -137,3 +171,21 @@ UNARY_OP(float, urelu_f32, relu_fwd(x))
 UNARY_OP(double, urelu_f64, relu_fwd(x))
 UNARY_OP1(float, uelu_f32, elu_fwd(x, param))
 UNARY_OP1(double, uelu_f64, elu_fwd(x, param))
+UNARY_OP(float, ugelu_erf_f32, gelu_erf_fwd(x))
+UNARY_OP(float, utanh_f32, tanhg(x))
+UNARY_OP(float, uerf_f32, erfg(x))
+UNARY_OP(float, ufloor_f32, floorg(x))
+UNARY_OP(float, uceil_f32, ceilg(x))
+UNARY_OP(float, uround_f32, roundg(x))
+UNARY_OP(float, usigmoid_f32, sigmoid_fwd(x))
+UNARY_OP(float, usoftplus_f32, softplus_fwd(x))
+UNARY_OP(float, ulog1p_f32, log1pg(x))
+UNARY_OP(double, ugelu_erf_f64, gelu_erf_fwd(x))
+UNARY_OP(double, utanh_f64, tanhg(x))
+UNARY_OP(double, uerf_f64, erfg(x))
+UNARY_OP(double, ufloor_f64, floorg(x))
+UNARY_OP(double, uceil_f64, ceilg(x))
+UNARY_OP(double, uround_f64, roundg(x))
+UNARY_OP(double, usigmoid_f64, sigmoid_fwd(x))
+UNARY_OP(double, usoftplus_f64, softplus_fwd(x))
+UNARY_OP(double, ulog1p_f64, log1pg(x))
@@ -55,9 +55,9 @@ impl GroupNorm {
         };
         let x = x.reshape((b_sz, self.num_groups, hidden_size))?;
         let x = x.to_dtype(internal_dtype)?;
-        let mean_x = (x.sum_keepdim(2)? / hidden_size as f64)?;
+        let mean_x = x.mean_keepdim(2)?;
         let x = x.broadcast_sub(&mean_x)?;
-        let norm_x = (x.sqr()?.sum_keepdim(2)? / hidden_size as f64)?;
+        let norm_x = x.sqr()?.mean_keepdim(2)?;
         let x_normed = x.broadcast_div(&(norm_x + self.eps)?.sqrt()?)?;
         let mut w_dims = vec![1; x_shape.len()];
         w_dims[1] = n_channels;
@@ -10,14 +10,25 @@ pub mod linear;
 pub mod loss;
 pub mod ops;
 pub mod optim;
+pub mod pool;
+pub mod upsample;
 pub mod var_builder;
 
 pub use activation::Activation;
-pub use conv::{conv1d, conv2d, Conv1d, Conv1dConfig, Conv2d, Conv2dConfig};
+pub use conv::{
+    conv1d, conv2d, conv3d, conv_transpose1d, conv_transpose2d, Conv1d, Conv1dConfig, Conv2d,
+    Conv2dConfig, Conv3d, Conv3dConfig, ConvPadding, ConvTranspose1d, ConvTranspose1dConfig,
+    ConvTranspose2d, ConvTranspose2dConfig,
+};
 pub use embedding::{embedding, Embedding};
 pub use group_norm::{group_norm, GroupNorm};
 pub use init::Init;
 pub use layer_norm::{layer_norm, LayerNorm};
 pub use linear::{linear, linear_no_bias, Linear};
 pub use optim::{AdamW, ParamsAdamW, SGD};
+pub use pool::{
+    AdaptiveAvgPool2d, AvgPool1d, AvgPool2d, AvgPool3d, MaxPool1d, MaxPool2d, MaxPool3d,
+    Pool1dConfig, Pool2dConfig, Pool3dConfig,
+};
+pub use upsample::{Upsample, UpsampleTarget};
 pub use var_builder::{VarBuilder, VarMap};
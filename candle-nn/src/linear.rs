@@ -2,8 +2,8 @@
 //!
 //! This layer applies a linear transformation to the incoming data, `y = x@w.t() + b`.
 //! The bias is optional. The `forward` method can be used to apply the layer, it supports input
-//! with a batch dimension (so of shape `(b_sz, in_c)`) or without (of shape `(in_c,)`), the
-//! output has shape `(b_sz, out_c)` and `(out_c,)` respectively.
+//! with any number of leading batch dimensions, e.g. of shape `(b_sz, in_c)` or
+//! `(b_sz, seq_len, in_c)`, the output then has shape `(b_sz, out_c)` or `(b_sz, seq_len, out_c)`.
 //!
 //! ```rust
 //! use candle::{Tensor, Device::Cpu};
@@ -31,11 +31,8 @@ impl Linear {
     }
 
     pub fn forward(&self, x: &Tensor) -> candle::Result<Tensor> {
-        let w = match x.dims() {
-            &[bsize, _, _] => self.weight.broadcast_left(bsize)?.t()?,
-            _ => self.weight.t()?,
-        };
-        let x = x.matmul(&w)?;
+        // The weight gets broadcasted over the batch dimensions of x by matmul.
+        let x = x.matmul(&self.weight.t()?)?;
         match &self.bias {
             None => Ok(x),
             Some(bias) => x.broadcast_add(bias),
@@ -0,0 +1,205 @@
+//! Pooling Layers.
+//!
+//! When no stride is specified in the config, the stride defaults to the kernel size as in
+//! PyTorch. Avg pooling counts the padding as zeros whereas max pooling ignores it.
+use candle::{Result, Tensor};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pool1dConfig {
+    pub stride: Option<usize>,
+    pub padding: usize,
+    pub ceil_mode: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pool2dConfig {
+    pub stride: Option<(usize, usize)>,
+    pub padding: (usize, usize),
+    pub ceil_mode: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pool3dConfig {
+    pub stride: Option<(usize, usize, usize)>,
+    pub padding: (usize, usize, usize),
+    pub ceil_mode: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AvgPool1d {
+    kernel_size: usize,
+    config: Pool1dConfig,
+}
+
+impl AvgPool1d {
+    pub fn new(kernel_size: usize, config: Pool1dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool1dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let k = self.kernel_size;
+        let stride = cfg.stride.unwrap_or(k);
+        x.avg_pool_nd(&[k], &[stride], &[cfg.padding], cfg.ceil_mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPool1d {
+    kernel_size: usize,
+    config: Pool1dConfig,
+}
+
+impl MaxPool1d {
+    pub fn new(kernel_size: usize, config: Pool1dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool1dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let k = self.kernel_size;
+        let stride = cfg.stride.unwrap_or(k);
+        x.max_pool_nd(&[k], &[stride], &[cfg.padding], cfg.ceil_mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AvgPool2d {
+    kernel_size: (usize, usize),
+    config: Pool2dConfig,
+}
+
+impl AvgPool2d {
+    pub fn new(kernel_size: (usize, usize), config: Pool2dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool2dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let (k_h, k_w) = self.kernel_size;
+        let (s_h, s_w) = cfg.stride.unwrap_or(self.kernel_size);
+        let (p_h, p_w) = cfg.padding;
+        x.avg_pool_nd(&[k_h, k_w], &[s_h, s_w], &[p_h, p_w], cfg.ceil_mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPool2d {
+    kernel_size: (usize, usize),
+    config: Pool2dConfig,
+}
+
+impl MaxPool2d {
+    pub fn new(kernel_size: (usize, usize), config: Pool2dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool2dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let (k_h, k_w) = self.kernel_size;
+        let (s_h, s_w) = cfg.stride.unwrap_or(self.kernel_size);
+        let (p_h, p_w) = cfg.padding;
+        x.max_pool_nd(&[k_h, k_w], &[s_h, s_w], &[p_h, p_w], cfg.ceil_mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AvgPool3d {
+    kernel_size: (usize, usize, usize),
+    config: Pool3dConfig,
+}
+
+impl AvgPool3d {
+    pub fn new(kernel_size: (usize, usize, usize), config: Pool3dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool3dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let (k_d, k_h, k_w) = self.kernel_size;
+        let (s_d, s_h, s_w) = cfg.stride.unwrap_or(self.kernel_size);
+        let (p_d, p_h, p_w) = cfg.padding;
+        let (k, s, p) = ([k_d, k_h, k_w], [s_d, s_h, s_w], [p_d, p_h, p_w]);
+        x.avg_pool_nd(&k, &s, &p, cfg.ceil_mode)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPool3d {
+    kernel_size: (usize, usize, usize),
+    config: Pool3dConfig,
+}
+
+impl MaxPool3d {
+    pub fn new(kernel_size: (usize, usize, usize), config: Pool3dConfig) -> Self {
+        Self {
+            kernel_size,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Pool3dConfig {
+        &self.config
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        let cfg = &self.config;
+        let (k_d, k_h, k_w) = self.kernel_size;
+        let (s_d, s_h, s_w) = cfg.stride.unwrap_or(self.kernel_size);
+        let (p_d, p_h, p_w) = cfg.padding;
+        let (k, s, p) = ([k_d, k_h, k_w], [s_d, s_h, s_w], [p_d, p_h, p_w]);
+        x.max_pool_nd(&k, &s, &p, cfg.ceil_mode)
+    }
+}
+
+/// Averages the input over windows chosen so that the output has a fixed spatial size whatever
+/// the input size, this is typically used before the classification head of a cnn.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveAvgPool2d {
+    output_size: (usize, usize),
+}
+
+impl AdaptiveAvgPool2d {
+    pub fn new(output_size: (usize, usize)) -> Self {
+        Self { output_size }
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        x.adaptive_avg_pool2d(self.output_size)
+    }
+}
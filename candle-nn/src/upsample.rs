@@ -0,0 +1,43 @@
+//! Upsampling Layer.
+use candle::{InterpolateMode, Result, Tensor};
+
+/// The target of an [`Upsample`] layer, either a fixed spatial size or scale factors applied to
+/// the input size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpsampleTarget {
+    Size(usize, usize),
+    ScaleFactor(f64, f64),
+}
+
+/// Resizes the spatial dimensions of inputs of shape `(b_size, c, h, w)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Upsample {
+    target: UpsampleTarget,
+    mode: InterpolateMode,
+}
+
+impl Upsample {
+    pub fn new(target: UpsampleTarget, mode: InterpolateMode) -> Self {
+        Self { target, mode }
+    }
+
+    pub fn with_size(h: usize, w: usize, mode: InterpolateMode) -> Self {
+        Self::new(UpsampleTarget::Size(h, w), mode)
+    }
+
+    pub fn with_scale_factor(scale_factor: f64, mode: InterpolateMode) -> Self {
+        Self::new(
+            UpsampleTarget::ScaleFactor(scale_factor, scale_factor),
+            mode,
+        )
+    }
+
+    pub fn forward(&self, x: &Tensor) -> Result<Tensor> {
+        match self.target {
+            UpsampleTarget::Size(h, w) => x.interpolate2d(h, w, self.mode),
+            UpsampleTarget::ScaleFactor(s_h, s_w) => {
+                x.interpolate2d_with_scale(s_h, s_w, self.mode)
+            }
+        }
+    }
+}
@@ -91,6 +91,7 @@ enum Tensors<'a> {
         safetensors: Vec<SafeTensors<'a>>,
     },
     Npz(candle::npy::NpzTensors),
+    Pth(candle::pickle::PthTensors),
     TensorMap(HashMap<String, Tensor>),
     Zeros,
     VarMap(VarMap),
@@ -146,6 +147,15 @@ impl<'a> TensorData<'a> {
         })
     }
 
+    fn from_pth<P: AsRef<std::path::Path>>(file: P, dtype: DType, device: &Device) -> Result<Self> {
+        let pth = candle::pickle::PthTensors::new(file)?;
+        Ok(Self {
+            tensors: Tensors::Pth(pth),
+            device: device.clone(),
+            dtype,
+        })
+    }
+
     fn from_varmap(varmap: &VarMap, dtype: DType, device: &Device) -> Self {
         Self {
             tensors: Tensors::VarMap(varmap.clone()),
@@ -208,6 +218,19 @@ impl<'a> VarBuilder<'a> {
         })
     }
 
+    /// Create a `VarBuilder` accessing data from a PyTorch checkpoint, i.e. a `.pt` or `.pth` file.
+    pub fn from_pth<P: AsRef<std::path::Path>>(
+        file: P,
+        dtype: DType,
+        device: &Device,
+    ) -> Result<Self> {
+        let data = TensorData::from_pth(file, dtype, device)?;
+        Ok(Self {
+            data: Arc::new(data),
+            path: vec![],
+        })
+    }
+
     pub fn push_prefix(&self, s: &str) -> Self {
         let mut path = self.path.clone();
         path.push(s.to_string());
@@ -337,6 +360,16 @@ impl<'a> VarBuilder<'a> {
                 }
                 .bt()
             })?,
+            Tensors::Pth(pth) => pth
+                .get(&path)?
+                .ok_or_else(|| {
+                    Error::CannotFindTensor {
+                        path: path.to_string(),
+                    }
+                    .bt()
+                })?
+                .to_device(&data.device)?
+                .to_dtype(data.dtype)?,
             Tensors::SafeTensorWithRouting {
                 routing,
                 safetensors,
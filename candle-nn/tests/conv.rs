@@ -0,0 +1,149 @@
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+use anyhow::Result;
+use candle::{DType, Device, Tensor};
+use candle_nn::{
+    Conv1d, Conv1dConfig, Conv2d, Conv2dConfig, Conv3d, Conv3dConfig, ConvPadding, ConvTranspose1d,
+    ConvTranspose1dConfig, ConvTranspose2dConfig, VarBuilder, VarMap,
+};
+
+#[test]
+fn conv1d_padding() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::new(&[[[1f32, 2., 3., 4., 5.]]], device)?;
+    let w = Tensor::new(&[[[1f32, 10., 100., 1000.]]], device)?;
+
+    // Same padding with an even kernel adds one zero at the start and two at the end.
+    let cfg = Conv1dConfig {
+        padding: ConvPadding::Same,
+        ..Default::default()
+    };
+    let conv = Conv1d::new(w.clone(), None, cfg);
+    let res = conv.forward(&inp)?;
+    assert_eq!(
+        res.to_vec3::<f32>()?,
+        [[[3210f32, 4321., 5432., 543., 54.]]]
+    );
+
+    let cfg = Conv1dConfig {
+        padding: ConvPadding::Asymmetric { start: 3, end: 0 },
+        ..Default::default()
+    };
+    let conv = Conv1d::new(w.clone(), None, cfg);
+    let res = conv.forward(&inp)?;
+    assert_eq!(
+        res.to_vec3::<f32>()?,
+        [[[1000f32, 2100., 3210., 4321., 5432.]]]
+    );
+
+    // With a dilation of 2, the kernel spans 7 elements.
+    let cfg = Conv1dConfig {
+        padding: ConvPadding::Same,
+        dilation: 2,
+        ..Default::default()
+    };
+    let conv = Conv1d::new(w.clone(), None, cfg);
+    assert_eq!(conv.forward(&inp)?.dims(), [1, 1, 5]);
+
+    let cfg = Conv1dConfig {
+        padding: ConvPadding::Same,
+        stride: 2,
+        ..Default::default()
+    };
+    let conv = Conv1d::new(w, None, cfg);
+    assert!(conv.forward(&inp).is_err());
+    Ok(())
+}
+
+#[test]
+fn conv2d_depthwise() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::arange(0f32, 18., device)?.reshape((1, 2, 3, 3))?;
+    let w = Tensor::new(&[1f32, -1.], device)?.reshape((2, 1, 1, 1))?;
+    let cfg = Conv2dConfig {
+        padding: ConvPadding::Same,
+        groups: 2,
+        ..Default::default()
+    };
+    let conv = Conv2d::new(w, None, cfg);
+    let res = conv.forward(&inp)?;
+    assert_eq!(res.dims(), [1, 2, 3, 3]);
+    assert_eq!(
+        res.flatten_all()?.to_vec1::<f32>()?,
+        [
+            0f32, 1., 2., 3., 4., 5., 6., 7., 8., -9., -10., -11., -12., -13., -14., -15., -16.,
+            -17.
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn conv_transpose() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::new(&[[[1f32, 2., 3.]]], device)?;
+    let w = Tensor::new(&[[[1f32, 10., 100.]]], device)?;
+    let b = Tensor::new(&[0.5f32], device)?;
+    let cfg = ConvTranspose1dConfig {
+        padding: 1,
+        output_padding: 1,
+        stride: 2,
+        ..Default::default()
+    };
+    let conv = ConvTranspose1d::new(w, Some(b), cfg);
+    let res = conv.forward(&inp)?;
+    assert_eq!(
+        res.to_vec3::<f32>()?,
+        [[[10.5f32, 102.5, 20.5, 203.5, 30.5, 300.5]]]
+    );
+
+    let varmap = VarMap::new();
+    let vb = VarBuilder::from_varmap(&varmap, DType::F32, device);
+    let cfg = ConvTranspose2dConfig {
+        stride: 2,
+        ..Default::default()
+    };
+    let conv = candle_nn::conv_transpose2d(4, 3, 2, cfg, vb.pp("up"))?;
+    assert_eq!(varmap.all_vars().len(), 2);
+    let inp = Tensor::zeros((2, 4, 5, 7), DType::F32, device)?;
+    assert_eq!(conv.forward(&inp)?.dims(), [2, 3, 10, 14]);
+    Ok(())
+}
+
+#[test]
+fn conv3d() -> Result<()> {
+    let device = &Device::Cpu;
+    // A kernel summing the 2x2x2 neighbourhood of each element.
+    let w = Tensor::ones((1, 1, 2, 2, 2), DType::F32, device)?;
+    let b = Tensor::new(&[0.5f32], device)?;
+    let inp = Tensor::arange(0f32, 8., device)?.reshape((1, 1, 2, 2, 2))?;
+    let conv = Conv3d::new(w.clone(), Some(b), Default::default());
+    assert_eq!(conv.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?, [28.5]);
+
+    // Same padding keeps the spatial dims, the extra zero goes at the end.
+    let cfg = Conv3dConfig {
+        padding: ConvPadding::Same,
+        ..Default::default()
+    };
+    let conv = Conv3d::new(w, None, cfg);
+    let res = conv.forward(&inp)?;
+    assert_eq!(res.dims(), [1, 1, 2, 2, 2]);
+    assert_eq!(
+        res.flatten_all()?.to_vec1::<f32>()?,
+        [28f32, 16., 18., 10., 22., 12., 13., 7.]
+    );
+
+    let varmap = VarMap::new();
+    let vb = VarBuilder::from_varmap(&varmap, DType::F32, device);
+    let cfg = Conv3dConfig {
+        stride: 2,
+        groups: 2,
+        ..Default::default()
+    };
+    let conv = candle_nn::conv3d(4, 6, 3, cfg, vb.pp("conv"))?;
+    assert_eq!(varmap.all_vars().len(), 2);
+    let inp = Tensor::zeros((2, 4, 5, 7, 9), DType::F32, device)?;
+    assert_eq!(conv.forward(&inp)?.dims(), [2, 6, 2, 3, 4]);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+use anyhow::Result;
+use candle::{Device, Tensor};
+use candle_nn::Linear;
+
+#[test]
+fn linear() -> Result<()> {
+    let device = &Device::Cpu;
+    let w = Tensor::new(&[[1f32, 2.], [3., 4.], [5., 6.]], device)?;
+    let b = Tensor::new(&[-1f32, 0., 1.], device)?;
+    let layer = Linear::new(w, Some(b));
+
+    let xs = Tensor::new(&[[10f32, 100.]], device)?;
+    let ys = layer.forward(&xs)?;
+    assert_eq!(ys.to_vec2::<f32>()?, &[[209.0, 430.0, 651.0]]);
+
+    // Inputs with a sequence dimension and with multiple batch dimensions.
+    let xs = Tensor::new(&[[[10f32, 100.], [1., 0.]], [[0., 1.], [2., 2.]]], device)?;
+    let ys = layer.forward(&xs)?;
+    assert_eq!(
+        ys.to_vec3::<f32>()?,
+        &[
+            [[209.0, 430.0, 651.0], [0.0, 3.0, 6.0]],
+            [[1.0, 4.0, 7.0], [5.0, 14.0, 23.0]]
+        ]
+    );
+    let ys = layer.forward(&xs.reshape((2, 1, 2, 2))?)?;
+    assert_eq!(ys.dims(), [2, 1, 2, 3]);
+    assert_eq!(
+        ys.flatten_all()?.to_vec1::<f32>()?,
+        [209., 430., 651., 0., 3., 6., 1., 4., 7., 5., 14., 23.]
+    );
+    Ok(())
+}
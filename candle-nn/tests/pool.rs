@@ -0,0 +1,117 @@
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+mod test_utils;
+
+use anyhow::Result;
+use candle::{Device, InterpolateMode, Tensor};
+use candle_nn::{
+    AdaptiveAvgPool2d, AvgPool1d, AvgPool2d, MaxPool1d, MaxPool2d, MaxPool3d, Pool1dConfig,
+    Pool2dConfig, Pool3dConfig, Upsample,
+};
+
+#[test]
+fn pool1d() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::new(&[[[1f32, 5., 2., 4., 3.]]], device)?;
+
+    // The stride defaults to the kernel size.
+    let pool = MaxPool1d::new(2, Default::default());
+    assert_eq!(pool.forward(&inp)?.to_vec3::<f32>()?, [[[5f32, 4.]]]);
+    let cfg = Pool1dConfig {
+        ceil_mode: true,
+        ..Default::default()
+    };
+    let pool = MaxPool1d::new(2, cfg);
+    assert_eq!(pool.forward(&inp)?.to_vec3::<f32>()?, [[[5f32, 4., 3.]]]);
+
+    // The padding counts as zeros in the average.
+    let cfg = Pool1dConfig {
+        stride: Some(1),
+        padding: 1,
+        ceil_mode: false,
+    };
+    let pool = AvgPool1d::new(2, cfg);
+    assert_eq!(
+        pool.forward(&inp)?.to_vec3::<f32>()?,
+        [[[0.5f32, 3., 3.5, 3., 3.5, 1.5]]]
+    );
+    Ok(())
+}
+
+#[test]
+fn pool2d_3d() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::arange(0f32, 20., device)?.reshape((1, 1, 4, 5))?;
+    let cfg = Pool2dConfig {
+        stride: Some((2, 2)),
+        padding: (1, 1),
+        ceil_mode: false,
+    };
+    let pool = MaxPool2d::new((3, 3), cfg);
+    assert_eq!(
+        pool.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [6f32, 8., 9., 16., 18., 19.]
+    );
+    let pool = AvgPool2d::new((2, 2), Default::default());
+    assert_eq!(
+        pool.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [3f32, 5., 13., 15.]
+    );
+
+    let inp = Tensor::arange(0f32, 27., device)?.reshape((1, 1, 3, 3, 3))?;
+    let cfg = Pool3dConfig {
+        ceil_mode: true,
+        ..Default::default()
+    };
+    let pool = MaxPool3d::new((2, 2, 2), cfg);
+    assert_eq!(
+        pool.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [13f32, 14., 16., 17., 22., 23., 25., 26.]
+    );
+    Ok(())
+}
+
+#[test]
+fn adaptive_avg_pool2d() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::arange(0f32, 30., device)?.reshape((1, 2, 3, 5))?;
+    let pool = AdaptiveAvgPool2d::new((1, 1));
+    let res = pool.forward(&inp)?.flatten_all()?;
+    assert_eq!(test_utils::to_vec1_round(&res, 4)?, [7f32, 22.]);
+    // The windows overlap: rows 0..2 and 1..3, columns 0..3 and 2..5.
+    let pool = AdaptiveAvgPool2d::new((2, 2));
+    assert_eq!(
+        pool.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [3.5f32, 5.5, 8.5, 10.5, 18.5, 20.5, 23.5, 25.5]
+    );
+    Ok(())
+}
+
+#[test]
+fn upsample() -> Result<()> {
+    let device = &Device::Cpu;
+    let inp = Tensor::new(&[1f32, 2., 3., 4.], device)?.reshape((1, 1, 2, 2))?;
+    let up = Upsample::with_scale_factor(2., InterpolateMode::Nearest);
+    assert_eq!(
+        up.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [1f32, 1., 2., 2., 1., 1., 2., 2., 3., 3., 4., 4., 3., 3., 4., 4.]
+    );
+    let mode = InterpolateMode::Bilinear {
+        align_corners: false,
+    };
+    let up = Upsample::with_scale_factor(2., mode);
+    assert_eq!(
+        up.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [1f32, 1.25, 1.75, 2., 1.5, 1.75, 2.25, 2.5, 2.5, 2.75, 3.25, 3.5, 3., 3.25, 3.75, 4.]
+    );
+    let mode = InterpolateMode::Bicubic {
+        align_corners: true,
+    };
+    let up = Upsample::with_size(3, 1, mode);
+    assert_eq!(
+        up.forward(&inp)?.flatten_all()?.to_vec1::<f32>()?,
+        [1f32, 2., 3.]
+    );
+    Ok(())
+}
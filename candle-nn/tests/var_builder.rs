@@ -0,0 +1,36 @@
+#[cfg(feature = "mkl")]
+extern crate intel_mkl_src;
+
+use anyhow::Result;
+use candle::{DType, Device};
+use std::io::Write;
+
+// The pickled content of `torch.save(sd, ...)` where `sd` is a dict holding a (2, 3) weight and a
+// (2,) bias, both stored in the same storage of 8 elements.
+const DATA_PKL: &[u8] = b"\x80\x02}q\x00(X\x0d\x00\x00\x00linear.weightq\x01ctorch._utils\n_rebuild_tensor_v2\nq\x02((X\x07\x00\x00\x00storageq\x03ctorch\nFloatStorage\nq\x04X\x01\x00\x00\x000q\x05X\x03\x00\x00\x00cpuq\x06K\x08tq\x07QK\x00K\x02K\x03\x86q\x08K\x03K\x01\x86q\t\x89ccollections\nOrderedDict\nq\n)Rq\x0btq\x0cRq\rX\x0b\x00\x00\x00linear.biasq\x0eh\x02((h\x03h\x04h\x05h\x06K\x08tq\x0fQK\x06K\x02\x85q\x10K\x01\x85q\x11\x89h\n)Rq\x12tq\x13Rq\x14u.";
+
+#[test]
+fn var_builder_from_pth() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("candle-vb-pth-{}.pth", std::process::id()));
+    let file = std::fs::File::create(&path)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("archive/data.pkl", options)?;
+    zip.write_all(DATA_PKL)?;
+    zip.start_file("archive/data/0", options)?;
+    for v in [1f32, 2., 3., 4., 5., 6., -1., 1.] {
+        zip.write_all(&v.to_le_bytes())?
+    }
+    zip.finish()?;
+
+    let vb = candle_nn::VarBuilder::from_pth(&path, DType::F64, &Device::Cpu)?;
+    let linear = candle_nn::linear(3, 2, vb.pp("linear"))?;
+    let xs = candle::Tensor::new(&[[1f64, 0., 1.]], &Device::Cpu)?;
+    assert_eq!(linear.forward(&xs)?.to_vec2::<f64>()?, [[3., 11.]]);
+    // Shape mismatches and missing tensors are reported as errors.
+    assert!(candle_nn::linear(2, 2, vb.pp("linear")).is_err());
+    assert!(vb.get(2, "bias").is_err());
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
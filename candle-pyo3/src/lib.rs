@@ -73,6 +73,7 @@ static CUDA_DEVICE: std::sync::Mutex<Option<Device>> = std::sync::Mutex::new(Non
 enum PyDevice {
     Cpu,
     Cuda,
+    Lazy,
 }
 
 impl PyDevice {
@@ -80,12 +81,14 @@ impl PyDevice {
         match device {
             Device::Cpu => Self::Cpu,
             Device::Cuda(_) => Self::Cuda,
+            Device::Lazy => Self::Lazy,
         }
     }
 
     fn as_device(&self) -> PyResult<Device> {
         match self {
             Self::Cpu => Ok(Device::Cpu),
+            Self::Lazy => Ok(Device::Lazy),
             Self::Cuda => {
                 let mut device = CUDA_DEVICE.lock().unwrap();
                 if let Some(device) = device.as_ref() {
@@ -105,6 +108,7 @@ impl<'source> FromPyObject<'source> for PyDevice {
         let device = match device {
             "cpu" => PyDevice::Cpu,
             "cuda" => PyDevice::Cuda,
+            "lazy" => PyDevice::Lazy,
             _ => Err(PyTypeError::new_err(format!("invalid device '{device}'")))?,
         };
         Ok(device)
@@ -116,6 +120,7 @@ impl ToPyObject for PyDevice {
         let str = match self {
             PyDevice::Cpu => "cpu",
             PyDevice::Cuda => "cuda",
+            PyDevice::Lazy => "lazy",
         };
         str.to_object(py)
     }
@@ -136,6 +141,10 @@ macro_rules! pydtype {
 }
 pydtype!(u8, |v| v);
 pydtype!(u32, |v| v);
+pydtype!(i8, |v| v);
+pydtype!(i16, |v| v);
+pydtype!(i32, |v| v);
+pydtype!(i64, |v| v);
 pydtype!(f16, f32::from);
 pydtype!(bf16, f32::from);
 pydtype!(f32, |v| v);
@@ -148,8 +157,12 @@ trait MapDType {
 
     fn map(&self, t: &Tensor) -> PyResult<Self::Output> {
         match t.dtype() {
-            DType::U8 => self.f::<u8>(t),
+            DType::U8 | DType::Bool => self.f::<u8>(t),
             DType::U32 => self.f::<u32>(t),
+            DType::I8 => self.f::<i8>(t),
+            DType::I16 => self.f::<i16>(t),
+            DType::I32 => self.f::<i32>(t),
+            DType::I64 => self.f::<i64>(t),
             DType::BF16 => self.f::<bf16>(t),
             DType::F16 => self.f::<f16>(t),
             DType::F32 => self.f::<f32>(t),
@@ -463,6 +476,11 @@ fn candle(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyDType>()?;
     m.add("u8", PyDType(DType::U8))?;
     m.add("u32", PyDType(DType::U32))?;
+    m.add("i8", PyDType(DType::I8))?;
+    m.add("i16", PyDType(DType::I16))?;
+    m.add("i32", PyDType(DType::I32))?;
+    m.add("i64", PyDType(DType::I64))?;
+    m.add("bool", PyDType(DType::Bool))?;
     m.add("bf16", PyDType(DType::BF16))?;
     m.add("f16", PyDType(DType::F16))?;
     m.add("f32", PyDType(DType::F32))?;
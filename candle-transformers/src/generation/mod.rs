@@ -4,23 +4,57 @@ use rand::{distributions::Distribution, SeedableRng};
 pub struct LogitsProcessor {
     rng: rand::rngs::StdRng,
     temperature: Option<f64>,
+    top_p: Option<f64>,
 }
 
 impl LogitsProcessor {
     pub fn new(seed: u64, temperature: Option<f64>) -> Self {
+        Self::new_with_top_p(seed, temperature, None)
+    }
+
+    /// Same as [`LogitsProcessor::new`] with nucleus sampling when `top_p` is set, this only
+    /// applies when sampling with a temperature.
+    pub fn new_with_top_p(seed: u64, temperature: Option<f64>, top_p: Option<f64>) -> Self {
         Self {
             rng: rand::rngs::StdRng::seed_from_u64(seed),
             temperature,
+            top_p,
         }
     }
 
+    // Nucleus sampling, the sample is restricted to the smallest set of most probable tokens
+    // whose cumulative probability is at least `top_p`.
+    fn sample_top_p(&mut self, prs: &Tensor, top_p: f64) -> Result<u32> {
+        let (prs, indexes) = prs.sort(D::Minus1, false)?;
+        // The number of tokens for which the cumulative probability is below `top_p`, the next
+        // token is also kept so that the cutoff is reached.
+        let cutoff = Tensor::new(top_p as f32, prs.device())?.broadcast_as(prs.shape())?;
+        let len = prs
+            .cumsum(D::Minus1)?
+            .lt(&cutoff)?
+            .to_dtype(DType::U32)?
+            .sum_all()?
+            .to_scalar::<u32>()? as usize;
+        let len = usize::min(len + 1, prs.dim(D::Minus1)?);
+        let prs: Vec<f32> = prs.narrow(D::Minus1, 0, len)?.to_vec1()?;
+        let distr = rand::distributions::WeightedIndex::new(prs).map_err(Error::wrap)?;
+        let index = distr.sample(&mut self.rng);
+        indexes.get(index)?.to_scalar::<u32>()
+    }
+
     pub fn sample(&mut self, logits: &Tensor) -> Result<u32> {
         let logits = logits.to_dtype(DType::F32)?;
         let next_token = if let Some(temperature) = self.temperature {
             let prs = candle_nn::ops::softmax(&(&logits / temperature)?, D::Minus1)?;
-            let prs: Vec<f32> = prs.to_vec1()?;
-            let distr = rand::distributions::WeightedIndex::new(prs).map_err(Error::wrap)?;
-            distr.sample(&mut self.rng) as u32
+            match self.top_p {
+                Some(top_p) if top_p < 1.0 => self.sample_top_p(&prs, top_p)?,
+                _ => {
+                    let prs: Vec<f32> = prs.to_vec1()?;
+                    let distr =
+                        rand::distributions::WeightedIndex::new(prs).map_err(Error::wrap)?;
+                    distr.sample(&mut self.rng) as u32
+                }
+            }
         } else {
             let logits_v: Vec<f32> = logits.to_vec1()?;
             logits_v
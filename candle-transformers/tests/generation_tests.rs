@@ -0,0 +1,35 @@
+use candle::{Device, Result, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+
+// Samples `n` tokens and returns how many times each token was drawn.
+fn counts(processor: &mut LogitsProcessor, logits: &Tensor, n: usize) -> Result<Vec<usize>> {
+    let mut counts = vec![0; logits.dim(0)?];
+    for _ in 0..n {
+        counts[processor.sample(logits)? as usize] += 1
+    }
+    Ok(counts)
+}
+
+#[test]
+fn sample_with_top_p() -> Result<()> {
+    // The probabilities are not sorted so that the tokens have to be mapped back to their index.
+    let prs = Tensor::new(&[0.15f32, 0.5, 0.05, 0.3], &Device::Cpu)?;
+    let logits = prs.log()?;
+
+    // The cumulative probabilities are 0.5, 0.8, 0.95, 1., so only tokens 1 and 3 are kept.
+    let mut processor = LogitsProcessor::new_with_top_p(42, Some(1.0), Some(0.7));
+    let c = counts(&mut processor, &logits, 1000)?;
+    assert_eq!((c[0], c[2]), (0, 0), "{c:?}");
+    assert!(c[1] > c[3] && c[3] > 0, "{c:?}");
+
+    let mut processor = LogitsProcessor::new_with_top_p(42, Some(1.0), Some(0.9));
+    let c = counts(&mut processor, &logits, 1000)?;
+    assert_eq!(c[2], 0, "{c:?}");
+    assert!(c[1] > c[3] && c[3] > c[0] && c[0] > 0, "{c:?}");
+
+    // Without top-p every token can be sampled.
+    let mut processor = LogitsProcessor::new_with_top_p(42, Some(1.0), None);
+    let c = counts(&mut processor, &logits, 1000)?;
+    assert!(c.iter().all(|&c| c > 0), "{c:?}");
+    Ok(())
+}